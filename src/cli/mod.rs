@@ -0,0 +1,358 @@
+//! Parses the command line arguments. With no arguments, the review configured in the settings is run.
+use std::fmt;
+
+/// The usage shown on `--help`, or on an invalid argument
+pub(crate) const USAGE: &str = "\
+Usage: cosmonaut_code [OPTIONS] [COMMAND]
+
+Commands:
+    (none)                      Runs the review configured in the settings file
+    providers check [PROVIDER]  Sends a trivial prompt to each configured provider and service, or only those of
+                                PROVIDER, reporting reachability, authentication, model availability and latency
+    staged                      Reviews only the files staged for commit, printing the findings, and exits with 1 if
+                                the review is Red, e.g., as a pre-commit hook
+    patch FILE                  Reviews only the hunks of the unified diff in FILE, e.g., from 'git format-patch' or
+                                a GitHub pull request, with findings per hunk
+    benchmark [--files N]       Reviews the same sample of N files (default 5) with each configured provider and
+                                service, comparing latency, token use, JSON validity, findings and cost
+    feedback false-positive FINGERPRINT [REASON]
+                                Marks the finding with the FINGERPRINT, as in the report, as a false positive, so it
+                                is dropped from later reviews, and, if 'feedback_counter_examples' is set, given to
+                                the LLM as an example of what not to report
+    delta PREVIOUS CURRENT      Compares the JSON report in CURRENT with the earlier one in PREVIOUS, reporting the new
+                                and resolved findings, the files that changed RAG status, and the change in statistics
+    merge REPORT REPORT...      Merges the JSON reports of partial reviews of the repository, e.g., of each
+                                sub-project or CI shard, into one report, with its statistics and summary recomputed
+    schema                      Prints the versioned JSON schema of the JSON report
+
+Options:
+    --dry-run                   Prints the files that would be reviewed, with the estimated tokens and cost per
+                                configured provider and service, without making any requests, as JSON with '--output -'
+    --gate                      Fails the review, e.g., in CI, with an exit code per 'quality_gate' threshold exceeded:
+                                3 if the repository is Red, 4 if the critical and high security issues exceed
+                                'max_critical_high_security_issues', or 5 if the errors exceed 'max_errors'
+    --baseline FILE             Marks the findings that recur from the JSON report in FILE as known, so that only new
+                                findings affect the RAG status
+    --rereview FILE             Reviews only the files that were Red, or had Critical or High security issues, in the
+                                JSON report in FILE, showing whether each improved, e.g., to verify remediation
+    --output PATH               Writes the reports to the directory PATH, rather than the 'report_output_path', or, if
+                                '-', only the JSON report to stdout, with the logs on stderr, e.g., to pipe it to jq
+    -h, --help                  Prints this help
+
+The settings file is read from the path in the 'SENSITIVE_SETTINGS_PATH' environment variable.";
+
+/// The command to run
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Command {
+    Review,
+    Staged,
+    Patch {
+        path: String,
+    },
+    ProvidersCheck {
+        provider: Option<String>,
+    },
+    Benchmark {
+        num_files: Option<usize>,
+    },
+    FeedbackFalsePositive {
+        fingerprint: String,
+        reason: Option<String>,
+    },
+    Delta {
+        previous_path: String,
+        path: String,
+    },
+    Merge {
+        paths: Vec<String>,
+    },
+    Schema,
+    Help,
+}
+/// The parsed command line arguments
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Cli {
+    pub(crate) command: Command,
+    pub(crate) baseline: Option<String>,
+    pub(crate) rereview: Option<String>,
+    pub(crate) output: Option<String>,
+    pub(crate) dry_run: bool,
+    pub(crate) gate: bool,
+}
+impl Cli {
+    /// Parses the arguments, excluding the programme name
+    pub(crate) fn parse<I>(args: I) -> Result<Self, CliError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut args: Vec<String> = args.into_iter().collect();
+        if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+            return Ok(Self {
+                command: Command::Help,
+                baseline: None,
+                rereview: None,
+                output: None,
+                dry_run: false,
+                gate: false,
+            });
+        }
+        let baseline: Option<String> =
+            remove_option(&mut args, "--baseline", "Missing the baseline report")?;
+        let rereview: Option<String> =
+            remove_option(&mut args, "--rereview", "Missing the report to re-review")?;
+        let output: Option<String> =
+            remove_option(&mut args, "--output", "Missing the output path")?;
+        let dry_run: bool = remove_flag(&mut args, "--dry-run");
+        let gate: bool = remove_flag(&mut args, "--gate");
+        let command = match args.as_slice() {
+            [] => Command::Review,
+            [command] if command == "staged" => Command::Staged,
+            [command, previous_path, path] if command == "delta" => Command::Delta {
+                previous_path: previous_path.to_string(),
+                path: path.to_string(),
+            },
+            [command, paths @ ..] if command == "merge" => match paths {
+                [_, _, ..] => Command::Merge {
+                    paths: paths.to_vec(),
+                },
+                _ => {
+                    return Err(CliError(
+                        "Expected two or more reports to merge".to_string(),
+                    ))
+                }
+            },
+            [command] if command == "schema" => Command::Schema,
+            [command, path] if command == "patch" => Command::Patch {
+                path: path.to_string(),
+            },
+            [group, action, rest @ ..] if group == "providers" && action == "check" => match rest {
+                [] => Command::ProvidersCheck { provider: None },
+                [provider] => Command::ProvidersCheck {
+                    provider: Some(provider.to_string()),
+                },
+                _ => {
+                    return Err(CliError(format!(
+                        "Unexpected arguments: {}",
+                        rest.join(" ")
+                    )))
+                }
+            },
+            [command, rest @ ..] if command == "benchmark" => match rest {
+                [] => Command::Benchmark { num_files: None },
+                [flag, num_files] if flag == "--files" => Command::Benchmark {
+                    num_files: Some(num_files.parse().map_err(|_| {
+                        CliError(format!("Invalid number of files: {}", num_files))
+                    })?),
+                },
+                _ => {
+                    return Err(CliError(format!(
+                        "Unexpected arguments: {}",
+                        rest.join(" ")
+                    )))
+                }
+            },
+            [group, action, rest @ ..] if group == "feedback" && action == "false-positive" => {
+                match rest {
+                    [fingerprint] => Command::FeedbackFalsePositive {
+                        fingerprint: fingerprint.to_string(),
+                        reason: None,
+                    },
+                    [fingerprint, reason] => Command::FeedbackFalsePositive {
+                        fingerprint: fingerprint.to_string(),
+                        reason: Some(reason.to_string()),
+                    },
+                    _ => {
+                        return Err(CliError(
+                            "Expected a FINGERPRINT and an optional REASON".to_string(),
+                        ))
+                    }
+                }
+            }
+            _ => return Err(CliError(format!("Unknown command: {}", args.join(" ")))),
+        };
+        Ok(Self {
+            command,
+            baseline,
+            rereview,
+            output,
+            dry_run,
+            gate,
+        })
+    }
+}
+/// Removes the option, and its value, from the arguments, returning the value, if present, or an error if it has none
+fn remove_option(
+    args: &mut Vec<String>,
+    option: &str,
+    missing: &str,
+) -> Result<Option<String>, CliError> {
+    match args.iter().position(|arg| arg == option) {
+        Some(index) if index + 1 < args.len() => {
+            let value = args.remove(index + 1);
+            args.remove(index);
+            Ok(Some(value))
+        }
+        Some(_) => Err(CliError(missing.to_string())),
+        None => Ok(None),
+    }
+}
+/// Removes the flag from the arguments, returning whether it was present
+fn remove_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    match args.iter().position(|arg| arg == flag) {
+        Some(index) => {
+            args.remove(index);
+            true
+        }
+        None => false,
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct CliError(String);
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl std::error::Error for CliError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli, CliError> {
+        Cli::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(parse(&[]).unwrap().command, Command::Review);
+        assert_eq!(parse(&["--help"]).unwrap().command, Command::Help);
+        assert_eq!(parse(&["staged"]).unwrap().command, Command::Staged);
+        assert_eq!(parse(&["schema"]).unwrap().command, Command::Schema);
+        assert_eq!(
+            parse(&["delta", "old.json", "new.json"]).unwrap().command,
+            Command::Delta {
+                previous_path: "old.json".to_string(),
+                path: "new.json".to_string()
+            }
+        );
+        assert_eq!(
+            parse(&["merge", "backend.json", "frontend.json"])
+                .unwrap()
+                .command,
+            Command::Merge {
+                paths: vec!["backend.json".to_string(), "frontend.json".to_string()]
+            }
+        );
+        assert!(parse(&["merge", "backend.json"]).is_err());
+        assert_eq!(
+            parse(&["patch", "pr.diff"]).unwrap().command,
+            Command::Patch {
+                path: "pr.diff".to_string()
+            }
+        );
+        assert!(parse(&["patch"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_dry_run() {
+        let cli = parse(&["--dry-run"]).unwrap();
+        assert_eq!(cli.command, Command::Review);
+        assert!(cli.dry_run);
+        let cli = parse(&["staged", "--dry-run"]).unwrap();
+        assert_eq!(cli.command, Command::Staged);
+        assert!(cli.dry_run);
+        assert!(!parse(&[]).unwrap().dry_run);
+        let cli = parse(&["--gate", "--baseline", "previous-report.json"]).unwrap();
+        assert_eq!(cli.command, Command::Review);
+        assert!(cli.gate);
+        assert!(!cli.dry_run);
+    }
+
+    #[test]
+    fn test_parse_baseline() {
+        let cli = parse(&["--baseline", "previous-report.json"]).unwrap();
+        assert_eq!(cli.command, Command::Review);
+        assert_eq!(cli.baseline, Some("previous-report.json".to_string()));
+        let cli = parse(&["staged", "--baseline", "previous-report.json"]).unwrap();
+        assert_eq!(cli.command, Command::Staged);
+        assert!(parse(&["--baseline"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_benchmark() {
+        assert_eq!(
+            parse(&["benchmark", "--files", "3"]).unwrap().command,
+            Command::Benchmark { num_files: Some(3) }
+        );
+        assert!(parse(&["benchmark", "--files", "three"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_providers_check() {
+        assert_eq!(
+            parse(&["providers", "check"]).unwrap().command,
+            Command::ProvidersCheck { provider: None }
+        );
+        assert_eq!(
+            parse(&["providers", "check", "openai"]).unwrap().command,
+            Command::ProvidersCheck {
+                provider: Some("openai".to_string())
+            }
+        );
+        assert!(parse(&["providers"]).is_err());
+        assert!(parse(&["providers", "check", "openai", "google"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_rereview() {
+        let cli = parse(&["--rereview", "previous-report.json", "--dry-run"]).unwrap();
+        assert_eq!(cli.command, Command::Review);
+        assert_eq!(cli.rereview, Some("previous-report.json".to_string()));
+        assert_eq!(cli.baseline, None);
+        assert!(cli.dry_run);
+        assert!(parse(&["--rereview"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_output() {
+        let cli = parse(&["--output", "-", "--gate"]).unwrap();
+        assert_eq!(cli.command, Command::Review);
+        assert_eq!(cli.output, Some("-".to_string()));
+        assert!(cli.gate);
+        let cli = parse(&["merge", "a.json", "b.json", "--output", "reports"]).unwrap();
+        assert_eq!(cli.output, Some("reports".to_string()));
+        assert_eq!(parse(&[]).unwrap().output, None);
+        assert!(parse(&["--output"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_feedback() {
+        assert_eq!(
+            parse(&["feedback", "false-positive", "3f2a"])
+                .unwrap()
+                .command,
+            Command::FeedbackFalsePositive {
+                fingerprint: "3f2a".to_string(),
+                reason: None
+            }
+        );
+        assert_eq!(
+            parse(&[
+                "feedback",
+                "false-positive",
+                "3f2a",
+                "Validated by the caller"
+            ])
+            .unwrap()
+            .command,
+            Command::FeedbackFalsePositive {
+                fingerprint: "3f2a".to_string(),
+                reason: Some("Validated by the caller".to_string())
+            }
+        );
+        assert!(parse(&["feedback", "false-positive"]).is_err());
+        assert!(parse(&["feedback"]).is_err());
+    }
+}
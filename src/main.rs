@@ -4,11 +4,13 @@
 #[cfg(debug_assertions)]
 mod dev_mode;
 
+mod cli;
 mod common;
 mod provider;
 mod retrieval;
 mod review;
 mod settings;
+use cli::{Cli, Command};
 use log::{error, info};
 use std::time::{Duration, Instant};
 
@@ -17,9 +19,146 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let start = Instant::now();
     env_logger::init();
 
+    let cli_args = match Cli::parse(std::env::args().skip(1)) {
+        Ok(cli_args) => cli_args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if cli_args.command == Command::Help {
+        println!("{}", cli::USAGE);
+        return Ok(());
+    }
+    if cli_args.command == Command::Schema {
+        println!("{}", review::schema::REPOSITORY_REVIEW_SCHEMA);
+        return Ok(());
+    }
+
     // Load settings
-    let settings: settings::Settings = settings::Settings::new()?;
+    let mut settings: settings::Settings = settings::Settings::new()?;
     // Should have the settings fully loaded and trusted at this point.
+    if cli_args.baseline.is_some() {
+        settings.baseline_path = cli_args.baseline.clone();
+    }
+    if cli_args.rereview.is_some() {
+        settings.rereview_report_path = cli_args.rereview.clone();
+    }
+    if let Some(output) = &cli_args.output {
+        settings.report_output_path = output.clone();
+    }
+    // Only the JSON report is then written to stdout, so nothing else may be
+    let report_to_stdout: bool = settings.report_output_path == review::report::STDOUT_OUTPUT_PATH;
+
+    if let Command::ProvidersCheck { provider: name } = &cli_args.command {
+        let checks = provider::health::check_providers(&settings, name.as_deref()).await?;
+        for check in &checks {
+            println!("{}", check);
+        }
+        if !checks.iter().all(|check| check.status.is_ok()) {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if let Command::FeedbackFalsePositive {
+        fingerprint,
+        reason,
+    } = &cli_args.command
+    {
+        let mut feedback = review::feedback::FeedbackStore::from_settings(&settings);
+        if feedback.mark_false_positive(fingerprint, reason.clone()) {
+            println!("Marked as a false positive: {}", fingerprint);
+        } else {
+            println!("Already marked as a false positive: {}", fingerprint);
+        }
+        feedback.save()?;
+        return Ok(());
+    }
+    if let Command::Delta {
+        previous_path,
+        path,
+    } = &cli_args.command
+    {
+        let report_delta = review::delta::ReportDelta::load(previous_path, path)?;
+        if !report_to_stdout {
+            println!("{}", report_delta);
+        }
+        let report_output = review::report::create_delta_report(&settings, &report_delta)?;
+        info!("DELTA COMPLETE. See the output report: {}", report_output);
+        return Ok(());
+    }
+    if let Command::Merge { paths } = &cli_args.command {
+        let review = review::merge::merge_reports(paths)?;
+        let report_output = review::report::create_report(&settings, &review)?;
+        info!("MERGE COMPLETE. See the output report: {}", report_output);
+        return Ok(());
+    }
+    if cli_args.dry_run && matches!(cli_args.command, Command::Review | Command::Staged) {
+        if cli_args.command == Command::Staged {
+            settings.review_scope = settings::ReviewScope::Staged;
+        }
+        let dry_run = review::dry_run::estimate_review(&settings)?;
+        if report_to_stdout {
+            println!("{}", dry_run.to_json()?);
+        } else {
+            print!("{}", dry_run);
+        }
+        return Ok(());
+    }
+    if cli_args.gate && matches!(cli_args.command, Command::Review | Command::Staged) {
+        if cli_args.command == Command::Staged {
+            settings.review_scope = settings::ReviewScope::Staged;
+        }
+        let review = review::review_codebase(&settings).await?;
+        let report_output = review::report::create_report(&settings, &review)?;
+        info!(
+            "CODE REVIEW COMPLETE. See the output report: {}",
+            report_output
+        );
+        print_exec_duration(start.elapsed());
+        let quality_gate = settings.quality_gate.clone().unwrap_or_default();
+        if let Some(failure) = review::gate::check_quality_gate(&quality_gate, &review) {
+            error!("QUALITY GATE FAILED: {}", failure);
+            std::process::exit(failure.exit_code());
+        }
+        info!("QUALITY GATE PASSED");
+        return Ok(());
+    }
+    if cli_args.command == Command::Staged {
+        settings.review_scope = settings::ReviewScope::Staged;
+        let review = review::review_codebase(&settings).await?;
+        if report_to_stdout {
+            review::report::create_report(&settings, &review)?;
+        } else {
+            print!("{}", review::report::render_findings(&review));
+        }
+        if *review.get_repository_rag_status() == review::data::RAGStatus::Red {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if let Command::Patch { path } = &cli_args.command {
+        let report_output = review::assess_patch(settings, path).await?;
+        info!(
+            "PATCH REVIEW COMPLETE. See the output report: {}",
+            report_output
+        );
+        print_exec_duration(start.elapsed());
+        return Ok(());
+    }
+    if let Command::Benchmark { num_files } = &cli_args.command {
+        let results = review::benchmark::run_benchmark(
+            &settings,
+            num_files.unwrap_or(review::benchmark::DEFAULT_BENCHMARK_FILES),
+        )
+        .await?;
+        println!("{}", review::benchmark::get_benchmark_table_header());
+        for result in &results {
+            println!("{}", result);
+        }
+        print_exec_duration(start.elapsed());
+        return Ok(());
+    }
 
     #[cfg(debug_assertions)]
     {
@@ -31,8 +170,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             let report_output = review::assess_codebase(settings).await?;
 
             info!("CODE REVIEW COMPLETE. See the output report for details.");
-            if let Err(e) = open_file_or_files(&report_output) {
-                error!("Failed to open file: {}", e);
+            // The report on stdout is not a file to open
+            if !report_to_stdout {
+                if let Err(e) = open_file_or_files(&report_output) {
+                    error!("Failed to open file: {}", e);
+                }
             }
         } else {
             info!("Taking developer path.");
@@ -28,6 +28,39 @@ pub(crate) struct ProviderCompletionResponse {
     pub(crate) id: String,
     pub(crate) model: String,
     pub(crate) choices: Vec<ProviderResponseChoice>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) usage: Option<TokenUsage>,
+}
+
+// Struct for the token usage reported by the language model provider for a request.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+pub(crate) struct TokenUsage {
+    pub(crate) prompt_tokens: i64,
+    pub(crate) completion_tokens: i64,
+    /// The number of the `prompt_tokens` served from the provider's prompt cache, if reported
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub(crate) cached_prompt_tokens: i64,
+}
+impl TokenUsage {
+    pub(crate) const fn new() -> Self {
+        Self {
+            prompt_tokens: 0,
+            completion_tokens: 0,
+            cached_prompt_tokens: 0,
+        }
+    }
+    /// Adds the passed [`TokenUsage`] to this one
+    pub(crate) fn add(&mut self, other: &TokenUsage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.cached_prompt_tokens += other.cached_prompt_tokens;
+    }
+    pub(crate) fn total_tokens(&self) -> i64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+fn is_zero(value: &i64) -> bool {
+    *value == 0
 }
 
 #[derive(Debug, Deserialize, Serialize)]
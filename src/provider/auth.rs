@@ -0,0 +1,159 @@
+//! Gets the bearer token used to authenticate with a provider.
+//!
+//! By default this is the static `api_key` from the [`crate::settings::SensitiveSettings`]. If `token_auth` is set, a
+//! short-lived token is fetched instead, e.g., for Azure OpenAI, or an internal gateway, that uses Azure AD (OAuth):
+//! * `client_credentials` - via the OAuth client credentials flow against the `token_url`
+//! * `command` - from the output of a user supplied command, e.g., `az account get-access-token --query accessToken -o tsv`
+//!
+//! Fetched tokens are reused, per provider, until shortly before they expire.
+use crate::settings::{SensitiveSettings, TokenAuth};
+use log::debug;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a token printed by a `command` is reused, as the command does not say when it expires
+const TOKEN_COMMAND_TTL: Duration = Duration::from_secs(5 * 60);
+/// How long before its expiry a token is refreshed
+const TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+/// Holds the last token fetched for each provider, by name, and when it expires
+static TOKEN_CACHE: Mutex<Option<HashMap<String, CachedToken>>> = Mutex::new(None);
+
+#[derive(Debug, Clone)]
+struct CachedToken {
+    token: String,
+    expires_at: Instant,
+}
+/// The response from an OAuth token endpoint
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+}
+
+/// Gets the bearer token for the named provider, from `token_auth` if set, otherwise the `api_key`
+pub(crate) async fn get_bearer_token(
+    provider_name: &str,
+    sensitive: &SensitiveSettings,
+) -> Result<String, Box<dyn Error>> {
+    match &sensitive.token_auth {
+        Some(token_auth) => get_token(provider_name, token_auth).await,
+        None => Ok(sensitive
+            .api_key
+            .as_ref()
+            .ok_or("No API Key, or token_auth, set, please set to user provider service")?
+            .use_key(|key| key.to_string())),
+    }
+}
+/// Gets a token via the [`TokenAuth`] method, reusing the cached token if it has not expired
+async fn get_token(provider_name: &str, token_auth: &TokenAuth) -> Result<String, Box<dyn Error>> {
+    if let Some(cached) = TOKEN_CACHE
+        .lock()
+        .ok()
+        .and_then(|cache| cache.as_ref()?.get(provider_name).cloned())
+        .filter(|cached| cached.expires_at > Instant::now())
+    {
+        return Ok(cached.token);
+    }
+    let (token, lifetime) = match token_auth {
+        TokenAuth::ClientCredentials {
+            token_url,
+            client_id,
+            client_secret,
+            scope,
+        } => {
+            debug!("Fetching token via client credentials from: {}", token_url);
+            let mut form = vec![
+                ("grant_type", "client_credentials".to_string()),
+                ("client_id", client_id.to_string()),
+                (
+                    "client_secret",
+                    client_secret.use_key(|key| key.to_string()),
+                ),
+            ];
+            if let Some(scope) = scope {
+                form.push(("scope", scope.to_string()));
+            }
+            let response = reqwest::Client::new()
+                .post(token_url)
+                .form(&form)
+                .send()
+                .await?
+                .error_for_status()?;
+            let token_response: TokenResponse = response.json().await?;
+            let lifetime = token_response
+                .expires_in
+                .map_or(TOKEN_COMMAND_TTL, Duration::from_secs);
+            (token_response.access_token, lifetime)
+        }
+        TokenAuth::Command { command } => {
+            debug!("Fetching token via command");
+            (run_token_command(command).await?, TOKEN_COMMAND_TTL)
+        }
+    };
+    if let Ok(mut cache) = TOKEN_CACHE.lock() {
+        cache.get_or_insert_with(HashMap::new).insert(
+            provider_name.to_string(),
+            CachedToken {
+                token: token.clone(),
+                expires_at: Instant::now() + lifetime.saturating_sub(TOKEN_EXPIRY_MARGIN),
+            },
+        );
+    }
+    Ok(token)
+}
+/// Runs the command in the shell, returning the token it prints
+async fn run_token_command(command: &str) -> Result<String, Box<dyn Error>> {
+    #[cfg(windows)]
+    let output = tokio::process::Command::new("cmd")
+        .args(["/C", command])
+        .output()
+        .await?;
+    #[cfg(not(windows))]
+    let output = tokio::process::Command::new("sh")
+        .args(["-c", command])
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "Token command failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    let token = String::from_utf8(output.stdout)?.trim().to_string();
+    if token.is_empty() {
+        return Err("Token command printed no token".into());
+    }
+    Ok(token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_response_deserialization() {
+        let json = r#"{"token_type": "Bearer", "expires_in": 3599, "access_token": "abc.def.ghi"}"#;
+        let token_response: TokenResponse = serde_json::from_str(json).unwrap();
+
+        assert_eq!(token_response.access_token, "abc.def.ghi");
+        assert_eq!(token_response.expires_in, Some(3599));
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_run_token_command() {
+        assert_eq!(
+            run_token_command("echo '  a-token  '").await.unwrap(),
+            "a-token"
+        );
+        assert!(run_token_command("exit 1").await.is_err());
+        assert!(run_token_command("true").await.is_err());
+    }
+}
@@ -0,0 +1,280 @@
+//! Estimates the cost of requests made to a provider, according to the pricing configured per service,
+//! and tracks the token usage of each request made during a review.
+use super::api::{ProviderCompletionResponse, TokenUsage};
+use super::models::get_model_info;
+use super::RequestType;
+use crate::settings::{ProviderSettings, ServiceSettings, Settings};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+/// Accumulates the usage and cost of every request made to a provider during the run
+static COST_TRACKER: Mutex<CostBreakdown> = Mutex::new(CostBreakdown::new());
+
+/// The price of a service's tokens, in USD per million tokens. Prompt tokens served from the provider's prompt cache
+/// are priced at `cached_prompt_per_million`, if set, otherwise as any other prompt token.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub(crate) struct ModelPricing {
+    pub(crate) prompt_per_million: f64,
+    pub(crate) completion_per_million: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) cached_prompt_per_million: Option<f64>,
+}
+impl ModelPricing {
+    /// Estimates the cost, in USD, of the passed [`TokenUsage`]
+    pub(crate) fn estimate_cost(&self, usage: &TokenUsage) -> f64 {
+        let uncached_prompt_tokens = usage.prompt_tokens - usage.cached_prompt_tokens;
+        (uncached_prompt_tokens as f64 * self.prompt_per_million
+            + usage.cached_prompt_tokens as f64 * self.get_cached_prompt_per_million()
+            + usage.completion_tokens as f64 * self.completion_per_million)
+            / 1_000_000.0
+    }
+    /// Estimates the saving, in USD, from the prompt tokens of the passed [`TokenUsage`] served from the prompt cache
+    pub(crate) fn estimate_cache_savings(&self, usage: &TokenUsage) -> f64 {
+        usage.cached_prompt_tokens as f64
+            * (self.prompt_per_million - self.get_cached_prompt_per_million())
+            / 1_000_000.0
+    }
+    fn get_cached_prompt_per_million(&self) -> f64 {
+        self.cached_prompt_per_million
+            .unwrap_or(self.prompt_per_million)
+    }
+}
+
+/// The estimated cost, in USD, of a review, broken down by [`RequestType`] and by file
+///
+/// #Fields:
+/// * `total_cost` - The estimated total cost of all requests
+/// * `usage` - The total [`TokenUsage`] of all requests
+/// * `request_types` - The cost of the requests per [`RequestType`], e.g., 'review', 'summarise'
+/// * `files` - The cost of the requests made per reviewed file
+/// * `cache_savings` - The estimated saving from prompt tokens served from the provider's prompt cache
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub(crate) struct CostBreakdown {
+    pub(crate) total_cost: f64,
+    #[serde(default)]
+    pub(crate) cache_savings: f64,
+    pub(crate) usage: TokenUsage,
+    pub(crate) request_types: Vec<RequestCost>,
+    pub(crate) files: Vec<RequestCost>,
+}
+impl CostBreakdown {
+    pub(crate) const fn new() -> Self {
+        Self {
+            total_cost: 0.0,
+            cache_savings: 0.0,
+            usage: TokenUsage::new(),
+            request_types: Vec::new(),
+            files: Vec::new(),
+        }
+    }
+    /// Adds the [`TokenUsage`] and cost of a single request
+    pub(crate) fn add(
+        &mut self,
+        request_type: &RequestType,
+        file: Option<&str>,
+        usage: &TokenUsage,
+        cost: f64,
+    ) {
+        self.total_cost += cost;
+        self.usage.add(usage);
+        add_request_cost(
+            &mut self.request_types,
+            &request_type.to_string(),
+            usage,
+            cost,
+        );
+        if let Some(file) = file {
+            add_request_cost(&mut self.files, file, usage, cost);
+        }
+    }
+    /// Adds the usage and cost of another breakdown, e.g., of a partial review of the same repository
+    pub(crate) fn merge(&mut self, other: &CostBreakdown) {
+        self.total_cost += other.total_cost;
+        self.cache_savings += other.cache_savings;
+        self.usage.add(&other.usage);
+        for (costs, other_costs) in [
+            (&mut self.request_types, &other.request_types),
+            (&mut self.files, &other.files),
+        ] {
+            for other_cost in other_costs {
+                match costs
+                    .iter_mut()
+                    .find(|request_cost| request_cost.name == other_cost.name)
+                {
+                    Some(request_cost) => {
+                        request_cost.num_requests += other_cost.num_requests;
+                        request_cost.usage.add(&other_cost.usage);
+                        request_cost.cost += other_cost.cost;
+                    }
+                    None => costs.push(other_cost.clone()),
+                }
+            }
+        }
+    }
+}
+/// The accumulated usage and cost of the requests made for a named item, e.g., a file or [`RequestType`]
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub(crate) struct RequestCost {
+    pub(crate) name: String,
+    pub(crate) num_requests: i32,
+    pub(crate) usage: TokenUsage,
+    pub(crate) cost: f64,
+}
+/// Adds the usage and cost to the named [`RequestCost`], adding a new one if it doesn't exist
+fn add_request_cost(costs: &mut Vec<RequestCost>, name: &str, usage: &TokenUsage, cost: f64) {
+    match costs
+        .iter_mut()
+        .find(|request_cost| request_cost.name == name)
+    {
+        Some(request_cost) => {
+            request_cost.num_requests += 1;
+            request_cost.usage.add(usage);
+            request_cost.cost += cost;
+        }
+        None => costs.push(RequestCost {
+            name: name.to_string(),
+            num_requests: 1,
+            usage: usage.clone(),
+            cost,
+        }),
+    }
+}
+
+/// Records the [`TokenUsage`] of a provider response against the [`RequestType`] and, if given, the file reviewed.
+/// The service's pricing is used, if configured, otherwise that of its model in the registry, see [`super::models`].
+pub(crate) fn record_usage(
+    settings: &Settings,
+    request_type: &RequestType,
+    file: Option<&str>,
+    provider_settings: &ProviderSettings,
+    service_settings: &ServiceSettings,
+    response: &ProviderCompletionResponse,
+) {
+    let usage = match &response.usage {
+        Some(usage) => usage,
+        None => {
+            debug!(
+                "No token usage returned by provider: {}",
+                provider_settings.name
+            );
+            return;
+        }
+    };
+    let pricing: Option<ModelPricing> = get_pricing(settings, service_settings);
+    let pricing = pricing.as_ref();
+    let cost = pricing.map_or(0.0, |pricing| pricing.estimate_cost(usage));
+
+    if let Ok(mut tracker) = COST_TRACKER.lock() {
+        tracker.add(request_type, file, usage, cost);
+        tracker.cache_savings +=
+            pricing.map_or(0.0, |pricing| pricing.estimate_cache_savings(usage));
+    }
+}
+/// Gets the [`ModelPricing`] of the service, if configured, otherwise that of its model in the registry, if known
+pub(crate) fn get_pricing(
+    settings: &Settings,
+    service_settings: &ServiceSettings,
+) -> Option<ModelPricing> {
+    match &service_settings.pricing {
+        Some(pricing) => Some(pricing.clone()),
+        None => get_model_info(settings, &service_settings.model)
+            .and_then(|model_info| model_info.pricing),
+    }
+}
+/// Gets the estimated total cost, in USD, of the requests made so far
+pub(crate) fn get_total_cost() -> f64 {
+    COST_TRACKER
+        .lock()
+        .map(|tracker| tracker.total_cost)
+        .unwrap_or_default()
+}
+/// Takes the [`CostBreakdown`] accumulated so far, resetting the tracker
+pub(crate) fn take_cost_breakdown() -> CostBreakdown {
+    COST_TRACKER
+        .lock()
+        .map(|mut tracker| std::mem::take(&mut *tracker))
+        .unwrap_or_default()
+}
+/// Logs the [`CostBreakdown`] to the console at info log level
+pub(crate) fn log_cost_breakdown(cost: &CostBreakdown) {
+    info!(
+        "ESTIMATED COST: ${:.4} USD ({} prompt tokens, {} completion tokens)",
+        cost.total_cost, cost.usage.prompt_tokens, cost.usage.completion_tokens
+    );
+    if cost.usage.cached_prompt_tokens > 0 {
+        info!(
+            "  - prompt cache: {} cached prompt tokens, saving ${:.4} USD",
+            cost.usage.cached_prompt_tokens, cost.cache_savings
+        );
+    }
+    for request_type in &cost.request_types {
+        info!(
+            "  - {}: ${:.4} USD over {} requests ({} tokens)",
+            request_type.name,
+            request_type.cost,
+            request_type.num_requests,
+            request_type.usage.total_tokens()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_cost() {
+        let pricing = ModelPricing {
+            prompt_per_million: 10.0,
+            completion_per_million: 30.0,
+            cached_prompt_per_million: None,
+        };
+        let usage = TokenUsage {
+            prompt_tokens: 1_000,
+            completion_tokens: 500,
+            cached_prompt_tokens: 0,
+        };
+
+        assert!((pricing.estimate_cost(&usage) - 0.025).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_estimate_cost_with_cached_prompt_tokens() {
+        let pricing = ModelPricing {
+            prompt_per_million: 10.0,
+            completion_per_million: 30.0,
+            cached_prompt_per_million: Some(5.0),
+        };
+        let usage = TokenUsage {
+            prompt_tokens: 1_000,
+            completion_tokens: 500,
+            cached_prompt_tokens: 800,
+        };
+
+        assert!((pricing.estimate_cost(&usage) - 0.021).abs() < 1e-9);
+        assert!((pricing.estimate_cache_savings(&usage) - 0.004).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_cost_breakdown_add() {
+        let mut breakdown = CostBreakdown::new();
+        let usage = TokenUsage {
+            prompt_tokens: 100,
+            completion_tokens: 50,
+            cached_prompt_tokens: 0,
+        };
+
+        breakdown.add(&RequestType::Review, Some("src/main.rs"), &usage, 0.5);
+        breakdown.add(&RequestType::Review, Some("src/lib.rs"), &usage, 0.25);
+        breakdown.add(&RequestType::Summarise, None, &usage, 0.25);
+
+        assert!((breakdown.total_cost - 1.0).abs() < f64::EPSILON);
+        assert_eq!(breakdown.usage.prompt_tokens, 300);
+        assert_eq!(breakdown.usage.completion_tokens, 150);
+        assert_eq!(breakdown.request_types.len(), 2);
+        assert_eq!(breakdown.request_types[0].name, "review");
+        assert_eq!(breakdown.request_types[0].num_requests, 2);
+        assert_eq!(breakdown.files.len(), 2);
+    }
+}
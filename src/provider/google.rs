@@ -2,16 +2,20 @@ pub(crate) mod gemini {
     use crate::provider::api::{ProviderCompletionResponse, ProviderResponseConverter};
     use crate::provider::prompts::PromptData;
     use crate::provider::{APIProvider, RequestType};
-    use crate::settings::{ProviderSettings, Settings};
+    use crate::settings::{ModelParameters, ProviderSettings, Settings};
 
     use google_generative_ai_rs::v1::api::Client;
     use google_generative_ai_rs::v1::api::PostResult;
-    use google_generative_ai_rs::v1::gemini::request::Request;
+    use google_generative_ai_rs::v1::gemini::request::{GenerationConfig, Request};
     use google_generative_ai_rs::v1::gemini::{Content, Part, Role};
+    use log::{debug, warn};
     use serde_json::json;
 
     use super::data::GeminiResponseConverter;
 
+    /// The timeout, in seconds, if neither `api_timeout` nor a `request_timeouts` entry is set
+    const DEFAULT_API_TIMEOUT: u64 = 30;
+
     /// The Google Gemini public API provider works on the the following URL structure:
     /// - The API URL base - 'https://generativelanguage.googleapis.com/v1'
     /// - The 'models' extension - '/models', i.e., 'gemini-pro'
@@ -21,37 +25,54 @@ pub(crate) mod gemini {
     /// For public API use, the API key is required.
     pub(crate) struct GeminiProvider {
         pub(crate) model: String,
+        pub(crate) parameters: ModelParameters,
     }
 
     #[async_trait::async_trait]
     impl APIProvider for GeminiProvider {
         async fn ask_request_of_provider(
             &self,
-            _request_type: &RequestType,
+            request_type: &RequestType,
             settings: &Settings,
+            provider: &ProviderSettings,
             prompt_data: &PromptData,
         ) -> Result<ProviderCompletionResponse, Box<dyn std::error::Error>> {
-            let provider: &ProviderSettings = settings.get_active_provider()?;
-
             let client = Client::new(
-                settings
-                    .sensitive
+                provider
+                    .get_sensitive(&settings.sensitive)
                     .api_key
                     .as_ref()
                     .ok_or("No API Key set, please set to user provider service")?
                     .use_key(|key| key.to_owned()),
             );
 
-            Ok(ask_request_of_gemini(&self.model.clone(), &client, provider, prompt_data).await?)
+            Ok(ask_request_of_gemini(
+                &self.model.clone(),
+                &self.parameters,
+                &client,
+                provider,
+                request_type,
+                prompt_data,
+            )
+            .await?)
         }
     }
 
     pub(super) async fn ask_request_of_gemini(
         model: &str,
+        parameters: &ModelParameters,
         client: &Client,
         provider: &ProviderSettings,
+        request_type: &RequestType,
         prompt_data: &PromptData,
     ) -> Result<ProviderCompletionResponse, Box<dyn std::error::Error>> {
+        if provider.headers.is_some() {
+            // The google_generative_ai_rs client does not expose its HTTP request
+            warn!(
+                "Custom headers are not supported for provider: {}, and are ignored",
+                provider.name
+            );
+        }
         let prompt_msgs: Vec<serde_json::Value> = prompt_data
             .messages
             .iter()
@@ -73,24 +94,54 @@ pub(crate) mod gemini {
             }],
             tools: vec![],
             safety_settings: vec![],
-            generation_config: None,
+            generation_config: get_generation_config(parameters),
         };
 
         let post_result: PostResult = client
-            .post(provider.api_timeout.unwrap_or(30), &request)
+            .post(
+                provider
+                    .get_api_timeout(request_type)
+                    .unwrap_or(DEFAULT_API_TIMEOUT),
+                &request,
+            )
             .await?;
 
         let converter = GeminiResponseConverter::new(model.to_string());
 
         Ok(converter.to_generic_provider_response(&post_result))
     }
+    /// Gets the [`GenerationConfig`] from the configured [`ModelParameters`], or [`None`] if none are set.
+    ///
+    /// Gemini does not take a 'seed', so, if one is set, the nearest equivalent is applied instead: a single candidate,
+    /// and a temperature of '0.0', unless the temperature is set explicitly.
+    fn get_generation_config(parameters: &ModelParameters) -> Option<GenerationConfig> {
+        if *parameters == ModelParameters::default() {
+            return None;
+        }
+        let is_deterministic = parameters.seed.is_some();
+        if is_deterministic {
+            debug!("Gemini does not support 'seed', applying a single candidate and zero temperature instead");
+        }
+        Some(GenerationConfig {
+            temperature: match parameters.temperature {
+                Some(temperature) => Some(temperature),
+                None if is_deterministic => Some(0.0),
+                None => None,
+            },
+            top_p: parameters.top_p,
+            top_k: None,
+            candidate_count: is_deterministic.then_some(1),
+            max_output_tokens: parameters.max_output_tokens.map(|tokens| tokens as i32),
+            stop_sequences: None,
+        })
+    }
 }
 
 pub(crate) mod vertex_ai {
     use crate::provider::api::ProviderCompletionResponse;
     use crate::provider::prompts::PromptData;
     use crate::provider::{APIProvider, RequestType};
-    use crate::settings::{ProviderSettings, Settings};
+    use crate::settings::{ModelParameters, ProviderSettings, Settings};
 
     use google_generative_ai_rs::v1::api::Client;
     use google_generative_ai_rs::v1::errors::GoogleAPIError;
@@ -99,40 +150,37 @@ pub(crate) mod vertex_ai {
     /// For private API use, application default credentials (ADC) are required. See [`gcp_auth::AuthenticationManager`] for more details.
     pub(crate) struct VertexAiProvider {
         pub(crate) model: String,
+        pub(crate) parameters: ModelParameters,
     }
 
     #[async_trait::async_trait]
     impl APIProvider for VertexAiProvider {
         async fn ask_request_of_provider(
             &self,
-            _request_type: &RequestType,
+            request_type: &RequestType,
             settings: &Settings,
+            provider: &ProviderSettings,
             prompt_data: &PromptData,
         ) -> Result<ProviderCompletionResponse, Box<dyn std::error::Error>> {
-            let provider: &ProviderSettings = settings.get_active_provider()?;
-
-            let region = settings
-                .sensitive
-                .region
-                .as_ref()
-                .ok_or_else(|| GoogleAPIError {
-                    message: format!("No provider region specified for {}", provider.name),
+            let (region, project_id) = provider
+                .get_region_and_project_id(&settings.sensitive)
+                .map_err(|e| GoogleAPIError {
+                    message: e.to_string(),
                     code: None,
                 })?;
-            let project_id =
-                settings
-                    .sensitive
-                    .project_id
-                    .as_ref()
-                    .ok_or_else(|| GoogleAPIError {
-                        message: format!("No provider project_id specified for {}", provider.name),
-                        code: None,
-                    })?;
 
             let client =
                 Client::new_from_region_project_id(region.to_string(), project_id.to_string());
 
-            Ok(ask_request_of_gemini(&self.model.clone(), &client, provider, prompt_data).await?)
+            Ok(ask_request_of_gemini(
+                &self.model.clone(),
+                &self.parameters,
+                &client,
+                provider,
+                request_type,
+                prompt_data,
+            )
+            .await?)
         }
     }
     impl VertexAiProvider {}
@@ -141,10 +189,11 @@ pub(crate) mod vertex_ai {
 /// The data structures for the Google API response
 pub(super) mod data {
     use google_generative_ai_rs::v1::api::PostResult;
+    use google_generative_ai_rs::v1::gemini::response::UsageMetadata;
 
     use crate::provider::api::{
         ProviderCompletionResponse, ProviderResponseChoice, ProviderResponseConverter,
-        ProviderResponseMessage,
+        ProviderResponseMessage, TokenUsage,
     };
     // Implementation of ProviderResponseConverter for the Gemini FM.
     pub(crate) struct GeminiResponseConverter {
@@ -159,11 +208,13 @@ pub(super) mod data {
             google_response: &PostResult,
         ) -> ProviderCompletionResponse {
             let mut messages: Vec<ProviderResponseMessage> = vec![];
+            let mut usage: Option<TokenUsage> = None;
             // TODO: If this is a streamed response, it has much finer-tuned harm handling
             // than the REST response (right now). It may be rejecting the prompt input as harmful
             // and stopping the feed. I need to handle this and flag it to the user.
             match google_response {
                 PostResult::Rest(response) => {
+                    usage = response.usage_metadata.as_ref().map(convert_usage_metadata);
                     for candidate in &response.candidates {
                         for part in &candidate.content.parts {
                             messages.push(ProviderResponseMessage {
@@ -174,6 +225,10 @@ pub(super) mod data {
                 }
                 PostResult::Streamed(streamed_response) => {
                     for gemini_completion_response in &streamed_response.streamed_candidates {
+                        // The usage is cumulative over the stream, so the last reported is the total
+                        if let Some(usage_metadata) = &gemini_completion_response.usage_metadata {
+                            usage = Some(convert_usage_metadata(usage_metadata));
+                        }
                         for candidate in &gemini_completion_response.candidates {
                             for part in &candidate.content.parts {
                                 messages.push(ProviderResponseMessage {
@@ -196,7 +251,16 @@ pub(super) mod data {
                             .join("\n"),
                     },
                 }],
+                usage,
             }
         }
     }
+    /// Converts the Gemini [`UsageMetadata`] into a generic [`TokenUsage`]
+    fn convert_usage_metadata(usage_metadata: &UsageMetadata) -> TokenUsage {
+        TokenUsage {
+            prompt_tokens: usage_metadata.prompt_token_count as i64,
+            completion_tokens: usage_metadata.candidates_token_count as i64,
+            cached_prompt_tokens: 0,
+        }
+    }
 }
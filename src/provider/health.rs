@@ -0,0 +1,186 @@
+//! Checks the connectivity and health of each configured provider and service, so that a misconfiguration is
+//! diagnosed before a long review run fails part way through.
+//!
+//! Each service is sent a trivial prompt, once, and the outcome, e.g., unreachable, failed authentication, or
+//! unknown model, is reported along with the latency.
+use super::api::{ProviderCompletionMessage, ProviderMessageRole};
+use super::prompts::PromptData;
+use super::retry::ProviderRequestError;
+use super::{create_api_provider, extract_http_status, RequestType};
+use crate::settings::{ProviderSettings, ServiceSettings, Settings};
+use std::error::Error;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// The trivial prompt sent to each service
+const CHECK_PROMPT: &str = "Reply with the single word: OK";
+
+/// The outcome of checking a provider's service
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum CheckStatus {
+    Ok,
+    Unreachable(String),
+    AuthenticationFailed(String),
+    ModelUnavailable(String),
+    RateLimited(String),
+    Failed(String),
+}
+impl CheckStatus {
+    /// Classifies an error returned from a provider into a [`CheckStatus`]
+    fn from_error(error: &(dyn Error + 'static)) -> Self {
+        let message = error.to_string();
+        let status = match error.downcast_ref::<ProviderRequestError>() {
+            Some(request_error) => match request_error.status {
+                Some(status) => Some(status),
+                None => return CheckStatus::Unreachable(message),
+            },
+            None => extract_http_status(&message),
+        };
+        match status {
+            Some(401 | 403) => CheckStatus::AuthenticationFailed(message),
+            Some(404) => CheckStatus::ModelUnavailable(message),
+            Some(429) => CheckStatus::RateLimited(message),
+            _ => CheckStatus::Failed(message),
+        }
+    }
+    pub(crate) fn is_ok(&self) -> bool {
+        matches!(self, CheckStatus::Ok | CheckStatus::RateLimited(_))
+    }
+}
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckStatus::Ok => write!(f, "OK"),
+            CheckStatus::Unreachable(e) => write!(f, "UNREACHABLE - {}", e),
+            CheckStatus::AuthenticationFailed(e) => write!(f, "AUTHENTICATION FAILED - {}", e),
+            CheckStatus::ModelUnavailable(e) => write!(f, "MODEL UNAVAILABLE - {}", e),
+            CheckStatus::RateLimited(e) => write!(f, "REACHABLE, BUT RATE LIMITED - {}", e),
+            CheckStatus::Failed(e) => write!(f, "FAILED - {}", e),
+        }
+    }
+}
+
+/// The result of checking a single service of a provider
+#[derive(Debug, Clone)]
+pub(crate) struct ProviderCheck {
+    pub(crate) provider: String,
+    pub(crate) service: String,
+    pub(crate) model: String,
+    pub(crate) is_active: bool,
+    pub(crate) status: CheckStatus,
+    pub(crate) latency: Duration,
+}
+impl fmt::Display for ProviderCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{} / {} ({}): {} [{} ms]",
+            if self.is_active { "* " } else { "  " },
+            self.provider,
+            self.service,
+            self.model,
+            self.status,
+            self.latency.as_millis()
+        )
+    }
+}
+
+/// Checks every service of each configured provider, or only of the named provider, if given
+pub(crate) async fn check_providers(
+    settings: &Settings,
+    provider_name: Option<&str>,
+) -> Result<Vec<ProviderCheck>, Box<dyn Error>> {
+    let providers: Vec<&ProviderSettings> = settings
+        .providers
+        .iter()
+        .filter(|provider| match provider_name {
+            Some(name) => provider.name == name,
+            None => true,
+        })
+        .collect();
+    if providers.is_empty() {
+        return Err(format!(
+            "No provider configured with name: {}",
+            provider_name.unwrap_or_default()
+        )
+        .into());
+    }
+    let active_provider = settings.get_active_provider().ok();
+
+    let mut checks: Vec<ProviderCheck> = Vec::new();
+    for provider in providers {
+        let active_service = provider.get_active_service().ok();
+        for service in &provider.services {
+            let is_active = active_provider.is_some_and(|active| active.name == provider.name)
+                && active_service.is_some_and(|active| active.name == service.name);
+            checks.push(check_service(settings, provider, service, is_active).await);
+        }
+    }
+    Ok(checks)
+}
+/// Sends the trivial prompt to the service, timing the response
+async fn check_service(
+    settings: &Settings,
+    provider: &ProviderSettings,
+    service: &ServiceSettings,
+    is_active: bool,
+) -> ProviderCheck {
+    let prompt_data = PromptData {
+        id: None,
+        messages: vec![ProviderCompletionMessage {
+            role: ProviderMessageRole::User,
+            content: CHECK_PROMPT.to_string(),
+        }],
+    };
+    let start = Instant::now();
+    let status = match create_api_provider(provider, service) {
+        Ok(provider_handler) => match provider_handler
+            .ask_request_of_provider(&RequestType::Summarise, settings, provider, &prompt_data)
+            .await
+        {
+            Ok(response) if response.choices.is_empty() => {
+                CheckStatus::Failed("No choices returned in the response".to_string())
+            }
+            Ok(_) => CheckStatus::Ok,
+            Err(e) => CheckStatus::from_error(e.as_ref()),
+        },
+        Err(e) => CheckStatus::Failed(e.to_string()),
+    };
+
+    ProviderCheck {
+        provider: provider.name.clone(),
+        service: service.name.clone(),
+        model: service.model.clone(),
+        is_active,
+        status,
+        latency: start.elapsed(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_status_from_error() {
+        let error = |status: Option<u16>| ProviderRequestError {
+            message: "error".to_string(),
+            status,
+            retry_after: None,
+        };
+        assert!(matches!(
+            CheckStatus::from_error(&error(None)),
+            CheckStatus::Unreachable(_)
+        ));
+        assert!(matches!(
+            CheckStatus::from_error(&error(Some(401))),
+            CheckStatus::AuthenticationFailed(_)
+        ));
+        assert!(matches!(
+            CheckStatus::from_error(&error(Some(404))),
+            CheckStatus::ModelUnavailable(_)
+        ));
+        assert!(CheckStatus::from_error(&error(Some(429))).is_ok());
+        assert!(!CheckStatus::from_error(&error(Some(500))).is_ok());
+    }
+}
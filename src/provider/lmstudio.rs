@@ -1,70 +1,94 @@
 //!
 use super::api::{
     ProviderCompletionResponse, ProviderResponseChoice, ProviderResponseConverter,
-    ProviderResponseMessage,
+    ProviderResponseMessage, TokenUsage,
 };
-use super::{APIProvider, RequestType};
+use super::retry::ProviderRequestError;
+use super::{with_custom_headers, APIProvider, RequestType};
 use crate::provider::prompts::PromptData;
-use crate::settings::{ProviderSettings, Settings};
+use crate::settings::{ModelParameters, ProviderSettings, Settings};
 use reqwest::Client;
 use serde::Deserialize;
 use serde_json::json;
+use std::time::Duration;
 
-pub(super) struct LMStudioProvider {}
+pub(super) struct LMStudioProvider {
+    pub(super) model: String,
+    pub(super) parameters: ModelParameters,
+}
 
 #[async_trait::async_trait]
 impl APIProvider for LMStudioProvider {
     async fn ask_request_of_provider(
         &self,
-        _request_type: &RequestType,
+        request_type: &RequestType,
         settings: &Settings,
+        provider: &ProviderSettings,
         prompt_data: &PromptData,
     ) -> Result<ProviderCompletionResponse, Box<dyn std::error::Error>> {
-        let provider: &ProviderSettings = settings.get_active_provider()?;
-
-        // TODO set a timeout on the client
-        // let client: Client = Client::builder().timeout(settings).build()?;
-        let client: Client = Client::builder().build()?;
+        let mut client_builder = Client::builder();
+        if let Some(timeout) = provider.get_api_timeout(request_type) {
+            client_builder = client_builder.timeout(Duration::from_secs(timeout));
+        }
+        let client: Client = client_builder.build()?;
 
-        let response: Result<reqwest::Response, reqwest::Error> = client
-            .post(provider.api_url.clone())
+        let res = with_custom_headers(client.post(provider.api_url.clone()), provider)
             .header("Content-Type", "application/json")
-            .json(&json!({
-                "messages": prompt_data.messages,
-                "max_tokens": -1,
-                "temperature": 0.7,
-                "stream": false,
-            }))
+            .json(&self.build_request_body(provider, prompt_data))
             .send()
-            .await;
-        match response {
-            Ok(res) => {
-                if res.status().is_success() {
-                    match res.json::<LMStudioCompletionResponse>().await {
-                        Ok(data) => {
-                            Ok(LMStudioResponseConverter.to_generic_provider_response(&data))
-                        }
-                        Err(e) => Err(format!("Failed to deserialize response: {}", e).into()),
-                    }
-                } else {
-                    Err(format!("Server returned error: {}", res.status()).into())
-                }
-            }
-            Err(e) => {
-                if e.is_timeout() {
-                    Err("Network request timed out".into())
-                } else if e.is_status() {
-                    Err(format!("Server returned error: {}", e.status().unwrap()).into())
-                } else {
-                    Err(format!("Network request failed: {}", e).into())
-                }
-            }
+            .await
+            .map_err(ProviderRequestError::from)?;
+        if !res.status().is_success() {
+            return Err(Box::new(
+                ProviderRequestError::from_response(&provider.name, res).await,
+            ));
+        }
+        match res.json::<LMStudioCompletionResponse>().await {
+            Ok(data) => Ok(LMStudioResponseConverter.to_generic_provider_response(&data)),
+            Err(e) => Err(format!("Failed to deserialize response: {}", e).into()),
+        }
+    }
+}
+impl LMStudioProvider {
+    /// Builds the request body, with the [`ModelParameters`] that are set. The 'max_tokens' is the service's
+    /// `max_output_tokens`, else the provider's `max_tokens`, else -1, which means no limit.
+    fn build_request_body(
+        &self,
+        provider: &ProviderSettings,
+        prompt_data: &PromptData,
+    ) -> serde_json::Value {
+        let mut body = json!({
+            "model": self.model,
+            "messages": prompt_data.messages,
+            "max_tokens": self
+                .parameters
+                .max_output_tokens
+                .or(provider.max_tokens)
+                .unwrap_or(-1),
+            "stream": false,
+        });
+        if let Some(temperature) = self.parameters.temperature {
+            body["temperature"] = json!(temperature);
+        }
+        if let Some(top_p) = self.parameters.top_p {
+            body["top_p"] = json!(top_p);
         }
+        if let Some(seed) = self.parameters.seed {
+            body["seed"] = json!(seed);
+        }
+        body
     }
 }
 #[derive(Debug, Deserialize)]
 pub struct LMStudioCompletionResponse {
     pub choices: Vec<Choice>,
+    pub usage: Option<Usage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Usage {
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -95,6 +119,11 @@ impl ProviderResponseConverter<LMStudioCompletionResponse> for LMStudioResponseC
                 .iter()
                 .map(convert_chat_choice_to_provider_choice)
                 .collect(),
+            usage: response.usage.as_ref().map(|usage| TokenUsage {
+                prompt_tokens: usage.prompt_tokens,
+                completion_tokens: usage.completion_tokens,
+                cached_prompt_tokens: 0,
+            }),
         }
     }
 }
@@ -108,3 +137,45 @@ fn convert_chat_message_to_provider_message(chat_message: &Message) -> ProviderR
         content: chat_message.content.clone(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::api::{ProviderCompletionMessage, ProviderMessageRole};
+
+    #[test]
+    fn test_build_request_body() {
+        let provider: ProviderSettings = serde_json::from_str(
+            r#"{
+            "name": "local",
+            "services": [],
+            "default_service": "lm-studio",
+            "api_url": "http://localhost:1234/v1/chat/completions",
+            "max_tokens": 4096
+        }"#,
+        )
+        .unwrap();
+        let prompt_data = PromptData {
+            id: None,
+            messages: vec![ProviderCompletionMessage {
+                role: ProviderMessageRole::User,
+                content: "Review this".to_string(),
+            }],
+        };
+        let mut lm_studio = LMStudioProvider {
+            model: "deepseek-coder-6.7B-instruct".to_string(),
+            parameters: ModelParameters::default(),
+        };
+
+        let body = lm_studio.build_request_body(&provider, &prompt_data);
+        assert_eq!(body["model"], "deepseek-coder-6.7B-instruct");
+        assert_eq!(body["max_tokens"], 4096);
+        assert!(body.get("temperature").is_none());
+
+        lm_studio.parameters.max_output_tokens = Some(1024);
+        lm_studio.parameters.temperature = Some(0.5);
+        let body = lm_studio.build_request_body(&provider, &prompt_data);
+        assert_eq!(body["max_tokens"], 1024);
+        assert_eq!(body["temperature"], 0.5);
+    }
+}
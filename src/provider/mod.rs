@@ -2,14 +2,25 @@
 //!  Handles the access to the LLM with utility functions for specified actions
 //!
 pub(crate) mod api;
+pub(crate) mod auth;
+pub(crate) mod cost;
 pub(crate) mod google;
+pub(crate) mod health;
 pub(crate) mod lmstudio;
+pub(crate) mod models;
 pub(crate) mod openai;
 pub(crate) mod prompts;
+pub(crate) mod rate_limit;
+pub(crate) mod replay;
+pub(crate) mod retry;
+pub(crate) mod scheduler;
+pub(crate) mod transcript;
 use crate::provider::prompts::PromptData;
 use crate::settings::{ProviderSettings, ServiceSettings, Settings};
+use std::fmt;
 
 use self::api::ProviderCompletionResponse;
+use self::retry::RetryPolicy;
 
 /// Sends text contents to an LLM agent to evaluate according to the prompt passed to it.
 ///
@@ -23,17 +34,70 @@ use self::api::ProviderCompletionResponse;
 ///
 /// * A response from the LLM ['ProviderResponseMessage']
 ///
+/// The token usage of the response is recorded for the cost estimate, against the file named in `prompt_data.id`, if any.
+/// Failed requests are retried according to the provider's [`RetryPolicy`]. Each attempt is written to the transcript, if enabled.
 pub(crate) async fn review_or_summarise(
     request_type: RequestType,
     settings: &Settings,
     provider_settings: &ProviderSettings,
     prompt_data: &PromptData,
 ) -> Result<ProviderCompletionResponse, Box<dyn std::error::Error>> {
-    match create_api_provider(provider_settings) {
+    review_or_summarise_with_service(
+        request_type,
+        settings,
+        provider_settings,
+        provider_settings.get_active_service()?,
+        prompt_data,
+    )
+    .await
+}
+/// As [`review_or_summarise`], but with the passed service of the provider, rather than its active service
+pub(crate) async fn review_or_summarise_with_service(
+    request_type: RequestType,
+    settings: &Settings,
+    provider_settings: &ProviderSettings,
+    service_settings: &ServiceSettings,
+    prompt_data: &PromptData,
+) -> Result<ProviderCompletionResponse, Box<dyn std::error::Error>> {
+    match create_api_provider(provider_settings, service_settings) {
         Ok(provider_handler) => {
-            provider_handler
-                .ask_request_of_provider(&request_type, settings, prompt_data)
-                .await
+            let provider_handler = &provider_handler;
+            let response = RetryPolicy::from_provider_settings(provider_settings)
+                .retry(
+                    &format!("{} request to {}", request_type, provider_settings.name),
+                    move || async move {
+                        rate_limit::acquire(provider_settings, prompt_data.estimate_tokens()).await;
+                        let result = provider_handler
+                            .ask_request_of_provider(
+                                &request_type,
+                                settings,
+                                provider_settings,
+                                prompt_data,
+                            )
+                            .await;
+                        transcript::record(
+                            settings,
+                            provider_settings,
+                            &request_type,
+                            prompt_data,
+                            &result,
+                        );
+                        result
+                    },
+                )
+                .await?;
+            if let Some(usage) = &response.usage {
+                rate_limit::record_usage(provider_settings, usage);
+            }
+            cost::record_usage(
+                settings,
+                &request_type,
+                prompt_data.id.as_deref(),
+                provider_settings,
+                service_settings,
+                &response,
+            );
+            Ok(response)
         }
         Err(err) => Err(Box::new(std::io::Error::new(
             std::io::ErrorKind::Other,
@@ -41,21 +105,29 @@ pub(crate) async fn review_or_summarise(
         ))),
     }
 }
-/// Creates an APIProvider according to provider_settings.name
+/// Creates an APIProvider according to provider_settings.name, for the model of the passed service
 fn create_api_provider(
     provider_settings: &ProviderSettings,
+    service_settings: &ServiceSettings,
 ) -> Result<Box<dyn APIProvider>, Box<dyn std::error::Error>> {
     match provider_settings.name.to_lowercase().as_str() {
         "openai" => Ok(Box::new(openai::OpenAIProvider {
-            model: provider_settings.get_active_service()?.model.to_string(),
+            model: service_settings.model.to_string(),
+            parameters: service_settings.parameters.clone(),
         })),
         "google" => Ok(Box::new(google::gemini::GeminiProvider {
-            model: provider_settings.get_active_service()?.model.to_string(),
+            model: service_settings.model.to_string(),
+            parameters: service_settings.parameters.clone(),
         })),
         "vertex-ai" => Ok(Box::new(google::vertex_ai::VertexAiProvider {
-            model: provider_settings.get_active_service()?.model.to_string(),
+            model: service_settings.model.to_string(),
+            parameters: service_settings.parameters.clone(),
         })),
-        "local" => Ok(Box::new(lmstudio::LMStudioProvider {})),
+        "local" => Ok(Box::new(lmstudio::LMStudioProvider {
+            model: service_settings.model.to_string(),
+            parameters: service_settings.parameters.clone(),
+        })),
+        "replay" => Ok(Box::new(replay::ReplayProvider {})),
         _ => Err(Box::new(std::io::Error::new(
             std::io::ErrorKind::Other,
             format!("Unsupported provider: {}", provider_settings.name),
@@ -70,15 +142,43 @@ trait APIProvider {
         &self,
         request_type: &RequestType,
         settings: &Settings,
+        provider: &ProviderSettings,
         prompt_data: &PromptData,
     ) -> Result<ProviderCompletionResponse, Box<dyn std::error::Error>>;
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub(crate) enum RequestType {
     Review,
     Summarise,
+    Plan,
+}
+impl fmt::Display for RequestType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                RequestType::Review => "review",
+                RequestType::Summarise => "summarise",
+                RequestType::Plan => "plan",
+            }
+        )
+    }
 }
 
+/// Attaches the custom headers configured for the provider, if any, to the request
+pub(crate) fn with_custom_headers(
+    mut request: reqwest::RequestBuilder,
+    provider_settings: &ProviderSettings,
+) -> reqwest::RequestBuilder {
+    if let Some(headers) = &provider_settings.headers {
+        for (name, value) in headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+    }
+    request
+}
 /// Returns the provider and model from the settings file
 pub(crate) fn get_service_and_model(settings: &Settings) -> Option<String> {
     let provider: &ProviderSettings = get_provider(settings);
@@ -113,6 +213,8 @@ enum HttpErrorCode {
     Unauthorized = 401,
     Forbidden = 403,
     NotFound = 404,
+    RequestTimeout = 408,
+    TooManyRequests = 429,
     InternalServerError = 500,
     BadGateway = 502,
     ServiceUnavailable = 503,
@@ -133,6 +235,12 @@ fn extract_http_status(error_message: &str) -> Option<u16> {
     if error_message.contains("404") {
         return Some(HttpErrorCode::NotFound as u16);
     }
+    if error_message.contains("408") {
+        return Some(HttpErrorCode::RequestTimeout as u16);
+    }
+    if error_message.contains("429") {
+        return Some(HttpErrorCode::TooManyRequests as u16);
+    }
     if error_message.contains("500") {
         return Some(HttpErrorCode::InternalServerError as u16);
     }
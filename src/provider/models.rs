@@ -0,0 +1,118 @@
+//! A registry of the context window, maximum output tokens, and pricing, of known models, so that the limits and cost
+//! of a request can be computed for the model of the configured service, rather than guessed.
+//!
+//! The built-in registry is extended, or overridden, by the `models` in the [`Settings`]. A model is matched by its
+//! exact name, otherwise by the longest registered name it starts with, e.g., 'gpt-4-1106-preview' matches 'gpt-4'
+//! if it is not registered itself.
+use super::cost::ModelPricing;
+use super::prompts::PromptData;
+use crate::settings::Settings;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+const BUILT_IN_MODELS: &str = include_str!("../provider/specification/models.json");
+
+/// The limits, and pricing, of a model
+///
+/// #Fields:
+/// * `model` - The name of the model, as in `ServiceSettings.model`, e.g., 'gpt-4-1106-preview'
+/// * `context_window` - The maximum number of tokens, prompt and response, in a request
+/// * `max_output_tokens` - The maximum number of tokens the model can generate, if less than the context window
+/// * `pricing` - The [`ModelPricing`] used if the service has none configured
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub(crate) struct ModelInfo {
+    pub(crate) model: String,
+    pub(crate) context_window: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) max_output_tokens: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) pricing: Option<ModelPricing>,
+}
+impl ModelInfo {
+    /// The number of tokens available for the prompt, leaving room for the largest response
+    pub(crate) fn max_prompt_tokens(&self) -> u64 {
+        self.context_window
+            .saturating_sub(self.max_output_tokens.unwrap_or_default())
+    }
+}
+
+/// Gets the [`ModelInfo`] for the model, from the user's `models`, then the built-in registry, if known
+pub(crate) fn get_model_info(settings: &Settings, model: &str) -> Option<ModelInfo> {
+    find_model_info(&settings.models, model).or_else(|| {
+        let built_in: Vec<ModelInfo> = serde_json::from_str(BUILT_IN_MODELS)
+            .expect("The built-in model registry should be valid");
+        find_model_info(&built_in, model)
+    })
+}
+/// Warns if the prompt is estimated to exceed the context window of the model, as the request may fail, or be truncated
+pub(crate) fn warn_if_exceeds_context_window(
+    settings: &Settings,
+    model: &str,
+    prompt_data: &PromptData,
+) {
+    if let Some(model_info) = get_model_info(settings, model) {
+        let prompt_tokens = prompt_data.estimate_tokens();
+        if prompt_tokens > model_info.max_prompt_tokens() {
+            warn!(
+                "The prompt for: {}, of ~{} tokens, may exceed the {} tokens available for the prompt with model: {}, and be truncated or fail",
+                prompt_data.id.as_deref().unwrap_or_default(),
+                prompt_tokens,
+                model_info.max_prompt_tokens(),
+                model
+            );
+        }
+    }
+}
+/// Trims the prompt to the tokens available for the prompt with the model, if known, rather than let the provider reject
+/// the request, see [`PromptData::trim_to`]. Returns a description of each trim made, if any.
+pub(crate) fn trim_to_context_window(
+    settings: &Settings,
+    model: &str,
+    prompt_data: &mut PromptData,
+) -> Vec<String> {
+    let Some(model_info) = get_model_info(settings, model) else {
+        return Vec::new();
+    };
+    let trims = prompt_data.trim_to(model_info.max_prompt_tokens());
+    if !trims.is_empty() {
+        warn!(
+            "The prompt for: {} exceeds the {} tokens available for the prompt with model: {}, so was trimmed: {}",
+            prompt_data.id.as_deref().unwrap_or_default(),
+            model_info.max_prompt_tokens(),
+            model,
+            trims.join(", ")
+        );
+    }
+    trims
+}
+/// Finds the exact match for the model, otherwise the longest registered name that the model starts with
+fn find_model_info(models: &[ModelInfo], model: &str) -> Option<ModelInfo> {
+    models
+        .iter()
+        .find(|model_info| model_info.model == model)
+        .or_else(|| {
+            models
+                .iter()
+                .filter(|model_info| model.starts_with(&model_info.model))
+                .max_by_key(|model_info| model_info.model.len())
+        })
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_model_info() {
+        let built_in: Vec<ModelInfo> = serde_json::from_str(BUILT_IN_MODELS).unwrap();
+
+        let exact = find_model_info(&built_in, "gpt-4-1106-preview").unwrap();
+        assert_eq!(exact.context_window, 128000);
+        assert_eq!(exact.max_prompt_tokens(), 128000 - 4096);
+
+        let by_prefix = find_model_info(&built_in, "gpt-4-32k-0613").unwrap();
+        assert_eq!(by_prefix.model, "gpt-4-32k");
+        assert!(find_model_info(&built_in, "unknown-model").is_none());
+    }
+}
@@ -2,32 +2,30 @@
 use super::{
     api::{
         ProviderCompletionMessage, ProviderMessageRole, ProviderResponseChoice,
-        ProviderResponseMessage,
+        ProviderResponseMessage, TokenUsage,
     },
     APIProvider, RequestType,
 };
 use crate::provider::prompts::PromptData;
 use crate::provider::{
     api::{ProviderCompletionResponse, ProviderMessageConverter, ProviderResponseConverter},
-    extract_http_status, HttpErrorCode,
+    auth::get_bearer_token,
+    retry::ProviderRequestError,
+    with_custom_headers,
 };
-use crate::settings::Settings;
-use log::{info, warn};
-use openai_api_rs::v1::{
-    api::Client,
-    chat_completion::{
-        ChatCompletionChoice, ChatCompletionMessage, ChatCompletionMessageForResponse,
-        ChatCompletionRequest, ChatCompletionResponse, Content, MessageRole,
-    },
+use crate::settings::{ModelParameters, ProviderSettings, Settings};
+use openai_api_rs::v1::chat_completion::{
+    ChatCompletionChoice, ChatCompletionMessage, ChatCompletionMessageForResponse,
+    ChatCompletionRequest, ChatCompletionResponse, Content, MessageRole,
 };
+use reqwest::Client;
 use serde_json::json;
+use std::time::Duration;
 
-/// Holds a consistent 'seed' value, see https://cookbook.openai.com/examples/deterministic_outputs_with_the_seed_parameter
-const SEED_VAL: i64 = 1234;
-
-/// Creates an OpenAI API provider, uses the openai_api_rs crate
+/// Creates an OpenAI API provider, uses the openai_api_rs crate for the request and response types
 pub(super) struct OpenAIProvider {
     pub(super) model: String,
+    pub(super) parameters: ModelParameters,
 }
 #[async_trait::async_trait]
 impl APIProvider for OpenAIProvider {
@@ -35,20 +33,23 @@ impl APIProvider for OpenAIProvider {
         &self,
         request_type: &RequestType,
         settings: &Settings,
+        provider: &ProviderSettings,
         prompt_data: &PromptData,
     ) -> Result<ProviderCompletionResponse, Box<dyn std::error::Error>> {
-        let key = settings
-            .sensitive
-            .api_key
-            .as_ref()
-            .ok_or("No API Key set, please set to user provider service")?
-            .use_key(|key| key.to_string());
-
-        let client: Client = Client::new(key);
+        let sensitive = provider.get_sensitive(&settings.sensitive);
+        let key = get_bearer_token(&provider.name, sensitive).await?;
+
         let completion_msgs = OpenAIMessageConverter.convert_messages(&prompt_data.messages);
         let req = self.build_chat_completion_request(request_type, completion_msgs);
 
-        self.attempt_api_request(&client, &req, settings).await
+        self.send_api_request(
+            provider,
+            request_type,
+            &key,
+            sensitive.org_id.as_deref(),
+            &req,
+        )
+        .await
     }
 }
 
@@ -60,55 +61,76 @@ impl OpenAIProvider {
     ) -> ChatCompletionRequest {
         let mut request = ChatCompletionRequest::new(self.model.to_string(), completion_msgs);
 
-        if self.model.contains("preview") || self.model.contains("turbo") {
-            // Apply 'seed' for both 'Summarise' and 'Review'
-            request = request.seed(SEED_VAL);
+        if let Some(temperature) = self.parameters.temperature {
+            request = request.temperature(f64::from(temperature));
+        }
+        if let Some(top_p) = self.parameters.top_p {
+            request = request.top_p(f64::from(top_p));
+        }
+        if let Some(max_output_tokens) = self.parameters.max_output_tokens {
+            request = request.max_tokens(max_output_tokens);
+        }
+        // Apply 'seed' for both 'Summarise' and 'Review', see https://cookbook.openai.com/examples/deterministic_outputs_with_the_seed_parameter
+        if let Some(seed) = self.parameters.seed {
+            request = request.seed(seed);
+        }
 
-            // Apply 'response_format' only for 'Review'
-            if let RequestType::Review = request_type {
-                let res_format = json!({ "type": "json_object" });
-                request = request.response_format(res_format);
-            }
+        // Apply 'response_format' only for 'Review'
+        if (self.model.contains("preview") || self.model.contains("turbo"))
+            && matches!(request_type, RequestType::Review)
+        {
+            let res_format = json!({ "type": "json_object" });
+            request = request.response_format(res_format);
         }
 
         request
     }
-
-    async fn attempt_api_request(
+    /// Sends a single request to the OpenAI API. Failures are returned as a [`ProviderRequestError`], with the
+    /// HTTP status and any 'retry-after' header, so the caller's [`super::retry::RetryPolicy`] can decide whether to retry.
+    async fn send_api_request(
         &self,
-        client: &openai_api_rs::v1::api::Client,
+        provider: &ProviderSettings,
+        request_type: &RequestType,
+        key: &str,
+        org_id: Option<&str>,
         req: &ChatCompletionRequest,
-        settings: &Settings,
     ) -> Result<ProviderCompletionResponse, Box<dyn std::error::Error>> {
-        let max_retries = settings
-            .get_active_provider()
-            .map_or(0, |provider_settings| {
-                provider_settings.max_retries.unwrap_or(0)
-            });
-
-        let mut attempts = 0;
-        while attempts < max_retries {
-            match client.chat_completion(req.clone()) {
-                Ok(openai_res) => {
-                    return Ok(OpenAIResponseConverter.to_generic_provider_response(&openai_res));
-                }
-                Err(openai_err) => {
-                    attempts += 1;
-                    if let Some(err_code) = extract_http_status(&openai_err.message) {
-                        if err_code == HttpErrorCode::BadGateway as u16 {
-                            warn!(
-                                "Received 502 error, retrying... (Attempt {} of {})",
-                                attempts, max_retries
-                            );
-                            info!("Retrying request to OpenAI API.");
-                            continue;
-                        }
-                    }
-                    return Err(format!("OpenAI API request failed: {}", openai_err).into());
+        let mut client_builder = Client::builder();
+        if let Some(timeout) = provider.get_api_timeout(request_type) {
+            client_builder = client_builder.timeout(Duration::from_secs(timeout));
+        }
+        let client: Client = client_builder.build()?;
+        let mut request = with_custom_headers(client.post(&provider.api_url), provider)
+            .bearer_auth(key)
+            .json(req);
+        if let Some(org_id) = org_id {
+            request = request.header("OpenAI-Organization", org_id);
+        }
+        let res = request.send().await.map_err(ProviderRequestError::from)?;
+        if !res.status().is_success() {
+            return Err(Box::new(
+                ProviderRequestError::from_response(&provider.name, res).await,
+            ));
+        }
+        let res_json: serde_json::Value = res
+            .json()
+            .await
+            .map_err(|e| format!("Failed to deserialize OpenAI response: {}", e))?;
+        match serde_json::from_value::<ChatCompletionResponse>(res_json.clone()) {
+            Ok(openai_res) => {
+                let mut response =
+                    OpenAIResponseConverter.to_generic_provider_response(&openai_res);
+                // Not in the openai_api_rs response type, so is read from the raw response
+                if let (Some(usage), Some(cached_tokens)) = (
+                    response.usage.as_mut(),
+                    res_json["usage"]["prompt_tokens_details"]["cached_tokens"].as_i64(),
+                ) {
+                    usage.cached_prompt_tokens = cached_tokens;
                 }
+                Ok(response)
             }
+            Err(e) => Err(format!("Failed to deserialize OpenAI response: {}", e).into()),
         }
-        Err(format!("OpenAI API request failed after {} attempts", max_retries).into())
     }
 }
 
@@ -163,6 +185,11 @@ impl ProviderResponseConverter<ChatCompletionResponse> for OpenAIResponseConvert
                 .iter()
                 .map(convert_chat_choice_to_provider_choice)
                 .collect(),
+            usage: Some(TokenUsage {
+                prompt_tokens: response.usage.prompt_tokens as i64,
+                completion_tokens: response.usage.completion_tokens as i64,
+                cached_prompt_tokens: 0,
+            }),
         }
     }
 }
@@ -213,9 +240,9 @@ mod tests {
     #[test]
     fn test_openai_response_converter() {
         let usage = Usage {
-            prompt_tokens: 0,
-            completion_tokens: 0,
-            total_tokens: 0,
+            prompt_tokens: 10,
+            completion_tokens: 5,
+            total_tokens: 15,
         };
         let converter = OpenAIResponseConverter;
         let response = ChatCompletionResponse {
@@ -245,5 +272,13 @@ mod tests {
             converted_response.choices[0].message.content,
             "Test content"
         );
+        assert_eq!(
+            converted_response.usage,
+            Some(TokenUsage {
+                prompt_tokens: 10,
+                completion_tokens: 5,
+                cached_prompt_tokens: 0,
+            })
+        );
     }
 }
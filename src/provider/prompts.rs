@@ -3,30 +3,74 @@
 //! This is the location to 'tune' the prompts using something like 'Step-Back' or similar
 //! The prompt can be specific to a provider
 //!
+//! Each prompt may be overridden by a file of the same name, e.g., `code_review.json`, in the `prompts_path` directory.
 use crate::provider::api::{ProviderCompletionMessage, ProviderMessageRole};
+use log::{debug, warn};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::path::Path;
 
-const FILE_REVIEW_SCHEMA: &str = include_str!("../provider/specification/file_review.schema.json");
-const CODE_REVIEW_PROMPT: &str = include_str!("../provider/prompts/code_review.json");
-const SECURITY_REVIEW_PROMPT: &str = include_str!("../provider/prompts/security_review.json");
-#[allow(dead_code)]
-const README_SUMMARY_PROMPT: &str = include_str!("../provider/prompts/readme_summary.json");
-const REPOSITORY_SUMMARY_PROMPT: &str = include_str!("../provider/prompts/repository_summary.json");
+pub(crate) const FILE_REVIEW_SCHEMA: &str =
+    include_str!("../provider/specification/file_review.schema.json");
+const CODE_REVIEW_PROMPT: (&str, &str) = (
+    "code_review.json",
+    include_str!("../provider/prompts/code_review.json"),
+);
+const SECURITY_REVIEW_PROMPT: (&str, &str) = (
+    "security_review.json",
+    include_str!("../provider/prompts/security_review.json"),
+);
+const README_SUMMARY_PROMPT: (&str, &str) = (
+    "readme_summary.json",
+    include_str!("../provider/prompts/readme_summary.json"),
+);
+const REPOSITORY_SUMMARY_PROMPT: (&str, &str) = (
+    "repository_summary.json",
+    include_str!("../provider/prompts/repository_summary.json"),
+);
+const FILE_PLAN_PROMPT: (&str, &str) = (
+    "file_plan.json",
+    include_str!("../provider/prompts/file_plan.json"),
+);
+const ARCHITECTURE_REVIEW_PROMPT: (&str, &str) = (
+    "architecture_review.json",
+    include_str!("../provider/prompts/architecture_review.json"),
+);
+const DEPENDENCY_REVIEW_PROMPT: (&str, &str) = (
+    "dependency_review.json",
+    include_str!("../provider/prompts/dependency_review.json"),
+);
+const EXECUTIVE_SUMMARY_PROMPT: (&str, &str) = (
+    "executive_summary.json",
+    include_str!("../provider/prompts/executive_summary.json"),
+);
+const LANGUAGE_GUIDANCE: (&str, &str) = (
+    "language_guidance.json",
+    include_str!("../provider/prompts/language_guidance.json"),
+);
+const JSON_REPAIR_PROMPT: (&str, &str) = (
+    "json_repair.json",
+    include_str!("../provider/prompts/json_repair.json"),
+);
 
 const LANGUAGE: &str = "British English";
+/// A rough ratio of characters to tokens, used where the provider's tokeniser is not available
+const CHARS_PER_TOKEN: usize = 4;
 
 /// Holds the id and [`Vec`] of [`ProviderCompletionMessage`]s
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub(crate) struct PromptData {
     pub(crate) id: Option<String>,
     pub(crate) messages: Vec<ProviderCompletionMessage>,
 }
 
 impl PromptData {
-    /// Adds a user Message to the Vec of Messages
+    /// Adds a user Message to the Vec of Messages.
+    ///
+    /// The variable content is always added last, after the static system prompt and schema, so that providers with
+    /// prompt caching, e.g., OpenAI, can serve the common prefix from their cache.
     pub(crate) fn add_user_message_prompt(&mut self, content: String) {
         let user_message = ProviderCompletionMessage {
             role: ProviderMessageRole::User,
@@ -34,41 +78,296 @@ impl PromptData {
         };
         self.messages.push(user_message);
     }
-    pub(crate) fn get_code_review_prompt() -> Result<Self, Box<dyn std::error::Error>> {
+    /// Adds a system Message with the review guidance specific to the language of the file, e.g., the scrutiny of
+    /// 'unsafe' blocks in Rust, or the generic guidance if the language is not known, or has none.
+    ///
+    /// The guidance is added before the variable content, so the prompt prefix is common to all files of a language.
+    pub(crate) fn add_language_guidance(
+        &mut self,
+        prompts_path: Option<&str>,
+        language: Option<&str>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let guidance: Value = serde_json::from_str(&read_prompt(prompts_path, LANGUAGE_GUIDANCE)?)?;
+        let content = language
+            .and_then(|language| guidance["languages"][language].as_str())
+            .or_else(|| guidance["default"].as_str());
+        if let Some(content) = content {
+            self.messages.push(ProviderCompletionMessage {
+                role: ProviderMessageRole::System,
+                content: content.to_string(),
+            });
+        }
+        Ok(())
+    }
+    /// Adds a system Message with the context of the repository, e.g., its purpose and layout, that the file is from
+    pub(crate) fn add_repository_context(&mut self, repository_context: &str) {
+        self.messages.push(ProviderCompletionMessage {
+            role: ProviderMessageRole::System,
+            content: repository_context.to_string(),
+        });
+    }
+    /// Adds the findings marked as false positives in earlier reviews, as examples of what not to report
+    pub(crate) fn add_counter_examples(&mut self, counter_examples: &str) {
+        self.messages.push(ProviderCompletionMessage {
+            role: ProviderMessageRole::System,
+            content: counter_examples.to_string(),
+        });
+    }
+    /// Adds the summary of what the file does, from the first, 'plan', request, for the review to build upon
+    pub(crate) fn add_file_plan(&mut self, plan: &str) {
+        self.messages.push(ProviderCompletionMessage {
+            role: ProviderMessageRole::System,
+            content: format!(
+                "A summary of what the source file to review does, from your first read of it: {}",
+                plan
+            ),
+        });
+    }
+    /// Gets a copy of the [`PromptData`] that asks the LLM to correct its previous, invalid, response, given the error
+    pub(crate) fn get_json_repair_prompt(
+        &self,
+        prompts_path: Option<&str>,
+        invalid_response: &str,
+        error: &str,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let json_content = create_content(&[("error", error)]);
+        let result = substitute_tokens(
+            &read_prompt(prompts_path, JSON_REPAIR_PROMPT)?,
+            &json_content,
+        )?;
+
+        let mut repair_prompt = self.clone();
+        repair_prompt.messages.push(ProviderCompletionMessage {
+            role: ProviderMessageRole::Assistant,
+            content: invalid_response.to_string(),
+        });
+        repair_prompt.messages.extend(get_messages_from(&result)?);
+        Ok(repair_prompt)
+    }
+    /// Estimates the number of tokens the messages will use in a request
+    pub(crate) fn estimate_tokens(&self) -> u64 {
+        self.messages
+            .iter()
+            .map(|message| estimate_tokens(&message.content))
+            .sum()
+    }
+    /// Trims the prompt to fit the number of tokens, if over, returning a description of each trim made. In order, until
+    /// it fits:
+    /// 1. drops the few-shot examples, i.e., the user and assistant messages before the last message
+    /// 2. compresses any JSON, e.g., the schema, in the system messages
+    /// 3. truncates the last message, i.e., the file under review, with a marker
+    pub(crate) fn trim_to(&mut self, max_tokens: u64) -> Vec<String> {
+        let mut trims: Vec<String> = Vec::new();
+        if self.estimate_tokens() <= max_tokens || self.messages.is_empty() {
+            return trims;
+        }
+        let last_index = self.messages.len() - 1;
+        let num_messages = self.messages.len();
+        let mut index = 0;
+        self.messages.retain(|message| {
+            let keep = index == last_index || message.role == ProviderMessageRole::System;
+            index += 1;
+            keep
+        });
+        if self.messages.len() < num_messages {
+            trims.push(format!(
+                "dropped {} example messages",
+                num_messages - self.messages.len()
+            ));
+        }
+        if self.estimate_tokens() <= max_tokens {
+            return trims;
+        }
+        let last_index = self.messages.len() - 1;
+        let mut compressed = false;
+        for message in &mut self.messages[..last_index] {
+            if let Some(compressed_content) = compress_json_in(&message.content) {
+                message.content = compressed_content;
+                compressed = true;
+            }
+        }
+        if compressed {
+            trims.push("compressed the schema".to_string());
+        }
+        if self.estimate_tokens() <= max_tokens {
+            return trims;
+        }
+        let other_tokens: u64 = self.messages[..last_index]
+            .iter()
+            .map(|message| estimate_tokens(&message.content))
+            .sum();
+        let last_message = &mut self.messages[last_index];
+        let num_chars = last_message.content.chars().count();
+        let marker = format!(
+            "\n... [TRUNCATED: the rest of the file, of {} characters, was not sent as it exceeds the model's context window]",
+            num_chars
+        );
+        let available_chars = (max_tokens.saturating_sub(other_tokens) as usize * CHARS_PER_TOKEN)
+            .saturating_sub(marker.chars().count());
+        last_message.content = last_message
+            .content
+            .chars()
+            .take(available_chars)
+            .collect::<String>()
+            + &marker;
+        trims.push(format!(
+            "truncated the file to {} of {} characters",
+            available_chars, num_chars
+        ));
+        trims
+    }
+    pub(crate) fn get_code_review_prompt(
+        prompts_path: Option<&str>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
         let json_content = create_content(&[
             ("language", LANGUAGE),
             ("file_review_schema", FILE_REVIEW_SCHEMA),
         ]);
-        let result = substitute_tokens(CODE_REVIEW_PROMPT, &json_content)?;
+        let result = substitute_tokens(
+            &read_prompt(prompts_path, CODE_REVIEW_PROMPT)?,
+            &json_content,
+        )?;
         let messages = get_messages_from(&result)?;
         Ok(Self { id: None, messages })
     }
-    pub(crate) fn get_security_review_prompt() -> Result<Self, Box<dyn std::error::Error>> {
+    pub(crate) fn get_security_review_prompt(
+        prompts_path: Option<&str>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
         let json_content = create_content(&[
             ("language", LANGUAGE),
             ("file_review_schema", FILE_REVIEW_SCHEMA),
         ]);
-        let result = substitute_tokens(SECURITY_REVIEW_PROMPT, &json_content)?;
+        let result = substitute_tokens(
+            &read_prompt(prompts_path, SECURITY_REVIEW_PROMPT)?,
+            &json_content,
+        )?;
         let messages = get_messages_from(&result)?;
         Ok(Self { id: None, messages })
     }
-    /// gets a [`PromptData`] for a LLM to summarise the README in a repository for the RepositoryReview.repository_purpose field
-    // TODO not yet used. Part of the documentation review module
-    pub(crate) fn _get_readme_summary_prompt() -> Result<Self, Box<dyn std::error::Error>> {
+    /// Gets the [`PromptData`] that asks the LLM to summarise what a file does, before it is reviewed
+    pub(crate) fn get_file_plan_prompt(
+        prompts_path: Option<&str>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let json_content = create_content(&[("language", LANGUAGE)]);
+        let result =
+            substitute_tokens(&read_prompt(prompts_path, FILE_PLAN_PROMPT)?, &json_content)?;
+        let messages = get_messages_from(&result)?;
+        Ok(Self { id: None, messages })
+    }
+    /// Gets the [`PromptData`] of a [`crate::settings::CustomReviewType`] from its prompt file, substituting the schema
+    pub(crate) fn get_custom_review_prompt(
+        prompt_path: &str,
+        file_review_schema: &str,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let prompt = std::fs::read_to_string(prompt_path).map_err(|e| {
+            format!(
+                "Failed to read custom review prompt: {}: {}",
+                prompt_path, e
+            )
+        })?;
+        let json_content = create_content(&[
+            ("language", LANGUAGE),
+            ("file_review_schema", file_review_schema),
+        ]);
+        let result = substitute_tokens(&prompt, &json_content)?;
+        let messages = get_messages_from(&result)?;
+        Ok(Self { id: None, messages })
+    }
+    /// Gets the [`PromptData`] that asks the LLM to review the architecture of the repository, from its directory tree and
+    /// the summaries of its file reviews
+    pub(crate) fn get_architecture_review_prompt(
+        prompts_path: Option<&str>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let json_content = create_content(&[("language", LANGUAGE)]);
+        let result = substitute_tokens(
+            &read_prompt(prompts_path, ARCHITECTURE_REVIEW_PROMPT)?,
+            &json_content,
+        )?;
+        let messages = get_messages_from(&result)?;
+        Ok(Self { id: None, messages })
+    }
+    /// Gets the [`PromptData`] that asks the LLM to review the hygiene of the repository's dependencies, e.g., whether
+    /// their versions are pinned, or they are abandoned
+    pub(crate) fn get_dependency_review_prompt(
+        prompts_path: Option<&str>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
         let json_content = create_content(&[("language", LANGUAGE)]);
-        let result = substitute_tokens(README_SUMMARY_PROMPT, &json_content)?;
+        let result = substitute_tokens(
+            &read_prompt(prompts_path, DEPENDENCY_REVIEW_PROMPT)?,
+            &json_content,
+        )?;
+        let messages = get_messages_from(&result)?;
+        Ok(Self { id: None, messages })
+    }
+    /// Gets the [`PromptData`] that asks the LLM to summarise the review for a non-technical audience, i.e., a verdict,
+    /// the top risks, and the effort to remediate the findings
+    pub(crate) fn get_executive_summary_prompt(
+        prompts_path: Option<&str>,
+        language: Option<&str>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let json_content = create_content(&[("language", language.unwrap_or(LANGUAGE))]);
+        let result = substitute_tokens(
+            &read_prompt(prompts_path, EXECUTIVE_SUMMARY_PROMPT)?,
+            &json_content,
+        )?;
+        let messages = get_messages_from(&result)?;
+        Ok(Self { id: None, messages })
+    }
+    /// gets a [`PromptData`] for a LLM to summarise the README in a repository for the RepositoryReview.repository_purpose field
+    pub(crate) fn get_readme_summary_prompt(
+        prompts_path: Option<&str>,
+        language: Option<&str>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let json_content = create_content(&[("language", language.unwrap_or(LANGUAGE))]);
+        let result = substitute_tokens(
+            &read_prompt(prompts_path, README_SUMMARY_PROMPT)?,
+            &json_content,
+        )?;
         let messages = get_messages_from(&result)?;
         Ok(Self { id: None, messages })
     }
     /// gets a [`PromptData`] for a LLM to summarise the overall review from a [`Vec`] of [`FileReview`]  
     #[allow(dead_code)]
-    pub(crate) fn get_overall_summary_prompt() -> Result<Self, Box<dyn std::error::Error>> {
-        let json_content = create_content(&[("language", LANGUAGE)]);
-        let result = substitute_tokens(REPOSITORY_SUMMARY_PROMPT, &json_content)?;
+    pub(crate) fn get_overall_summary_prompt(
+        prompts_path: Option<&str>,
+        language: Option<&str>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let json_content = create_content(&[("language", language.unwrap_or(LANGUAGE))]);
+        let result = substitute_tokens(
+            &read_prompt(prompts_path, REPOSITORY_SUMMARY_PROMPT)?,
+            &json_content,
+        )?;
         let messages = get_messages_from(&result)?;
         Ok(Self { id: None, messages })
     }
 }
+/// Reads the prompt, as a (file name, compiled in default), from the file of that name in the `prompts_path` directory,
+/// if it exists, otherwise the default is used
+fn read_prompt(
+    prompts_path: Option<&str>,
+    (file_name, default): (&str, &str),
+) -> Result<String, Box<dyn std::error::Error>> {
+    let Some(path) = prompts_path
+        .map(|prompts_path| Path::new(prompts_path).join(file_name))
+        .filter(|path| path.is_file())
+    else {
+        return Ok(default.to_string());
+    };
+    debug!("Using prompt override: {}", path.display());
+    std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read prompt override: {}: {}", path.display(), e).into())
+}
+/// Estimates the number of tokens in a text, without a provider specific tokeniser
+pub(crate) fn estimate_tokens(text: &str) -> u64 {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN) as u64
+}
+/// Compresses the JSON that follows the text of the content, e.g., a schema, if it is shorter than as is
+fn compress_json_in(content: &str) -> Option<String> {
+    let start = content.find('{')?;
+    let json: Value = serde_json::from_str(&content[start..]).ok()?;
+    let compressed = format!("{}{}", &content[..start], json);
+    (compressed.len() < content.len()).then_some(compressed)
+}
 /// Creates a [`HashMap`] from a slice of tuples
 fn create_content(pairs: &[(&str, &str)]) -> HashMap<String, String> {
     pairs
@@ -76,14 +375,55 @@ fn create_content(pairs: &[(&str, &str)]) -> HashMap<String, String> {
         .map(|(k, v)| (k.to_string(), v.to_string()))
         .collect()
 }
-/// Gets a [`Vec`] of [`ProviderCompletionMessage`]s from a JSON string
+/// Gets a [`Vec`] of [`ProviderCompletionMessage`]s from a JSON string, normalising each role, see [`normalise_role`].
+///
+/// Any few-shot `examples`, as `{ "input": "...", "output": ... }`, follow the messages as a user message of the input,
+/// and an assistant message of the output, which may be a string, or JSON, e.g., an example review.
 fn get_messages_from(json_data: &str) -> Result<Vec<ProviderCompletionMessage>, serde_json::Error> {
-    let v: Value = serde_json::from_str(json_data)?;
-    let messages: Vec<ProviderCompletionMessage> = serde_json::from_value(v["messages"].clone())?;
+    let mut v: Value = serde_json::from_str(json_data)?;
+    for message in v["messages"].as_array_mut().into_iter().flatten() {
+        if let Some(role) = message["role"].as_str() {
+            message["role"] = Value::from(normalise_role(role));
+        }
+    }
+    let mut messages: Vec<ProviderCompletionMessage> =
+        serde_json::from_value(v["messages"].clone())?;
 
+    for example in v["examples"].as_array().into_iter().flatten() {
+        messages.push(ProviderCompletionMessage {
+            role: ProviderMessageRole::User,
+            content: get_example_content(&example["input"]),
+        });
+        messages.push(ProviderCompletionMessage {
+            role: ProviderMessageRole::Assistant,
+            content: get_example_content(&example["output"]),
+        });
+    }
     Ok(messages)
 }
-/// Substitutes tokens in a JSON string with values from a [`HashMap`].
+/// Gets the content of an example input or output, as is if a string, otherwise as JSON
+fn get_example_content(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.to_string(),
+        _ => value.to_string(),
+    }
+}
+/// Normalises a message role to its serialised [`ProviderMessageRole`], e.g., "System" to 'system', or "model" to
+/// 'assistant'. An unknown role is logged and treated as 'user'.
+fn normalise_role(role: &str) -> &'static str {
+    match role.trim().to_lowercase().as_str() {
+        "system" | "developer" => "system",
+        "user" | "human" => "user",
+        "assistant" | "model" | "ai" | "bot" => "assistant",
+        "function" | "tool" => "function",
+        _ => {
+            warn!("Unknown message role: '{}', treated as: 'user'", role);
+            "user"
+        }
+    }
+}
+/// Substitutes tokens in a JSON string with values from a [`HashMap`], in the content of the messages, and in the
+/// strings of any examples.
 /// Usage: `substitute_tokens(json_str, &[("token", "value")])`
 fn substitute_tokens(
     json_str: &str,
@@ -96,23 +436,121 @@ fn substitute_tokens(
     if let Some(array) = v["messages"].as_array_mut() {
         for message in array {
             if let Some(content_str) = message["content"].as_str() {
-                let mut new_content = content_str.to_string();
-                for cap in re.captures_iter(content_str) {
-                    if let Some(replacement) = content.get(&cap[1]) {
-                        new_content = new_content.replace(&cap[0], replacement);
-                    }
-                }
-                message["content"] = json!(new_content);
+                message["content"] = json!(substitute_in(content_str, &re, content));
             }
         }
     }
+    if let Some(examples) = v.get_mut("examples") {
+        substitute_in_strings(examples, &re, content);
+    }
 
     Ok(v.to_string())
 }
+/// Substitutes tokens in every string of a JSON value
+fn substitute_in_strings(value: &mut Value, re: &Regex, content: &HashMap<String, String>) {
+    match value {
+        Value::String(text) => *text = substitute_in(text, re, content),
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| substitute_in_strings(item, re, content)),
+        Value::Object(object) => object
+            .values_mut()
+            .for_each(|field| substitute_in_strings(field, re, content)),
+        _ => {}
+    }
+}
+/// Substitutes the tokens in the text, leaving any without a value as is
+fn substitute_in(text: &str, re: &Regex, content: &HashMap<String, String>) -> String {
+    let mut new_content = text.to_string();
+    for cap in re.captures_iter(text) {
+        if let Some(replacement) = content.get(&cap[1]) {
+            new_content = new_content.replace(&cap[0], replacement);
+        }
+    }
+    new_content
+}
 #[cfg(test)]
 mod tests {
     use super::*;
     #[test]
+    fn test_read_prompt_override() {
+        let prompts_dir = tempfile::tempdir().unwrap();
+        let prompts_path = prompts_dir.path().to_str();
+        let custom_prompt =
+            r#"{ "messages": [{ "role": "system", "content": "Review in {{language}}" }] }"#;
+        std::fs::write(prompts_dir.path().join("code_review.json"), custom_prompt).unwrap();
+
+        assert_eq!(
+            read_prompt(prompts_path, CODE_REVIEW_PROMPT).unwrap(),
+            custom_prompt
+        );
+        assert_eq!(
+            read_prompt(prompts_path, SECURITY_REVIEW_PROMPT).unwrap(),
+            SECURITY_REVIEW_PROMPT.1
+        );
+        assert_eq!(
+            read_prompt(None, CODE_REVIEW_PROMPT).unwrap(),
+            CODE_REVIEW_PROMPT.1
+        );
+
+        let prompt_data = PromptData::get_code_review_prompt(prompts_path).unwrap();
+        assert_eq!(prompt_data.messages.len(), 1);
+        assert_eq!(prompt_data.messages[0].content, "Review in British English");
+    }
+    #[test]
+    fn test_add_language_guidance() {
+        let mut prompt_data = PromptData::get_code_review_prompt(None).unwrap();
+        let num_messages = prompt_data.messages.len();
+        prompt_data
+            .add_language_guidance(None, Some("Rust"))
+            .unwrap();
+        assert_eq!(prompt_data.messages.len(), num_messages + 1);
+        assert!(prompt_data.messages[num_messages]
+            .content
+            .contains("unsafe"));
+
+        let mut prompt_data = PromptData::get_code_review_prompt(None).unwrap();
+        prompt_data
+            .add_language_guidance(None, Some("COBOL"))
+            .unwrap();
+        let default: Value = serde_json::from_str(LANGUAGE_GUIDANCE.1).unwrap();
+        assert_eq!(
+            prompt_data.messages[num_messages].content,
+            default["default"].as_str().unwrap()
+        );
+    }
+    #[test]
+    fn test_add_file_plan() {
+        let mut prompt_data = PromptData::get_file_plan_prompt(None).unwrap();
+        let num_messages = prompt_data.messages.len();
+        prompt_data.add_file_plan("Parses the command line arguments");
+        assert_eq!(prompt_data.messages.len(), num_messages + 1);
+        assert_eq!(
+            prompt_data.messages[num_messages].role,
+            ProviderMessageRole::System
+        );
+        assert!(prompt_data.messages[num_messages]
+            .content
+            .ends_with("Parses the command line arguments"));
+    }
+    #[test]
+    fn test_get_json_repair_prompt() {
+        let prompt_data = PromptData::get_code_review_prompt(None).unwrap();
+        let num_messages = prompt_data.messages.len();
+
+        let repair_prompt = prompt_data
+            .get_json_repair_prompt(None, "{ \"summary\": ", "EOF while parsing an object")
+            .unwrap();
+        assert_eq!(repair_prompt.messages.len(), num_messages + 2);
+        assert_eq!(
+            repair_prompt.messages[num_messages].role,
+            ProviderMessageRole::Assistant
+        );
+        assert!(repair_prompt.messages[num_messages + 1]
+            .content
+            .contains("EOF while parsing an object"));
+    }
+    #[test]
     fn test_add_user_message_prompt() {
         let mut prompt_data = PromptData {
             id: Some("123".to_string()),
@@ -128,6 +566,42 @@ mod tests {
         assert_eq!(prompt_data.messages[1].content, "World");
     }
     #[test]
+    fn test_trim_to() {
+        let message = |role: ProviderMessageRole, content: &str| ProviderCompletionMessage {
+            role,
+            content: content.to_string(),
+        };
+        let mut prompt_data = PromptData {
+            id: None,
+            messages: vec![
+                message(
+                    ProviderMessageRole::System,
+                    "Comply with the schema: \n\n {\n    \"type\": \"object\"\n}",
+                ),
+                message(ProviderMessageRole::User, "An example file"),
+                message(ProviderMessageRole::Assistant, "An example review"),
+                message(ProviderMessageRole::User, &"x".repeat(400)),
+            ],
+        };
+        assert!(prompt_data.clone().trim_to(1_000).is_empty());
+
+        let trims = prompt_data.trim_to(50);
+        assert_eq!(trims.len(), 3);
+        assert_eq!(prompt_data.messages.len(), 2);
+        assert_eq!(
+            prompt_data.messages[0].content,
+            "Comply with the schema: \n\n {\"type\":\"object\"}"
+        );
+        assert!(prompt_data.messages[1].content.contains("[TRUNCATED"));
+        assert!(prompt_data.estimate_tokens() <= 50);
+    }
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+    }
+    #[test]
     fn test_create_content() {
         let pairs = &[("language", "English"), ("file_review_schema", "Schema")];
         let content = create_content(pairs);
@@ -148,6 +622,10 @@ mod tests {
                     {
                         "role": "system",
                         "content": "Welcome"
+                    },
+                    {
+                        "role": "Model",
+                        "content": "Hi"
                     }
                 ]
             }
@@ -155,11 +633,42 @@ mod tests {
 
         let messages = get_messages_from(json_data).unwrap();
 
-        assert_eq!(messages.len(), 2);
+        assert_eq!(messages.len(), 3);
         assert_eq!(messages[0].role, ProviderMessageRole::User);
         assert_eq!(messages[0].content, "Hello");
         assert_eq!(messages[1].role, ProviderMessageRole::System);
         assert_eq!(messages[1].content, "Welcome");
+        assert_eq!(messages[2].role, ProviderMessageRole::Assistant);
+    }
+    #[test]
+    fn test_get_messages_from_with_examples() {
+        let json_str = r#"
+            {
+                "messages": [
+                    { "role": "system", "content": "Respond in {{language}}" }
+                ],
+                "examples": [
+                    {
+                        "input": "Source file to review: {{language}}",
+                        "output": { "summary": "Reviewed in {{language}}", "security_issues": [] }
+                    }
+                ]
+            }
+        "#;
+        let content = create_content(&[("language", "British English")]);
+
+        let result = substitute_tokens(json_str, &content).unwrap();
+        let messages = get_messages_from(&result).unwrap();
+
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1].role, ProviderMessageRole::User);
+        assert_eq!(
+            messages[1].content,
+            "Source file to review: British English"
+        );
+        assert_eq!(messages[2].role, ProviderMessageRole::Assistant);
+        let output: Value = serde_json::from_str(&messages[2].content).unwrap();
+        assert_eq!(output["summary"], "Reviewed in British English");
     }
     #[test]
     fn test_substitute_tokens() {
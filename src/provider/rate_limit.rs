@@ -0,0 +1,197 @@
+//! A client-side rate limiter per provider, so that large repositories do not trip the provider's rate limits.
+//!
+//! The limits are set per provider via [`ProviderSettings`] `requests_per_minute` and `tokens_per_minute`.
+//! Each request waits, if needed, until it fits within a sliding window of one minute.
+use super::api::TokenUsage;
+use crate::settings::ProviderSettings;
+use log::info;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The sliding window over which the limits apply
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Holds a [`RateLimiter`] for each provider that has limits configured
+static RATE_LIMITERS: Mutex<Vec<RateLimiter>> = Mutex::new(Vec::new());
+
+/// Tracks the requests, and tokens used, within the window for a provider
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    name: String,
+    requests_per_minute: Option<u32>,
+    tokens_per_minute: Option<u32>,
+    window: VecDeque<(Instant, u64)>,
+}
+impl RateLimiter {
+    pub(crate) fn new(
+        name: String,
+        requests_per_minute: Option<u32>,
+        tokens_per_minute: Option<u32>,
+    ) -> Self {
+        Self {
+            name,
+            requests_per_minute,
+            tokens_per_minute,
+            window: VecDeque::new(),
+        }
+    }
+    /// Gets the time to wait before a request of `tokens` can be sent, or [`None`] if it can be sent now
+    pub(crate) fn time_until_available(&mut self, now: Instant, tokens: u64) -> Option<Duration> {
+        while let Some((sent, _)) = self.window.front() {
+            if now.duration_since(*sent) >= RATE_LIMIT_WINDOW {
+                self.window.pop_front();
+            } else {
+                break;
+            }
+        }
+        let (oldest, _) = self.window.front()?;
+
+        let requests_exceeded = self
+            .requests_per_minute
+            .is_some_and(|rpm| self.window.len() as u64 >= rpm as u64);
+        // A request larger than the whole limit is let through once the window is empty
+        let used_tokens: u64 = self.window.iter().map(|(_, tokens)| tokens).sum();
+        let tokens_exceeded = self
+            .tokens_per_minute
+            .is_some_and(|tpm| used_tokens + tokens > tpm as u64);
+
+        if requests_exceeded || tokens_exceeded {
+            Some((*oldest + RATE_LIMIT_WINDOW).saturating_duration_since(now))
+        } else {
+            None
+        }
+    }
+    /// Records a request of `tokens` sent at `now`
+    pub(crate) fn record(&mut self, now: Instant, tokens: u64) {
+        self.window.push_back((now, tokens));
+    }
+    /// Adds tokens used to the latest request, e.g., once the completion tokens are known
+    pub(crate) fn add_tokens(&mut self, tokens: u64) {
+        if let Some((_, used)) = self.window.back_mut() {
+            *used += tokens;
+        }
+    }
+}
+
+/// Waits until a request of `estimated_tokens` fits within the limits configured for the provider, then records it
+pub(crate) async fn acquire(provider_settings: &ProviderSettings, estimated_tokens: u64) {
+    if !has_limits(provider_settings) {
+        return;
+    }
+    loop {
+        let wait = {
+            let mut limiters = match RATE_LIMITERS.lock() {
+                Ok(limiters) => limiters,
+                Err(_) => return,
+            };
+            let limiter = get_or_insert_limiter(&mut limiters, provider_settings);
+            let now = Instant::now();
+            match limiter.time_until_available(now, estimated_tokens) {
+                Some(wait) => wait,
+                None => {
+                    limiter.record(now, estimated_tokens);
+                    return;
+                }
+            }
+        };
+        info!(
+            "Rate limit reached for provider: {}, waiting {} seconds",
+            provider_settings.name,
+            wait.as_secs()
+        );
+        tokio::time::sleep(wait).await;
+    }
+}
+/// Gets the time a request of `estimated_tokens` would wait for the limits configured for the provider, without
+/// recording it, e.g., zero if it has no limits
+pub(crate) fn get_wait(provider_settings: &ProviderSettings, estimated_tokens: u64) -> Duration {
+    if !has_limits(provider_settings) {
+        return Duration::ZERO;
+    }
+    match RATE_LIMITERS.lock() {
+        Ok(mut limiters) => get_or_insert_limiter(&mut limiters, provider_settings)
+            .time_until_available(Instant::now(), estimated_tokens)
+            .unwrap_or_default(),
+        Err(_) => Duration::ZERO,
+    }
+}
+/// Records the completion tokens of the latest request against the limits for the provider
+pub(crate) fn record_usage(provider_settings: &ProviderSettings, usage: &TokenUsage) {
+    if !has_limits(provider_settings) {
+        return;
+    }
+    if let Ok(mut limiters) = RATE_LIMITERS.lock() {
+        get_or_insert_limiter(&mut limiters, provider_settings)
+            .add_tokens(usage.completion_tokens.max(0) as u64);
+    }
+}
+fn has_limits(provider_settings: &ProviderSettings) -> bool {
+    provider_settings.requests_per_minute.is_some() || provider_settings.tokens_per_minute.is_some()
+}
+fn get_or_insert_limiter<'a>(
+    limiters: &'a mut Vec<RateLimiter>,
+    provider_settings: &ProviderSettings,
+) -> &'a mut RateLimiter {
+    match limiters
+        .iter()
+        .position(|limiter| limiter.name == provider_settings.name)
+    {
+        Some(index) => &mut limiters[index],
+        None => {
+            limiters.push(RateLimiter::new(
+                provider_settings.name.clone(),
+                provider_settings.requests_per_minute,
+                provider_settings.tokens_per_minute,
+            ));
+            limiters.last_mut().unwrap()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requests_per_minute_limit() {
+        let mut limiter = RateLimiter::new("test".to_string(), Some(2), None);
+        let start = Instant::now();
+
+        assert_eq!(limiter.time_until_available(start, 10), None);
+        limiter.record(start, 10);
+        assert_eq!(limiter.time_until_available(start, 10), None);
+        limiter.record(start, 10);
+
+        let later = start + Duration::from_secs(20);
+        assert_eq!(
+            limiter.time_until_available(later, 10),
+            Some(Duration::from_secs(40))
+        );
+        // Once the window has passed the requests no longer count
+        assert_eq!(
+            limiter.time_until_available(start + RATE_LIMIT_WINDOW, 10),
+            None
+        );
+    }
+
+    #[test]
+    fn test_tokens_per_minute_limit() {
+        let mut limiter = RateLimiter::new("test".to_string(), None, Some(1_000));
+        let start = Instant::now();
+
+        limiter.record(start, 600);
+        limiter.add_tokens(200);
+        assert_eq!(limiter.time_until_available(start, 200), None);
+        assert_eq!(
+            limiter.time_until_available(start, 300),
+            Some(RATE_LIMIT_WINDOW)
+        );
+    }
+
+    #[test]
+    fn test_oversized_request_allowed_on_empty_window() {
+        let mut limiter = RateLimiter::new("test".to_string(), None, Some(1_000));
+        assert_eq!(limiter.time_until_available(Instant::now(), 5_000), None);
+    }
+}
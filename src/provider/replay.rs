@@ -0,0 +1,107 @@
+//! A provider that replays the responses from a previously recorded transcript, see [`super::transcript`], so that
+//! the whole pipeline, i.e., review, summary and report, can be run offline, e.g., in CI, with no API key or cost.
+//!
+//! The `api_url` of the 'replay' provider is the transcript directory. Each request is matched to the most recent
+//! successful response of the same [`RequestType`] with the same input, i.e., the hash of the file under review.
+use super::api::ProviderCompletionResponse;
+use super::prompts::PromptData;
+use super::retry::ProviderRequestError;
+use super::transcript::{get_input_hash, read_entries, TranscriptEntry};
+use super::{APIProvider, RequestType};
+use crate::settings::{ProviderSettings, Settings};
+use std::path::Path;
+
+pub(super) struct ReplayProvider {}
+
+#[async_trait::async_trait]
+impl APIProvider for ReplayProvider {
+    async fn ask_request_of_provider(
+        &self,
+        request_type: &RequestType,
+        _settings: &Settings,
+        provider: &ProviderSettings,
+        prompt_data: &PromptData,
+    ) -> Result<ProviderCompletionResponse, Box<dyn std::error::Error>> {
+        let entries = read_entries(Path::new(&provider.api_url))?;
+        find_response(entries, request_type, &get_input_hash(prompt_data)).ok_or_else(|| {
+            Box::new(ProviderRequestError {
+                message: format!(
+                    "No recorded {} response in: {}, for: {}",
+                    request_type,
+                    provider.api_url,
+                    prompt_data.id.as_deref().unwrap_or("summary")
+                ),
+                // Not found, so is not retried
+                status: Some(404),
+                retry_after: None,
+            }) as Box<dyn std::error::Error>
+        })
+    }
+}
+/// Finds the most recent recorded response for the [`RequestType`] and input hash
+fn find_response(
+    entries: Vec<TranscriptEntry>,
+    request_type: &RequestType,
+    input_hash: &str,
+) -> Option<ProviderCompletionResponse> {
+    let request_type = request_type.to_string();
+    entries
+        .into_iter()
+        .rev()
+        .filter(|entry| entry.request_type == request_type && entry.input_hash == input_hash)
+        .find_map(|entry| entry.response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::api::{ProviderResponseChoice, ProviderResponseMessage};
+
+    fn test_entry(request_type: &str, input_hash: &str, content: Option<&str>) -> TranscriptEntry {
+        TranscriptEntry {
+            timestamp: String::new(),
+            request_type: request_type.to_string(),
+            provider: "openai".to_string(),
+            model: "gpt-4".to_string(),
+            id: None,
+            input_hash: input_hash.to_string(),
+            messages: Vec::new(),
+            response: content.map(|content| ProviderCompletionResponse {
+                id: String::new(),
+                model: "gpt-4".to_string(),
+                choices: vec![ProviderResponseChoice {
+                    message: ProviderResponseMessage {
+                        content: content.to_string(),
+                    },
+                }],
+                usage: None,
+            }),
+            error: content.is_none().then(|| "Failed".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_find_response() {
+        let entries = || {
+            vec![
+                test_entry("review", "abc", Some("first")),
+                test_entry("review", "abc", Some("second")),
+                test_entry("review", "abc", None),
+                test_entry("summarise", "def", Some("summary")),
+            ]
+        };
+        let content = |response: Option<ProviderCompletionResponse>| {
+            response.map(|response| response.choices[0].message.content.clone())
+        };
+
+        assert_eq!(
+            content(find_response(entries(), &RequestType::Review, "abc")),
+            Some("second".to_string())
+        );
+        assert_eq!(
+            content(find_response(entries(), &RequestType::Summarise, "def")),
+            Some("summary".to_string())
+        );
+        assert!(find_response(entries(), &RequestType::Summarise, "abc").is_none());
+    }
+}
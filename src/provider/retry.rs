@@ -0,0 +1,310 @@
+//! A shared retry policy, applied uniformly to all requests made to providers.
+//!
+//! Retries use exponential backoff with jitter, honour any 'retry-after' given by the provider, and stop once
+//! either the configured `max_retries`, or the maximum elapsed time, is reached.
+use super::extract_http_status;
+use crate::settings::ProviderSettings;
+use log::warn;
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// The backoff before the first retry, if not configured, in milliseconds
+const DEFAULT_INITIAL_BACKOFF_MS: u64 = 1_000;
+/// The longest backoff between two attempts, in seconds
+const MAX_BACKOFF_SECS: u64 = 60;
+
+/// The policy that determines whether, and when, a failed request is retried
+///
+/// #Fields:
+/// * `max_retries` - The number of retries after the first attempt
+/// * `initial_backoff` - The backoff before the first retry, doubled for each subsequent retry
+/// * `max_backoff` - The cap on the backoff between two attempts
+/// * `max_elapsed` - An [`Option`]al limit on the total time spent retrying
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RetryPolicy {
+    pub(crate) max_retries: u32,
+    pub(crate) initial_backoff: Duration,
+    pub(crate) max_backoff: Duration,
+    pub(crate) max_elapsed: Option<Duration>,
+}
+impl RetryPolicy {
+    /// Creates the [`RetryPolicy`] from the configured [`ProviderSettings`]
+    pub(crate) fn from_provider_settings(provider_settings: &ProviderSettings) -> Self {
+        Self {
+            max_retries: provider_settings.max_retries.unwrap_or(0).max(0) as u32,
+            initial_backoff: Duration::from_millis(
+                provider_settings
+                    .initial_backoff_ms
+                    .unwrap_or(DEFAULT_INITIAL_BACKOFF_MS),
+            ),
+            max_backoff: Duration::from_secs(MAX_BACKOFF_SECS),
+            max_elapsed: provider_settings
+                .max_retry_elapsed_secs
+                .map(Duration::from_secs),
+        }
+    }
+    /// Gets the exponential backoff for the (zero-based) retry `attempt`, scaled by the `jitter` factor
+    pub(crate) fn backoff_for(&self, attempt: u32, jitter: f64) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2_u32.saturating_pow(attempt))
+            .min(self.max_backoff)
+            .mul_f64(jitter)
+    }
+    /// Runs the `operation` until it succeeds, fails with an error that is not retryable, or the policy is exhausted
+    pub(crate) async fn retry<T, F, Fut>(
+        &self,
+        description: &str,
+        mut operation: F,
+    ) -> Result<T, Box<dyn Error>>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Box<dyn Error>>>,
+    {
+        let start = Instant::now();
+        let mut attempt: u32 = 0;
+        loop {
+            let delay = match operation().await {
+                Ok(result) => return Ok(result),
+                Err(e) => {
+                    if !is_retryable(e.as_ref()) || attempt >= self.max_retries {
+                        return Err(Box::new(RetryError {
+                            attempts: attempt + 1,
+                            source: e,
+                        }));
+                    }
+                    let delay = self
+                        .backoff_for(attempt, jitter_factor())
+                        .max(get_retry_after(e.as_ref()).unwrap_or_default());
+                    if self
+                        .max_elapsed
+                        .is_some_and(|max_elapsed| start.elapsed() + delay > max_elapsed)
+                    {
+                        return Err(Box::new(RetryError {
+                            attempts: attempt + 1,
+                            source: e,
+                        }));
+                    }
+                    warn!(
+                        "{} failed: {}. Retrying in {} ms (attempt {} of {})",
+                        description,
+                        e,
+                        delay.as_millis(),
+                        attempt + 1,
+                        self.max_retries
+                    );
+                    delay
+                }
+            };
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// An error from a request to a provider, carrying the HTTP status and any 'retry-after' header, so the [`RetryPolicy`] can act on them
+#[derive(Debug)]
+pub(crate) struct ProviderRequestError {
+    pub(crate) message: String,
+    pub(crate) status: Option<u16>,
+    pub(crate) retry_after: Option<Duration>,
+}
+impl ProviderRequestError {
+    /// Builds the error from an unsuccessful [`reqwest::Response`], capturing the status, 'retry-after' header and body
+    pub(crate) async fn from_response(provider_name: &str, response: reqwest::Response) -> Self {
+        let status = response.status();
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_retry_after);
+        let body = response.text().await.unwrap_or_default();
+        Self {
+            message: format!(
+                "{} API request failed with {}: {}",
+                provider_name, status, body
+            ),
+            status: Some(status.as_u16()),
+            retry_after,
+        }
+    }
+    /// Whether the request may succeed if tried again, e.g., rate limited, timed out or a server error
+    pub(crate) fn is_retryable(&self) -> bool {
+        match self.status {
+            Some(status) => matches!(status, 408 | 429 | 500..=599),
+            None => true,
+        }
+    }
+}
+impl From<reqwest::Error> for ProviderRequestError {
+    fn from(error: reqwest::Error) -> Self {
+        let message = if error.is_timeout() {
+            "Network request timed out".to_string()
+        } else {
+            format!("Network request failed: {}", error)
+        };
+        Self {
+            message,
+            status: error.status().map(|status| status.as_u16()),
+            retry_after: None,
+        }
+    }
+}
+impl fmt::Display for ProviderRequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+impl Error for ProviderRequestError {}
+
+/// The error returned once a [`RetryPolicy`] gives up, holding the last error
+#[derive(Debug)]
+pub(crate) struct RetryError {
+    pub(crate) attempts: u32,
+    pub(crate) source: Box<dyn Error>,
+}
+impl fmt::Display for RetryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Failed after {} attempt(s): {}",
+            self.attempts, self.source
+        )
+    }
+}
+impl Error for RetryError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+/// Whether an error is worth retrying. Errors where retries are already exhausted, or client errors, such as
+/// a bad request or failed authorisation, are not. Malformed JSON returned by the LLM is worth retrying.
+fn is_retryable(error: &(dyn Error + 'static)) -> bool {
+    if error.is::<RetryError>() {
+        return false;
+    }
+    if error.is::<serde_json::Error>() {
+        return true;
+    }
+    if let Some(request_error) = error.downcast_ref::<ProviderRequestError>() {
+        return request_error.is_retryable();
+    }
+    !matches!(
+        extract_http_status(&error.to_string()),
+        Some(400 | 401 | 403 | 404)
+    )
+}
+/// Gets any 'retry-after' the provider gave with the error
+fn get_retry_after(error: &(dyn Error + 'static)) -> Option<Duration> {
+    error
+        .downcast_ref::<ProviderRequestError>()
+        .and_then(|request_error| request_error.retry_after)
+}
+/// Parses a 'retry-after' header value given in seconds
+pub(crate) fn parse_retry_after(value: &str) -> Option<Duration> {
+    value.trim().parse::<u64>().ok().map(Duration::from_secs)
+}
+/// A jitter factor between 0.5 and 1.0, so that concurrent retries do not all fire at once
+fn jitter_factor() -> f64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.subsec_nanos())
+        .unwrap_or(0);
+    0.5 + (nanos % 1_000) as f64 / 2_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn test_policy(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            initial_backoff: Duration::from_millis(0),
+            max_backoff: Duration::from_secs(MAX_BACKOFF_SECS),
+            max_elapsed: None,
+        }
+    }
+
+    #[test]
+    fn test_backoff_for() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_secs(1),
+            ..test_policy(5)
+        };
+        assert_eq!(policy.backoff_for(0, 1.0), Duration::from_secs(1));
+        assert_eq!(policy.backoff_for(3, 1.0), Duration::from_secs(8));
+        assert_eq!(policy.backoff_for(3, 0.5), Duration::from_secs(4));
+        assert_eq!(
+            policy.backoff_for(10, 1.0),
+            Duration::from_secs(MAX_BACKOFF_SECS)
+        );
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        let rate_limited = ProviderRequestError {
+            message: "Too many requests".to_string(),
+            status: Some(429),
+            retry_after: Some(Duration::from_secs(2)),
+        };
+        let unauthorised = ProviderRequestError {
+            message: "Unauthorized".to_string(),
+            status: Some(401),
+            retry_after: None,
+        };
+        assert!(is_retryable(&rate_limited));
+        assert!(!is_retryable(&unauthorised));
+        assert_eq!(get_retry_after(&rate_limited), Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after(" 30 "), Some(Duration::from_secs(30)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+    }
+
+    #[tokio::test]
+    async fn test_retry_until_success() {
+        let calls = Cell::new(0);
+        let result = test_policy(3)
+            .retry("test", || {
+                calls.set(calls.get() + 1);
+                let attempt = calls.get();
+                async move {
+                    if attempt < 3 {
+                        Err::<i32, Box<dyn Error>>("transient".into())
+                    } else {
+                        Ok(attempt)
+                    }
+                }
+            })
+            .await;
+
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_stops_when_not_retryable() {
+        let calls = Cell::new(0);
+        let result = test_policy(3)
+            .retry("test", || {
+                calls.set(calls.get() + 1);
+                async {
+                    Err::<(), Box<dyn Error>>(Box::new(ProviderRequestError {
+                        message: "Bad request".to_string(),
+                        status: Some(400),
+                        retry_after: None,
+                    }))
+                }
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls.get(), 1);
+    }
+}
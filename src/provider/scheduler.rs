@@ -0,0 +1,119 @@
+//! Schedules the file reviews across the active provider and the `scheduled_providers`, so that, with several reviews
+//! at once, each goes to the provider with room for it within its `requests_per_minute` and `tokens_per_minute`,
+//! rather than all queueing on one provider, or tripping its rate limits only to be retried.
+//!
+//! A provider is chosen for the shortest wait under its limits, then for the fewest reviews in flight, so the reviews
+//! are interleaved across the providers while each has room.
+use super::rate_limit;
+use crate::settings::{ProviderError, ProviderSettings, Settings};
+use log::debug;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// The number of requests in flight for each provider scheduled, by name
+static IN_FLIGHT: Mutex<Vec<(String, usize)>> = Mutex::new(Vec::new());
+
+/// A provider scheduled for a review, which is counted as in flight until dropped
+pub(crate) struct ScheduledProvider<'a> {
+    pub(crate) provider: &'a ProviderSettings,
+}
+impl Drop for ScheduledProvider<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut in_flight) = IN_FLIGHT.lock() {
+            if let Some((_, count)) = in_flight
+                .iter_mut()
+                .find(|(name, _)| *name == self.provider.name)
+            {
+                *count = count.saturating_sub(1);
+            }
+        }
+    }
+}
+
+/// Schedules a review of `estimated_tokens` on the provider with the most room for it, i.e., the active provider
+/// unless `scheduled_providers` are set
+pub(crate) fn schedule(
+    settings: &Settings,
+    estimated_tokens: u64,
+) -> Result<ScheduledProvider<'_>, ProviderError> {
+    let providers: Vec<&ProviderSettings> = get_scheduled_providers(settings)?;
+    let mut in_flight = IN_FLIGHT
+        .lock()
+        .map_err(|e| ProviderError::InvalidSetting(format!("Failed to schedule: {}", e)))?;
+    let candidates: Vec<(Duration, usize)> = providers
+        .iter()
+        .map(|provider| {
+            let count: usize = in_flight
+                .iter()
+                .find(|(name, _)| *name == provider.name)
+                .map_or(0, |(_, count)| *count);
+            (rate_limit::get_wait(provider, estimated_tokens), count)
+        })
+        .collect();
+    let provider: &ProviderSettings = providers[choose_provider(&candidates)];
+    match in_flight
+        .iter_mut()
+        .find(|(name, _)| *name == provider.name)
+    {
+        Some((_, count)) => *count += 1,
+        None => in_flight.push((provider.name.clone(), 1)),
+    }
+    if providers.len() > 1 {
+        debug!("Scheduled on provider: {}", provider.name);
+    }
+    Ok(ScheduledProvider { provider })
+}
+/// Gets the providers to schedule on, the active provider first
+fn get_scheduled_providers(settings: &Settings) -> Result<Vec<&ProviderSettings>, ProviderError> {
+    let mut providers: Vec<&ProviderSettings> = vec![settings.get_active_provider()?];
+    for name in &settings.scheduled_providers {
+        if providers.iter().any(|provider| provider.name == *name) {
+            continue;
+        }
+        let provider = settings
+            .providers
+            .iter()
+            .find(|provider| provider.name == *name)
+            .ok_or_else(|| ProviderError::NotFound(name.clone()))?;
+        providers.push(provider);
+    }
+    Ok(providers)
+}
+/// Chooses the index of the candidate, as its wait and requests in flight, with the shortest wait, then the fewest in
+/// flight, then the first, i.e., the active provider
+fn choose_provider(candidates: &[(Duration, usize)]) -> usize {
+    candidates
+        .iter()
+        .enumerate()
+        .min_by_key(|(index, candidate)| (candidate.0, candidate.1, *index))
+        .map_or(0, |(index, _)| index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choose_provider() {
+        assert_eq!(choose_provider(&[]), 0);
+        assert_eq!(choose_provider(&[(Duration::ZERO, 3)]), 0);
+        // Both have room, so the reviews are interleaved
+        assert_eq!(
+            choose_provider(&[(Duration::ZERO, 1), (Duration::ZERO, 0)]),
+            1
+        );
+        assert_eq!(
+            choose_provider(&[(Duration::ZERO, 1), (Duration::ZERO, 1)]),
+            0
+        );
+        // A provider at its limits is passed over, however busy the other
+        assert_eq!(
+            choose_provider(&[(Duration::from_secs(30), 0), (Duration::ZERO, 4)]),
+            1
+        );
+        assert_eq!(
+            choose_provider(&[(Duration::from_secs(30), 0), (Duration::from_secs(5), 4)]),
+            1
+        );
+    }
+}
@@ -0,0 +1,210 @@
+//! Records every prompt sent to, and response received from, a provider to a transcript directory, for debugging, e.g.,
+//! to investigate a failed JSON parse, or odd model behaviour, after a run.
+//!
+//! Each request is written as a single JSON [`TranscriptEntry`] file. Any configured secret, e.g., an `api_key`, or a
+//! custom header value, is redacted before the entry is written.
+use super::api::{ProviderCompletionMessage, ProviderCompletionResponse, ProviderMessageRole};
+use super::prompts::PromptData;
+use super::RequestType;
+use crate::retrieval::code::calculate_hash_from;
+use crate::settings::{ProviderSettings, SensitiveSettings, Settings, TokenAuth, REPLAY_PROVIDER};
+use chrono::Utc;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Replaces any secret found in a transcript
+const REDACTED: &str = "***REDACTED***";
+
+/// Orders the entries written within the same millisecond
+static SEQUENCE: AtomicUsize = AtomicUsize::new(0);
+
+/// A single request to a provider and its outcome
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct TranscriptEntry {
+    pub(crate) timestamp: String,
+    pub(crate) request_type: String,
+    pub(crate) provider: String,
+    pub(crate) model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) id: Option<String>,
+    /// The hash of the final user message, i.e., the file under review, or the summaries to summarise
+    pub(crate) input_hash: String,
+    pub(crate) messages: Vec<ProviderCompletionMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) response: Option<ProviderCompletionResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+}
+
+/// Writes the request and its outcome to the `transcript_path`, if set. A failure to write is logged, not returned.
+pub(crate) fn record(
+    settings: &Settings,
+    provider: &ProviderSettings,
+    request_type: &RequestType,
+    prompt_data: &PromptData,
+    result: &Result<ProviderCompletionResponse, Box<dyn Error>>,
+) {
+    let Some(transcript_path) = settings.transcript_path.as_deref() else {
+        return;
+    };
+    if provider.name == REPLAY_PROVIDER {
+        // Replayed responses are already in a transcript
+        return;
+    }
+    let secrets = get_secrets(settings, provider);
+    let redact = |text: &str| redact_secrets(text, &secrets);
+
+    let entry = TranscriptEntry {
+        timestamp: Utc::now().to_rfc3339(),
+        request_type: request_type.to_string(),
+        provider: provider.name.clone(),
+        model: provider
+            .get_active_service()
+            .map(|service| service.model.clone())
+            .unwrap_or_default(),
+        id: prompt_data.id.clone(),
+        input_hash: get_input_hash(prompt_data),
+        messages: prompt_data
+            .messages
+            .iter()
+            .map(|message| ProviderCompletionMessage {
+                role: message.role.clone(),
+                content: redact(&message.content),
+            })
+            .collect(),
+        response: None,
+        error: result.as_ref().err().map(|e| redact(&e.to_string())),
+    };
+    let written = serde_json::to_value(&entry)
+        .and_then(|mut value| {
+            if let Ok(response) = result {
+                value["response"] =
+                    serde_json::from_str(&redact(&serde_json::to_string(response)?))?;
+            }
+            serde_json::to_string_pretty(&value)
+        })
+        .map_err(|e| Box::new(e) as Box<dyn Error>)
+        .and_then(|contents| write_entry(Path::new(transcript_path), request_type, &contents));
+    if let Err(e) = written {
+        warn!("Failed to write transcript to: {}: {}", transcript_path, e);
+    }
+}
+/// Gets the hash of the final user message in the [`PromptData`], which identifies the request independent of the prompt
+pub(crate) fn get_input_hash(prompt_data: &PromptData) -> String {
+    calculate_hash_from(
+        prompt_data
+            .messages
+            .iter()
+            .rev()
+            .find(|message| message.role == ProviderMessageRole::User)
+            .map_or("", |message| message.content.as_str()),
+    )
+}
+/// Reads all the [`TranscriptEntry`]s in the directory, oldest first, skipping any that are not valid
+pub(crate) fn read_entries(transcript_dir: &Path) -> Result<Vec<TranscriptEntry>, Box<dyn Error>> {
+    let mut paths: Vec<_> = fs::read_dir(transcript_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+
+    Ok(paths
+        .iter()
+        .filter_map(|path| {
+            let contents = fs::read_to_string(path).ok()?;
+            match serde_json::from_str::<TranscriptEntry>(&contents) {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    warn!("Ignoring invalid transcript: {}: {}", path.display(), e);
+                    None
+                }
+            }
+        })
+        .collect())
+}
+fn write_entry(
+    transcript_dir: &Path,
+    request_type: &RequestType,
+    contents: &str,
+) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(transcript_dir)?;
+    let file_name = format!(
+        "{}-{:06}-{}.json",
+        Utc::now().format("%Y%m%dT%H%M%S%3f"),
+        SEQUENCE.fetch_add(1, Ordering::Relaxed),
+        request_type
+    );
+    fs::write(transcript_dir.join(file_name), contents)?;
+    Ok(())
+}
+/// Gets the configured secrets that must not appear in a transcript
+fn get_secrets(settings: &Settings, provider: &ProviderSettings) -> Vec<String> {
+    let mut secrets: Vec<String> = Vec::new();
+    let mut add_sensitive = |sensitive: &SensitiveSettings| {
+        if let Some(api_key) = &sensitive.api_key {
+            secrets.push(api_key.use_key(|key| key.to_string()));
+        }
+        if let Some(TokenAuth::ClientCredentials { client_secret, .. }) = &sensitive.token_auth {
+            secrets.push(client_secret.use_key(|key| key.to_string()));
+        }
+    };
+    add_sensitive(&settings.sensitive);
+    if let Some(sensitive) = &provider.sensitive {
+        add_sensitive(sensitive);
+    }
+    if let Some(headers) = &provider.headers {
+        secrets.extend(headers.values().cloned());
+    }
+    secrets.retain(|secret| !secret.is_empty());
+    secrets
+}
+/// Replaces each of the secrets in the text
+fn redact_secrets(text: &str, secrets: &[String]) -> String {
+    secrets.iter().fold(text.to_string(), |text, secret| {
+        text.replace(secret.as_str(), REDACTED)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_secrets() {
+        let secrets = vec!["sk-abc123".to_string(), "gateway-token".to_string()];
+        assert_eq!(
+            redact_secrets("key: sk-abc123, header: gateway-token", &secrets),
+            "key: ***REDACTED***, header: ***REDACTED***"
+        );
+        assert_eq!(redact_secrets("nothing secret", &secrets), "nothing secret");
+    }
+
+    #[test]
+    fn test_get_input_hash() {
+        let prompt_data = |system: &str| PromptData {
+            id: None,
+            messages: vec![
+                ProviderCompletionMessage {
+                    role: ProviderMessageRole::System,
+                    content: system.to_string(),
+                },
+                ProviderCompletionMessage {
+                    role: ProviderMessageRole::User,
+                    content: "fn main() {}".to_string(),
+                },
+            ],
+        };
+        assert_eq!(
+            get_input_hash(&prompt_data("Review this")),
+            get_input_hash(&prompt_data("Review this for security"))
+        );
+        assert_eq!(
+            get_input_hash(&prompt_data("Review this")),
+            calculate_hash_from("fn main() {}")
+        );
+    }
+}
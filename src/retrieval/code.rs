@@ -5,6 +5,7 @@
 //! - **\*Info**: data representation struct for a specific purpose, e.g. [`SourceFileInfo`], which is used to build [`SourceFileReview`]s
 //! - **\*Breakdown**: a builder data struct that builds information for a specific purpose, e.g. [`LanguageBreakdown`], which is used to build [`LanguageFileType`]s
 use crate::review::data::{RAGStatus, Severity, SourceFileReview};
+use crate::settings::{RagThresholds, Settings};
 use linguist::{
     container::InMemoryLanguageContainer,
     resolver::{resolve_language_from_content_str, Language, Scope},
@@ -13,12 +14,14 @@ use linguist::{
         is_vendor_from_str,
     },
 };
-use log::{error, info};
+use log::{debug, error, info};
 use regex::RegexSet;
 use sha2::{Digest, Sha256};
 use std::ffi::OsStr;
 
 use super::data::{LanguageType, SourceFileInfo};
+use super::generated::get_generated_reason;
+use super::markers::count_markers;
 /// Contains the predefined languages, heuristics, vendors and documentation regexes from the GitHub Linguist project
 pub(crate) mod predefined {
     include!(concat!(env!("OUT_DIR"), "/languages.rs"));
@@ -29,6 +32,32 @@ pub(crate) mod predefined {
 /// The prefixes that indicate a comment in a file
 /// TODO: move to tokei crate
 const COMMENT_PREFIXES: &[&str] = &["//", "///", "//!", "#", "\"\"\" "];
+/// The keywords and operators that branch, for a language without its own
+const BRANCHES: &[&str] = &["if", "for", "foreach", "while", "case", "catch", "&&", "||"];
+/// The keywords and operators that branch, per language
+const LANGUAGE_BRANCHES: &[(&str, &[&str])] = &[
+    ("Rust", &["if", "for", "while", "loop", "=>", "&&", "||"]),
+    ("Go", &["if", "for", "case", "&&", "||"]),
+    (
+        "Python",
+        &["if", "elif", "for", "while", "except", "case", "and", "or"],
+    ),
+    (
+        "Ruby",
+        &[
+            "if", "elsif", "unless", "for", "while", "until", "when", "rescue", "&&", "||", "and",
+            "or",
+        ],
+    ),
+    (
+        "Shell",
+        &["if", "elif", "for", "while", "until", "&&", "||"],
+    ),
+    (
+        "Kotlin",
+        &["if", "for", "while", "when", "catch", "&&", "||"],
+    ),
+];
 
 /// Initialize the language analysis by registering the predefined languages and heuristics as provided by the [`linguist`] crate
 pub(crate) fn initialize_language_analysis() -> (InMemoryLanguageContainer, RegexSet, RegexSet) {
@@ -48,9 +77,12 @@ pub(crate) fn initialize_language_analysis() -> (InMemoryLanguageContainer, Rege
 
 /// Analyse the file language, returning the language, file size and lines of code
 /// #Returns:
-/// - Some((Language, file_size u64, loc i64)) if successful
+/// - Some((Language, file_size u64, loc i64)) if successful, and the language is reviewed, as per the [`Settings`]
 // TODO: refactor to handle documentation, dotfiles, etc.
-pub(crate) fn analyse_file_language(file_info: &mut SourceFileInfo) -> Option<&SourceFileInfo> {
+pub(crate) fn analyse_file_language<'a>(
+    settings: &Settings,
+    file_info: &'a mut SourceFileInfo,
+) -> Option<&'a SourceFileInfo> {
     let (lc, rules, docs) = initialize_language_analysis();
 
     // TODO: resolve the type of file if docs, dotfile, or config and handle separately, particularly documentation, which needs to be summarised
@@ -78,6 +110,13 @@ pub(crate) fn analyse_file_language(file_info: &mut SourceFileInfo) -> Option<&S
             if lang.scope != Scope::Programming && lang.scope != Scope::Markup {
                 return None;
             }
+            if !settings.is_language_reviewed(&lang.name) {
+                debug!(
+                    "Not reviewing the {} file: {}",
+                    lang.name, file_info.relative_path
+                );
+                return None;
+            }
             lang
         }
         _ => return None,
@@ -102,14 +141,25 @@ pub(crate) fn analyse_file_language(file_info: &mut SourceFileInfo) -> Option<&S
     file_info.language = Some(LanguageType::from_language(language)); // At this point we don't know whether there are other language types so we set the stats later
     file_info.statistics.size = file_size;
     file_info.statistics.loc = loc;
+    file_info.statistics.complexity = Some(calculate_complexity(
+        &file_info.get_source_file_contents(),
+        &language.name,
+    ));
+    file_info.markers = count_markers(&file_info.get_source_file_contents());
+    file_info.generated = get_generated_reason(
+        &file_info.relative_path,
+        &file_info.get_source_file_contents(),
+    );
     file_info.statistics.num_files += 1;
 
     Some(file_info)
 }
 
-/// Calculates the RAG status for a [`SourceFileReview`] on the number of errors, improvements and security_issues, according to lines of code
+/// Calculates the RAG status for a [`SourceFileReview`] on the number of errors, improvements and security_issues, according to lines of code,
+/// within the [`RagThresholds`] of the file's language
 pub(crate) fn calculate_rag_status_for_reviewed_file(
     reviewed_file: &SourceFileReview,
+    thresholds: &RagThresholds,
 ) -> Option<RAGStatus> {
     let errors_count = reviewed_file
         .errors
@@ -133,11 +183,6 @@ pub(crate) fn calculate_rag_status_for_reviewed_file(
     let security_issues_ratio = security_issues_count as f64 / loc as f64;
     let improvements_ratio = improvements_count as f64 / loc as f64;
 
-    let green_error_threshold = 0.07; // 7% of loc
-    let amber_error_threshold = 0.18; // 18% of loc
-    let green_improvement_threshold = 0.15; // 15% of loc
-    let amber_improvement_threshold = 0.40; // 40% of loc
-
     if let Some(security_issues) = &reviewed_file.security_issues {
         for issue in security_issues {
             match issue.severity {
@@ -146,19 +191,67 @@ pub(crate) fn calculate_rag_status_for_reviewed_file(
             }
         }
     }
-    if error_ratio <= green_error_threshold
-        && security_issues_ratio <= 0.05 // 5% of loc
-        && improvements_ratio <= green_improvement_threshold
+    if error_ratio <= thresholds.green_error_ratio
+        && security_issues_ratio <= thresholds.green_security_issue_ratio
+        && improvements_ratio <= thresholds.green_improvement_ratio
     {
+        // A file with little of it covered by tests is at risk, however few its findings
+        if reviewed_file
+            .source_file_info
+            .coverage
+            .is_some_and(|coverage| coverage < thresholds.min_coverage)
+        {
+            return Some(RAGStatus::Amber);
+        }
         return Some(RAGStatus::Green);
-    } else if error_ratio <= amber_error_threshold
-        && security_issues_ratio <= 0.12 // 12% of loc
-        && improvements_ratio <= amber_improvement_threshold
+    } else if error_ratio <= thresholds.amber_error_ratio
+        && security_issues_ratio <= thresholds.amber_security_issue_ratio
+        && improvements_ratio <= thresholds.amber_improvement_ratio
     {
         return Some(RAGStatus::Amber);
     }
     Some(RAGStatus::Red)
 }
+/// Calculates a cyclomatic-style complexity of the file contents, i.e., 1 plus the number of branching keywords, e.g.,
+/// 'if' or 'while', and operators, e.g., '&&', of the language, outside of comment lines
+pub(crate) fn calculate_complexity(file_contents: &str, language: &str) -> i64 {
+    let branches: &[&str] = LANGUAGE_BRANCHES
+        .iter()
+        .find(|(name, _)| *name == language)
+        .map_or(BRANCHES, |(_, branches)| branches);
+    let (keywords, operators): (Vec<&str>, Vec<&str>) = branches
+        .iter()
+        .partition(|branch| branch.chars().all(char::is_alphanumeric));
+
+    let mut is_comment_block = false;
+    let mut complexity: i64 = 1;
+    for line in file_contents.lines() {
+        let line = line.trim();
+        if line.starts_with("/*") {
+            is_comment_block = true;
+        }
+        if line.ends_with("*/") {
+            is_comment_block = false;
+            continue;
+        }
+        if is_comment_block
+            || COMMENT_PREFIXES
+                .iter()
+                .any(|&prefix| line.starts_with(prefix))
+        {
+            continue;
+        }
+        complexity += line
+            .split(|character: char| !character.is_alphanumeric() && character != '_')
+            .filter(|word| keywords.contains(word))
+            .count() as i64;
+        complexity += operators
+            .iter()
+            .map(|operator| line.matches(operator).count() as i64)
+            .sum::<i64>();
+    }
+    complexity
+}
 /// Calculates the size of the file_contents in bytes
 fn get_file_contents_size(file_contents: impl AsRef<OsStr>) -> Result<u64, &'static str> {
     let content_str = file_contents
@@ -181,7 +274,7 @@ pub(crate) fn calculate_hash_from(content: &str) -> String {
 }
 /// Function to count lines of code in a file, skipping comments and empty lines
 // TODO: shift to using tokei crate to improve maintainability and accuracy
-fn count_lines_of_code(file_content: String) -> Result<i64, &'static str> {
+pub(crate) fn count_lines_of_code(file_content: String) -> Result<i64, &'static str> {
     let mut is_comment_block = false;
     let mut functional_lines = 0;
 
@@ -227,4 +320,63 @@ mod tests {
         let result: Result<i64, &str> = count_lines_of_code(file_content.to_string());
         assert_eq!(result, Ok(3));
     }
+
+    #[test]
+    fn test_calculate_complexity() {
+        let rust = r#"fn main() {
+    // if this were a comment, it would not count
+    if a && b {
+        for x in y {}
+    }
+    match z {
+        Some(_) => {}
+        None => {}
+    }
+}"#;
+        assert_eq!(calculate_complexity(rust, "Rust"), 6);
+        let python =
+            "def f(a):\n    if a and b:\n        return 1\n    elif c:\n        return 2\n";
+        assert_eq!(calculate_complexity(python, "Python"), 4);
+        assert_eq!(calculate_complexity("let verify = 1;", "Java"), 1);
+    }
+
+    #[test]
+    fn test_calculate_rag_status_with_thresholds() {
+        let mut reviewed_file: SourceFileReview = serde_json::from_str(
+            r#"{
+                "source_file_info": { "name": "main.rs", "relative_path": "src/main.rs" },
+                "summary": "A summary",
+                "errors": [{ "code": "parse().unwrap()", "issue": "Unchecked unwrap", "resolution": "Handle the error" }]
+            }"#,
+        )
+        .unwrap();
+        reviewed_file.source_file_info.statistics.loc = 10;
+
+        let default_thresholds = RagThresholds::default();
+        assert_eq!(
+            calculate_rag_status_for_reviewed_file(&reviewed_file, &default_thresholds),
+            Some(RAGStatus::Amber)
+        );
+        let looser_thresholds = RagThresholds {
+            green_error_ratio: 0.1,
+            ..default_thresholds
+        };
+        assert_eq!(
+            calculate_rag_status_for_reviewed_file(&reviewed_file, &looser_thresholds),
+            Some(RAGStatus::Green)
+        );
+        let stricter_thresholds = RagThresholds {
+            amber_error_ratio: 0.05,
+            ..default_thresholds
+        };
+        assert_eq!(
+            calculate_rag_status_for_reviewed_file(&reviewed_file, &stricter_thresholds),
+            Some(RAGStatus::Red)
+        );
+        reviewed_file.source_file_info.coverage = Some(12.0);
+        assert_eq!(
+            calculate_rag_status_for_reviewed_file(&reviewed_file, &looser_thresholds),
+            Some(RAGStatus::Amber)
+        );
+    }
 }
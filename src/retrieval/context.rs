@@ -0,0 +1,131 @@
+//! Builds a short context of the repository, e.g., its predominant language, purpose and directory layout, that is given
+//! with each file review, so the LLM understands the role of the file within the repository.
+use super::data::SourceFileInfo;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The maximum depth of the directories listed in the layout
+const MAX_LAYOUT_DEPTH: usize = 2;
+/// The maximum number of directories listed in the layout, to keep the context short
+const MAX_LAYOUT_ENTRIES: usize = 25;
+
+/// The context of the repository given with each file review
+///
+/// #Fields:
+/// * `predominant_language` - The language with the most lines of code
+/// * `purpose` - The purpose of the repository, as summarised from its README
+/// * `layout` - The directories of the repository, with the number of source files under each
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct RepositoryContext {
+    pub(crate) predominant_language: String,
+    pub(crate) purpose: Option<String>,
+    pub(crate) layout: Vec<String>,
+}
+impl RepositoryContext {
+    pub(crate) fn new(
+        predominant_language: String,
+        purpose: Option<String>,
+        source_files: &[SourceFileInfo],
+    ) -> Self {
+        Self {
+            predominant_language,
+            purpose,
+            layout: get_directory_layout(source_files),
+        }
+    }
+    /// Gets the context as the content of a prompt message
+    pub(crate) fn to_prompt(&self) -> String {
+        let mut prompt =
+            String::from("The context of the repository the source file to review is from:\n");
+        if !self.predominant_language.is_empty() {
+            prompt.push_str(&format!(
+                " predominant language: {}\n",
+                self.predominant_language
+            ));
+        }
+        if let Some(purpose) = &self.purpose {
+            prompt.push_str(&format!(" purpose: {}\n", purpose));
+        }
+        if !self.layout.is_empty() {
+            prompt.push_str(" directory layout:\n");
+            for directory in &self.layout {
+                prompt.push_str(&format!("  {}\n", directory));
+            }
+        }
+        prompt
+    }
+}
+/// Gets the directories, to [`MAX_LAYOUT_DEPTH`], with the number of source files under each, e.g., 'src/review/ (6 files)'
+fn get_directory_layout(source_files: &[SourceFileInfo]) -> Vec<String> {
+    let mut directories: BTreeMap<String, usize> = BTreeMap::new();
+    for source_file in source_files {
+        let Some(parent) = Path::new(&source_file.relative_path).parent() else {
+            continue;
+        };
+        let components: Vec<String> = parent
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().to_string())
+            .collect();
+        for depth in 1..=components.len().min(MAX_LAYOUT_DEPTH) {
+            *directories
+                .entry(format!("{}/", components[..depth].join("/")))
+                .or_default() += 1;
+        }
+    }
+    let mut layout: Vec<String> = directories
+        .iter()
+        .take(MAX_LAYOUT_ENTRIES)
+        .map(|(directory, num_files)| format!("{} ({} files)", directory, num_files))
+        .collect();
+    if directories.len() > MAX_LAYOUT_ENTRIES {
+        layout.push(format!(
+            "... and {} more directories",
+            directories.len() - MAX_LAYOUT_ENTRIES
+        ));
+    }
+    layout
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retrieval::data::{LanguageType, Statistics};
+
+    fn source_file(relative_path: &str) -> SourceFileInfo {
+        SourceFileInfo::new(
+            relative_path.to_string(),
+            relative_path.to_string(),
+            LanguageType {
+                name: "Rust".to_string(),
+                extension: "rs".to_string(),
+                statistics: None,
+            },
+            String::new(),
+            Statistics::new(),
+        )
+    }
+
+    #[test]
+    fn test_repository_context() {
+        let source_files = vec![
+            source_file("build.rs"),
+            source_file("src/main.rs"),
+            source_file("src/review/mod.rs"),
+            source_file("src/review/templates/report.rs"),
+        ];
+        let context = RepositoryContext::new(
+            "Rust".to_string(),
+            Some("A code review tool".to_string()),
+            &source_files,
+        );
+
+        assert_eq!(
+            context.layout,
+            vec!["src/ (3 files)", "src/review/ (2 files)"]
+        );
+        let prompt = context.to_prompt();
+        assert!(prompt.contains("predominant language: Rust"));
+        assert!(prompt.contains("purpose: A code review tool"));
+        assert!(prompt.contains("  src/review/ (2 files)"));
+    }
+}
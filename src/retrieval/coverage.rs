@@ -0,0 +1,179 @@
+//! Reads a test coverage report, in LCOV or Cobertura XML format, e.g., from `cargo llvm-cov --lcov` or `coverage xml`,
+//! to give the line coverage of each source file.
+//!
+//! The paths in a coverage report may be absolute, or relative to a different root than the repository's, so a file is
+//! matched to the path in the report that is its relative path, otherwise to that with the longest matching suffix,
+//! i.e., that ends with its relative path, or the longest that its relative path ends with.
+use super::data::SourceFileInfo;
+use log::info;
+use regex::Regex;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+
+/// The line coverage of each file of a coverage report
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Coverage {
+    /// The lines covered and the lines found, by the path in the report
+    files: HashMap<String, (u64, u64)>,
+}
+impl Coverage {
+    /// Reads the coverage report at the path, in LCOV or Cobertura XML format
+    pub(crate) fn from_path(path: &str) -> Result<Self, Box<dyn Error>> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read the coverage report: {}: {}", path, e))?;
+        let coverage = if contents.trim_start().starts_with('<') {
+            Self::parse_cobertura(&contents)?
+        } else {
+            Self::parse_lcov(&contents)
+        };
+        info!(
+            "Read the coverage of {} files from: {}",
+            coverage.files.len(),
+            path
+        );
+        Ok(coverage)
+    }
+    /// Parses an LCOV tracefile, i.e., an 'SF:' record per file, with its 'DA:' lines, or 'LH:' and 'LF:' totals
+    fn parse_lcov(contents: &str) -> Self {
+        let mut coverage = Self::default();
+        let mut path: Option<&str> = None;
+        let (mut covered, mut found) = (0, 0);
+        let (mut lines_hit, mut lines_found): (Option<u64>, Option<u64>) = (None, None);
+        for line in contents.lines().map(str::trim) {
+            if let Some(source_file) = line.strip_prefix("SF:") {
+                path = Some(source_file);
+                (covered, found, lines_hit, lines_found) = (0, 0, None, None);
+            } else if let Some(data) = line.strip_prefix("DA:") {
+                found += 1;
+                if data
+                    .split(',')
+                    .nth(1)
+                    .and_then(|hits| hits.parse::<u64>().ok())
+                    .is_some_and(|hits| hits > 0)
+                {
+                    covered += 1;
+                }
+            } else if let Some(hit) = line.strip_prefix("LH:") {
+                lines_hit = hit.parse().ok();
+            } else if let Some(total) = line.strip_prefix("LF:") {
+                lines_found = total.parse().ok();
+            } else if line == "end_of_record" {
+                if let Some(path) = path.take() {
+                    coverage.add(
+                        path,
+                        lines_hit.unwrap_or(covered),
+                        lines_found.unwrap_or(found),
+                    );
+                }
+            }
+        }
+        coverage
+    }
+    /// Parses a Cobertura XML report, i.e., a 'class' element per file, each with its 'line' elements and their hits
+    fn parse_cobertura(contents: &str) -> Result<Self, Box<dyn Error>> {
+        let filename = Regex::new(r#"\bfilename="([^"]+)""#)?;
+        let hits = Regex::new(r#"<line\b[^>]*\bhits="(\d+)""#)?;
+        let mut coverage = Self::default();
+        // Each class runs to the next, so its lines are those before the next class
+        for class in contents.split("<class ").skip(1) {
+            let Some(captures) = filename.captures(class) else {
+                continue;
+            };
+            let (mut covered, mut found) = (0, 0);
+            for line_hits in hits.captures_iter(class) {
+                found += 1;
+                if line_hits[1].parse::<u64>().is_ok_and(|hits| hits > 0) {
+                    covered += 1;
+                }
+            }
+            coverage.add(&captures[1], covered, found);
+        }
+        if coverage.files.is_empty() {
+            return Err("No 'class' elements with a 'filename' in the Cobertura report".into());
+        }
+        Ok(coverage)
+    }
+    /// Adds the lines of a file, e.g., one of its classes, to those already found for it
+    fn add(&mut self, path: &str, covered: u64, found: u64) {
+        let lines = self.files.entry(path.replace('\\', "/")).or_insert((0, 0));
+        lines.0 += covered;
+        lines.1 += found;
+    }
+    /// Gets the line coverage of the file at the relative path, as a percentage, or [`None`] if it is not in the report,
+    /// or has no lines. A path that is the relative path is preferred to one with the longest matching suffix, with
+    /// any tie broken by the path, so that the match does not depend on the order of the [`HashMap`].
+    pub(crate) fn get_percentage(&self, relative_path: &str) -> Option<f64> {
+        let (covered, found) = match self.files.get(relative_path) {
+            Some(lines) => lines,
+            None => self
+                .files
+                .iter()
+                .filter(|(path, _)| is_path_of(path, relative_path))
+                .max_by(|(path, _), (other_path, _)| {
+                    path.len()
+                        .min(relative_path.len())
+                        .cmp(&other_path.len().min(relative_path.len()))
+                        .then_with(|| other_path.cmp(path))
+                })
+                .map(|(_, lines)| lines)?,
+        };
+        (*found > 0).then(|| *covered as f64 / *found as f64 * 100.0)
+    }
+    /// Sets the coverage of each source file in the report
+    pub(crate) fn apply(&self, source_files: &mut [SourceFileInfo]) {
+        for file_info in source_files {
+            file_info.coverage = self.get_percentage(&file_info.relative_path);
+        }
+    }
+}
+/// Whether a path in a report, which may be absolute, or relative to a different root, is that of the file at the
+/// relative path, i.e., one ends with the other
+pub(crate) fn is_path_of(path: &str, relative_path: &str) -> bool {
+    path == relative_path
+        || path.ends_with(&format!("/{}", relative_path))
+        || relative_path.ends_with(&format!("/{}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_coverage() {
+        let lcov = Coverage::parse_lcov(
+            "TN:\nSF:/home/ci/repo/src/main.rs\nDA:1,1\nDA:2,0\nDA:3,4\nDA:4,0\nend_of_record\nSF:src/lib.rs\nLH:0\nLF:8\nend_of_record\n",
+        );
+        assert_eq!(lcov.get_percentage("src/main.rs"), Some(50.0));
+        assert_eq!(lcov.get_percentage("src/lib.rs"), Some(0.0));
+        assert_eq!(lcov.get_percentage("src/other.rs"), None);
+
+        // An exact path is preferred, then the longest matching suffix
+        let lcov = Coverage::parse_lcov(
+            "SF:main.rs\nLH:1\nLF:4\nend_of_record\nSF:src/main.rs\nLH:2\nLF:4\nend_of_record\n\
+            SF:app/src/main.rs\nLH:3\nLF:4\nend_of_record\n",
+        );
+        assert_eq!(lcov.get_percentage("src/main.rs"), Some(50.0));
+        assert_eq!(lcov.get_percentage("app/src/main.rs"), Some(75.0));
+        assert_eq!(lcov.get_percentage("lib/src/main.rs"), Some(50.0));
+
+        let cobertura = Coverage::parse_cobertura(
+            r#"<?xml version="1.0" ?>
+            <coverage line-rate="0.75">
+                <packages><package name="app"><classes>
+                    <class name="models.py" filename="app/models.py" line-rate="0.75">
+                        <lines>
+                            <line number="1" hits="1"/>
+                            <line number="2" hits="3"/>
+                            <line number="3" hits="0"/>
+                            <line number="4" hits="2"/>
+                        </lines>
+                    </class>
+                </classes></package></packages>
+            </coverage>"#,
+        )
+        .unwrap();
+        assert_eq!(cobertura.get_percentage("src/app/models.py"), Some(75.0));
+        assert!(Coverage::parse_cobertura("<coverage></coverage>").is_err());
+    }
+}
@@ -3,7 +3,12 @@ use std::{ffi::OsString, fmt, sync::Arc};
 
 use chrono::{DateTime, Utc};
 use linguist::resolver::Language;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::provider::api::TokenUsage;
+use crate::retrieval::linters::Diagnostic;
+use crate::retrieval::markers::MarkerCounts;
+use crate::retrieval::units::CodeUnit;
 
 /// Struct to hold statistics on the code in a repository
 ///
@@ -13,6 +18,10 @@ use serde::{Deserialize, Serialize};
 /// * `num_file` - The number of files in the repository
 /// * `num_commits` - The number of commits in the repository
 /// * `frequency` - The frequency of commits to the repository, as a ratio of commits to total commits in the repository
+/// * `token_usage` - The [`TokenUsage`] of the requests made to the provider, if any
+/// * `complexity` - The cyclomatic-style complexity of a source file, i.e., 1 plus its branches, if calculated
+/// * `test_loc` - The lines of test code in the repository, i.e., in test files and inline test modules
+/// * `test_ratio` - The ratio of test to production lines of code in the repository, if calculated
 #[derive(Clone, Default, Serialize, Deserialize, Debug, PartialEq)]
 pub(crate) struct Statistics {
     pub(crate) size: i64,
@@ -20,6 +29,14 @@ pub(crate) struct Statistics {
     pub(crate) num_files: i32,
     pub(crate) num_commits: i32,
     pub(crate) frequency: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) token_usage: Option<TokenUsage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) complexity: Option<i64>,
+    #[serde(default)]
+    pub(crate) test_loc: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) test_ratio: Option<f32>,
 }
 impl Statistics {
     pub(crate) fn new() -> Self {
@@ -29,6 +46,10 @@ impl Statistics {
             num_files: 0,
             num_commits: 0,
             frequency: 0.0,
+            token_usage: None,
+            complexity: None,
+            test_loc: 0,
+            test_ratio: None,
         }
     }
 }
@@ -60,6 +81,28 @@ impl Contributor {
             statistics,
         }
     }
+    pub(crate) fn get_name(&self) -> &str {
+        &self.name
+    }
+    pub(crate) fn get_last_contribution(&self) -> &DateTime<Utc> {
+        &self.last_contribution
+    }
+    pub(crate) fn get_percentage_contribution(&self) -> f32 {
+        self.percentage_contribution
+    }
+    pub(crate) fn get_statistics(&self) -> &Statistics {
+        &self.statistics
+    }
+}
+/// The number of commits to a repository in a week
+///
+/// # Fields:
+/// * `week` - The date of the Monday of the week, e.g., '2024-01-15'
+/// * `commits` - The number of commits made in the week, by their author date
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct CommitActivity {
+    pub(crate) week: String,
+    pub(crate) commits: i32,
 }
 /// Top-level struct to hold statistics on the [`LanguageType`]s found in the repository.
 /// Each source file will be assigned a [`LanguageType`] based on the language and file extension.
@@ -150,6 +193,11 @@ impl LanguageType {
 /// * `id_hash` - The (SHA256) hash of the file
 /// * `source_file` - The contents of the file in a [`SourceFile`] container
 /// * `statistics` - The [`Statistics`] on the file
+/// * `redacted_secrets` - The kinds of secret redacted from the contents before they were sent for review, if any
+/// * `patch_hunk` - The header of the hunk of a unified diff, e.g., '@@ -10,7 +10,9 @@', if the contents are only that hunk
+/// * `code_unit` - The [`CodeUnit`], i.e., function, of the file, if the contents are only that unit
+/// * `coverage` - The percentage of the file's lines covered by tests, if a coverage report is given
+/// * `diagnostics` - The [`Diagnostic`]s of the linters for the file, if any
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub(crate) struct SourceFileInfo {
     pub(crate) name: String,
@@ -159,8 +207,22 @@ pub(crate) struct SourceFileInfo {
     pub(crate) id_hash: Option<String>,
     #[serde(skip)]
     pub(crate) source_file: Option<Box<SourceFile>>,
-    #[serde(skip_deserializing)]
+    #[serde(default, deserialize_with = "deserialize_file_statistics")]
     pub(crate) statistics: Statistics,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) redacted_secrets: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) patch_hunk: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) code_unit: Option<CodeUnit>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) coverage: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) diagnostics: Option<Vec<Diagnostic>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) markers: Option<MarkerCounts>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) generated: Option<String>,
 }
 impl SourceFileInfo {
     pub(crate) fn new(
@@ -177,6 +239,13 @@ impl SourceFileInfo {
             id_hash: Some(id_hash),
             source_file: None,
             statistics,
+            redacted_secrets: None,
+            patch_hunk: None,
+            code_unit: None,
+            coverage: None,
+            diagnostics: None,
+            markers: None,
+            generated: None,
         }
     }
     pub(crate) fn set_source_file_contents(&mut self, contents: String) {
@@ -199,6 +268,33 @@ impl SourceFileInfo {
         }
     }
 }
+/// Deserialises the [`Statistics`] of a file, e.g., from a report to be merged, or the default if null or not valid, as
+/// the LLM is asked to leave them null in its review
+fn deserialize_file_statistics<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Statistics, D::Error> {
+    let value: Option<serde_json::Value> = Option::deserialize(deserializer)?;
+    Ok(value
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default())
+}
+#[cfg(test)]
+impl SourceFileInfo {
+    /// Gets a [`SourceFileInfo`] of a Rust file, named by its relative path, with an id hash of '0', for tests
+    pub(crate) fn new_rust(relative_path: &str, statistics: Statistics) -> Self {
+        Self::new(
+            relative_path.to_string(),
+            relative_path.to_string(),
+            LanguageType {
+                name: "Rust".to_string(),
+                extension: "rs".to_string(),
+                statistics: None,
+            },
+            "0".to_string(),
+            statistics,
+        )
+    }
+}
 /// Represents the contents of a source file
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct SourceFile {
@@ -224,6 +320,10 @@ impl SourceFileChangeFrequency {
             num_files: 0,
             num_commits: self.file_commits,
             frequency: self.frequency,
+            token_usage: None,
+            complexity: None,
+            test_loc: 0,
+            test_ratio: None,
         }
     }
 }
@@ -256,6 +356,25 @@ impl std::error::Error for SourceFileError {}
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_deserialize_file_statistics() {
+        let file_info: SourceFileInfo = serde_json::from_str(
+            r#"{ "name": "main.rs", "relative_path": "src/main.rs", "statistics": { "size": 100, "loc": 10,
+                "num_files": 1, "num_commits": 2, "frequency": 0.5 } }"#,
+        )
+        .unwrap();
+        assert_eq!(file_info.statistics.loc, 10);
+        // The LLM is asked to leave the statistics null
+        for statistics in ["null", r#""none""#] {
+            let file_info: SourceFileInfo = serde_json::from_str(&format!(
+                r#"{{ "name": "main.rs", "relative_path": "src/main.rs", "statistics": {} }}"#,
+                statistics
+            ))
+            .unwrap();
+            assert_eq!(file_info.statistics, Statistics::new());
+        }
+    }
+
     #[test]
     fn test_has_extension_of() {
         let file_types = vec![
@@ -287,6 +406,10 @@ mod tests {
                     num_files: 3,
                     num_commits: 12,
                     frequency: 12.34,
+                    token_usage: None,
+                    complexity: None,
+                    test_loc: 0,
+                    test_ratio: None,
                 }),
             },
             LanguageType {
@@ -298,6 +421,10 @@ mod tests {
                     num_files: 10,
                     num_commits: 12,
                     frequency: 12.34,
+                    token_usage: None,
+                    complexity: None,
+                    test_loc: 0,
+                    test_ratio: None,
                 }),
             },
         ];
@@ -317,6 +444,10 @@ mod tests {
                     num_files: 3,
                     num_commits: 12,
                     frequency: 12.34,
+                    token_usage: None,
+                    complexity: None,
+                    test_loc: 0,
+                    test_ratio: None,
                 }),
             },
             LanguageType {
@@ -328,6 +459,10 @@ mod tests {
                     num_files: 3,
                     num_commits: 12,
                     frequency: 12.34,
+                    token_usage: None,
+                    complexity: None,
+                    test_loc: 0,
+                    test_ratio: None,
                 }),
             },
             LanguageType {
@@ -339,6 +474,10 @@ mod tests {
                     num_files: 3,
                     num_commits: 12,
                     frequency: 12.34,
+                    token_usage: None,
+                    complexity: None,
+                    test_loc: 0,
+                    test_ratio: None,
                 }),
             },
         ];
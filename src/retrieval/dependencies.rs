@@ -0,0 +1,299 @@
+//! Lists the direct dependencies of a repository, with their versions, from its dependency manifests: 'Cargo.toml',
+//! 'package.json' and 'requirements.txt'.
+//!
+//! The version is as it is required in the manifest, e.g., '^1.2' or '>=2.0', so that its hygiene, e.g., whether it is
+//! pinned, can be assessed. A dependency on a git repository or a local path, rather than a registry, has the version
+//! 'git' or 'path'.
+use serde::{Deserialize, Serialize};
+
+/// The names of the manifests whose dependencies are listed
+pub(crate) const DEPENDENCY_MANIFESTS: [&str; 3] =
+    ["Cargo.toml", "package.json", "requirements.txt"];
+/// The version of a dependency that does not require one
+const ANY_VERSION: &str = "*";
+/// The operators of a requirement specifier in 'requirements.txt', longest first
+const PYTHON_OPERATORS: [&str; 8] = ["===", "==", ">=", "<=", "~=", "!=", ">", "<"];
+
+/// A direct dependency declared in a manifest
+///
+/// #Fields:
+/// * `manifest` - The relative path of the manifest
+/// * `ecosystem` - The package ecosystem, as named by OSV, i.e., 'crates.io', 'npm' or 'PyPI'
+/// * `name` - The name of the package
+/// * `version` - The version required, e.g., '1.0', '^2.3.1' or '==4.2', '*' if any, or 'git' or 'path'
+/// * `dev` - Whether it is only for development, e.g., a dev or build dependency
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct Dependency {
+    pub(crate) manifest: String,
+    pub(crate) ecosystem: String,
+    pub(crate) name: String,
+    pub(crate) version: String,
+    #[serde(default)]
+    pub(crate) dev: bool,
+}
+
+/// Gets the direct dependencies of the manifests, each as its relative path and contents
+pub(crate) fn get_dependencies(manifests: &[(String, String)]) -> Vec<Dependency> {
+    manifests
+        .iter()
+        .flat_map(|(relative_path, contents)| {
+            let file_name: &str = relative_path.rsplit('/').next().unwrap_or(relative_path);
+            match file_name {
+                "Cargo.toml" => get_cargo_dependencies(relative_path, contents),
+                "package.json" => get_npm_dependencies(relative_path, contents),
+                "requirements.txt" => get_python_dependencies(relative_path, contents),
+                _ => Vec::new(),
+            }
+        })
+        .collect()
+}
+/// Gets the dependencies of a 'Cargo.toml', from its '[dependencies]', '[dev-dependencies]' and '[build-dependencies]'
+/// tables, including those of a target or the workspace, as a 'name = "version"' or an inline table, or as a
+/// '[dependencies.name]' table
+fn get_cargo_dependencies(relative_path: &str, contents: &str) -> Vec<Dependency> {
+    let mut dependencies: Vec<Dependency> = Vec::new();
+    // The kind of dependency table, i.e., whether dev, and, for a '[dependencies.name]' table, the name
+    let mut table: Option<(bool, Option<String>)> = None;
+    for line in contents
+        .lines()
+        .map(|line| line.split(" #").next().unwrap_or(line).trim())
+    {
+        if let Some(header) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            table = get_cargo_dependency_table(header);
+            if let Some((dev, Some(name))) = &table {
+                dependencies.push(Dependency {
+                    manifest: relative_path.to_string(),
+                    ecosystem: "crates.io".to_string(),
+                    name: name.clone(),
+                    version: ANY_VERSION.to_string(),
+                    dev: *dev,
+                });
+            }
+            continue;
+        }
+        let Some((dev, name)) = &table else {
+            continue;
+        };
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let (key, value) = (key.trim().trim_matches('"'), value.trim());
+        match name {
+            // A '[dependencies.name]' table has the dependency's keys, e.g., 'version'
+            Some(_) => {
+                if let (Some(dependency), Some(version)) =
+                    (dependencies.last_mut(), get_cargo_version(key, value))
+                {
+                    if key == "version" || dependency.version == ANY_VERSION {
+                        dependency.version = version;
+                    }
+                }
+            }
+            None => {
+                // A dotted key, e.g., 'serde.workspace = true', is a key of the dependency's table
+                let (key, version): (&str, Option<String>) = match key.split_once('.') {
+                    Some((key, dotted)) => (key, get_cargo_version(dotted, value)),
+                    None => (key, Some(get_cargo_inline_version(value))),
+                };
+                if let Some(version) = version {
+                    dependencies.push(Dependency {
+                        manifest: relative_path.to_string(),
+                        ecosystem: "crates.io".to_string(),
+                        name: key.to_string(),
+                        version,
+                        dev: *dev,
+                    });
+                }
+            }
+        }
+    }
+    dependencies
+}
+/// Gets whether the table is of dev dependencies, and the name of the dependency, if it is a '[dependencies.name]'
+/// table, or [`None`] if it is not a dependency table
+fn get_cargo_dependency_table(header: &str) -> Option<(bool, Option<String>)> {
+    let header: &str = header.trim();
+    let header: &str = header
+        .strip_prefix("workspace.")
+        .or_else(|| header.strip_prefix("target.").and_then(strip_cargo_target))
+        .unwrap_or(header);
+    let (table, name) = match header.split_once('.') {
+        Some((table, name)) => (table, Some(name.trim_matches('"').to_string())),
+        None => (header, None),
+    };
+    match table {
+        "dependencies" => Some((false, name)),
+        "dev-dependencies" | "build-dependencies" => Some((true, name)),
+        _ => None,
+    }
+}
+/// Strips the target from a target's table, e.g., 'x86_64-pc-windows-msvc.dependencies', or a quoted 'cfg', which may
+/// have dots, e.g., "'cfg(target_os = \"linux\")'.dependencies"
+fn strip_cargo_target(target: &str) -> Option<&str> {
+    match target.chars().next() {
+        Some(quote @ ('\'' | '"')) => target[1..]
+            .split_once(quote)
+            .and_then(|(_, table)| table.strip_prefix('.')),
+        _ => target.split_once('.').map(|(_, table)| table),
+    }
+}
+/// Gets the version of a dependency from a key of its table, e.g., 'version = "1.0"', or 'git' or 'path'
+fn get_cargo_version(key: &str, value: &str) -> Option<String> {
+    match key {
+        "version" => Some(value.trim_matches('"').to_string()),
+        "git" | "path" => Some(key.to_string()),
+        "workspace" => Some("workspace".to_string()),
+        _ => None,
+    }
+}
+/// Gets the version of a dependency from its value, either '"1.0"' or an inline table, e.g., '{ version = "1.0" }'
+fn get_cargo_inline_version(value: &str) -> String {
+    let Some(table) = value.strip_prefix('{') else {
+        return value.trim_matches('"').to_string();
+    };
+    let mut version: Option<String> = None;
+    for (key, value) in table
+        .trim_end_matches('}')
+        .split(',')
+        .filter_map(|pair| pair.split_once('='))
+    {
+        if let Some(key_version) = get_cargo_version(key.trim(), value.trim()) {
+            if key.trim() == "version" || version.is_none() {
+                version = Some(key_version);
+            }
+        }
+    }
+    version.unwrap_or_else(|| ANY_VERSION.to_string())
+}
+/// Gets the dependencies of a 'package.json', from its 'dependencies' and 'devDependencies'
+fn get_npm_dependencies(relative_path: &str, contents: &str) -> Vec<Dependency> {
+    let Ok(manifest) = serde_json::from_str::<serde_json::Value>(contents) else {
+        return Vec::new();
+    };
+    [("dependencies", false), ("devDependencies", true)]
+        .into_iter()
+        .filter_map(|(key, dev)| Some((manifest[key].as_object()?, dev)))
+        .flat_map(|(packages, dev)| {
+            packages.iter().map(move |(name, version)| Dependency {
+                manifest: relative_path.to_string(),
+                ecosystem: "npm".to_string(),
+                name: name.clone(),
+                version: version.as_str().unwrap_or(ANY_VERSION).to_string(),
+                dev,
+            })
+        })
+        .collect()
+}
+/// Gets the dependencies of a 'requirements.txt', i.e., a requirement specifier per line, ignoring options, e.g., '-r',
+/// comments, extras and environment markers
+fn get_python_dependencies(relative_path: &str, contents: &str) -> Vec<Dependency> {
+    contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or(line).trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('-'))
+        .filter_map(|line| {
+            let requirement: &str = line.split(';').next().unwrap_or(line).trim();
+            let (name, version) = match PYTHON_OPERATORS
+                .iter()
+                .filter_map(|operator| requirement.find(operator))
+                .min()
+            {
+                Some(index) => (&requirement[..index], requirement[index..].replace(' ', "")),
+                None if requirement.contains("://") || requirement.contains(" @ ") => (
+                    requirement.split(" @ ").next().unwrap_or(requirement),
+                    "git".to_string(),
+                ),
+                None => (requirement, ANY_VERSION.to_string()),
+            };
+            let name: &str = name.split('[').next().unwrap_or(name).trim();
+            (!name.is_empty() && !name.contains("://")).then(|| Dependency {
+                manifest: relative_path.to_string(),
+                ecosystem: "PyPI".to_string(),
+                name: name.to_string(),
+                version,
+                dev: false,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn versions(dependencies: &[Dependency]) -> Vec<(&str, &str, bool)> {
+        dependencies
+            .iter()
+            .map(|dependency| {
+                (
+                    dependency.name.as_str(),
+                    dependency.version.as_str(),
+                    dependency.dev,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_get_dependencies() {
+        let cargo = r#"
+[package]
+name = "app"
+version = "0.1.0"
+
+[dependencies]
+serde = { version = "1.0", features = ["derive"] }
+log = "0.4" # logging
+linguist-rs = { git = "https://github.com/example/linguist-rs.git" }
+
+[dependencies.tokio]
+version = "1"
+features = ["full"]
+
+[target.'cfg(unix)'.dependencies]
+nix = "0.27"
+
+[dev-dependencies]
+tempfile = "3"
+"#;
+        let dependencies = get_dependencies(&[("Cargo.toml".to_string(), cargo.to_string())]);
+        assert_eq!(
+            versions(&dependencies),
+            vec![
+                ("serde", "1.0", false),
+                ("log", "0.4", false),
+                ("linguist-rs", "git", false),
+                ("tokio", "1", false),
+                ("nix", "0.27", false),
+                ("tempfile", "3", true),
+            ]
+        );
+        assert_eq!(dependencies[0].ecosystem, "crates.io");
+
+        let npm = r#"{"name": "web", "dependencies": {"react": "^18.2.0"}, "devDependencies": {"jest": "29.7.0"}}"#;
+        assert_eq!(
+            versions(&get_dependencies(&[(
+                "web/package.json".to_string(),
+                npm.to_string()
+            )])),
+            vec![("react", "^18.2.0", false), ("jest", "29.7.0", true)]
+        );
+
+        let python = "-r base.txt\n# pinned\nrequests==2.31.0\nDjango >= 4.2, <5 ; python_version > '3.8'\nuvicorn[standard]\nmylib @ git+https://github.com/example/mylib.git\n";
+        assert_eq!(
+            versions(&get_dependencies(&[(
+                "requirements.txt".to_string(),
+                python.to_string()
+            )])),
+            vec![
+                ("requests", "==2.31.0", false),
+                ("Django", ">=4.2,<5", false),
+                ("uvicorn", "*", false),
+                ("mylib", "git", false),
+            ]
+        );
+    }
+}
@@ -0,0 +1,307 @@
+//! Retrieves the documentation of a repository, e.g., the README, that describes its purpose, and measures how much of
+//! its code is documented.
+//!
+//! The documentation coverage is the share of public items, e.g., a Rust `pub fn` or a Python `def`, that have a doc
+//! comment or docstring, for the languages whose public items are recognised. Like the units of a file, an item is
+//! found by its declaration, so the measure is approximate.
+use super::data::SourceFileInfo;
+use crate::review::data::Documentation;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// The README file names, in order of preference
+const README_NAMES: [&str; 6] = [
+    "README.md",
+    "README.rst",
+    "README.txt",
+    "README.adoc",
+    "README.org",
+    "README",
+];
+/// The maximum number of characters of the README sent for summary, as the opening sections describe the purpose
+const MAX_README_CHARS: usize = 12_000;
+
+/// The extensions of documentation files, e.g., Markdown, which are not source files
+const DOCUMENTATION_EXTENSIONS: [&str; 5] = ["md", "markdown", "rst", "adoc", "org"];
+/// The names of the directories whose files are documentation
+const DOCUMENTATION_DIRS: [&str; 2] = ["docs", "doc"];
+/// The starts of a doc comment before a declaration, i.e., of its lines, e.g., Rust's or C#'s '///', or of the block
+/// comment, i.e., '/**', e.g., Javadoc or JSDoc. A plain comment, e.g., '//' or '/*', is not a doc comment.
+const DOC_COMMENT_PREFIXES: [&str; 4] = ["///", "//!", "/**", "#[doc"];
+/// The starts of a Go doc comment, which is any comment directly before a declaration
+const GO_DOC_COMMENT_PREFIXES: [&str; 2] = ["//", "/*"];
+/// The number of lines after a Python declaration searched for the end of its signature
+const MAX_SIGNATURE_LINES: usize = 10;
+
+/// The documentation coverage of a language, or of the repository as a whole
+///
+/// #Fields:
+/// * `language` - The name of the language, e.g., 'Rust', or [`None`] for the repository as a whole
+/// * `public_items` - The number of public items, e.g., functions and types, found
+/// * `documented_items` - The number of public items with a doc comment or docstring
+/// * `doc_files` - The number of documentation files, e.g., Markdown, of the repository as a whole
+/// * `languages` - The coverage of each language with public items, of the repository as a whole
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub(crate) struct DocumentationCoverage {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) language: Option<String>,
+    pub(crate) public_items: i32,
+    pub(crate) documented_items: i32,
+    #[serde(default)]
+    pub(crate) doc_files: i32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) languages: Vec<DocumentationCoverage>,
+}
+impl DocumentationCoverage {
+    /// The percentage of public items documented, or [`None`] if none are found
+    pub(crate) fn get_percentage(&self) -> Option<f32> {
+        (self.public_items > 0)
+            .then(|| self.documented_items as f32 / self.public_items as f32 * 100.0)
+    }
+    /// Gets the [`Documentation`] rating: 'Excellent' for 80% of public items documented, with documentation files,
+    /// 'Good' for 50%, 'Some' for 10%, or for documentation files alone, otherwise 'None'
+    pub(crate) fn get_documentation(&self) -> Documentation {
+        let percentage: f32 = self.get_percentage().unwrap_or(0.0);
+        if percentage >= 80.0 && self.doc_files > 0 {
+            Documentation::Excellent
+        } else if percentage >= 50.0 {
+            Documentation::Good
+        } else if percentage >= 10.0 || self.doc_files > 0 {
+            Documentation::Some
+        } else {
+            Documentation::None
+        }
+    }
+}
+/// Where a language documents its public items
+enum DocPosition {
+    /// In a comment on the lines before the declaration, above any attributes or annotations, starting with any of the
+    /// prefixes
+    Before(&'static [&'static str]),
+    /// In a docstring on the line after the declaration's signature
+    After,
+}
+
+/// Gets the [`DocumentationCoverage`] of the source files, with the number of documentation files of the repository
+pub(crate) fn get_documentation_coverage(
+    source_files: &[SourceFileInfo],
+    doc_files: usize,
+) -> DocumentationCoverage {
+    let mut languages: Vec<DocumentationCoverage> = Vec::new();
+    for file_info in source_files {
+        let Some(language) = file_info.language.as_ref().map(|language| &language.name) else {
+            continue;
+        };
+        let Some((public_items, documented_items)) =
+            count_public_items(&file_info.get_source_file_contents(), language)
+        else {
+            continue;
+        };
+        let index: usize = match languages
+            .iter()
+            .position(|coverage| coverage.language.as_ref() == Some(language))
+        {
+            Some(index) => index,
+            None => {
+                languages.push(DocumentationCoverage {
+                    language: Some(language.clone()),
+                    ..Default::default()
+                });
+                languages.len() - 1
+            }
+        };
+        languages[index].public_items += public_items;
+        languages[index].documented_items += documented_items;
+    }
+    languages.retain(|coverage| coverage.public_items > 0);
+    languages.sort_by(|a, b| a.language.cmp(&b.language));
+    DocumentationCoverage {
+        language: None,
+        public_items: languages.iter().map(|coverage| coverage.public_items).sum(),
+        documented_items: languages
+            .iter()
+            .map(|coverage| coverage.documented_items)
+            .sum(),
+        doc_files: doc_files as i32,
+        languages,
+    }
+}
+/// Whether the file, by its relative path, is documentation, e.g., Markdown, or in a 'docs' directory
+pub(crate) fn is_documentation_file(relative_path: &str) -> bool {
+    let path: &Path = Path::new(relative_path);
+    let is_documentation_extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            DOCUMENTATION_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+        });
+    let is_in_documentation_dir = path
+        .parent()
+        .and_then(|parent| parent.components().next())
+        .and_then(|dir| dir.as_os_str().to_str())
+        .is_some_and(|dir| DOCUMENTATION_DIRS.contains(&dir));
+    is_documentation_extension || is_in_documentation_dir
+}
+/// Counts the public items of the contents, and those documented, or [`None`] if the language's public items are not
+/// recognised
+fn count_public_items(contents: &str, language: &str) -> Option<(i32, i32)> {
+    let (declaration, position): (&str, DocPosition) = match language {
+        "Rust" => (
+            r"^\s*pub(\([^)]*\))?\s+((async|const|unsafe|extern)\s+)*(fn|struct|enum|trait|mod|type|const|static|union)\s",
+            DocPosition::Before(&DOC_COMMENT_PREFIXES),
+        ),
+        "Go" => (
+            r"^(func|type)\s+(\([^)]*\)\s*)?[A-Z]",
+            DocPosition::Before(&GO_DOC_COMMENT_PREFIXES),
+        ),
+        "JavaScript" | "TypeScript" | "TSX" | "JSX" => {
+            (r"^\s*export\s", DocPosition::Before(&DOC_COMMENT_PREFIXES))
+        }
+        "Java" | "Kotlin" | "C#" | "Scala" => {
+            (r"^\s*public\s", DocPosition::Before(&DOC_COMMENT_PREFIXES))
+        }
+        "Python" => (r"^\s*(async\s+)?(def|class)\s+[A-Za-z]", DocPosition::After),
+        _ => return None,
+    };
+    let declaration: Regex = Regex::new(declaration).ok()?;
+    let lines: Vec<&str> = contents.lines().collect();
+    let mut public_items: i32 = 0;
+    let mut documented_items: i32 = 0;
+    for (index, line) in lines.iter().enumerate() {
+        if !declaration.is_match(line) {
+            continue;
+        }
+        public_items += 1;
+        let documented: bool = match position {
+            DocPosition::Before(prefixes) => is_documented_before(&lines, index, prefixes),
+            DocPosition::After => is_documented_after(&lines, index),
+        };
+        if documented {
+            documented_items += 1;
+        }
+    }
+    Some((public_items, documented_items))
+}
+/// Whether the declaration at the index has a doc comment before it, above any attributes or annotations, i.e., a line,
+/// or a block comment, starting with any of the prefixes
+fn is_documented_before(lines: &[&str], index: usize, prefixes: &[&str]) -> bool {
+    let Some(end) = lines[..index].iter().rposition(|line| {
+        let line: &str = line.trim();
+        !(line.starts_with("#[") && !line.starts_with("#[doc")) && !line.starts_with('@')
+    }) else {
+        return false;
+    };
+    // A block comment is a doc comment if the line that starts it does, e.g., '/**', not '/*'
+    let start: Option<&str> = if lines[end].trim().ends_with("*/") {
+        lines[..=end]
+            .iter()
+            .rev()
+            .map(|line| line.trim())
+            .find(|line| line.contains("/*"))
+    } else {
+        Some(lines[end].trim())
+    };
+    start.is_some_and(|line| prefixes.iter().any(|prefix| line.starts_with(prefix)))
+}
+/// Whether the declaration at the index has a docstring on the line after its signature, i.e., the line ending ':'
+fn is_documented_after(lines: &[&str], index: usize) -> bool {
+    let Some(signature_end) = lines[index..]
+        .iter()
+        .take(MAX_SIGNATURE_LINES)
+        .position(|line| line.trim_end().ends_with(':'))
+    else {
+        return false;
+    };
+    lines[index + signature_end + 1..]
+        .iter()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty())
+        .is_some_and(|line| {
+            ["\"\"\"", "'''", "r\"\"\"", "r'''"]
+                .iter()
+                .any(|quote| line.starts_with(quote))
+        })
+}
+/// Finds the README in the root of the repository, matching the name regardless of case, e.g., 'readme.md'
+pub(crate) fn find_readme(repository_root: &Path) -> Option<PathBuf> {
+    let entries: Vec<PathBuf> = std::fs::read_dir(repository_root)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+    README_NAMES.iter().find_map(|name| {
+        entries
+            .iter()
+            .find(|path| {
+                path.file_name()
+                    .and_then(|file_name| file_name.to_str())
+                    .is_some_and(|file_name| file_name.eq_ignore_ascii_case(name))
+            })
+            .cloned()
+    })
+}
+/// Reads the README in the root of the repository, if any, truncated to [`MAX_README_CHARS`]
+pub(crate) fn read_readme(repository_root: &Path) -> Option<(PathBuf, String)> {
+    let readme_path = find_readme(repository_root)?;
+    let contents = std::fs::read_to_string(&readme_path).ok()?;
+    if contents.trim().is_empty() {
+        return None;
+    }
+    Some((
+        readme_path,
+        contents.chars().take(MAX_README_CHARS).collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_count_public_items() {
+        let rust = "/// Documented\npub fn a() {}\n#[derive(Debug)]\npub struct B;\n/// Documented\n#[derive(Debug)]\npub(crate) enum C {}\nfn private() {}\n";
+        assert_eq!(count_public_items(rust, "Rust"), Some((3, 2)));
+        // A plain comment is not a doc comment
+        let rust = "// TODO: document\npub fn a() {}\n/* Not a doc */\npub fn b() {}\n/**\n * Documented\n */\npub fn c() {}\n";
+        assert_eq!(count_public_items(rust, "Rust"), Some((3, 1)));
+
+        let typescript = "/** Documented */\nexport const a = 1;\n/*\n * Not a doc\n */\nexport function b() {}\n";
+        assert_eq!(count_public_items(typescript, "TypeScript"), Some((2, 1)));
+
+        let python = "def a():\n    \"\"\"Documented\"\"\"\n\nclass B(\n    Base,\n):\n    '''Documented'''\n\ndef c():\n    return 1\n";
+        assert_eq!(count_public_items(python, "Python"), Some((3, 2)));
+
+        let go = "// A documents\nfunc A() {}\nfunc b() {}\ntype C struct {}\n";
+        assert_eq!(count_public_items(go, "Go"), Some((2, 1)));
+        assert_eq!(count_public_items("SELECT 1;", "SQL"), None);
+
+        let mut coverage = DocumentationCoverage {
+            public_items: 10,
+            documented_items: 8,
+            ..Default::default()
+        };
+        assert_eq!(coverage.get_documentation(), Documentation::Good);
+        coverage.doc_files = 1;
+        assert_eq!(coverage.get_documentation(), Documentation::Excellent);
+        assert!(is_documentation_file("docs/guide/setup.txt"));
+        assert!(is_documentation_file("CHANGELOG.md"));
+        assert!(!is_documentation_file("src/main.rs"));
+    }
+
+    #[test]
+    fn test_find_readme() {
+        let dir = tempdir().unwrap();
+        assert!(find_readme(dir.path()).is_none());
+
+        std::fs::write(dir.path().join("readme"), "A plain readme").unwrap();
+        std::fs::write(dir.path().join("readme.md"), "# A markdown readme").unwrap();
+        let readme_path = find_readme(dir.path()).unwrap();
+        assert_eq!(readme_path.file_name().unwrap(), "readme.md");
+
+        let (_, contents) = read_readme(dir.path()).unwrap();
+        assert_eq!(contents, "# A markdown readme");
+    }
+}
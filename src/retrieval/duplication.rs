@@ -0,0 +1,346 @@
+//! Detects duplicated and near-duplicated code across the repository, without the LLM.
+//!
+//! Files with the same content hash are identical. Otherwise each file is broken into shingles, i.e., overlapping
+//! windows of [`SHINGLE_LINES`] normalised lines, and:
+//! - a MinHash signature of its shingles estimates its similarity to each other file, with locality sensitive hashing,
+//!   i.e., banding, so that only likely pairs are compared
+//! - a shingle found more than once marks its lines as duplicated, which gives the duplicated blocks, and the
+//!   duplication percentage of the repository
+//!
+//! Lines are normalised by trimming them, and those without any alphanumeric character, e.g., '}', are ignored.
+use super::data::SourceFileInfo;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap};
+use std::hash::{Hash, Hasher};
+
+/// The number of consecutive normalised lines in a shingle, i.e., the smallest duplicated block detected
+const SHINGLE_LINES: usize = 6;
+/// The number of hashes in the MinHash signature of a file
+const NUM_HASHES: usize = 64;
+/// The number of bands the signature is split into to find the likely pairs of similar files
+const NUM_BANDS: usize = 16;
+/// The estimated similarity from which two files are near duplicates
+const NEAR_DUPLICATE_SIMILARITY: f64 = 0.8;
+/// The maximum number of duplicated blocks listed, longest first
+const MAX_DUPLICATED_BLOCKS: usize = 50;
+
+/// The duplication found across the repository
+///
+/// #Fields:
+/// * `percentage` - The percentage of normalised lines that are in a duplicated block
+/// * `duplicated_lines` - The number of normalised lines that are in a duplicated block
+/// * `total_lines` - The number of normalised lines of all files
+/// * `duplicate_files` - The sets of identical, or near-identical, files
+/// * `duplicated_blocks` - The longest [`DuplicatedBlock`]s, up to [`MAX_DUPLICATED_BLOCKS`]
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub(crate) struct Duplication {
+    pub(crate) percentage: f64,
+    pub(crate) duplicated_lines: usize,
+    pub(crate) total_lines: usize,
+    pub(crate) duplicate_files: Vec<DuplicateFiles>,
+    pub(crate) duplicated_blocks: Vec<DuplicatedBlock>,
+}
+/// A set of files that are identical, with a similarity of 100%, or near-identical, with the estimated similarity
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct DuplicateFiles {
+    pub(crate) files: Vec<String>,
+    pub(crate) similarity: f64,
+}
+/// A block of lines of a file that is duplicated, with the other files it is also in, or the file itself, if it is
+/// duplicated only within the file
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct DuplicatedBlock {
+    pub(crate) relative_path: String,
+    pub(crate) start_line: usize,
+    pub(crate) end_line: usize,
+    pub(crate) also_in: Vec<String>,
+}
+
+/// The normalised lines of a file, each with its line number, from 1
+struct NormalisedFile<'a> {
+    file_info: &'a SourceFileInfo,
+    lines: Vec<(usize, String)>,
+}
+impl<'a> NormalisedFile<'a> {
+    fn new(file_info: &'a SourceFileInfo) -> Self {
+        let lines = file_info
+            .get_source_file_contents()
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim().to_string()))
+            .filter(|(_, line)| line.chars().any(char::is_alphanumeric))
+            .collect();
+        Self { file_info, lines }
+    }
+    /// The hash of each shingle, in the order of the lines
+    fn get_shingles(&self) -> Vec<u64> {
+        if self.lines.len() < SHINGLE_LINES {
+            return Vec::new();
+        }
+        self.lines
+            .windows(SHINGLE_LINES)
+            .map(|window| {
+                let mut hasher = DefaultHasher::new();
+                for (_, line) in window {
+                    line.hash(&mut hasher);
+                }
+                hasher.finish()
+            })
+            .collect()
+    }
+}
+
+/// Detects the duplicated files and blocks of the source files
+pub(crate) fn detect_duplication(source_files: &[SourceFileInfo]) -> Duplication {
+    let files: Vec<NormalisedFile> = source_files.iter().map(NormalisedFile::new).collect();
+    let shingles: Vec<Vec<u64>> = files.iter().map(NormalisedFile::get_shingles).collect();
+
+    let mut duplicate_files: Vec<DuplicateFiles> = get_identical_files(&files);
+    duplicate_files.extend(get_near_duplicate_files(&files, &shingles));
+
+    // Where each shingle is found, as the index of the file and the position of the shingle in it
+    let mut occurrences: HashMap<u64, Vec<(usize, usize)>> = HashMap::new();
+    for (file_index, file_shingles) in shingles.iter().enumerate() {
+        for (position, shingle) in file_shingles.iter().enumerate() {
+            occurrences
+                .entry(*shingle)
+                .or_default()
+                .push((file_index, position));
+        }
+    }
+    // Per file, whether each normalised line is duplicated, and the other files of the shingles starting at each line
+    let mut duplicated: Vec<Vec<bool>> = files
+        .iter()
+        .map(|file| vec![false; file.lines.len()])
+        .collect();
+    let mut also_in: Vec<HashMap<usize, BTreeSet<usize>>> = vec![HashMap::new(); files.len()];
+    for locations in occurrences.values().filter(|locations| locations.len() > 1) {
+        for (file_index, position) in locations {
+            duplicated[*file_index][*position..*position + SHINGLE_LINES].fill(true);
+            let others = also_in[*file_index].entry(*position).or_default();
+            for (other_index, other_position) in locations {
+                if other_index != file_index || other_position != position {
+                    others.insert(*other_index);
+                }
+            }
+        }
+    }
+
+    let mut duplicated_blocks: Vec<DuplicatedBlock> = Vec::new();
+    for (file_index, file) in files.iter().enumerate() {
+        let mut position = 0;
+        while position < file.lines.len() {
+            if !duplicated[file_index][position] {
+                position += 1;
+                continue;
+            }
+            let start = position;
+            while position < file.lines.len() && duplicated[file_index][position] {
+                position += 1;
+            }
+            let others: BTreeSet<usize> = (start..position)
+                .filter_map(|position| also_in[file_index].get(&position))
+                .flatten()
+                .copied()
+                .collect();
+            duplicated_blocks.push(DuplicatedBlock {
+                relative_path: file.file_info.relative_path.clone(),
+                start_line: file.lines[start].0,
+                end_line: file.lines[position - 1].0,
+                also_in: others
+                    .iter()
+                    .map(|other| files[*other].file_info.relative_path.clone())
+                    .collect(),
+            });
+        }
+    }
+    duplicated_blocks.sort_by_key(|block| std::cmp::Reverse(block.end_line - block.start_line));
+    duplicated_blocks.truncate(MAX_DUPLICATED_BLOCKS);
+
+    let duplicated_lines: usize = duplicated.iter().flatten().filter(|line| **line).count();
+    let total_lines: usize = files.iter().map(|file| file.lines.len()).sum();
+    Duplication {
+        percentage: if total_lines == 0 {
+            0.0
+        } else {
+            duplicated_lines as f64 / total_lines as f64 * 100.0
+        },
+        duplicated_lines,
+        total_lines,
+        duplicate_files,
+        duplicated_blocks,
+    }
+}
+/// Gets the sets of files with the same content hash, i.e., identical files, ignoring those without any lines
+fn get_identical_files(files: &[NormalisedFile]) -> Vec<DuplicateFiles> {
+    let mut by_hash: HashMap<&str, Vec<String>> = HashMap::new();
+    for file in files.iter().filter(|file| !file.lines.is_empty()) {
+        if let Some(id_hash) = &file.file_info.id_hash {
+            by_hash
+                .entry(id_hash)
+                .or_default()
+                .push(file.file_info.relative_path.clone());
+        }
+    }
+    let mut identical_files: Vec<DuplicateFiles> = by_hash
+        .into_values()
+        .filter(|files| files.len() > 1)
+        .map(|files| DuplicateFiles {
+            files,
+            similarity: 100.0,
+        })
+        .collect();
+    identical_files.sort_by(|a, b| a.files.cmp(&b.files));
+    identical_files
+}
+/// Gets the pairs of files, that are not identical, whose estimated similarity is at least [`NEAR_DUPLICATE_SIMILARITY`]
+fn get_near_duplicate_files(
+    files: &[NormalisedFile],
+    shingles: &[Vec<u64>],
+) -> Vec<DuplicateFiles> {
+    let signatures: Vec<Option<Vec<u64>>> = shingles
+        .iter()
+        .map(|file_shingles| get_minhash_signature(file_shingles))
+        .collect();
+    let rows: usize = NUM_HASHES / NUM_BANDS;
+    let mut candidates: BTreeSet<(usize, usize)> = BTreeSet::new();
+    for band in 0..NUM_BANDS {
+        let mut buckets: HashMap<&[u64], Vec<usize>> = HashMap::new();
+        for (file_index, signature) in signatures.iter().enumerate() {
+            if let Some(signature) = signature {
+                buckets
+                    .entry(&signature[band * rows..(band + 1) * rows])
+                    .or_default()
+                    .push(file_index);
+            }
+        }
+        for bucket in buckets.values() {
+            for (i, first) in bucket.iter().enumerate() {
+                for second in &bucket[i + 1..] {
+                    candidates.insert((*first, *second));
+                }
+            }
+        }
+    }
+
+    let mut near_duplicates: Vec<DuplicateFiles> = Vec::new();
+    for (first, second) in candidates {
+        if files[first].file_info.id_hash == files[second].file_info.id_hash {
+            continue;
+        }
+        let (Some(first_signature), Some(second_signature)) =
+            (&signatures[first], &signatures[second])
+        else {
+            continue;
+        };
+        let similarity = first_signature
+            .iter()
+            .zip(second_signature)
+            .filter(|(a, b)| a == b)
+            .count() as f64
+            / NUM_HASHES as f64;
+        if similarity >= NEAR_DUPLICATE_SIMILARITY {
+            near_duplicates.push(DuplicateFiles {
+                files: vec![
+                    files[first].file_info.relative_path.clone(),
+                    files[second].file_info.relative_path.clone(),
+                ],
+                similarity: similarity * 100.0,
+            });
+        }
+    }
+    near_duplicates
+}
+/// Gets the MinHash signature of the shingles, i.e., the minimum of each of [`NUM_HASHES`] seeded hashes of them, or
+/// [`None`] if there are no shingles
+fn get_minhash_signature(shingles: &[u64]) -> Option<Vec<u64>> {
+    if shingles.is_empty() {
+        return None;
+    }
+    Some(
+        (0..NUM_HASHES as u64)
+            .map(|seed| {
+                shingles
+                    .iter()
+                    .map(|shingle| {
+                        let mut hasher = DefaultHasher::new();
+                        seed.hash(&mut hasher);
+                        shingle.hash(&mut hasher);
+                        hasher.finish()
+                    })
+                    .min()
+                    .unwrap_or(u64::MAX)
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retrieval::code::calculate_hash_from;
+    use crate::retrieval::data::{LanguageType, Statistics};
+
+    fn source_file(relative_path: &str, contents: &str) -> SourceFileInfo {
+        let mut file_info = SourceFileInfo::new(
+            relative_path.to_string(),
+            relative_path.to_string(),
+            LanguageType {
+                name: "Rust".to_string(),
+                extension: "rs".to_string(),
+                statistics: None,
+            },
+            calculate_hash_from(contents),
+            Statistics::new(),
+        );
+        file_info.set_source_file_contents(contents.to_string());
+        file_info
+    }
+
+    #[test]
+    fn test_detect_duplication() {
+        let shared: String = (1..=20)
+            .map(|line| format!("let value_{} = compute({});\n", line, line))
+            .collect();
+        let unique: String = (1..=20)
+            .map(|line| format!("let other_{} = other({});\n", line, line))
+            .collect();
+        let source_files = vec![
+            source_file("src/a.rs", &shared),
+            source_file("src/b.rs", &shared),
+            source_file("src/c.rs", &format!("{}let extra = 1;\n", shared)),
+            source_file("src/d.rs", &unique),
+        ];
+        let duplication = detect_duplication(&source_files);
+
+        assert_eq!(
+            duplication.duplicate_files[0],
+            DuplicateFiles {
+                files: vec!["src/a.rs".to_string(), "src/b.rs".to_string()],
+                similarity: 100.0,
+            }
+        );
+        assert!(duplication.duplicate_files.iter().any(|duplicate_files| {
+            duplicate_files.similarity < 100.0
+                && duplicate_files.files.contains(&"src/c.rs".to_string())
+        }));
+        assert!(!duplication
+            .duplicate_files
+            .iter()
+            .any(|duplicate_files| duplicate_files.files.contains(&"src/d.rs".to_string())));
+
+        assert_eq!(duplication.total_lines, 81);
+        assert_eq!(duplication.duplicated_lines, 60);
+        let block = duplication
+            .duplicated_blocks
+            .iter()
+            .find(|block| block.relative_path == "src/c.rs")
+            .unwrap();
+        assert_eq!((block.start_line, block.end_line), (1, 20));
+        assert_eq!(
+            block.also_in,
+            vec!["src/a.rs".to_string(), "src/b.rs".to_string()]
+        );
+    }
+}
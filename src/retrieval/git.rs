@@ -2,12 +2,27 @@
 
 /// Functions to gather data on the 'git' repository
 pub(crate) mod repository {
-    use crate::retrieval::data::SourceFileError;
-    use git2::{Commit, Repository, Revwalk};
-    use log::{debug, warn};
-    use std::fs;
+    use crate::retrieval::data::{CommitActivity, SourceFileError};
+    use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, Utc};
+    use git2::{Commit, Repository, RepositoryOpenFlags, Revwalk};
+    use ignore::{DirEntry, WalkBuilder};
+    use log::warn;
+    use std::ffi::OsStr;
     use std::path::Path;
-    use walkdir::DirEntry;
+
+    /// Opens the git repository at the path, without searching its parent directories. The path may be a working tree,
+    /// with a '.git' directory, a linked worktree, with a '.git' file, or a bare repository.
+    pub(crate) fn open_repository(repo_path: &Path) -> Result<Repository, git2::Error> {
+        Repository::open_ext(
+            repo_path,
+            RepositoryOpenFlags::NO_SEARCH,
+            std::iter::empty::<&OsStr>(),
+        )
+    }
+    /// Whether the git repository at the path is bare, i.e., has no working tree of files to review
+    pub(crate) fn is_bare_repository(repo_path: &str) -> bool {
+        Repository::open(repo_path).is_ok_and(|repo| repo.is_bare())
+    }
 
     /// Gets the total number of commits for a git repository
     pub(crate) fn get_total_commits(repo_path: &str) -> Result<i32, SourceFileError> {
@@ -23,50 +38,217 @@ pub(crate) mod repository {
         }
         Ok(total_commits)
     }
-    /// Checks whether the dir passed in is on the blacklist, e.g., '.git'
-    pub(crate) fn is_not_blacklisted(entry: &DirEntry, blacklist: &[String]) -> bool {
-        // Not in the blacklist
-        !entry
-            .file_name()
-            .to_str()
-            .map(|s| blacklist.contains(&s.to_string()))
-            .unwrap_or(false)
-    }
-    /// Gets the the blacklist from either defaults or dynamically from '.gitignore'
-    pub(crate) fn get_blacklist_dirs(repo_path: &Path) -> Vec<String> {
-        let mut blacklist = vec![String::from(".git")];
-
-        // Path to the `.gitignore` file
-        let gitignore_path = repo_path.join(".gitignore");
-
-        if gitignore_path.exists() {
-            debug!("Collecting .gitignore entries");
-            if let Ok(contents) = fs::read_to_string(gitignore_path) {
-                for line in contents.lines() {
-                    if !line.starts_with('#') && !line.trim().is_empty() {
-                        // Simple check for directories (ending with '/')
-                        if line.contains('[') && line.contains(']') {
-                            // Manually expand the character class patterns
-                            handle_character_class_pattern(&mut blacklist, line);
-                        } else {
-                            blacklist.push(line.trim_matches('/').to_string());
-                        }
-                    }
-                }
-            } else {
-                warn!("Failed to read '.gitignore' file");
+    /// Gets the number of commits to a git repository in each of the `weeks` up to, and including, the week of `now`,
+    /// oldest first, so a week with no commits is counted as 0
+    pub(crate) fn get_commit_activity(
+        repo_path: &str,
+        now: DateTime<Utc>,
+        weeks: usize,
+    ) -> Result<Vec<CommitActivity>, SourceFileError> {
+        let first_week: NaiveDate =
+            get_week_start(now.date_naive()) - Duration::weeks(weeks as i64 - 1);
+        let mut activity: Vec<CommitActivity> = (0..weeks)
+            .map(|week| CommitActivity {
+                week: (first_week + Duration::weeks(week as i64))
+                    .format("%Y-%m-%d")
+                    .to_string(),
+                commits: 0,
+            })
+            .collect();
+
+        let repo: Repository = Repository::open(repo_path)?;
+        let mut revwalk: Revwalk<'_> = repo.revwalk()?;
+        revwalk.push_head()?;
+        for commit_id in revwalk {
+            let commit: Commit<'_> = repo.find_commit(commit_id?)?;
+            let Some(date) = NaiveDateTime::from_timestamp_opt(commit.author().when().seconds(), 0)
+            else {
+                continue;
+            };
+            let week: i64 = (get_week_start(date.date()) - first_week).num_weeks();
+            if let Some(week_activity) = usize::try_from(week)
+                .ok()
+                .and_then(|week| activity.get_mut(week))
+            {
+                week_activity.commits += 1;
             }
         }
-        blacklist
+        Ok(activity)
+    }
+    /// Gets the date of the Monday of the week of the date
+    fn get_week_start(date: NaiveDate) -> NaiveDate {
+        date - Duration::days(date.weekday().num_days_from_monday() as i64)
+    }
+    /// Gets the id of the commit at 'HEAD' of a git repository
+    pub(crate) fn get_head_sha(repo_path: &str) -> Result<String, SourceFileError> {
+        let repo: Repository = Repository::open(repo_path)?;
+        let head: Commit<'_> = repo.head()?.peel_to_commit()?;
+        Ok(head.id().to_string())
+    }
+    /// Gets the URL of the 'origin' remote of a git repository, if any
+    pub(crate) fn get_origin_url(repo_path: &str) -> Option<String> {
+        let repo: Repository = Repository::open(repo_path).ok()?;
+        let origin = repo.find_remote("origin").ok()?;
+        origin.url().map(str::to_string)
     }
-    /// Adds the character class pattern to the blacklist
-    fn handle_character_class_pattern(blacklist: &mut Vec<String>, line: &str) {
-        // Trying to match patterns like '[Rr]elease/' found in '.gitignore' files
-        if line.starts_with("[Rr]") && line.ends_with('/') {
-            let base = &line[4..line.len() - 1]; // Remove [Rr] and trailing '/'
-            debug!("Adding: '{}' to blacklist.", line);
-            blacklist.push(format!("R{}", base));
-            blacklist.push(format!("r{}", base));
+    /// The file of entries, in the same syntax as '.gitignore', that are excluded from review but not from git
+    const COSMONAUT_IGNORE_FILE: &str = ".cosmonautignore";
+    /// The directories never walked, whatever the ignore files say; '.cosmonaut' holds the review cache, etc.
+    const ALWAYS_IGNORED_DIRS: [&str; 2] = [".git", ".cosmonaut"];
+
+    /// Gets the files of the repository that are not ignored, i.e., by a '.gitignore', at any depth, the repository's
+    /// 'info/exclude', or a '.cosmonautignore', with their full semantics, e.g., negations and globs. Hidden files, e.g.,
+    /// CI workflows, are included, but not symlinks.
+    pub(crate) fn get_unignored_files(repo_path: &Path) -> Vec<DirEntry> {
+        WalkBuilder::new(repo_path)
+            .hidden(false)
+            .parents(false)
+            .git_global(false)
+            .require_git(false)
+            .add_custom_ignore_filename(COSMONAUT_IGNORE_FILE)
+            .filter_entry(|entry| {
+                !entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| ALWAYS_IGNORED_DIRS.contains(&name))
+            })
+            .build()
+            .filter_map(|entry| match entry {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    warn!("Failed to walk the repository: {}", e);
+                    None
+                }
+            })
+            .filter(|entry| {
+                entry
+                    .file_type()
+                    .is_some_and(|file_type| file_type.is_file())
+            })
+            .collect()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::retrieval::git::test_utils::{commit_all, write};
+        use chrono::TimeZone;
+        use std::fs;
+        use tempfile::tempdir;
+
+        #[test]
+        fn test_get_commit_activity() {
+            let dir = tempdir().unwrap();
+            let repo = Repository::init(dir.path()).unwrap();
+            // A Tuesday, two commits on the Tuesday of the week before, and one of a year before
+            for date in [
+                Utc.with_ymd_and_hms(2023, 1, 10, 9, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 1, 9, 9, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 1, 9, 17, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 1, 16, 9, 0, 0).unwrap(),
+            ] {
+                commit_all(&repo, "cosmonaut", "commit", Some(date.timestamp()));
+            }
+
+            let activity = get_commit_activity(
+                dir.path().to_str().unwrap(),
+                Utc.with_ymd_and_hms(2024, 1, 17, 12, 0, 0).unwrap(),
+                4,
+            )
+            .unwrap();
+            assert_eq!(
+                activity
+                    .iter()
+                    .map(|week| (week.week.as_str(), week.commits))
+                    .collect::<Vec<_>>(),
+                vec![
+                    ("2023-12-25", 0),
+                    ("2024-01-01", 0),
+                    ("2024-01-08", 2),
+                    ("2024-01-15", 1)
+                ]
+            );
+        }
+
+        #[test]
+        fn test_open_repository() {
+            let dir = tempdir().unwrap();
+            let bare_path = dir.path().join("bare.git");
+            Repository::init_bare(&bare_path).unwrap();
+            assert!(open_repository(&bare_path).unwrap().is_bare());
+            assert!(is_bare_repository(bare_path.to_str().unwrap()));
+
+            let repo_path = dir.path().join("repo");
+            let repo = Repository::init(&repo_path).unwrap();
+            write(&repo_path, "main.rs", "fn main() {}");
+            commit_all(&repo, "cosmonaut", "main", None);
+            let linked_path = dir.path().join("linked");
+            repo.worktree("linked", &linked_path, None).unwrap();
+            // A linked worktree has a '.git' file, pointing to the repository
+            assert!(linked_path.join(".git").is_file());
+            assert!(open_repository(&linked_path).is_ok());
+            assert!(!is_bare_repository(linked_path.to_str().unwrap()));
+
+            // A directory in a repository is not itself a repository
+            let src_path = repo_path.join("src");
+            fs::create_dir(&src_path).unwrap();
+            assert!(open_repository(&src_path).is_err());
+        }
+
+        #[test]
+        fn test_get_unignored_files() {
+            let dir = tempdir().unwrap();
+            fs::write(
+                dir.path().join(".gitignore"),
+                "target/\n[Rr]elease/\n*.log\n!keep.log\n",
+            )
+            .unwrap();
+            fs::write(
+                dir.path().join(COSMONAUT_IGNORE_FILE),
+                "# Generated code\ngenerated/\nschema.rs\n",
+            )
+            .unwrap();
+            for relative_path in [
+                ".git/config",
+                ".cosmonaut/cache.json",
+                "target/debug/main",
+                "Release/app.dll",
+                "release/app.dll",
+                "generated/api.rs",
+                "src/schema.rs",
+                "build.log",
+                "keep.log",
+                "web/.gitignore",
+                "web/dist/app.js",
+                "web/src/app.js",
+                ".github/workflows/ci.yml",
+            ] {
+                let path = dir.path().join(relative_path);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(&path, "").unwrap();
+            }
+            // A nested '.gitignore' applies to its own directory
+            fs::write(dir.path().join("web/.gitignore"), "dist/\n").unwrap();
+
+            let mut files: Vec<String> = get_unignored_files(dir.path())
+                .iter()
+                .filter_map(|entry| {
+                    let relative_path = entry.path().strip_prefix(dir.path()).ok()?;
+                    Some(relative_path.to_str()?.replace('\\', "/"))
+                })
+                .collect();
+            files.sort();
+            assert_eq!(
+                files,
+                vec![
+                    ".cosmonautignore",
+                    ".github/workflows/ci.yml",
+                    ".gitignore",
+                    "keep.log",
+                    "web/.gitignore",
+                    "web/src/app.js",
+                ]
+            );
         }
     }
 }
@@ -134,16 +316,21 @@ pub(crate) mod contributor {
     use git2::Repository;
     use std::collections::HashMap;
 
+    use super::blame::FileOwner;
     use crate::retrieval::data::{Contributor, Statistics};
     /// Gets the contributors from the repository passed as the 'repo_path'.
-    /// TODO: add other contributor statistics, e.g., frequency, lines of code changed in commits(?), num_files changed in commits(?), etc.
+    /// TODO: add other contributor statistics, e.g., frequency, lines of code changed in commits(?), etc.
     ///
     /// #Arguments:
     /// * `repo_path` - The path to the repository
+    /// * `file_owners` - The [`FileOwner`]s of the files, whose number owned by each contributor is their `num_files`
     ///
     /// #Returns:
     /// * A [`Vec`] of [`Contributor`]s
-    pub(crate) fn get_git_contributors(repo_path: &str) -> Vec<Contributor> {
+    pub(crate) fn get_git_contributors(
+        repo_path: &str,
+        file_owners: &[FileOwner],
+    ) -> Vec<Contributor> {
         let repo = Repository::open(repo_path).expect("Failed to open repository");
         let mut revwalk = repo.revwalk().expect("Failed to get revwalk");
         revwalk.push_head().expect("Failed to push head");
@@ -171,15 +358,548 @@ pub(crate) mod contributor {
             .into_iter()
             .map(|(name, (last_contribution, num_commits))| {
                 let percentage = num_commits as f32 / total_contributions as f32 * 100.0;
+                // The files of which the contributor is the main owner
+                let num_files = file_owners
+                    .iter()
+                    .filter(|file_owner| file_owner.owner == name)
+                    .count() as i32;
                 let statistics = Statistics {
                     size: 0, // Not relevant for contributors
                     loc: 0,
-                    num_files: 0,
+                    num_files,
                     num_commits,
                     frequency: 0.0,
+                    token_usage: None,
+                    complexity: None,
+                    test_loc: 0,
+                    test_ratio: None,
                 };
                 Contributor::new(name, last_contribution, percentage, statistics)
             })
             .collect()
     }
 }
+
+/// Functions to gather the changes between revisions of a 'git' repository
+pub(crate) mod diff {
+    use crate::retrieval::data::SourceFileError;
+    use git2::{Blob, Commit, Delta, Diff, DiffOptions, IndexEntry, Repository, Revwalk, Tree};
+    use std::collections::HashSet;
+    use std::path::Path;
+
+    /// Gets the paths of the files added or modified between the 'base_ref' and the 'head_ref', e.g., a branch, tag or commit
+    pub(crate) fn get_changed_files(
+        repo_path: &str,
+        base_ref: &str,
+        head_ref: &str,
+    ) -> Result<HashSet<String>, SourceFileError> {
+        let repo: Repository = Repository::open(repo_path)?;
+        let base_tree: Tree<'_> = get_tree(&repo, base_ref)?;
+        let head_tree: Tree<'_> = get_tree(&repo, head_ref)?;
+
+        let diff = repo.diff_tree_to_tree(Some(&base_tree), Some(&head_tree), None)?;
+        Ok(get_added_or_modified_files(&diff))
+    }
+    /// Gets the paths of the files added or modified in the index, i.e., staged for the next commit, against 'HEAD'
+    pub(crate) fn get_staged_files(repo_path: &str) -> Result<HashSet<String>, SourceFileError> {
+        let repo: Repository = Repository::open(repo_path)?;
+        // There is no 'HEAD' before the first commit, when every staged file is added
+        let head_tree: Option<Tree<'_>> =
+            repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+
+        let diff = repo.diff_tree_to_index(head_tree.as_ref(), None, None)?;
+        Ok(get_added_or_modified_files(&diff))
+    }
+    /// Gets the paths of the files added or modified in the working tree, staged or not, or untracked, against 'HEAD'
+    pub(crate) fn get_uncommitted_files(
+        repo_path: &str,
+    ) -> Result<HashSet<String>, SourceFileError> {
+        let repo: Repository = Repository::open(repo_path)?;
+        let head_tree: Option<Tree<'_>> =
+            repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+
+        let mut options = DiffOptions::new();
+        options.include_untracked(true).recurse_untracked_dirs(true);
+        let diff = repo.diff_tree_to_workdir_with_index(head_tree.as_ref(), Some(&mut options))?;
+        Ok(get_added_or_modified_files(&diff))
+    }
+    /// Gets the contents of the file as staged in the index, not as in the working tree, which may have unstaged edits
+    pub(crate) fn get_staged_contents(
+        repo_path: &str,
+        relative_path: &str,
+    ) -> Result<String, SourceFileError> {
+        let repo: Repository = Repository::open(repo_path)?;
+        let entry: IndexEntry = repo
+            .index()?
+            .get_path(Path::new(relative_path), 0)
+            .ok_or_else(|| SourceFileError::GitError(format!("Not staged: {}", relative_path)))?;
+        let blob: Blob<'_> = repo.find_blob(entry.id)?;
+        String::from_utf8(blob.content().to_vec())
+            .map_err(|e| SourceFileError::GitError(format!("{}: {}", relative_path, e)))
+    }
+    /// Gets the paths of the files added or modified by the commits reachable from 'HEAD' that were made since the time,
+    /// in seconds since the epoch
+    pub(crate) fn get_files_changed_since(
+        repo_path: &str,
+        since: i64,
+    ) -> Result<HashSet<String>, SourceFileError> {
+        let repo: Repository = Repository::open(repo_path)?;
+        let mut revwalk: Revwalk<'_> = repo.revwalk()?;
+        revwalk.push_head()?;
+
+        let mut changed_files: HashSet<String> = HashSet::new();
+        for commit_id in revwalk {
+            let commit: Commit<'_> = repo.find_commit(commit_id?)?;
+            // The history is not strictly in time order, e.g., after a rebase, so every commit is checked
+            if commit.time().seconds() < since {
+                continue;
+            }
+            let parent_tree: Option<Tree<'_>> = match commit.parent(0) {
+                Ok(parent) => Some(parent.tree()?),
+                Err(_) => None,
+            };
+            let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+            changed_files.extend(get_added_or_modified_files(&diff));
+        }
+        Ok(changed_files)
+    }
+    /// Gets the commit range between the refs, with the abbreviated commit ids, e.g., 'main..feature (1a2b3c4..5d6e7f8)'
+    pub(crate) fn get_commit_range(
+        repo_path: &str,
+        base_ref: &str,
+        head_ref: &str,
+    ) -> Result<String, SourceFileError> {
+        let repo: Repository = Repository::open(repo_path)?;
+        Ok(format!(
+            "{}..{} ({}..{})",
+            base_ref,
+            head_ref,
+            get_short_id(&repo, base_ref)?,
+            get_short_id(&repo, head_ref)?
+        ))
+    }
+    fn get_tree<'a>(repo: &'a Repository, reference: &str) -> Result<Tree<'a>, SourceFileError> {
+        Ok(repo.revparse_single(reference)?.peel_to_tree()?)
+    }
+    fn get_short_id(repo: &Repository, reference: &str) -> Result<String, SourceFileError> {
+        let commit = repo.revparse_single(reference)?.peel_to_commit()?;
+        let short_id = commit.as_object().short_id()?;
+        Ok(short_id.as_str().unwrap_or_default().to_string())
+    }
+    /// Gets the paths of the files in the [`Diff`] that are not deleted, i.e., as they are after the change
+    fn get_added_or_modified_files(diff: &Diff<'_>) -> HashSet<String> {
+        diff.deltas()
+            .filter(|delta| delta.status() != Delta::Deleted)
+            .filter_map(|delta| delta.new_file().path()?.to_str().map(String::from))
+            .collect()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::retrieval::git::test_utils::{commit_all, write};
+        use std::fs;
+        use tempfile::tempdir;
+
+        #[test]
+        fn test_get_files_changed_since() {
+            let dir = tempdir().unwrap();
+            let repo = Repository::init(dir.path()).unwrap();
+            write(dir.path(), "old.rs", "fn old() {}");
+            // A commit of 30 days ago
+            let thirty_days_ago = chrono::Utc::now().timestamp() - 30 * 24 * 60 * 60;
+            commit_all(&repo, "cosmonaut", "old", Some(thirty_days_ago));
+            write(dir.path(), "new.rs", "fn new() {}");
+            commit_all(&repo, "cosmonaut", "new", None);
+
+            let repo_path = dir.path().to_str().unwrap();
+            let seven_days_ago = chrono::Utc::now().timestamp() - 7 * 24 * 60 * 60;
+            assert_eq!(
+                get_files_changed_since(repo_path, seven_days_ago).unwrap(),
+                HashSet::from(["new.rs".to_string()])
+            );
+            assert_eq!(
+                get_files_changed_since(repo_path, thirty_days_ago).unwrap(),
+                HashSet::from(["old.rs".to_string(), "new.rs".to_string()])
+            );
+        }
+
+        #[test]
+        fn test_get_changed_files() {
+            let dir = tempdir().unwrap();
+            let repo = Repository::init(dir.path()).unwrap();
+            write(dir.path(), "unchanged.rs", "fn unchanged() {}");
+            write(dir.path(), "modified.rs", "fn modified() {}");
+            write(dir.path(), "deleted.rs", "fn deleted() {}");
+            let base = commit_all(&repo, "cosmonaut", "base", None);
+
+            write(dir.path(), "modified.rs", "fn modified() { todo!() }");
+            write(dir.path(), "added.rs", "fn added() {}");
+            fs::remove_file(dir.path().join("deleted.rs")).unwrap();
+            commit_all(&repo, "cosmonaut", "head", None);
+
+            let repo_path = dir.path().to_str().unwrap();
+            let changed_files = get_changed_files(repo_path, &base.to_string(), "HEAD").unwrap();
+            assert_eq!(
+                changed_files,
+                HashSet::from(["modified.rs".to_string(), "added.rs".to_string()])
+            );
+            assert!(get_staged_files(repo_path).unwrap().is_empty());
+            assert!(get_uncommitted_files(repo_path).unwrap().is_empty());
+            write(dir.path(), "staged.rs", "fn staged() {}");
+            write(dir.path(), "unstaged.rs", "fn unstaged() {}");
+            let mut index = repo.index().unwrap();
+            index.add_path(Path::new("staged.rs")).unwrap();
+            index.write().unwrap();
+            assert_eq!(
+                get_staged_files(repo_path).unwrap(),
+                HashSet::from(["staged.rs".to_string()])
+            );
+            // An edit after staging is not reviewed
+            write(dir.path(), "staged.rs", "fn staged() { todo!() }");
+            assert_eq!(
+                get_staged_contents(repo_path, "staged.rs").unwrap(),
+                "fn staged() {}"
+            );
+            assert!(get_staged_contents(repo_path, "unstaged.rs").is_err());
+            assert_eq!(
+                get_uncommitted_files(repo_path).unwrap(),
+                HashSet::from(["staged.rs".to_string(), "unstaged.rs".to_string()])
+            );
+
+            let commit_range = get_commit_range(repo_path, &base.to_string(), "HEAD").unwrap();
+            assert!(commit_range.ends_with(')'));
+            assert!(commit_range.contains("..HEAD ("));
+        }
+    }
+}
+
+/// Functions to attribute the lines of a file to the 'git' authors who last changed them
+pub(crate) mod blame {
+    use crate::retrieval::data::SourceFileError;
+    use git2::Repository;
+    use log::debug;
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    /// The lines of a file last changed by an author
+    ///
+    /// #Fields:
+    /// * `name` - The name of the author
+    /// * `email` - The email of the author
+    /// * `lines` - The number of lines of the file the author last changed
+    #[derive(Clone, Debug, PartialEq)]
+    pub(crate) struct AuthorLines {
+        pub(crate) name: String,
+        pub(crate) email: String,
+        pub(crate) lines: usize,
+    }
+
+    /// The main owner of a file, i.e., the author who last changed most of its lines
+    ///
+    /// #Fields:
+    /// * `relative_path` - The relative path of the file
+    /// * `owner` - The name of the author who last changed most of the file's lines
+    /// * `concentration` - The percentage of the file's lines the owner last changed
+    #[derive(Clone, Debug, PartialEq)]
+    pub(crate) struct FileOwner {
+        pub(crate) relative_path: String,
+        pub(crate) owner: String,
+        pub(crate) concentration: f32,
+    }
+
+    /// Gets the [`FileOwner`] of each of the files. A file that cannot be blamed, e.g., not yet committed, is skipped.
+    pub(crate) fn get_file_owners(repo_path: &str, relative_paths: &[&str]) -> Vec<FileOwner> {
+        relative_paths
+            .iter()
+            .filter_map(|relative_path| {
+                let author_lines: Vec<AuthorLines> = get_author_lines(repo_path, relative_path)
+                    .map_err(|e| debug!("Failed to blame, so no owner: {}: {}", relative_path, e))
+                    .ok()?;
+                let total_lines: usize = author_lines.iter().map(|author| author.lines).sum();
+                let owner: &AuthorLines = author_lines.first().filter(|_| total_lines > 0)?;
+                Some(FileOwner {
+                    relative_path: relative_path.to_string(),
+                    owner: owner.name.clone(),
+                    concentration: owner.lines as f32 / total_lines as f32 * 100.0,
+                })
+            })
+            .collect()
+    }
+    /// Gets the lines of the file, as at 'HEAD', last changed by each author, most lines first
+    pub(crate) fn get_author_lines(
+        repo_path: &str,
+        file_path: &str,
+    ) -> Result<Vec<AuthorLines>, SourceFileError> {
+        let repo: Repository = Repository::open(repo_path)?;
+        let blame = repo.blame_file(Path::new(file_path), None)?;
+        let mut lines_by_author: HashMap<(String, String), usize> = HashMap::new();
+        for hunk in blame.iter() {
+            let signature = hunk.final_signature();
+            let author = (
+                signature.name().unwrap_or_default().to_string(),
+                signature.email().unwrap_or_default().to_string(),
+            );
+            *lines_by_author.entry(author).or_default() += hunk.lines_in_hunk();
+        }
+        let mut author_lines: Vec<AuthorLines> = lines_by_author
+            .into_iter()
+            .map(|((name, email), lines)| AuthorLines { name, email, lines })
+            .collect();
+        author_lines.sort_by(|a, b| b.lines.cmp(&a.lines).then_with(|| a.name.cmp(&b.name)));
+        Ok(author_lines)
+    }
+    /// Gets the name of the author who last changed each of the lines, from 1, of the contents of the file, e.g., as
+    /// reviewed in the working tree, which may differ from 'HEAD'. A line not yet committed, or past the end of the
+    /// contents, is [`None`].
+    pub(crate) fn get_line_authors(
+        repo_path: &str,
+        file_path: &str,
+        contents: &str,
+        lines: &[usize],
+    ) -> Result<Vec<Option<String>>, SourceFileError> {
+        let repo: Repository = Repository::open(repo_path)?;
+        let blame = repo
+            .blame_file(Path::new(file_path), None)?
+            .blame_buffer(contents.as_bytes())?;
+        Ok(lines
+            .iter()
+            .map(|&line| {
+                let hunk = blame.get_line(line)?;
+                if hunk.final_commit_id().is_zero() {
+                    return None;
+                }
+                hunk.final_signature().name().map(str::to_string)
+            })
+            .collect())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::retrieval::git::test_utils::{commit_all, write};
+        use tempfile::tempdir;
+
+        fn commit_as(repo: &Repository, root: &Path, author: &str, contents: &str) {
+            write(root, "main.rs", contents);
+            commit_all(repo, author, author, None);
+        }
+
+        #[test]
+        fn test_get_author_lines() {
+            let dir = tempdir().unwrap();
+            let repo = Repository::init(dir.path()).unwrap();
+            commit_as(
+                &repo,
+                dir.path(),
+                "ana",
+                "fn a() {}\nfn b() {}\nfn c() {}\n",
+            );
+            commit_as(
+                &repo,
+                dir.path(),
+                "ben",
+                "fn a() {}\nfn b() { todo!() }\nfn c() {}\nfn d() {}\n",
+            );
+
+            let author_lines = get_author_lines(dir.path().to_str().unwrap(), "main.rs").unwrap();
+            assert_eq!(
+                author_lines,
+                vec![
+                    AuthorLines {
+                        name: "ana".to_string(),
+                        email: "ana@example.com".to_string(),
+                        lines: 2,
+                    },
+                    AuthorLines {
+                        name: "ben".to_string(),
+                        email: "ben@example.com".to_string(),
+                        lines: 2,
+                    },
+                ]
+            );
+            // A line inserted in the working tree is not yet committed, and moves those after it
+            let contents =
+                "fn a() {}\nfn uncommitted() {}\nfn b() { todo!() }\nfn c() {}\nfn d() {}\n";
+            assert_eq!(
+                get_line_authors(
+                    dir.path().to_str().unwrap(),
+                    "main.rs",
+                    contents,
+                    &[1, 2, 3, 9]
+                )
+                .unwrap(),
+                vec![Some("ana".to_string()), None, Some("ben".to_string()), None]
+            );
+            let file_owners =
+                get_file_owners(dir.path().to_str().unwrap(), &["main.rs", "missing.rs"]);
+            assert_eq!(file_owners.len(), 1);
+            assert_eq!(file_owners[0].owner, "ana");
+            assert_eq!(file_owners[0].concentration, 50.0);
+        }
+    }
+}
+
+/// A temporary linked worktree of a 'git' repository, checked out at a branch, tag or commit, so that the ref is reviewed
+/// without changing the repository's own working tree
+pub(crate) mod worktree {
+    use crate::retrieval::data::SourceFileError;
+    use git2::build::CheckoutBuilder;
+    use git2::{BranchType, Repository, WorktreePruneOptions};
+    use log::{info, warn};
+    use std::env;
+    use std::path::PathBuf;
+
+    /// The worktree of the ref under review, which is removed when dropped
+    ///
+    /// #Fields:
+    /// * `path` - The path of the worktree, in the temporary directory
+    /// * `reference` - The branch, tag or commit checked out, as given
+    /// * `head_sha` - The id of the commit checked out
+    pub(crate) struct ReviewWorktree {
+        pub(crate) path: PathBuf,
+        pub(crate) reference: String,
+        pub(crate) head_sha: String,
+        repo_path: String,
+        name: String,
+    }
+    impl ReviewWorktree {
+        /// Checks out the branch, tag or commit of the repository into a new worktree, with a detached 'HEAD'
+        pub(crate) fn new(repo_path: &str, reference: &str) -> Result<Self, SourceFileError> {
+            let repo: Repository = Repository::open(repo_path)?;
+            let commit = repo.revparse_single(reference)?.peel_to_commit()?;
+            let head_sha: String = commit.id().to_string();
+            let name: String =
+                format!("cosmonaut-review-{}-{}", std::process::id(), &head_sha[..7]);
+            let path: PathBuf = env::temp_dir().join(&name);
+            // A new worktree is on a new branch, of its name, at 'HEAD', so it is detached at the commit, and the branch
+            // deleted
+            let worktree = repo.worktree(&name, &path, None)?;
+            // From here, the worktree is removed if the checkout fails
+            let review_worktree = Self {
+                path,
+                reference: reference.to_string(),
+                head_sha,
+                repo_path: repo_path.to_string(),
+                name,
+            };
+            let worktree_repo: Repository = Repository::open_from_worktree(&worktree)?;
+            worktree_repo.set_head_detached(commit.id())?;
+            worktree_repo.checkout_head(Some(CheckoutBuilder::new().force()))?;
+            repo.find_branch(&review_worktree.name, BranchType::Local)?
+                .delete()?;
+            info!(
+                "Checked out: {} ({}), into: {}",
+                reference,
+                review_worktree.head_sha,
+                review_worktree.path.display()
+            );
+            Ok(review_worktree)
+        }
+    }
+    impl Drop for ReviewWorktree {
+        /// Removes the worktree, both its files and its entry in the repository
+        fn drop(&mut self) {
+            let mut prune_options = WorktreePruneOptions::new();
+            prune_options.valid(true).working_tree(true);
+            let pruned = Repository::open(&self.repo_path)
+                .and_then(|repo| repo.find_worktree(&self.name))
+                .and_then(|worktree| worktree.prune(Some(&mut prune_options)));
+            if let Err(e) = pruned {
+                warn!(
+                    "Failed to remove the worktree: {}: {}",
+                    self.path.display(),
+                    e
+                );
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::retrieval::git::test_utils::{commit_all, write};
+        use std::fs;
+        use tempfile::tempdir;
+
+        fn commit_file(repo: &Repository, root: &std::path::Path, contents: &str) -> git2::Oid {
+            write(root, "main.rs", contents);
+            commit_all(repo, "cosmonaut", contents, None)
+        }
+
+        #[test]
+        fn test_review_worktree() {
+            let dir = tempdir().unwrap();
+            let repo = Repository::init(dir.path()).unwrap();
+            let first = commit_file(&repo, dir.path(), "fn first() {}");
+            repo.tag_lightweight("v1", &repo.find_object(first, None).unwrap(), false)
+                .unwrap();
+            commit_file(&repo, dir.path(), "fn second() {}");
+
+            let worktree = ReviewWorktree::new(dir.path().to_str().unwrap(), "v1").unwrap();
+            assert_eq!(worktree.head_sha, first.to_string());
+            assert_eq!(
+                fs::read_to_string(worktree.path.join("main.rs")).unwrap(),
+                "fn first() {}"
+            );
+            // The repository's own working tree is unchanged
+            assert_eq!(
+                fs::read_to_string(dir.path().join("main.rs")).unwrap(),
+                "fn second() {}"
+            );
+            let path = worktree.path.clone();
+            drop(worktree);
+            assert!(!path.exists());
+            assert!(repo.worktrees().unwrap().is_empty());
+        }
+    }
+}
+
+/// Helpers of the tests of the 'git' functions, to build a repository
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use git2::{IndexAddOption, Oid, Repository, Signature, Time};
+    use std::fs;
+    use std::path::Path;
+
+    /// Writes the contents of the file, relative to the root of the repository
+    pub(crate) fn write(root: &Path, file: &str, contents: &str) {
+        fs::write(root.join(file), contents).unwrap();
+    }
+    /// Commits every change of the working tree, including deletions, to 'HEAD', as the author, at the time, in
+    /// seconds since the epoch, if set, otherwise now
+    pub(crate) fn commit_all(
+        repo: &Repository,
+        author: &str,
+        message: &str,
+        time: Option<i64>,
+    ) -> Oid {
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"].iter(), IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.update_all(["*"].iter(), None).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let email = format!("{}@example.com", author);
+        let signature = match time {
+            Some(time) => Signature::new(author, &email, &Time::new(time, 0)),
+            None => Signature::now(author, &email),
+        }
+        .unwrap();
+        let parents = match repo.head() {
+            Ok(head) => vec![head.peel_to_commit().unwrap()],
+            Err(_) => Vec::new(),
+        };
+        let parents: Vec<_> = parents.iter().collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .unwrap()
+    }
+}
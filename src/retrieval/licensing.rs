@@ -0,0 +1,319 @@
+//! Inventories the licences of a repository, without the LLM, for a compliance review: the licence of the project, the
+//! licence header of each source file, and the licences declared in its dependency manifests, e.g., 'Cargo.toml',
+//! 'package.json', or, for the dependencies themselves, 'package-lock.json'.
+//!
+//! A licence is taken from an SPDX identifier, e.g., 'SPDX-License-Identifier: MIT', or recognised from the standard
+//! wording of its text. A copyleft licence, e.g., GPL or AGPL, in a project that is not itself under a copyleft licence,
+//! e.g., is permissive or proprietary, is flagged as a [`LicenceConflict`].
+use super::data::SourceFileInfo;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// The names of the licence files at the root of a repository, in the order they are looked for
+pub(crate) const LICENCE_FILES: [&str; 6] = [
+    "LICENSE",
+    "LICENSE.md",
+    "LICENSE.txt",
+    "LICENCE",
+    "LICENCE.md",
+    "COPYING",
+];
+/// The names of the dependency manifests whose licences are read
+pub(crate) const MANIFEST_FILES: [&str; 5] = [
+    "Cargo.toml",
+    "package.json",
+    "package-lock.json",
+    "pyproject.toml",
+    "composer.json",
+];
+/// The number of lines at the start of a file searched for its licence header
+const HEADER_LINES: usize = 40;
+/// The licence of a project with no licence file, nor any licence in its root manifest
+const NO_LICENCE: &str = "none (proprietary)";
+/// The strong copyleft licences, by the start of their SPDX identifier, that require the whole work to be under them
+const COPYLEFT_LICENCES: [&str; 2] = ["GPL", "AGPL"];
+/// The licences recognised from the standard wording of their text, most specific first, as their SPDX identifier
+const LICENCE_TEXTS: [(&str, &str); 9] = [
+    ("GNU AFFERO GENERAL PUBLIC LICENSE", "AGPL-3.0"),
+    ("GNU LESSER GENERAL PUBLIC LICENSE", "LGPL"),
+    ("GNU LIBRARY GENERAL PUBLIC LICENSE", "LGPL-2.0"),
+    ("GNU GENERAL PUBLIC LICENSE", "GPL"),
+    ("APACHE LICENSE", "Apache-2.0"),
+    ("MOZILLA PUBLIC LICENSE", "MPL-2.0"),
+    ("PERMISSION IS HEREBY GRANTED, FREE OF CHARGE", "MIT"),
+    ("REDISTRIBUTION AND USE IN SOURCE AND BINARY FORMS", "BSD"),
+    ("THIS IS FREE AND UNENCUMBERED SOFTWARE", "Unlicense"),
+];
+
+/// The licences of the repository
+///
+/// #Fields:
+/// * `project_licence` - The licence of the project, from its licence file, or its root manifest
+/// * `file_licences` - The [`LicenceSource`] of each source file with a licence header
+/// * `manifest_licences` - The [`LicenceSource`] of each package declared in a dependency manifest
+/// * `files_without_licence` - The number of source files without a licence header
+/// * `conflicts` - The [`LicenceConflict`]s with the licence of the project
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub(crate) struct Licensing {
+    pub(crate) project_licence: String,
+    pub(crate) file_licences: Vec<LicenceSource>,
+    pub(crate) manifest_licences: Vec<LicenceSource>,
+    pub(crate) files_without_licence: usize,
+    pub(crate) conflicts: Vec<LicenceConflict>,
+}
+/// A licence found in a file, with the package it is declared for, if the file is a manifest
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct LicenceSource {
+    pub(crate) relative_path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) package: Option<String>,
+    pub(crate) licence: String,
+}
+/// A licence that conflicts with the licence of the project, and why
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct LicenceConflict {
+    pub(crate) source: LicenceSource,
+    pub(crate) reason: String,
+}
+
+/// Inventories the [`Licensing`] of the repository from its licence file, if any, its dependency manifests, each as its
+/// relative path and contents, and its source files
+pub(crate) fn get_licensing(
+    licence_file: Option<&str>,
+    manifests: &[(String, String)],
+    source_files: &[SourceFileInfo],
+) -> Licensing {
+    let mut licensing = Licensing::default();
+    for file_info in source_files {
+        match get_header_licence(&file_info.get_source_file_contents()) {
+            Some(licence) => licensing.file_licences.push(LicenceSource {
+                relative_path: file_info.relative_path.clone(),
+                package: None,
+                licence,
+            }),
+            None => licensing.files_without_licence += 1,
+        }
+    }
+    for (relative_path, contents) in manifests {
+        licensing
+            .manifest_licences
+            .extend(get_manifest_licences(relative_path, contents));
+    }
+
+    // The root manifest's own licence, i.e., of the project, is not a dependency's, so cannot conflict
+    let root_licence: Option<&LicenceSource> = licensing
+        .manifest_licences
+        .iter()
+        .find(|source| !source.relative_path.contains('/') && is_project_manifest(source));
+    licensing.project_licence = licence_file
+        .and_then(get_text_licence)
+        .or_else(|| root_licence.map(|source| source.licence.clone()))
+        .unwrap_or_else(|| NO_LICENCE.to_string());
+
+    if !is_copyleft(&licensing.project_licence) {
+        let root_licence: Option<LicenceSource> = root_licence.cloned();
+        licensing.conflicts = licensing
+            .file_licences
+            .iter()
+            .chain(licensing.manifest_licences.iter())
+            .filter(|source| is_copyleft(&source.licence) && Some(*source) != root_licence.as_ref())
+            .map(|source| LicenceConflict {
+                source: source.clone(),
+                reason: format!(
+                    "{} is a copyleft licence, which requires the whole work to be under it, but the project is under: {}",
+                    source.licence, licensing.project_licence
+                ),
+            })
+            .collect();
+    }
+    licensing
+}
+/// Whether the licence is a strong copyleft licence. An expression, e.g., 'MIT OR GPL-3.0', is not, as the permissive
+/// licence may be chosen
+fn is_copyleft(licence: &str) -> bool {
+    !licence.contains(" OR ")
+        && licence.split(" AND ").any(|part| {
+            COPYLEFT_LICENCES
+                .iter()
+                .any(|copyleft| part.trim_start_matches('(').starts_with(copyleft))
+        })
+}
+/// Whether the source is the licence of a manifest's own package, rather than of a dependency in a lock file
+fn is_project_manifest(source: &LicenceSource) -> bool {
+    !source.relative_path.ends_with("package-lock.json")
+}
+/// Gets the licence of the header of the file, i.e., in its first lines, from an SPDX identifier or its wording
+fn get_header_licence(contents: &str) -> Option<String> {
+    static SPDX: OnceLock<Regex> = OnceLock::new();
+    let spdx = SPDX.get_or_init(|| {
+        Regex::new(
+            r"SPDX-License-Identifier:\s*(?P<licence>[A-Za-z0-9.+() -]+?)\s*(?:\*/|-->)?\s*$",
+        )
+        .expect("The SPDX pattern should be valid")
+    });
+    let header: String = contents
+        .lines()
+        .take(HEADER_LINES)
+        .collect::<Vec<&str>>()
+        .join("\n");
+    header
+        .lines()
+        .find_map(|line| spdx.captures(line))
+        .map(|captures| captures["licence"].to_string())
+        .or_else(|| get_text_licence(&header))
+}
+/// Gets the licence recognised from the standard wording of its text, with its version, if a GPL
+fn get_text_licence(text: &str) -> Option<String> {
+    let upper: String = text.to_uppercase();
+    let (_, licence) = LICENCE_TEXTS
+        .iter()
+        .find(|(wording, _)| upper.contains(wording))?;
+    if *licence != "GPL" && *licence != "LGPL" {
+        return Some(licence.to_string());
+    }
+    let version = ["3", "2.1", "2"]
+        .into_iter()
+        .find(|version| upper.contains(&format!("VERSION {}", version)));
+    Some(match version {
+        Some(version) if version.contains('.') => format!("{}-{}", licence, version),
+        Some(version) => format!("{}-{}.0", licence, version),
+        None => licence.to_string(),
+    })
+}
+/// Gets the licences declared in the manifest, i.e., of its package, or, for a lock file, of each dependency
+fn get_manifest_licences(relative_path: &str, contents: &str) -> Vec<LicenceSource> {
+    let file_name: &str = relative_path.rsplit('/').next().unwrap_or(relative_path);
+    let source = |package: Option<String>, licence: String| LicenceSource {
+        relative_path: relative_path.to_string(),
+        package,
+        licence,
+    };
+    match file_name {
+        "package.json" | "composer.json" => serde_json::from_str::<serde_json::Value>(contents)
+            .ok()
+            .and_then(|manifest| {
+                let licence = get_json_licence(&manifest["license"])?;
+                Some(vec![source(
+                    manifest["name"].as_str().map(str::to_string),
+                    licence,
+                )])
+            })
+            .unwrap_or_default(),
+        "package-lock.json" => serde_json::from_str::<serde_json::Value>(contents)
+            .ok()
+            .and_then(|lock| lock["packages"].as_object().cloned())
+            .map(|packages| {
+                packages
+                    .iter()
+                    // The root package, i.e., '', is the project's, as in 'package.json'
+                    .filter(|(path, _)| !path.is_empty())
+                    .filter_map(|(path, package)| {
+                        let licence = get_json_licence(&package["license"])?;
+                        let name = path.rsplit("node_modules/").next().unwrap_or(path);
+                        Some(source(Some(name.to_string()), licence))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+        "Cargo.toml" | "pyproject.toml" => get_toml_licence(contents)
+            .map(|(package, licence)| vec![source(package, licence)])
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+/// Gets the licence from a JSON manifest's 'license', either an SPDX expression or an object with a 'type'
+fn get_json_licence(licence: &serde_json::Value) -> Option<String> {
+    licence
+        .as_str()
+        .or_else(|| licence["type"].as_str())
+        .map(str::to_string)
+}
+/// Gets the package name, if any, and licence of the package of a TOML manifest, i.e., its 'license' key, either a
+/// string, or a table with a 'text', as in 'pyproject.toml'
+fn get_toml_licence(contents: &str) -> Option<(Option<String>, String)> {
+    static LICENCE: OnceLock<Regex> = OnceLock::new();
+    static NAME: OnceLock<Regex> = OnceLock::new();
+    let licence = LICENCE.get_or_init(|| {
+        Regex::new(r#"(?m)^\s*license\s*=\s*(?:\{\s*text\s*=\s*)?"(?P<licence>[^"]+)""#)
+            .expect("The licence pattern should be valid")
+    });
+    let name = NAME.get_or_init(|| {
+        Regex::new(r#"(?m)^\s*name\s*=\s*"(?P<name>[^"]+)""#)
+            .expect("The name pattern should be valid")
+    });
+    let licence: String = licence.captures(contents)?["licence"].to_string();
+    let package: Option<String> = name
+        .captures(contents)
+        .map(|captures| captures["name"].to_string());
+    Some((package, licence))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retrieval::data::{LanguageType, Statistics};
+
+    fn test_source_file(relative_path: &str, contents: &str) -> SourceFileInfo {
+        let mut file_info = SourceFileInfo::new(
+            relative_path.to_string(),
+            relative_path.to_string(),
+            LanguageType {
+                name: "Rust".to_string(),
+                extension: "rs".to_string(),
+                statistics: None,
+            },
+            relative_path.to_string(),
+            Statistics::new(),
+        );
+        file_info.set_source_file_contents(contents.to_string());
+        file_info
+    }
+
+    #[test]
+    fn test_get_licensing() {
+        let source_files = vec![
+            test_source_file("src/main.rs", "// SPDX-License-Identifier: MIT\nfn main() {}\n"),
+            test_source_file(
+                "src/vendored.rs",
+                "// This program is free software: you can redistribute it under the terms of the\n// GNU General Public License as published by the Free Software Foundation, either version 3\n",
+            ),
+            test_source_file("src/lib.rs", "pub fn lib() {}\n"),
+        ];
+        let manifests = vec![
+            (
+                "Cargo.toml".to_string(),
+                "[package]\nname = \"app\"\nlicense = \"MIT\"\n".to_string(),
+            ),
+            (
+                "package-lock.json".to_string(),
+                r#"{"packages": {"": {"name": "web", "license": "MIT"}, "node_modules/left-pad": {"license": "WTFPL"}, "node_modules/gpl-lib": {"license": "GPL-2.0-only"}, "node_modules/dual": {"license": "MIT OR GPL-3.0"}}}"#.to_string(),
+            ),
+        ];
+
+        let licensing = get_licensing(None, &manifests, &source_files);
+        assert_eq!(licensing.project_licence, "MIT");
+        assert_eq!(licensing.files_without_licence, 1);
+        assert_eq!(licensing.file_licences[1].licence, "GPL-3.0");
+        assert_eq!(licensing.manifest_licences.len(), 4);
+        let conflicts: Vec<&str> = licensing
+            .conflicts
+            .iter()
+            .map(|conflict| conflict.source.licence.as_str())
+            .collect();
+        assert_eq!(conflicts, vec!["GPL-3.0", "GPL-2.0-only"]);
+
+        let licensing = get_licensing(
+            Some("GNU GENERAL PUBLIC LICENSE\nVersion 3, 29 June 2007"),
+            &manifests,
+            &source_files,
+        );
+        assert_eq!(licensing.project_licence, "GPL-3.0");
+        assert!(licensing.conflicts.is_empty());
+
+        assert_eq!(
+            get_licensing(None, &[], &source_files[..1]).project_licence,
+            NO_LICENCE
+        );
+    }
+}
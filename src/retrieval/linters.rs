@@ -0,0 +1,320 @@
+//! Reads the diagnostics of static linters, i.e., clippy, eslint and flake8, from their output, or by running them if
+//! they are present in the repository, so that each file's diagnostics can be given with its review.
+//!
+//! WARNING: running a linter executes code of the repository under review: clippy builds it, including its build
+//! scripts and procedural macros, eslint is the repository's own 'node_modules/.bin/eslint', with its config and
+//! plugins, and flake8 loads its config and plugins. So a linter is only run if opted in by name in `run_linters`,
+//! which should only be for a trusted repository; otherwise the output saved by CI is read from `linter_reports`.
+//!
+//! The output read is, for clippy, the JSON lines of `cargo clippy --message-format=json`, for eslint, the JSON of
+//! `eslint -f json`, and for flake8, the JSON of the `flake8-json` formatter, or its default text output. As with a
+//! coverage report, a file is matched to the paths in the output by their ending, so they may be absolute.
+use super::coverage::is_path_of;
+use super::data::SourceFileInfo;
+use crate::settings::{Linter, LinterReport};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// A diagnostic of a linter for a line of a file
+///
+/// #Fields:
+/// * `tool` - The [`Linter`] that reported it
+/// * `line` - The line of the file, from 1
+/// * `rule` - The rule broken, e.g., 'clippy::needless_return', 'no-unused-vars' or 'E501', if any
+/// * `level` - The level of the diagnostic, i.e., 'error' or 'warning'
+/// * `message` - The message of the diagnostic
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct Diagnostic {
+    pub(crate) tool: Linter,
+    pub(crate) line: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) rule: Option<String>,
+    pub(crate) level: String,
+    pub(crate) message: String,
+}
+
+/// The diagnostics of the linters, by the path of the file in their output
+#[derive(Debug, Default)]
+pub(crate) struct Diagnostics {
+    files: Vec<(String, Diagnostic)>,
+}
+impl Diagnostics {
+    /// Reads the [`LinterReport`]s, and runs the linters of `run_linters` that are present in the repository
+    pub(crate) fn from_settings(
+        linter_reports: &[LinterReport],
+        run_linters: &[Linter],
+        repository_root: &Path,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut diagnostics = Self::default();
+        for linter_report in linter_reports {
+            let output = fs::read_to_string(&linter_report.path).map_err(|e| {
+                format!(
+                    "Failed to read the {} output: {}: {}",
+                    linter_report.tool, linter_report.path, e
+                )
+            })?;
+            diagnostics.add_output(&linter_report.tool, &output);
+        }
+        if !run_linters.is_empty() {
+            for (tool, output) in run_present_linters(run_linters, repository_root) {
+                diagnostics.add_output(&tool, &output);
+            }
+        }
+        info!("Read {} linter diagnostics", diagnostics.files.len());
+        Ok(diagnostics)
+    }
+    /// Adds the diagnostics of the output of the linter
+    fn add_output(&mut self, tool: &Linter, output: &str) {
+        let diagnostics = match tool {
+            Linter::Clippy => parse_clippy(output),
+            Linter::Eslint => parse_eslint(output),
+            Linter::Flake8 => parse_flake8(output),
+        };
+        if diagnostics.is_empty() {
+            warn!("No diagnostics found in the {} output", tool);
+        }
+        self.files.extend(diagnostics);
+    }
+    /// Sets the diagnostics of each source file, ordered by line
+    pub(crate) fn apply(&self, source_files: &mut [SourceFileInfo]) {
+        for file_info in source_files {
+            let mut diagnostics: Vec<Diagnostic> = self
+                .files
+                .iter()
+                .filter(|(path, _)| is_path_of(path, &file_info.relative_path))
+                .map(|(_, diagnostic)| diagnostic.clone())
+                .collect();
+            diagnostics.sort_by_key(|diagnostic| diagnostic.line);
+            file_info.diagnostics = (!diagnostics.is_empty()).then_some(diagnostics);
+        }
+    }
+}
+/// Runs each of the linters opted in that is present for the repository, i.e., clippy for a 'Cargo.toml', eslint if
+/// installed in its 'node_modules', and flake8 if on the path for a Python project, returning the output of each that
+/// ran. Each executes code of the repository, so is warned of.
+fn run_present_linters(run_linters: &[Linter], repository_root: &Path) -> Vec<(Linter, String)> {
+    let mut commands: Vec<(Linter, &str, Vec<&str>)> = Vec::new();
+    if repository_root.join("Cargo.toml").exists() {
+        commands.push((
+            Linter::Clippy,
+            "cargo",
+            vec!["clippy", "--quiet", "--message-format=json"],
+        ));
+    }
+    let eslint = repository_root.join("node_modules/.bin/eslint");
+    if eslint.exists() {
+        commands.push((
+            Linter::Eslint,
+            eslint.to_str().unwrap_or("eslint"),
+            vec!["-f", "json", "."],
+        ));
+    }
+    if [
+        "setup.py",
+        "setup.cfg",
+        "pyproject.toml",
+        "requirements.txt",
+        ".flake8",
+    ]
+    .iter()
+    .any(|file| repository_root.join(file).exists())
+    {
+        commands.push((Linter::Flake8, "flake8", vec!["--exit-zero", "."]));
+    }
+
+    let mut outputs: Vec<(Linter, String)> = Vec::new();
+    for (tool, program, args) in commands
+        .into_iter()
+        .filter(|(tool, _, _)| run_linters.contains(tool))
+    {
+        warn!(
+            "Running {}, which executes code of the repository: {} {}",
+            tool,
+            program,
+            args.join(" ")
+        );
+        // A linter exits with an error when it finds problems, so its output is read whatever its status
+        match Command::new(program)
+            .args(&args)
+            .current_dir(repository_root)
+            .output()
+        {
+            Ok(output) => outputs.push((tool, String::from_utf8_lossy(&output.stdout).to_string())),
+            Err(e) => debug!("Failed to run {}, skipping it: {}", tool, e),
+        }
+    }
+    outputs
+}
+/// Parses the JSON lines of `cargo clippy --message-format=json`, i.e., each compiler message at its primary span
+fn parse_clippy(output: &str) -> Vec<(String, Diagnostic)> {
+    output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|message| message["reason"] == "compiler-message")
+        .filter_map(|message| {
+            let message = &message["message"];
+            let level = message["level"].as_str()?;
+            if level != "error" && level != "warning" {
+                return None;
+            }
+            let span = message["spans"]
+                .as_array()?
+                .iter()
+                .find(|span| span["is_primary"] == true)?;
+            Some((
+                span["file_name"].as_str()?.to_string(),
+                Diagnostic {
+                    tool: Linter::Clippy,
+                    line: span["line_start"].as_u64()? as usize,
+                    rule: message["code"]["code"].as_str().map(str::to_string),
+                    level: level.to_string(),
+                    message: message["message"].as_str()?.to_string(),
+                },
+            ))
+        })
+        .collect()
+}
+/// Parses the JSON of `eslint -f json`, i.e., the messages of each file, of severity 2, an error, or 1, a warning
+fn parse_eslint(output: &str) -> Vec<(String, Diagnostic)> {
+    let Ok(Value::Array(files)) = serde_json::from_str::<Value>(output) else {
+        return Vec::new();
+    };
+    files
+        .iter()
+        .filter_map(|file| Some((file["filePath"].as_str()?, file["messages"].as_array()?)))
+        .flat_map(|(path, messages)| {
+            messages.iter().filter_map(move |message| {
+                Some((
+                    path.to_string(),
+                    Diagnostic {
+                        tool: Linter::Eslint,
+                        line: message["line"].as_u64().unwrap_or(1) as usize,
+                        rule: message["ruleId"].as_str().map(str::to_string),
+                        level: if message["severity"] == 2 {
+                            "error"
+                        } else {
+                            "warning"
+                        }
+                        .to_string(),
+                        message: message["message"].as_str()?.to_string(),
+                    },
+                ))
+            })
+        })
+        .collect()
+}
+/// Parses the JSON of flake8's `flake8-json` formatter, i.e., the errors of each file, or, if not JSON, its default
+/// text output, i.e., 'path:line:column: code message'
+fn parse_flake8(output: &str) -> Vec<(String, Diagnostic)> {
+    let diagnostic = |line: usize, code: &str, message: &str| Diagnostic {
+        tool: Linter::Flake8,
+        line,
+        rule: Some(code.to_string()),
+        // pyflakes' 'F' and syntax 'E9' codes are errors, the rest, e.g., style, warnings
+        level: if code.starts_with('F') || code.starts_with("E9") {
+            "error"
+        } else {
+            "warning"
+        }
+        .to_string(),
+        message: message.to_string(),
+    };
+    if let Ok(Value::Object(files)) = serde_json::from_str::<Value>(output) {
+        return files
+            .iter()
+            .filter_map(|(path, errors)| Some((path, errors.as_array()?)))
+            .flat_map(|(path, errors)| {
+                errors.iter().filter_map(move |error| {
+                    Some((
+                        path.clone(),
+                        diagnostic(
+                            error["line_number"].as_u64()? as usize,
+                            error["code"].as_str()?,
+                            error["text"].as_str()?,
+                        ),
+                    ))
+                })
+            })
+            .collect();
+    }
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(4, ':');
+            let (path, line, _, rest) =
+                (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+            let (code, message) = rest.trim().split_once(' ')?;
+            Some((
+                path.to_string(),
+                diagnostic(line.parse().ok()?, code, message.trim()),
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retrieval::data::{LanguageType, Statistics};
+
+    #[test]
+    fn test_parse_linter_output() {
+        let clippy = r#"{"reason":"compiler-artifact","package_id":"app"}
+{"reason":"compiler-message","message":{"code":{"code":"clippy::needless_return"},"level":"warning","message":"unneeded `return` statement","spans":[{"file_name":"src/main.rs","line_start":12,"is_primary":true}]}}
+{"reason":"compiler-message","message":{"code":null,"level":"warning","message":"1 warning emitted","spans":[]}}"#;
+        let diagnostics = parse_clippy(clippy);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].0, "src/main.rs");
+        assert_eq!(diagnostics[0].1.line, 12);
+        assert_eq!(
+            diagnostics[0].1.rule.as_deref(),
+            Some("clippy::needless_return")
+        );
+
+        let eslint = r#"[{"filePath":"/ci/web/src/app.js","messages":[{"ruleId":"no-unused-vars","severity":2,"message":"'x' is defined but never used.","line":3}]}]"#;
+        let diagnostics = parse_eslint(eslint);
+        assert_eq!(diagnostics[0].0, "/ci/web/src/app.js");
+        assert_eq!(diagnostics[0].1.level, "error");
+
+        let flake8 = "./app/models.py:4:80: E501 line too long (88 > 79 characters)\n./app/models.py:1:1: F401 'os' imported but unused\n";
+        let diagnostics = parse_flake8(flake8);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(
+            diagnostics[0].1.message,
+            "line too long (88 > 79 characters)"
+        );
+        assert_eq!(diagnostics[1].1.level, "error");
+        let flake8_json =
+            r#"{"./app/models.py": [{"code": "E501", "line_number": 4, "text": "line too long"}]}"#;
+        assert_eq!(parse_flake8(flake8_json)[0].1.line, 4);
+
+        let mut source_files = vec![SourceFileInfo::new(
+            "models.py".to_string(),
+            "app/models.py".to_string(),
+            LanguageType {
+                name: "Python".to_string(),
+                extension: "py".to_string(),
+                statistics: None,
+            },
+            "0".to_string(),
+            Statistics::new(),
+        )];
+        Diagnostics {
+            files: parse_flake8(flake8),
+        }
+        .apply(&mut source_files);
+        let lines: Vec<usize> = source_files[0]
+            .diagnostics
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|diagnostic| diagnostic.line)
+            .collect();
+        assert_eq!(lines, vec![1, 4]);
+    }
+}
@@ -0,0 +1,141 @@
+//! Counts the TODO, FIXME and HACK markers in the source files, i.e., the work their authors have left to do, per file
+//! and for the repository, listing the files with the most.
+//!
+//! A marker is counted where the word, in upper case, appears, e.g., in a comment, 'TODO: handle errors', so a marker
+//! in a string is also counted.
+use super::data::SourceFileInfo;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// The markers counted, as a regular expression of whole words
+const MARKER_PATTERN: &str = r"\b(TODO|FIXME|HACK)\b";
+/// The number of files with the most markers listed in the report
+const MAX_MARKER_FILES: usize = 10;
+
+/// The number of each marker in a file, or the repository
+///
+/// #Fields:
+/// * `todo` - The number of 'TODO' markers
+/// * `fixme` - The number of 'FIXME' markers
+/// * `hack` - The number of 'HACK' markers
+/// * `total` - The number of markers
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub(crate) struct MarkerCounts {
+    pub(crate) todo: i32,
+    pub(crate) fixme: i32,
+    pub(crate) hack: i32,
+    pub(crate) total: i32,
+}
+impl MarkerCounts {
+    /// Adds the counts of another file
+    fn add(&mut self, other: &MarkerCounts) {
+        self.todo += other.todo;
+        self.fixme += other.fixme;
+        self.hack += other.hack;
+        self.total += other.total;
+    }
+}
+/// The markers of the repository, and the files with the most
+///
+/// #Fields:
+/// * `counts` - The [`MarkerCounts`] of the repository
+/// * `files` - The relative paths, and [`MarkerCounts`], of the files with the most markers, most first
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub(crate) struct MarkerInventory {
+    pub(crate) counts: MarkerCounts,
+    pub(crate) files: Vec<FileMarkers>,
+}
+/// The markers of a file
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct FileMarkers {
+    pub(crate) relative_path: String,
+    pub(crate) counts: MarkerCounts,
+}
+impl MarkerInventory {
+    /// Gets a note of the markers, and the files with the most, for the summary of the repository, if any
+    pub(crate) fn get_summary_note(&self) -> Option<String> {
+        if self.counts.total == 0 {
+            return None;
+        }
+        let files: Vec<String> = self
+            .files
+            .iter()
+            .map(|file| format!("{} ({})", file.relative_path, file.counts.total))
+            .collect();
+        Some(format!(
+            "The code has {} TODO, {} FIXME and {} HACK markers, most in: {}",
+            self.counts.todo,
+            self.counts.fixme,
+            self.counts.hack,
+            files.join(", ")
+        ))
+    }
+}
+
+/// Counts the markers in the contents, or [`None`] if there are none
+pub(crate) fn count_markers(contents: &str) -> Option<MarkerCounts> {
+    let marker: Regex = Regex::new(MARKER_PATTERN).ok()?;
+    let mut counts = MarkerCounts::default();
+    for found in marker.find_iter(contents) {
+        match found.as_str() {
+            "TODO" => counts.todo += 1,
+            "FIXME" => counts.fixme += 1,
+            _ => counts.hack += 1,
+        }
+        counts.total += 1;
+    }
+    (counts.total > 0).then_some(counts)
+}
+/// Gets the [`MarkerInventory`] of the source files, from the markers counted in each
+pub(crate) fn get_marker_inventory(source_files: &[SourceFileInfo]) -> MarkerInventory {
+    let mut inventory = MarkerInventory::default();
+    for file_info in source_files {
+        if let Some(markers) = &file_info.markers {
+            inventory.counts.add(markers);
+            inventory.files.push(FileMarkers {
+                relative_path: file_info.relative_path.clone(),
+                counts: markers.clone(),
+            });
+        }
+    }
+    inventory.files.sort_by(|a, b| {
+        b.counts
+            .total
+            .cmp(&a.counts.total)
+            .then_with(|| a.relative_path.cmp(&b.relative_path))
+    });
+    inventory.files.truncate(MAX_MARKER_FILES);
+    inventory
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_markers() {
+        let contents = "// TODO: handle errors\n// FIXME(ana): off by one\nlet todo_list = 1; // HACK\n// TODOS\n";
+        assert_eq!(
+            count_markers(contents),
+            Some(MarkerCounts {
+                todo: 1,
+                fixme: 1,
+                hack: 1,
+                total: 3,
+            })
+        );
+        assert_eq!(count_markers("fn main() {}"), None);
+
+        let mut inventory = MarkerInventory::default();
+        assert_eq!(inventory.get_summary_note(), None);
+        inventory.counts = count_markers(contents).unwrap();
+        inventory.files.push(FileMarkers {
+            relative_path: "src/main.rs".to_string(),
+            counts: inventory.counts.clone(),
+        });
+        assert_eq!(
+            inventory.get_summary_note().unwrap(),
+            "The code has 1 TODO, 1 FIXME and 1 HACK markers, most in: src/main.rs (3)"
+        );
+    }
+}
@@ -1,4 +1,19 @@
 //! Entrypoint for the retrieval and structuring of static repository data
 pub(crate) mod code;
+pub(crate) mod context;
+pub(crate) mod coverage;
 pub(crate) mod data;
+pub(crate) mod dependencies;
+pub(crate) mod documentation;
+pub(crate) mod duplication;
+pub(crate) mod generated;
 pub(crate) mod git;
+pub(crate) mod licensing;
+pub(crate) mod linters;
+pub(crate) mod markers;
+pub(crate) mod notebook;
+pub(crate) mod patch;
+pub(crate) mod secrets;
+pub(crate) mod test_files;
+pub(crate) mod units;
+pub(crate) mod vulnerabilities;
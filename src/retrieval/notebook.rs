@@ -0,0 +1,137 @@
+//! Extracts the code of a Jupyter notebook, i.e., an `.ipynb` file, so that it is reviewed as code rather than as
+//! JSON. Only the code cells are kept, in order, each headed by its execution count, as with 'jupyter nbconvert --to
+//! script'; their outputs, the markdown cells, and any base64 blobs, e.g., embedded images, are stripped, as they are
+//! not code, but would take up most of the tokens.
+use regex::Regex;
+use serde_json::Value;
+use std::sync::OnceLock;
+
+/// The extension of a Jupyter notebook
+const NOTEBOOK_EXTENSION: &str = ".ipynb";
+/// The kernel languages whose comments start with '//', rather than '#'
+const SLASH_COMMENT_LANGUAGES: [&str; 9] = [
+    "c",
+    "c++",
+    "c#",
+    "go",
+    "java",
+    "javascript",
+    "kotlin",
+    "rust",
+    "scala",
+];
+/// The minimum length of a run of base64 characters that is taken to be a blob, rather than code
+const MIN_BASE64_BLOB_CHARS: usize = 200;
+
+/// Whether the file at the relative path is a Jupyter notebook
+pub(crate) fn is_notebook(relative_path: &str) -> bool {
+    relative_path
+        .to_ascii_lowercase()
+        .ends_with(NOTEBOOK_EXTENSION)
+}
+/// Gets the code cells of the notebook, in order, each headed by a comment with its execution count, e.g., '# In[3]:',
+/// with any base64 blobs replaced by '[BASE64]', or [`None`] if the contents are not a notebook
+pub(crate) fn get_notebook_code(contents: &str) -> Option<String> {
+    let notebook: Value = serde_json::from_str(contents).ok()?;
+    let cells: &Vec<Value> = notebook.get("cells")?.as_array()?;
+    let comment: &str = match get_kernel_language(&notebook) {
+        Some(language) if SLASH_COMMENT_LANGUAGES.contains(&language.as_str()) => "//",
+        _ => "#",
+    };
+    let code_cells: Vec<String> = cells
+        .iter()
+        .filter(|cell| cell.get("cell_type").and_then(Value::as_str) == Some("code"))
+        .filter_map(|cell| {
+            let source: String = get_cell_source(cell.get("source")?);
+            if source.trim().is_empty() {
+                return None;
+            }
+            let execution_count: String = cell
+                .get("execution_count")
+                .and_then(Value::as_u64)
+                .map_or(" ".to_string(), |count| count.to_string());
+            Some(format!(
+                "{} In[{}]:\n{}\n",
+                comment,
+                execution_count,
+                strip_base64_blobs(source.trim_end())
+            ))
+        })
+        .collect();
+    Some(code_cells.join("\n"))
+}
+/// Gets the language of the notebook's kernel, in lowercase, e.g., 'python', if set
+fn get_kernel_language(notebook: &Value) -> Option<String> {
+    let metadata: &Value = notebook.get("metadata")?;
+    metadata
+        .get("kernelspec")
+        .and_then(|kernelspec| kernelspec.get("language"))
+        .or_else(|| {
+            metadata
+                .get("language_info")
+                .and_then(|info| info.get("name"))
+        })
+        .and_then(Value::as_str)
+        .map(str::to_lowercase)
+}
+/// Gets the source of a cell, which is either a string or a list of lines
+fn get_cell_source(source: &Value) -> String {
+    match source {
+        Value::String(source) => source.clone(),
+        Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+/// Replaces each base64 blob, e.g., a data URI, or an image pasted into a string, with '[BASE64]'
+fn strip_base64_blobs(source: &str) -> String {
+    static BASE64_BLOB: OnceLock<Regex> = OnceLock::new();
+    let base64_blob: &Regex = BASE64_BLOB.get_or_init(|| {
+        Regex::new(&format!(
+            r"[A-Za-z0-9+/]{{{},}}={{0,2}}",
+            MIN_BASE64_BLOB_CHARS
+        ))
+        .unwrap()
+    });
+    base64_blob.replace_all(source, "[BASE64]").into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_notebook_code() {
+        let image: String = "iVBORw0KGgo".repeat(30);
+        let notebook: String = serde_json::json!({
+            "metadata": { "kernelspec": { "language": "python", "name": "python3" } },
+            "cells": [
+                { "cell_type": "markdown", "source": ["# Analysis\n", "Loads the data"] },
+                {
+                    "cell_type": "code",
+                    "execution_count": 1,
+                    "source": ["import pandas as pd\n", "df = pd.read_csv('data.csv')"],
+                    "outputs": [{ "output_type": "display_data", "data": { "image/png": image } }]
+                },
+                { "cell_type": "code", "execution_count": null, "source": "" },
+                {
+                    "cell_type": "code",
+                    "execution_count": null,
+                    "source": format!("logo = 'data:image/png;base64,{}'", image),
+                    "outputs": []
+                }
+            ]
+        })
+        .to_string();
+        assert_eq!(
+            get_notebook_code(&notebook),
+            Some(
+                "# In[1]:\nimport pandas as pd\ndf = pd.read_csv('data.csv')\n\n\
+                 # In[ ]:\nlogo = 'data:image/png;base64,[BASE64]'\n"
+                    .to_string()
+            )
+        );
+        assert_eq!(get_notebook_code("print('not a notebook')"), None);
+        assert!(is_notebook("notebooks/Analysis.IPYNB"));
+        assert!(!is_notebook("src/analysis.py"));
+    }
+}
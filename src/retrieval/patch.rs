@@ -0,0 +1,153 @@
+//! Parses a unified diff, e.g., from `git format-patch` or a GitHub pull request, into its hunks, so that only the changes
+//! are reviewed, each with the context lines around it.
+
+/// A hunk of a unified diff
+///
+/// #Fields:
+/// * `file_path` - The path of the file after the change, relative to the root of the repository
+/// * `header` - The header of the hunk, e.g., '@@ -10,7 +10,9 @@'
+/// * `contents` - The lines of the hunk, each prefixed with '+' if added, '-' if removed, or ' ' if unchanged context
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PatchHunk {
+    pub(crate) file_path: String,
+    pub(crate) header: String,
+    pub(crate) contents: String,
+}
+/// The hunk being parsed, with the number of old and new lines of it still to come
+struct OpenHunk {
+    hunk: PatchHunk,
+    old_remaining: usize,
+    new_remaining: usize,
+}
+impl OpenHunk {
+    fn push_line(&mut self, line: &str) {
+        self.hunk.contents.push_str(line);
+        self.hunk.contents.push('\n');
+    }
+}
+/// Parses the hunks of each added or modified file in the unified diff. The hunks of deleted files are skipped.
+pub(crate) fn parse_patch(patch: &str) -> Vec<PatchHunk> {
+    let mut hunks: Vec<PatchHunk> = Vec::new();
+    let mut file_path: Option<String> = None;
+    let mut open_hunk: Option<OpenHunk> = None;
+
+    for line in patch.lines() {
+        if let Some(open) = &mut open_hunk {
+            if open.old_remaining > 0 || open.new_remaining > 0 {
+                match line.chars().next() {
+                    Some('-') => open.old_remaining = open.old_remaining.saturating_sub(1),
+                    Some('+') => open.new_remaining = open.new_remaining.saturating_sub(1),
+                    // e.g., '\ No newline at end of file'
+                    Some('\\') => {}
+                    // Unchanged context, which may have had its trailing space stripped if blank
+                    _ => {
+                        open.old_remaining = open.old_remaining.saturating_sub(1);
+                        open.new_remaining = open.new_remaining.saturating_sub(1);
+                    }
+                }
+                open.push_line(line);
+                continue;
+            }
+            if line.starts_with('\\') {
+                open.push_line(line);
+                continue;
+            }
+            if let Some(open) = open_hunk.take() {
+                hunks.push(open.hunk);
+            }
+        }
+        if line.starts_with("diff --git ") {
+            file_path = None;
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            // The path may be followed by a tab and a timestamp
+            let path = path.split('\t').next().unwrap_or_default().trim();
+            file_path = match path {
+                "/dev/null" => None,
+                _ => Some(path.strip_prefix("b/").unwrap_or(path).to_string()),
+            };
+        } else if line.starts_with("@@ ") {
+            let (Some(path), Some((old_lines, new_lines))) = (&file_path, parse_hunk_header(line))
+            else {
+                continue;
+            };
+            open_hunk = Some(OpenHunk {
+                hunk: PatchHunk {
+                    file_path: path.clone(),
+                    header: get_hunk_range(line).to_string(),
+                    contents: String::new(),
+                },
+                old_remaining: old_lines,
+                new_remaining: new_lines,
+            });
+        }
+    }
+    if let Some(open) = open_hunk {
+        hunks.push(open.hunk);
+    }
+    hunks
+}
+/// Gets the range of the hunk header, e.g., '@@ -10,7 +10,9 @@', without the section heading that may follow it
+fn get_hunk_range(header: &str) -> &str {
+    match header[2..].find("@@") {
+        Some(end) => &header[..end + 4],
+        None => header,
+    }
+}
+/// Gets the number of old and new lines in the hunk from its header, e.g., (7, 9) from '@@ -10,7 +10,9 @@'
+fn parse_hunk_header(header: &str) -> Option<(usize, usize)> {
+    let mut ranges = get_hunk_range(header)
+        .trim_matches('@')
+        .split_whitespace()
+        .map(|range| {
+            // The number of lines is 1 if not given, e.g., '-10'
+            match range[1..].split_once(',') {
+                Some((_, num_lines)) => num_lines.parse::<usize>().ok(),
+                None => Some(1),
+            }
+        });
+    Some((ranges.next()??, ranges.next()??))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATCH: &str = "\
+From 1a2b3c4 Mon Sep 17 00:00:00 2001
+Subject: [PATCH] Handle the missing config
+
+diff --git a/src/main.rs b/src/main.rs
+index 1111111..2222222 100644
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -1,3 +1,4 @@ fn main() {
+ fn main() {
+-    let config = load().unwrap();
++    let config = load().unwrap_or_default();
++    run(config);
+ }
+@@ -10 +11 @@
+-// old
++// new
+diff --git a/src/old.rs b/src/old.rs
+deleted file mode 100644
+--- a/src/old.rs
++++ /dev/null
+@@ -1,1 +0,0 @@
+-fn old() {}
+";
+
+    #[test]
+    fn test_parse_patch() {
+        let hunks = parse_patch(PATCH);
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].file_path, "src/main.rs");
+        assert_eq!(hunks[0].header, "@@ -1,3 +1,4 @@");
+        assert_eq!(
+            hunks[0].contents,
+            " fn main() {\n-    let config = load().unwrap();\n+    let config = load().unwrap_or_default();\n+    run(config);\n }\n"
+        );
+        assert_eq!(hunks[1].header, "@@ -10 +11 @@");
+        assert_eq!(hunks[1].contents, "-// old\n+// new\n");
+    }
+}
@@ -0,0 +1,219 @@
+//! Classifies the source files as test or production code, by their paths and names, to give the ratio of test to
+//! production lines of code, and the production files with no corresponding tests.
+//!
+//! A test file is in a test directory, e.g., 'tests' or '__tests__', or is named as a test, e.g., 'test_main.py',
+//! 'main_test.go', 'main.test.ts' or 'MainTest.java'. A production file has tests if a test file is named for it, or,
+//! for Rust, if it has its own `#[cfg(test)]` module, whose lines are counted as test code.
+use super::data::SourceFileInfo;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// The directories whose files are tests
+const TEST_DIRS: [&str; 5] = ["test", "tests", "__tests__", "spec", "specs"];
+/// The prefixes of the names of test files, e.g., 'test_main.py'
+const TEST_PREFIXES: [&str; 1] = ["test_"];
+/// The suffixes of the names, without extension, of test files, e.g., 'main_test.go' or 'MainTest.java'
+const TEST_SUFFIXES: [&str; 8] = [
+    "_test", "_tests", "_spec", ".test", ".spec", "Test", "Tests", "Spec",
+];
+/// The names of files that are named by their directory, e.g., a Rust 'mod.rs'
+const DIRECTORY_MODULE_NAMES: [&str; 3] = ["mod", "index", "__init__"];
+/// The marker of an inline Rust test module
+const INLINE_TEST_MARKER: &str = "#[cfg(test)]";
+/// The languages whose production files are expected to have tests
+const TESTED_LANGUAGES: [&str; 15] = [
+    "C",
+    "C#",
+    "C++",
+    "Go",
+    "Java",
+    "JavaScript",
+    "Kotlin",
+    "PHP",
+    "Python",
+    "Ruby",
+    "Rust",
+    "Scala",
+    "Swift",
+    "TSX",
+    "TypeScript",
+];
+
+/// The test and production code of the source files
+///
+/// #Fields:
+/// * `test_loc` - The lines of test code, in test files and inline test modules
+/// * `production_loc` - The lines of production code
+/// * `untested_files` - The relative paths of the production files with no corresponding tests, largest first
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct TestStatistics {
+    pub(crate) test_loc: i64,
+    pub(crate) production_loc: i64,
+    pub(crate) untested_files: Vec<String>,
+}
+impl TestStatistics {
+    /// The ratio of test to production lines of code, or [`None`] if there is no production code
+    pub(crate) fn get_test_ratio(&self) -> Option<f32> {
+        (self.production_loc > 0).then(|| self.test_loc as f32 / self.production_loc as f32)
+    }
+}
+
+/// Gets the [`TestStatistics`] of the source files
+pub(crate) fn get_test_statistics(source_files: &[SourceFileInfo]) -> TestStatistics {
+    let mut statistics = TestStatistics::default();
+    let tested_modules: HashSet<String> = source_files
+        .iter()
+        .filter(|file_info| is_test_file(&file_info.relative_path))
+        .map(|file_info| get_tested_module(&file_info.relative_path))
+        .collect();
+    let mut untested_files: Vec<(&str, i64)> = Vec::new();
+    for file_info in source_files {
+        let loc: i64 = file_info.statistics.loc;
+        if is_test_file(&file_info.relative_path) {
+            statistics.test_loc += loc;
+            continue;
+        }
+        let language: Option<&str> = file_info
+            .language
+            .as_ref()
+            .map(|language| language.name.as_str());
+        let inline_test_loc: i64 = match language {
+            Some("Rust") => count_inline_test_loc(&file_info.get_source_file_contents()),
+            _ => 0,
+        };
+        statistics.test_loc += inline_test_loc;
+        statistics.production_loc += loc - inline_test_loc;
+        if inline_test_loc == 0
+            && language.is_some_and(|language| TESTED_LANGUAGES.contains(&language))
+            && !tested_modules.contains(&get_module_name(&file_info.relative_path))
+        {
+            untested_files.push((&file_info.relative_path, loc));
+        }
+    }
+    untested_files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    statistics.untested_files = untested_files
+        .into_iter()
+        .map(|(relative_path, _)| relative_path.to_string())
+        .collect();
+    statistics
+}
+/// Whether the file is a test, by its directory or name
+pub(crate) fn is_test_file(relative_path: &str) -> bool {
+    let path: &Path = Path::new(relative_path);
+    let in_test_dir: bool = path.parent().is_some_and(|parent| {
+        parent.components().any(|component| {
+            TEST_DIRS.contains(&component.as_os_str().to_str().unwrap_or_default())
+        })
+    });
+    let stem: &str = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default();
+    in_test_dir
+        || TEST_PREFIXES.iter().any(|prefix| stem.starts_with(prefix))
+        || TEST_SUFFIXES
+            .iter()
+            .any(|suffix| stem.ends_with(suffix) && stem.len() > suffix.len())
+}
+/// Gets the name of the module a test file tests, e.g., 'main' for 'test_main.py' or 'MainTest.java'
+fn get_tested_module(relative_path: &str) -> String {
+    let mut module: String = get_module_name(relative_path);
+    for prefix in TEST_PREFIXES {
+        if let Some(rest) = module.strip_prefix(prefix) {
+            module = rest.to_string();
+        }
+    }
+    for suffix in TEST_SUFFIXES {
+        let suffix = suffix.to_lowercase();
+        if let Some(rest) = module.strip_suffix(&suffix).filter(|rest| !rest.is_empty()) {
+            module = rest.to_string();
+            break;
+        }
+    }
+    module
+}
+/// Gets the name of the module of the file, lowercased, i.e., its name without extension, or its directory's name for a
+/// file named by its directory, e.g., 'provider' for 'src/provider/mod.rs'
+fn get_module_name(relative_path: &str) -> String {
+    let path: &Path = Path::new(relative_path);
+    let stem: &str = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default();
+    let stem: &str = match DIRECTORY_MODULE_NAMES.contains(&stem) {
+        true => path
+            .parent()
+            .and_then(|parent| parent.file_name())
+            .and_then(|name| name.to_str())
+            .unwrap_or(stem),
+        false => stem,
+    };
+    stem.to_lowercase()
+}
+/// Counts the lines of code of a Rust file's inline test module, i.e., from its `#[cfg(test)]` to the end of the file
+fn count_inline_test_loc(contents: &str) -> i64 {
+    contents
+        .lines()
+        .skip_while(|line| line.trim() != INLINE_TEST_MARKER)
+        .filter(|line| !line.trim().is_empty())
+        .count() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retrieval::data::{LanguageType, Statistics};
+
+    fn test_file_info(relative_path: &str, language: &str, loc: i64) -> SourceFileInfo {
+        let mut statistics = Statistics::new();
+        statistics.loc = loc;
+        SourceFileInfo::new(
+            relative_path.to_string(),
+            relative_path.to_string(),
+            LanguageType {
+                name: language.to_string(),
+                extension: String::new(),
+                statistics: None,
+            },
+            "0".to_string(),
+            statistics,
+        )
+    }
+
+    #[test]
+    fn test_get_test_statistics() {
+        for (relative_path, expected) in [
+            ("tests/integration.rs", true),
+            ("app/__tests__/App.jsx", true),
+            ("test_models.py", true),
+            ("server_test.go", true),
+            ("src/App.test.tsx", true),
+            ("src/main/java/ServiceTest.java", true),
+            ("src/main.rs", false),
+            ("src/test.rs", false),
+            ("src/contest.py", false),
+        ] {
+            assert_eq!(is_test_file(relative_path), expected, "{}", relative_path);
+        }
+        assert_eq!(
+            count_inline_test_loc("fn a() {}\n\n#[cfg(test)]\nmod tests {\n}\n"),
+            3
+        );
+
+        let source_files = vec![
+            test_file_info("app/models.py", "Python", 100),
+            test_file_info("tests/test_models.py", "Python", 50),
+            test_file_info("app/views.py", "Python", 300),
+            test_file_info("app/__init__.py", "Python", 10),
+            test_file_info("templates/index.html", "HTML", 40),
+        ];
+        let statistics = get_test_statistics(&source_files);
+        assert_eq!(statistics.test_loc, 50);
+        assert_eq!(statistics.production_loc, 450);
+        assert_eq!(
+            statistics.untested_files,
+            vec!["app/views.py".to_string(), "app/__init__.py".to_string()]
+        );
+        assert_eq!(statistics.get_test_ratio(), Some(50.0 / 450.0));
+    }
+}
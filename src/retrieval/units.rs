@@ -0,0 +1,213 @@
+//! Splits a source file into its function and method units, for a review at function granularity, so that findings are
+//! attached to a function name and line range rather than the whole file.
+//!
+//! The parsing is deliberately simple: a function is found by its declaration, e.g., `fn`, `function`, `func` or `def`,
+//! and ends where its braces balance, or, for Python, where the indentation returns to that of the declaration. A
+//! function nested in another is part of the outer unit. The code outside any function is its own '(top level)' unit.
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// The name of the unit of the code outside any function
+pub(crate) const TOP_LEVEL_UNIT: &str = "(top level)";
+/// The languages whose blocks are delimited by indentation rather than braces
+const INDENTED_LANGUAGES: [&str; 1] = ["Python"];
+
+/// A function or method of a source file, or the code outside any
+///
+/// #Fields:
+/// * `name` - The name of the function, or '(top level)'
+/// * `start_line` - The first line of the unit, from 1
+/// * `end_line` - The last line of the unit, inclusive
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct CodeUnit {
+    pub(crate) name: String,
+    pub(crate) start_line: usize,
+    pub(crate) end_line: usize,
+}
+
+/// Splits the contents into [`CodeUnit`]s, each with its contents, the '(top level)' unit first, if any. Returns no
+/// units if no function is found, in which case the file is reviewed whole.
+pub(crate) fn split_into_units(contents: &str, language: Option<&str>) -> Vec<(CodeUnit, String)> {
+    let lines: Vec<&str> = contents.lines().collect();
+    let ranges: Vec<(String, usize, usize)> =
+        if language.is_some_and(|language| INDENTED_LANGUAGES.contains(&language)) {
+            get_indented_function_ranges(&lines)
+        } else {
+            get_braced_function_ranges(&lines)
+        };
+    if ranges.is_empty() {
+        return Vec::new();
+    }
+
+    let mut units: Vec<(CodeUnit, String)> = Vec::new();
+    let top_level: Vec<usize> = (0..lines.len())
+        .filter(|index| {
+            !lines[*index].trim().is_empty()
+                && !ranges
+                    .iter()
+                    .any(|(_, start, end)| (*start..=*end).contains(index))
+        })
+        .collect();
+    if let (Some(first), Some(last)) = (top_level.first(), top_level.last()) {
+        units.push((
+            CodeUnit {
+                name: TOP_LEVEL_UNIT.to_string(),
+                start_line: first + 1,
+                end_line: last + 1,
+            },
+            top_level
+                .iter()
+                .map(|index| lines[*index])
+                .collect::<Vec<&str>>()
+                .join("\n"),
+        ));
+    }
+    for (name, start, end) in ranges {
+        units.push((
+            CodeUnit {
+                name,
+                start_line: start + 1,
+                end_line: end + 1,
+            },
+            lines[start..=end].join("\n"),
+        ));
+    }
+    units
+}
+/// Gets the name, and first and last line index, of each outermost function whose body is delimited by braces
+fn get_braced_function_ranges(lines: &[&str]) -> Vec<(String, usize, usize)> {
+    let declarations: [Regex; 5] = [
+        // Rust
+        Regex::new(r#"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:(?:const|async|unsafe)\s+)*(?:extern\s+"[^"]*"\s+)?fn\s+(\w+)"#)
+            .unwrap(),
+        // JavaScript and TypeScript
+        Regex::new(r"^\s*(?:export\s+)?(?:default\s+)?(?:async\s+)?function\s*\*?\s*(\w+)").unwrap(),
+        // Go and Swift
+        Regex::new(r"^\s*func\s+(?:\([^)]*\)\s*)?(\w+)").unwrap(),
+        // Kotlin
+        Regex::new(r"^\s*(?:(?:suspend|private|public|internal|protected|override)\s+)*fun\s+(\w+)").unwrap(),
+        // Java, C#, and C and C++ with a modifier
+        Regex::new(
+            r"^\s*(?:(?:public|private|protected|internal|static|final|abstract|synchronized|async|override|virtual|sealed)\s+)+[\w<>\[\],.?\s]*?(\w+)\s*\(",
+        )
+        .unwrap(),
+    ];
+    let mut ranges: Vec<(String, usize, usize)> = Vec::new();
+    let mut index = 0;
+    while index < lines.len() {
+        let name = declarations
+            .iter()
+            .find_map(|declaration| declaration.captures(lines[index]))
+            .map(|captures| captures[1].to_string());
+        match name.and_then(|name| get_braced_end(lines, index).map(|end| (name, end))) {
+            Some((name, end)) => {
+                ranges.push((name, index, end));
+                index = end + 1;
+            }
+            None => index += 1,
+        }
+    }
+    ranges
+}
+/// Gets the index of the line where the braces opened from the declaration balance, or the last line if they do not.
+/// Returns [`None`] if the declaration has no body, i.e., it ends with ';' before a brace is opened.
+fn get_braced_end(lines: &[&str], start: usize) -> Option<usize> {
+    let mut depth: i32 = 0;
+    let mut opened = false;
+    for (index, line) in lines.iter().enumerate().skip(start) {
+        for character in line.chars() {
+            match character {
+                '{' => {
+                    depth += 1;
+                    opened = true;
+                }
+                '}' => depth -= 1,
+                ';' if !opened => return None,
+                _ => {}
+            }
+            if opened && depth <= 0 {
+                return Some(index);
+            }
+        }
+    }
+    opened.then_some(lines.len() - 1)
+}
+/// Gets the name, and first and last line index, of each outermost function whose body is delimited by indentation
+fn get_indented_function_ranges(lines: &[&str]) -> Vec<(String, usize, usize)> {
+    let declaration = Regex::new(r"^(\s*)(?:async\s+)?def\s+(\w+)").unwrap();
+    let mut ranges: Vec<(String, usize, usize)> = Vec::new();
+    let mut index = 0;
+    while index < lines.len() {
+        let Some(captures) = declaration.captures(lines[index]) else {
+            index += 1;
+            continue;
+        };
+        let indent = captures[1].len();
+        let mut end = index;
+        for (next, line) in lines.iter().enumerate().skip(index + 1) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let next_indent = line.len() - line.trim_start().len();
+            if next_indent <= indent && !line.trim_start().starts_with(')') {
+                break;
+            }
+            end = next;
+        }
+        ranges.push((captures[2].to_string(), index, end));
+        index = end + 1;
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_into_units() {
+        let contents = "\
+use std::fs;
+
+pub(crate) fn read(path: &str) -> String {
+    let helper = || { 1 };
+    fs::read_to_string(path).unwrap()
+}
+
+trait Reader {
+    fn read(&self);
+}
+
+async fn write() {}
+";
+        let units = split_into_units(contents, Some("Rust"));
+        let names: Vec<(&str, usize, usize)> = units
+            .iter()
+            .map(|(unit, _)| (unit.name.as_str(), unit.start_line, unit.end_line))
+            .collect();
+        assert_eq!(
+            names,
+            vec![("(top level)", 1, 10), ("read", 3, 6), ("write", 12, 12)]
+        );
+        assert!(units[0].1.contains("fn read(&self);"));
+        assert!(units[1].1.ends_with('}'));
+
+        let python = "\
+import os
+
+def load(path):
+    with open(path) as file:
+        return file.read()
+
+class Store:
+    def save(self, data):
+        pass
+";
+        let units = split_into_units(python, Some("Python"));
+        let names: Vec<&str> = units.iter().map(|(unit, _)| unit.name.as_str()).collect();
+        assert_eq!(names, vec!["(top level)", "load", "save"]);
+        assert_eq!(units[1].0.end_line, 5);
+
+        assert!(split_into_units("let x = 1;\n", Some("Rust")).is_empty());
+    }
+}
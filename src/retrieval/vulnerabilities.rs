@@ -0,0 +1,392 @@
+//! Looks up the known vulnerabilities, e.g., CVEs, of the repository's dependencies in the OSV database, via the OSV.dev
+//! API, without the LLM.
+//!
+//! OSV is queried for an exact version, so a dependency is looked up at the versions its lockfile resolves it to,
+//! i.e., those of the 'Cargo.lock' or 'package-lock.json' in the directory of its manifest, or the nearest above it,
+//! or, for a 'requirements.txt', at its pinned version, e.g., '==2.31.0'. A requirement, e.g., '^1.2', does not say
+//! which version is installed, so a dependency without a lockfile, or a pin, is not looked up.
+use super::dependencies::Dependency;
+use crate::review::data::Severity;
+use crate::review::normalise::normalise_severity;
+use futures::stream::{self, StreamExt};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::error::Error;
+
+/// The OSV.dev API endpoint that queries the vulnerabilities of a package version
+const OSV_QUERY_URL: &str = "https://api.osv.dev/v1/query";
+/// The maximum number of queries to OSV.dev at once
+const MAX_CONCURRENT_QUERIES: usize = 8;
+/// The lockfiles of the resolved versions of the dependencies, as (name, ecosystem)
+pub(crate) const LOCKFILES: [(&str, &str); 2] =
+    [("Cargo.lock", "crates.io"), ("package-lock.json", "npm")];
+
+/// A known vulnerability of a dependency
+///
+/// #Fields:
+/// * `dependency` - The name of the dependency
+/// * `ecosystem` - The ecosystem of the dependency, e.g., 'crates.io'
+/// * `version` - The version looked up, i.e., as resolved by the lockfile, or pinned
+/// * `id` - The OSV identifier, e.g., 'GHSA-xxxx-xxxx-xxxx' or 'RUSTSEC-2024-0001'
+/// * `aliases` - The other identifiers of the vulnerability, e.g., its CVE
+/// * `summary` - The summary of the vulnerability
+/// * `severity` - The [`Severity`] of the vulnerability, 'Medium' if OSV does not give one
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct DependencyVulnerability {
+    pub(crate) dependency: String,
+    pub(crate) ecosystem: String,
+    pub(crate) version: String,
+    pub(crate) id: String,
+    #[serde(default)]
+    pub(crate) aliases: Vec<String>,
+    pub(crate) summary: String,
+    pub(crate) severity: Severity,
+}
+
+/// The resolved versions of the packages of a lockfile
+///
+/// #Fields:
+/// * `directory` - The relative path of the directory of the lockfile, empty if the root of the repository
+/// * `ecosystem` - The package ecosystem of the lockfile, as named by OSV, e.g., 'crates.io'
+/// * `versions` - The resolved versions of each package, as more than one version of a package may be resolved
+#[derive(Debug, Default, PartialEq)]
+struct Lockfile {
+    directory: String,
+    ecosystem: String,
+    versions: HashMap<String, Vec<String>>,
+}
+
+/// Looks up the known vulnerabilities of each dependency in OSV.dev, at the versions resolved by the lockfiles, each
+/// as its relative path and contents, or pinned. A failed lookup of a dependency is logged, and the dependency skipped.
+pub(crate) async fn lookup_vulnerabilities(
+    dependencies: &[Dependency],
+    lockfiles: &[(String, String)],
+) -> Result<Vec<DependencyVulnerability>, Box<dyn Error>> {
+    let client = reqwest::Client::builder().build()?;
+    let lockfiles: Vec<Lockfile> = get_lockfiles(lockfiles);
+    let queries: Vec<(&Dependency, String)> = dependencies
+        .iter()
+        .flat_map(|dependency| {
+            get_query_versions(dependency, &lockfiles)
+                .into_iter()
+                .map(move |version| (dependency, version))
+        })
+        .collect();
+    info!(
+        "Looking up the vulnerabilities of {} dependencies in OSV.dev",
+        queries.len()
+    );
+    let client = &client;
+    let vulnerabilities: Vec<Vec<DependencyVulnerability>> = stream::iter(queries)
+        .map(|(dependency, version)| async move {
+            match query_osv(client, dependency, &version).await {
+                Ok(response) => parse_osv_vulnerabilities(dependency, &version, &response),
+                Err(e) => {
+                    warn!(
+                        "Failed to look up the vulnerabilities of: {} {}: {}",
+                        dependency.name, version, e
+                    );
+                    Vec::new()
+                }
+            }
+        })
+        .buffered(MAX_CONCURRENT_QUERIES)
+        .collect()
+        .await;
+    Ok(vulnerabilities.into_iter().flatten().collect())
+}
+/// Queries OSV.dev for the vulnerabilities of the version of the dependency
+async fn query_osv(
+    client: &reqwest::Client,
+    dependency: &Dependency,
+    version: &str,
+) -> Result<Value, Box<dyn Error>> {
+    let query = json!({
+        "version": version,
+        "package": { "name": dependency.name, "ecosystem": dependency.ecosystem },
+    });
+    let response = client
+        .post(OSV_QUERY_URL)
+        .json(&query)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(response.json().await?)
+}
+/// Parses the vulnerabilities of an OSV.dev query response, i.e., its 'vulns'
+fn parse_osv_vulnerabilities(
+    dependency: &Dependency,
+    version: &str,
+    response: &Value,
+) -> Vec<DependencyVulnerability> {
+    let Some(vulns) = response["vulns"].as_array() else {
+        return Vec::new();
+    };
+    vulns
+        .iter()
+        .filter_map(|vuln| {
+            Some(DependencyVulnerability {
+                dependency: dependency.name.clone(),
+                ecosystem: dependency.ecosystem.clone(),
+                version: version.to_string(),
+                id: vuln["id"].as_str()?.to_string(),
+                aliases: vuln["aliases"]
+                    .as_array()
+                    .map(|aliases| {
+                        aliases
+                            .iter()
+                            .filter_map(|alias| alias.as_str().map(str::to_string))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                summary: vuln["summary"]
+                    .as_str()
+                    .or_else(|| vuln["details"].as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                severity: get_osv_severity(vuln),
+            })
+        })
+        .collect()
+}
+/// Gets the [`Severity`] of an OSV vulnerability, from the severity given by its database, e.g., 'MODERATE', if any
+fn get_osv_severity(vuln: &Value) -> Severity {
+    match vuln["database_specific"]["severity"]
+        .as_str()
+        .and_then(normalise_severity)
+    {
+        Some("Critical") => Severity::Critical,
+        Some("High") => Severity::High,
+        Some("Low") => Severity::Low,
+        _ => Severity::Medium,
+    }
+}
+/// Gets the versions of the dependency to query, i.e., those resolved by its lockfile, or its pinned version, e.g.,
+/// '==2.31.0' in a 'requirements.txt', or none if neither, or the dependency is not from a registry
+fn get_query_versions(dependency: &Dependency, lockfiles: &[Lockfile]) -> Vec<String> {
+    if ["git", "path"].contains(&dependency.version.as_str()) {
+        return Vec::new();
+    }
+    if dependency.ecosystem == "PyPI" {
+        return dependency
+            .version
+            .strip_prefix("===")
+            .or_else(|| dependency.version.strip_prefix("=="))
+            .filter(|version| !version.is_empty() && !version.contains([',', '*']))
+            .map(|version| vec![version.to_string()])
+            .unwrap_or_default();
+    }
+    // The lockfile of a workspace may be in a directory above the manifest
+    let manifest_directory: &str = get_directory(&dependency.manifest);
+    lockfiles
+        .iter()
+        .filter(|lockfile| {
+            lockfile.ecosystem == dependency.ecosystem
+                && (lockfile.directory.is_empty()
+                    || manifest_directory == lockfile.directory
+                    || manifest_directory.starts_with(&format!("{}/", lockfile.directory)))
+        })
+        .max_by_key(|lockfile| lockfile.directory.len())
+        .and_then(|lockfile| lockfile.versions.get(&dependency.name))
+        .cloned()
+        .unwrap_or_default()
+}
+/// Gets the [`Lockfile`]s, each as its relative path and contents, with the resolved versions of their packages
+fn get_lockfiles(lockfiles: &[(String, String)]) -> Vec<Lockfile> {
+    lockfiles
+        .iter()
+        .filter_map(|(relative_path, contents)| {
+            let file_name: &str = relative_path.rsplit('/').next().unwrap_or(relative_path);
+            let (name, ecosystem) = LOCKFILES.iter().find(|(name, _)| *name == file_name)?;
+            let versions: HashMap<String, Vec<String>> = match *name {
+                "Cargo.lock" => get_cargo_locked_versions(contents),
+                _ => get_npm_locked_versions(contents),
+            };
+            Some(Lockfile {
+                directory: get_directory(relative_path).to_string(),
+                ecosystem: ecosystem.to_string(),
+                versions,
+            })
+        })
+        .collect()
+}
+/// Gets the resolved versions of the packages of a 'Cargo.lock', i.e., of each '[[package]]' from a registry
+fn get_cargo_locked_versions(contents: &str) -> HashMap<String, Vec<String>> {
+    let mut versions: HashMap<String, Vec<String>> = HashMap::new();
+    for package in contents.split("[[package]]").skip(1) {
+        let mut fields: HashMap<&str, &str> = HashMap::new();
+        for line in package.lines() {
+            if let Some((key, value)) = line.split_once('=') {
+                fields.insert(key.trim(), value.trim().trim_matches('"'));
+            }
+        }
+        // A package without a source is of the workspace, and one from git is not in OSV
+        let from_registry: bool = fields
+            .get("source")
+            .is_some_and(|source| source.starts_with("registry+") || source.starts_with("sparse+"));
+        if let (true, Some(name), Some(version)) =
+            (from_registry, fields.get("name"), fields.get("version"))
+        {
+            add_locked_version(&mut versions, name, version);
+        }
+    }
+    versions
+}
+/// Gets the resolved versions of the packages of a 'package-lock.json', from its 'packages', keyed by their path in
+/// 'node_modules', or, in a lockfile of version 1, its 'dependencies'
+fn get_npm_locked_versions(contents: &str) -> HashMap<String, Vec<String>> {
+    let mut versions: HashMap<String, Vec<String>> = HashMap::new();
+    let Ok(lockfile) = serde_json::from_str::<Value>(contents) else {
+        return versions;
+    };
+    if let Some(packages) = lockfile["packages"].as_object() {
+        for (path, package) in packages {
+            // The root package has the empty path, and a linked package is of the workspace
+            let Some((_, name)) = path.rsplit_once("node_modules/") else {
+                continue;
+            };
+            if let (None, Some(version)) = (package["link"].as_bool(), package["version"].as_str())
+            {
+                add_locked_version(&mut versions, name, version);
+            }
+        }
+    } else if let Some(dependencies) = lockfile["dependencies"].as_object() {
+        for (name, dependency) in dependencies {
+            if let Some(version) = dependency["version"].as_str() {
+                add_locked_version(&mut versions, name, version);
+            }
+        }
+    }
+    versions
+}
+/// Adds the resolved version of the package, if not already added
+fn add_locked_version(versions: &mut HashMap<String, Vec<String>>, name: &str, version: &str) {
+    let package_versions = versions.entry(name.to_string()).or_default();
+    if !package_versions.iter().any(|locked| locked == version) {
+        package_versions.push(version.to_string());
+    }
+}
+/// Gets the relative path of the directory of the file, empty if the root of the repository
+fn get_directory(relative_path: &str) -> &str {
+    relative_path
+        .rsplit_once('/')
+        .map_or("", |(directory, _)| directory)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dependency(ecosystem: &str, version: &str) -> Dependency {
+        Dependency {
+            manifest: "manifest".to_string(),
+            ecosystem: ecosystem.to_string(),
+            name: "package".to_string(),
+            version: version.to_string(),
+            dev: false,
+        }
+    }
+
+    #[test]
+    fn test_get_query_versions() {
+        let cargo_lock = r#"
+version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+
+[[package]]
+name = "package"
+version = "1.0.2"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "package"
+version = "0.9.1"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "forked"
+version = "2.0.0"
+source = "git+https://github.com/example/forked.git#abc123"
+"#;
+        let package_lock = r#"{
+            "lockfileVersion": 3,
+            "packages": {
+                "": { "name": "web" },
+                "node_modules/package": { "version": "18.2.0" },
+                "node_modules/other/node_modules/package": { "version": "17.0.2" },
+                "node_modules/local": { "link": true }
+            }
+        }"#;
+        let lockfiles = get_lockfiles(&[
+            ("Cargo.lock".to_string(), cargo_lock.to_string()),
+            (
+                "web/package-lock.json".to_string(),
+                package_lock.to_string(),
+            ),
+        ]);
+        let mut dependency = test_dependency("crates.io", "^0.9");
+        dependency.manifest = "crates/app/Cargo.toml".to_string();
+        assert_eq!(
+            get_query_versions(&dependency, &lockfiles),
+            vec!["1.0.2", "0.9.1"]
+        );
+        dependency.name = "forked".to_string();
+        assert!(get_query_versions(&dependency, &lockfiles).is_empty());
+
+        let mut dependency = test_dependency("npm", "^18.0.0");
+        dependency.manifest = "web/package.json".to_string();
+        let mut versions = get_query_versions(&dependency, &lockfiles);
+        versions.sort();
+        assert_eq!(versions, vec!["17.0.2", "18.2.0"]);
+        // A manifest without a lockfile is not looked up
+        dependency.manifest = "docs/package.json".to_string();
+        assert!(get_query_versions(&dependency, &lockfiles).is_empty());
+
+        for (requirement, expected) in [
+            ("==2.31.0", vec!["2.31.0"]),
+            ("===2.31.0", vec!["2.31.0"]),
+            ("==2.*", vec![]),
+            (">=4.2,<5", vec![]),
+            ("*", vec![]),
+        ] {
+            assert_eq!(
+                get_query_versions(&test_dependency("PyPI", requirement), &lockfiles),
+                expected,
+                "{}",
+                requirement
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_osv_vulnerabilities() {
+        let response = json!({
+            "vulns": [
+                {
+                    "id": "GHSA-j8r2-6x86-q33q",
+                    "aliases": ["CVE-2023-32681"],
+                    "summary": "Unintended leak of Proxy-Authorization header",
+                    "database_specific": { "severity": "MODERATE" }
+                },
+                { "id": "PYSEC-2023-74", "details": "A leak" }
+            ]
+        });
+        let vulnerabilities =
+            parse_osv_vulnerabilities(&test_dependency("PyPI", "==2.30.0"), "2.30.0", &response);
+        assert_eq!(vulnerabilities.len(), 2);
+        assert_eq!(
+            vulnerabilities[0].aliases,
+            vec!["CVE-2023-32681".to_string()]
+        );
+        assert_eq!(vulnerabilities[0].severity, Severity::Medium);
+        assert_eq!(vulnerabilities[1].summary, "A leak");
+        assert!(
+            parse_osv_vulnerabilities(&test_dependency("PyPI", "1"), "1", &json!({})).is_empty()
+        );
+    }
+}
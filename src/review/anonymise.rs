@@ -0,0 +1,131 @@
+//! Pseudonymises the contributors throughout the review, e.g., before a report is shared outside the team, as their
+//! names are personal data. Each contributor is named by their rank by commits, e.g., 'Contributor 1', the same in
+//! every section of the review, i.e., its contributors, ownership and contributor findings.
+//!
+//! Unlike a hash of a name, a rank cannot be reversed by hashing the names of a team, so the pseudonyms are not stable
+//! across reviews.
+use crate::retrieval::data::Contributor;
+use crate::review::data::RepositoryReview;
+use std::collections::HashMap;
+
+/// Replaces the name of each contributor in the review with their pseudonym
+pub(crate) fn anonymise_contributors(repository_review: &mut RepositoryReview) {
+    let mut contributors: Vec<Contributor> = repository_review.get_contributors().to_vec();
+    contributors.sort_by(|a, b| {
+        b.get_statistics()
+            .num_commits
+            .cmp(&a.get_statistics().num_commits)
+            .then_with(|| a.get_name().cmp(b.get_name()))
+    });
+    let mut pseudonyms: HashMap<String, String> = HashMap::new();
+    let contributors: Vec<Contributor> = contributors
+        .iter()
+        .map(|contributor| {
+            Contributor::new(
+                get_pseudonym(&mut pseudonyms, contributor.get_name()),
+                *contributor.get_last_contribution(),
+                contributor.get_percentage_contribution(),
+                contributor.get_statistics().clone(),
+            )
+        })
+        .collect();
+    repository_review.contributors(contributors);
+
+    // Those who own files, or to whom findings are attributed, but made no commits, e.g., of an uncommitted change
+    if let Some(ownership) = &mut repository_review.ownership {
+        for key_contributor in ownership.key_contributors.iter_mut() {
+            key_contributor.name = get_pseudonym(&mut pseudonyms, &key_contributor.name);
+        }
+    }
+    for findings in repository_review.contributor_findings.iter_mut().flatten() {
+        findings.contributor = get_pseudonym(&mut pseudonyms, &findings.contributor);
+    }
+}
+/// Gets the pseudonym of the name, the next in turn if the name has none
+fn get_pseudonym(pseudonyms: &mut HashMap<String, String>, name: &str) -> String {
+    let next: usize = pseudonyms.len() + 1;
+    pseudonyms
+        .entry(name.to_string())
+        .or_insert_with(|| format!("Contributor {}", next))
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retrieval::data::Statistics;
+    use crate::review::data::{ContributorFindings, KeyContributor, Ownership};
+    use chrono::Utc;
+
+    fn test_contributor(name: &str, num_commits: i32) -> Contributor {
+        let mut statistics = Statistics::new();
+        statistics.num_commits = num_commits;
+        Contributor::new(name.to_string(), Utc::now(), 50.0, statistics)
+    }
+
+    #[test]
+    fn test_anonymise_contributors() {
+        let mut repository_review = RepositoryReview::new("repos_name".to_string());
+        repository_review.contributors(vec![
+            test_contributor("Ana Smith", 2),
+            test_contributor("Bo Jones", 5),
+        ]);
+        repository_review.ownership(Some(Ownership {
+            bus_factor: 1,
+            mean_concentration: 90.0,
+            key_contributors: vec![KeyContributor {
+                name: "Ana Smith".to_string(),
+                files_owned: 3,
+                percentage_files: 60.0,
+            }],
+        }));
+        repository_review.contributor_findings(Some(vec![
+            ContributorFindings {
+                contributor: "Not Committed Yet".to_string(),
+                security_issues: 0,
+                errors: 2,
+                improvements: 0,
+                total: 2,
+            },
+            ContributorFindings {
+                contributor: "Bo Jones".to_string(),
+                security_issues: 1,
+                errors: 0,
+                improvements: 0,
+                total: 1,
+            },
+        ]));
+
+        anonymise_contributors(&mut repository_review);
+        assert_eq!(
+            repository_review
+                .get_contributors()
+                .iter()
+                .map(|contributor| (
+                    contributor.get_name(),
+                    contributor.get_statistics().num_commits
+                ))
+                .collect::<Vec<_>>(),
+            vec![("Contributor 1", 5), ("Contributor 2", 2)]
+        );
+        assert_eq!(
+            repository_review
+                .ownership
+                .as_ref()
+                .unwrap()
+                .key_contributors[0]
+                .name,
+            "Contributor 2"
+        );
+        assert_eq!(
+            repository_review
+                .contributor_findings
+                .as_ref()
+                .unwrap()
+                .iter()
+                .map(|findings| findings.contributor.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Contributor 3", "Contributor 1"]
+        );
+    }
+}
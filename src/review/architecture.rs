@@ -0,0 +1,99 @@
+//! A second pass, after the file reviews, over the repository as a whole: the directory tree and the summaries of the
+//! file reviews are sent to the LLM for architecture-level findings, e.g., layering violations, god modules or missing
+//! tests, that no single file review can see.
+use super::strip_artifacts_from;
+use crate::provider::models::warn_if_exceeds_context_window;
+use crate::provider::prompts::PromptData;
+use crate::provider::{get_provider, get_service, review_or_summarise, RequestType};
+use crate::review::data::{ArchitectureFinding, SourceFileReview};
+use crate::settings::Settings;
+use log::info;
+use serde::Deserialize;
+use std::error::Error;
+
+/// The response of the LLM to the architecture review
+#[derive(Deserialize)]
+struct ArchitectureReviewResponse {
+    #[serde(default)]
+    findings: Vec<ArchitectureFinding>,
+}
+
+/// Reviews the architecture of the repository from its file reviews, returning the [`ArchitectureFinding`]s
+pub(crate) async fn review_architecture(
+    settings: &Settings,
+    file_reviews: &[SourceFileReview],
+) -> Result<Vec<ArchitectureFinding>, Box<dyn Error>> {
+    info!(
+        "Reviewing the architecture of the repository, from {} file reviews",
+        file_reviews.len()
+    );
+    let provider = get_provider(settings);
+    let mut prompt_data: PromptData =
+        PromptData::get_architecture_review_prompt(settings.prompts_path.as_deref())?;
+    prompt_data.add_user_message_prompt(get_architecture_review_request(file_reviews));
+    warn_if_exceeds_context_window(settings, &get_service(provider).model, &prompt_data);
+
+    let response =
+        review_or_summarise(RequestType::Summarise, settings, provider, &prompt_data).await?;
+    parse_architecture_review(&response.choices[0].message.content)
+}
+/// Gets the user message with the directory tree, i.e., the path of every reviewed file, and the summary of each review
+fn get_architecture_review_request(file_reviews: &[SourceFileReview]) -> String {
+    let mut relative_paths: Vec<&str> = file_reviews
+        .iter()
+        .map(|file_review| file_review.source_file_info.relative_path.as_str())
+        .collect();
+    relative_paths.sort_unstable();
+    relative_paths.dedup();
+
+    let mut request = String::from("Directory tree:\n");
+    for relative_path in relative_paths {
+        request.push_str(&format!(" {}\n", relative_path));
+    }
+    request.push_str("Summaries of the file reviews:\n");
+    for file_review in file_reviews {
+        request.push_str(&format!(
+            " {}: {}\n",
+            file_review.source_file_info.relative_path,
+            file_review.summary.trim()
+        ));
+    }
+    request
+}
+/// Parses the [`ArchitectureFinding`]s from the response, ignoring any text around the JSON
+fn parse_architecture_review(content: &str) -> Result<Vec<ArchitectureFinding>, Box<dyn Error>> {
+    let json: String = strip_artifacts_from(content)?;
+    let response: ArchitectureReviewResponse = serde_json::from_str(&json)?;
+    Ok(response.findings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_architecture_review_request_and_response() {
+        let file_reviews: Vec<SourceFileReview> = serde_json::from_str(
+            r#"[
+                { "source_file_info": { "name": "mod.rs", "relative_path": "src/review/mod.rs" }, "summary": "Reviews files" },
+                { "source_file_info": { "name": "main.rs", "relative_path": "src/main.rs" }, "summary": "Runs the CLI" }
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            get_architecture_review_request(&file_reviews),
+            "Directory tree:\n src/main.rs\n src/review/mod.rs\nSummaries of the file reviews:\n src/review/mod.rs: Reviews files\n src/main.rs: Runs the CLI\n"
+        );
+
+        let findings = parse_architecture_review(
+            r#"```json
+            {"findings": [{"category": "God module", "finding": "The review module does everything", "recommendation": "Split it", "files": ["src/review/mod.rs"]}]}
+            ```"#,
+        )
+        .unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].category, "God module");
+        assert_eq!(findings[0].files, vec!["src/review/mod.rs".to_string()]);
+        assert!(parse_architecture_review("No problems found").is_err());
+    }
+}
@@ -0,0 +1,150 @@
+//! Attributes the findings of the file reviews to the contributors who last changed their code, by git blame, so that
+//! training can be targeted to the areas each contributor works in. It is not a measure of a contributor's quality, as
+//! the last to change a line is not always its author.
+//!
+//! A finding is located by the first line of its code snippet found in the file reviewed. A finding whose code is not
+//! found, e.g., as the model paraphrased it, is not attributed. The contents reviewed, e.g., of the working tree, are
+//! blamed, not those of 'HEAD', so that a line is found where it was reviewed, and a line not yet committed is not
+//! attributed.
+use crate::retrieval::git::blame::get_line_authors;
+use crate::review::data::{ContributorFindings, SourceFileReview};
+use crate::review::read_reviewed_contents;
+use crate::settings::ReviewScope;
+use log::debug;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// The kind of a finding attributed to a contributor
+enum Finding {
+    SecurityIssue,
+    Error,
+    Improvement,
+}
+
+/// Gets the [`ContributorFindings`] of each contributor to whom findings are attributed, most findings first. If
+/// anonymised, each contributor is named by their rank, e.g., 'Contributor 1'.
+pub(crate) fn get_contributor_findings(
+    repository_path: &str,
+    review_scope: &ReviewScope,
+    file_reviews: &[SourceFileReview],
+    anonymise: bool,
+) -> Vec<ContributorFindings> {
+    let mut contributor_findings: HashMap<String, ContributorFindings> = HashMap::new();
+    for file_review in file_reviews {
+        let contents: String = file_review.source_file_info.get_source_file_contents();
+        // The lines of a unit, at function granularity, are from its start line in the file
+        let line_offset: usize = file_review
+            .source_file_info
+            .code_unit
+            .as_ref()
+            .map_or(0, |unit| unit.start_line - 1);
+        let (findings, lines): (Vec<Finding>, Vec<usize>) = get_finding_codes(file_review)
+            .into_iter()
+            .filter_map(|(finding, code)| {
+                Some((finding, find_code_line(&contents, code)? + line_offset))
+            })
+            .unzip();
+        if lines.is_empty() {
+            continue;
+        }
+        let relative_path: &str = &file_review.source_file_info.relative_path;
+        // The whole file is blamed, even if only a unit of it was reviewed
+        let Some(reviewed_contents) =
+            read_reviewed_contents(review_scope, Path::new(repository_path), relative_path)
+        else {
+            debug!(
+                "Failed to read, so not attributing findings: {}",
+                relative_path
+            );
+            continue;
+        };
+        let authors: Vec<Option<String>> =
+            match get_line_authors(repository_path, relative_path, &reviewed_contents, &lines) {
+                Ok(authors) => authors,
+                Err(e) => {
+                    debug!(
+                        "Failed to blame, so not attributing findings: {}: {}",
+                        relative_path, e
+                    );
+                    continue;
+                }
+            };
+        for (finding, author) in findings.iter().zip(authors) {
+            let Some(author) = author else {
+                continue;
+            };
+            contributor_findings
+                .entry(author.clone())
+                .or_insert_with(|| ContributorFindings {
+                    contributor: author,
+                    security_issues: 0,
+                    errors: 0,
+                    improvements: 0,
+                    total: 0,
+                })
+                .add(finding);
+        }
+    }
+    let mut contributor_findings: Vec<ContributorFindings> =
+        contributor_findings.into_values().collect();
+    contributor_findings.sort_by(|a, b| {
+        b.total
+            .cmp(&a.total)
+            .then_with(|| a.contributor.cmp(&b.contributor))
+    });
+    if anonymise {
+        for (rank, findings) in contributor_findings.iter_mut().enumerate() {
+            findings.contributor = format!("Contributor {}", rank + 1);
+        }
+    }
+    contributor_findings
+}
+impl ContributorFindings {
+    /// Counts a finding attributed to the contributor
+    fn add(&mut self, finding: &Finding) {
+        self.total += 1;
+        match finding {
+            Finding::SecurityIssue => self.security_issues += 1,
+            Finding::Error => self.errors += 1,
+            Finding::Improvement => self.improvements += 1,
+        }
+    }
+}
+/// Gets the code snippet of each finding of the review
+fn get_finding_codes(file_review: &SourceFileReview) -> Vec<(Finding, &str)> {
+    let security_issues = file_review.security_issues.iter().flatten();
+    let errors = file_review.errors.iter().flatten();
+    let improvements = file_review.improvements.iter().flatten();
+    security_issues
+        .map(|security_issue| (Finding::SecurityIssue, security_issue.code.as_str()))
+        .chain(errors.map(|error| (Finding::Error, error.code.as_str())))
+        .chain(improvements.map(|improvement| (Finding::Improvement, improvement.code.as_str())))
+        .collect()
+}
+/// Finds the line of the contents, from 1, that is the first non-blank line of the code snippet, both trimmed, if any.
+/// A line that only contains it, e.g., a common fragment such as '}', is not a match.
+pub(crate) fn find_code_line(contents: &str, code: &str) -> Option<usize> {
+    let first_line: &str = code.lines().map(str::trim).find(|line| !line.is_empty())?;
+    contents
+        .lines()
+        .position(|line| line.trim() == first_line)
+        .map(|index| index + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_code_line() {
+        let contents = "fn main() {\n    let x = input.unwrap();\n    query(x);\n}\n";
+        assert_eq!(
+            find_code_line(contents, "\n  let x = input.unwrap();\n  query(x);"),
+            Some(2)
+        );
+        assert_eq!(find_code_line(contents, "query(x);"), Some(3));
+        assert_eq!(find_code_line(contents, "query(x)"), None);
+        assert_eq!(find_code_line(contents, "the unwrap of the input"), None);
+        assert_eq!(find_code_line(contents, "  "), None);
+    }
+}
@@ -0,0 +1,182 @@
+//! A baseline of previously accepted findings, taken from an earlier JSON report, e.g., `--baseline previous-report.json`.
+//!
+//! Each finding is fingerprinted by the file's `id_hash`, its code and its issue. A finding that recurs in the baseline
+//! is moved to the `known_findings` of its [`SourceFileReview`], so that only new findings affect the RAG status.
+use crate::retrieval::code::calculate_hash_from;
+use crate::review::data::{KnownFinding, SourceFileReview};
+use crate::settings::Settings;
+use log::info;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+
+/// The fingerprints of the findings in the baseline report
+#[derive(Debug, Default)]
+pub(crate) struct Baseline {
+    fingerprints: HashSet<String>,
+}
+impl Baseline {
+    /// Loads the fingerprints of every finding, including those already known, in the file reviews of the JSON report
+    pub(crate) fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read the baseline: {}: {}", path, e))?;
+        let report: serde_json::Value = serde_json::from_str(&contents)?;
+        let file_reviews: Vec<SourceFileReview> = serde_json::from_value(
+            report
+                .get("file_reviews")
+                .cloned()
+                .ok_or_else(|| format!("The baseline is not a JSON report: {}", path))?,
+        )?;
+
+        let mut baseline = Self::default();
+        for file_review in &file_reviews {
+            for finding in get_findings(file_review) {
+                baseline.fingerprints.insert(finding.fingerprint);
+            }
+            for known_finding in file_review.known_findings.iter().flatten() {
+                baseline
+                    .fingerprints
+                    .insert(known_finding.fingerprint.clone());
+            }
+        }
+        info!(
+            "Baseline: {} findings from: {}",
+            baseline.fingerprints.len(),
+            path
+        );
+        Ok(baseline)
+    }
+    /// Loads the [`Baseline`], or [`None`] if `baseline_path` is not set in the [`Settings`]
+    pub(crate) fn from_settings(settings: &Settings) -> Result<Option<Self>, Box<dyn Error>> {
+        settings
+            .baseline_path
+            .as_deref()
+            .map(Self::load)
+            .transpose()
+    }
+    /// Moves the findings of the [`SourceFileReview`] that are in the baseline to its `known_findings`
+    pub(crate) fn apply(&self, file_review: &mut SourceFileReview) {
+        let known_findings: Vec<KnownFinding> = get_findings(file_review)
+            .into_iter()
+            .filter(|finding| self.fingerprints.contains(&finding.fingerprint))
+            .collect();
+        if known_findings.is_empty() {
+            return;
+        }
+        let is_known = |fingerprint: String| {
+            known_findings
+                .iter()
+                .any(|known_finding| known_finding.fingerprint == fingerprint)
+        };
+        let id_hash: &str = file_review
+            .source_file_info
+            .id_hash
+            .as_deref()
+            .unwrap_or("");
+        if let Some(security_issues) = &mut file_review.security_issues {
+            security_issues.retain(|security_issue| {
+                !is_known(get_fingerprint(
+                    id_hash,
+                    &security_issue.code,
+                    &security_issue.threat,
+                ))
+            });
+        }
+        if let Some(errors) = &mut file_review.errors {
+            errors.retain(|error| !is_known(get_fingerprint(id_hash, &error.code, &error.issue)));
+        }
+        if let Some(improvements) = &mut file_review.improvements {
+            improvements.retain(|improvement| {
+                !is_known(get_fingerprint(
+                    id_hash,
+                    &improvement.code,
+                    &improvement.suggestion,
+                ))
+            });
+        }
+        file_review.known_findings = Some(known_findings);
+    }
+}
+/// Gets each finding of the [`SourceFileReview`] as a [`KnownFinding`], i.e., with its fingerprint
+fn get_findings(file_review: &SourceFileReview) -> Vec<KnownFinding> {
+    let id_hash: &str = file_review
+        .source_file_info
+        .id_hash
+        .as_deref()
+        .unwrap_or("");
+    let security_issues = file_review
+        .security_issues
+        .iter()
+        .flatten()
+        .map(|security_issue| KnownFinding {
+            fingerprint: get_fingerprint(id_hash, &security_issue.code, &security_issue.threat),
+            description: format!("Security issue: {}", security_issue.threat),
+        });
+    let errors = file_review
+        .errors
+        .iter()
+        .flatten()
+        .map(|error| KnownFinding {
+            fingerprint: get_fingerprint(id_hash, &error.code, &error.issue),
+            description: format!("Error: {}", error.issue),
+        });
+    let improvements = file_review
+        .improvements
+        .iter()
+        .flatten()
+        .map(|improvement| KnownFinding {
+            fingerprint: get_fingerprint(id_hash, &improvement.code, &improvement.suggestion),
+            description: format!("Improvement: {}", improvement.suggestion),
+        });
+    security_issues.chain(errors).chain(improvements).collect()
+}
+/// Gets the fingerprint of a finding from the file's `id_hash`, and the finding's code and issue
+fn get_fingerprint(id_hash: &str, code: &str, issue: &str) -> String {
+    calculate_hash_from(&format!("{}:{}:{}", id_hash, code.trim(), issue.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const FILE_REVIEW: &str = r#"{
+        "source_file_info": { "name": "main.rs", "relative_path": "src/main.rs", "id_hash": "abc123" },
+        "summary": "A summary",
+        "security_issues": [
+            { "severity": "High", "code": "query(input)", "threat": "SQL injection", "mitigation": "Bind parameters" }
+        ],
+        "errors": [{ "code": "parse().unwrap()", "issue": "Unchecked unwrap", "resolution": "Handle the error" }]
+    }"#;
+
+    #[test]
+    fn test_baseline_apply() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("previous-report.json");
+        fs::write(
+            &path,
+            format!(
+                r#"{{ "repository_name": "repos_name", "file_reviews": [{}] }}"#,
+                FILE_REVIEW
+            ),
+        )
+        .unwrap();
+        let baseline = Baseline::load(path.to_str().unwrap()).unwrap();
+
+        let mut file_review: SourceFileReview = serde_json::from_str(FILE_REVIEW).unwrap();
+        file_review.errors = Some(serde_json::from_str(
+            r#"[{ "code": "read().unwrap()", "issue": "Unchecked unwrap", "resolution": "Handle the error" }]"#,
+        )
+        .unwrap());
+        baseline.apply(&mut file_review);
+
+        assert_eq!(file_review.security_issues, Some(Vec::new()));
+        assert_eq!(file_review.errors.as_ref().map(Vec::len), Some(1));
+        let known_findings = file_review.known_findings.unwrap();
+        assert_eq!(known_findings.len(), 1);
+        assert_eq!(
+            known_findings[0].description,
+            "Security issue: SQL injection"
+        );
+    }
+}
@@ -0,0 +1,206 @@
+//! Benchmarks each configured provider and service against the same small sample of files from the repository, to help
+//! pick the best model for a codebase.
+//!
+//! Each file is sent once, i.e., a response that is not valid JSON is counted, not retried, and the latency, token
+//! use, JSON validity rate, finding counts, and estimated cost, are compared in a table.
+use super::{
+    get_initial_source_file_info, get_prompt_data_based_on_review_type, get_review_request,
+    open_review_worktree, process_llm_response, valid_files_from_repository, validate_repository,
+};
+use crate::provider::api::TokenUsage;
+use crate::provider::{review_or_summarise_with_service, RequestType};
+use crate::retrieval::data::SourceFileInfo;
+use crate::retrieval::git::worktree::ReviewWorktree;
+use crate::settings::{ProviderSettings, ServiceSettings, Settings};
+use log::{info, warn};
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// The number of files sampled if not given
+pub(crate) const DEFAULT_BENCHMARK_FILES: usize = 5;
+
+/// The results of a single provider and service over the sample of files
+#[derive(Debug, Clone, Default)]
+pub(crate) struct BenchmarkResult {
+    pub(crate) provider: String,
+    pub(crate) service: String,
+    pub(crate) model: String,
+    pub(crate) num_files: usize,
+    pub(crate) num_valid_json: usize,
+    pub(crate) num_failed_requests: usize,
+    pub(crate) total_latency: Duration,
+    pub(crate) usage: TokenUsage,
+    pub(crate) num_findings: usize,
+    pub(crate) estimated_cost: f64,
+}
+impl BenchmarkResult {
+    /// The percentage of files for which a valid JSON review was returned
+    pub(crate) fn json_validity_rate(&self) -> f64 {
+        if self.num_files == 0 {
+            return 0.0;
+        }
+        self.num_valid_json as f64 / self.num_files as f64 * 100.0
+    }
+    /// The average latency of the requests that returned a response
+    pub(crate) fn average_latency(&self) -> Duration {
+        match self.num_files - self.num_failed_requests {
+            0 => Duration::ZERO,
+            num_responses => self.total_latency / num_responses as u32,
+        }
+    }
+}
+impl fmt::Display for BenchmarkResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<30} {:<30} {:>5} {:>6.0}% {:>6} {:>10} {:>10} {:>8} {:>10.4}",
+            format!("{} / {}", self.provider, self.service),
+            self.model,
+            self.num_files,
+            self.json_validity_rate(),
+            self.num_failed_requests,
+            self.average_latency().as_millis(),
+            self.usage.total_tokens(),
+            self.num_findings,
+            self.estimated_cost
+        )
+    }
+}
+/// Gets the header of the comparison table, aligned with the [`BenchmarkResult`] rows
+pub(crate) fn get_benchmark_table_header() -> String {
+    format!(
+        "{:<30} {:<30} {:>5} {:>7} {:>6} {:>10} {:>10} {:>8} {:>10}",
+        "PROVIDER / SERVICE",
+        "MODEL",
+        "FILES",
+        "VALID",
+        "FAILED",
+        "AVG MS",
+        "TOKENS",
+        "FINDINGS",
+        "COST (USD)"
+    )
+}
+/// Runs the same sample of files through every configured provider and service
+pub(crate) async fn run_benchmark(
+    settings: &Settings,
+    num_files: usize,
+) -> Result<Vec<BenchmarkResult>, Box<dyn Error>> {
+    let repository_root: PathBuf = validate_repository(
+        PathBuf::from(&settings.repository_path),
+        settings.require_git,
+    )?;
+    let worktree: Option<ReviewWorktree> = open_review_worktree(settings)?;
+    let repository_root: PathBuf = match &worktree {
+        Some(worktree) => worktree.path.clone(),
+        None => repository_root,
+    };
+    let sample: Vec<SourceFileInfo> = valid_files_from_repository(&repository_root)
+        .iter()
+        .filter_map(|entry| get_initial_source_file_info(settings, entry, &repository_root))
+        .take(num_files)
+        .collect();
+    if sample.is_empty() {
+        return Err("No source files found to benchmark".into());
+    }
+    info!(
+        "Benchmarking {} files: {}",
+        sample.len(),
+        sample
+            .iter()
+            .map(|file_info| file_info.relative_path.as_str())
+            .collect::<Vec<&str>>()
+            .join(", ")
+    );
+
+    let mut results: Vec<BenchmarkResult> = Vec::new();
+    for provider in &settings.providers {
+        for service in &provider.services {
+            results.push(benchmark_service(settings, provider, service, &sample).await?);
+        }
+    }
+    Ok(results)
+}
+/// Sends each file of the sample to the service, once, recording the outcome
+async fn benchmark_service(
+    settings: &Settings,
+    provider: &ProviderSettings,
+    service: &ServiceSettings,
+    sample: &[SourceFileInfo],
+) -> Result<BenchmarkResult, Box<dyn Error>> {
+    info!("Benchmarking: {} / {}", provider.name, service.name);
+    let mut result = BenchmarkResult {
+        provider: provider.name.clone(),
+        service: service.name.clone(),
+        model: service.model.clone(),
+        ..Default::default()
+    };
+    for file_info in sample {
+        let Some(mut prompt_data) = get_prompt_data_based_on_review_type(settings, file_info)?
+        else {
+            return Err("The 'code_stats' review type does not use a provider to benchmark".into());
+        };
+        prompt_data.id = Some(file_info.relative_path.clone());
+        prompt_data.add_user_message_prompt(get_review_request(file_info));
+
+        result.num_files += 1;
+        let start = Instant::now();
+        let response = match review_or_summarise_with_service(
+            RequestType::Review,
+            settings,
+            provider,
+            service,
+            &prompt_data,
+        )
+        .await
+        {
+            Ok(response) => response,
+            Err(e) => {
+                warn!(
+                    "Request failed for: {}, with {} / {}: {}",
+                    file_info.relative_path, provider.name, service.name, e
+                );
+                result.num_failed_requests += 1;
+                continue;
+            }
+        };
+        result.total_latency += start.elapsed();
+        if let Some(usage) = &response.usage {
+            result.usage.add(usage);
+            if let Some(pricing) = &service.pricing {
+                result.estimated_cost += pricing.estimate_cost(usage);
+            }
+        }
+        if let Ok(file_review) = process_llm_response(settings, &response) {
+            result.num_valid_json += 1;
+            result.num_findings += file_review.security_issues.map_or(0, |issues| issues.len())
+                + file_review.errors.map_or(0, |errors| errors.len())
+                + file_review
+                    .improvements
+                    .map_or(0, |improvements| improvements.len());
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_benchmark_result_rates() {
+        let result = BenchmarkResult {
+            num_files: 4,
+            num_valid_json: 3,
+            num_failed_requests: 2,
+            total_latency: Duration::from_millis(3000),
+            ..Default::default()
+        };
+        assert!((result.json_validity_rate() - 75.0).abs() < f64::EPSILON);
+        assert_eq!(result.average_latency(), Duration::from_millis(1500));
+        assert!(BenchmarkResult::default().json_validity_rate().abs() < f64::EPSILON);
+        assert_eq!(BenchmarkResult::default().average_latency(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,164 @@
+//! An on-disk cache of [`SourceFileReview`]s, so that re-running a review on an unchanged repository costs nothing.
+//!
+//! Each review is keyed by the file's `id_hash`, a hash of the prompt as sent, e.g., with its repository context and
+//! notes of the file's coverage and diagnostics, and the model it is sent to, so a change to any of these is a cache
+//! miss.
+use crate::provider::prompts::PromptData;
+use crate::retrieval::code::calculate_hash_from;
+use crate::review::data::SourceFileReview;
+use crate::settings::Settings;
+use log::warn;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+/// The cache location used if `review_cache_path` is not set, relative to the working directory
+pub(crate) const DEFAULT_REVIEW_CACHE_PATH: &str = ".cosmonaut/cache";
+
+/// Stores each [`SourceFileReview`] as a JSON file, named by the hash of its key, in the `cache_dir`
+#[derive(Debug)]
+pub(crate) struct ReviewCache {
+    cache_dir: PathBuf,
+}
+impl ReviewCache {
+    pub(crate) fn new(cache_dir: PathBuf) -> Self {
+        Self { cache_dir }
+    }
+    /// Creates the [`ReviewCache`], or [`None`] if the cache is disabled in the [`Settings`]
+    pub(crate) fn from_settings(settings: &Settings) -> Option<Self> {
+        if !settings.use_review_cache {
+            return None;
+        }
+        let cache_dir = settings
+            .review_cache_path
+            .as_deref()
+            .unwrap_or(DEFAULT_REVIEW_CACHE_PATH);
+        Some(Self::new(PathBuf::from(cache_dir)))
+    }
+    /// Gets the key for a file, from its `id_hash`, the model it is reviewed by, e.g., as scheduled, and the
+    /// [`PromptData`] as sent, with its review request
+    pub(crate) fn get_key(&self, id_hash: &str, model: &str, prompt_data: &PromptData) -> String {
+        let prompt_hash =
+            calculate_hash_from(&serde_json::to_string(&prompt_data.messages).unwrap_or_default());
+        calculate_hash_from(&format!("{}:{}:{}", id_hash, prompt_hash, model))
+    }
+    /// Gets the cached [`SourceFileReview`] for the key, if any. A cached review has no [`crate::provider::api::TokenUsage`].
+    pub(crate) fn get(&self, key: &str) -> Option<SourceFileReview> {
+        let contents = fs::read_to_string(self.get_entry_path(key)).ok()?;
+        match serde_json::from_str::<SourceFileReview>(&contents) {
+            Ok(mut file_review) => {
+                file_review.usage = None;
+                Some(file_review)
+            }
+            Err(e) => {
+                warn!("Ignoring invalid review cache entry: {}: {}", key, e);
+                None
+            }
+        }
+    }
+    /// Stores the [`SourceFileReview`] against the key
+    pub(crate) fn put(
+        &self,
+        key: &str,
+        file_review: &SourceFileReview,
+    ) -> Result<(), Box<dyn Error>> {
+        fs::create_dir_all(&self.cache_dir)?;
+        fs::write(
+            self.get_entry_path(key),
+            serde_json::to_string(file_review)?,
+        )?;
+        Ok(())
+    }
+    fn get_entry_path(&self, key: &str) -> PathBuf {
+        self.cache_dir.join(format!("{}.json", key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::api::{ProviderCompletionMessage, ProviderMessageRole, TokenUsage};
+    use crate::retrieval::data::{LanguageType, SourceFileInfo, Statistics};
+    use tempfile::tempdir;
+
+    fn test_prompt_data(content: &str) -> PromptData {
+        PromptData {
+            id: None,
+            messages: vec![ProviderCompletionMessage {
+                role: ProviderMessageRole::System,
+                content: content.to_string(),
+            }],
+        }
+    }
+
+    fn test_file_review() -> SourceFileReview {
+        SourceFileReview {
+            source_file_info: SourceFileInfo::new(
+                "main.rs".to_string(),
+                "src/main.rs".to_string(),
+                LanguageType {
+                    name: "Rust".to_string(),
+                    extension: "rs".to_string(),
+                    statistics: None,
+                },
+                "abc123".to_string(),
+                Statistics::new(),
+            ),
+            summary: "A summary".to_string(),
+            file_rag_status: None,
+            security_issues: None,
+            errors: None,
+            improvements: None,
+            usage: Some(TokenUsage {
+                prompt_tokens: 100,
+                completion_tokens: 50,
+                cached_prompt_tokens: 0,
+            }),
+            schema_violations: None,
+            prompt_trims: None,
+            plan: None,
+            suppressed_findings: None,
+            known_findings: None,
+            second_opinion: None,
+            link: None,
+        }
+    }
+
+    #[test]
+    fn test_review_cache_round_trip() {
+        let dir = tempdir().unwrap();
+        let cache = ReviewCache::new(dir.path().join("cache"));
+        let key = cache.get_key("abc123", "gpt-4", &test_prompt_data("Review this code"));
+
+        assert!(cache.get(&key).is_none());
+        cache.put(&key, &test_file_review()).unwrap();
+
+        let cached = cache.get(&key).unwrap();
+        assert_eq!(cached.summary, "A summary");
+        assert_eq!(cached.usage, None);
+    }
+
+    #[test]
+    fn test_review_cache_key_changes() {
+        let cache = ReviewCache::new(PathBuf::from("cache"));
+        let prompt_data = test_prompt_data("Review this code");
+        let key = cache.get_key("abc123", "gpt-4", &prompt_data);
+
+        assert_eq!(key, cache.get_key("abc123", "gpt-4", &prompt_data));
+        assert_ne!(key, cache.get_key("def456", "gpt-4", &prompt_data));
+        assert_ne!(
+            key,
+            cache.get_key(
+                "abc123",
+                "gpt-4",
+                &test_prompt_data("Review this code for security")
+            )
+        );
+        assert_ne!(key, cache.get_key("abc123", "gpt-3.5", &prompt_data));
+        // A note of the file's coverage, added to the prompt as sent, changes the key
+        let mut noted_prompt_data = prompt_data.clone();
+        noted_prompt_data
+            .add_user_message_prompt(" note: this file has 80% test coverage.".to_string());
+        assert_ne!(key, cache.get_key("abc123", "gpt-4", &noted_prompt_data));
+    }
+}
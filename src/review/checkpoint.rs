@@ -0,0 +1,170 @@
+//! A checkpoint of the [`SourceFileReview`]s completed so far in a run, so that if the run stops part way, e.g., on a
+//! crash or an expired API key, re-running resumes from where it stopped instead of starting over.
+//!
+//! Each review is appended, as a line of JSON, as it completes, and the checkpoint is removed once every file is
+//! reviewed. Only reviews of the same review type and model, and of unchanged files, are resumed.
+use crate::provider::get_service_and_model;
+use crate::retrieval::code::calculate_hash_from;
+use crate::retrieval::data::SourceFileInfo;
+use crate::review::data::SourceFileReview;
+use crate::settings::Settings;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// The location of the checkpoint used if `checkpoint_path` is not set, relative to the working directory
+pub(crate) const DEFAULT_CHECKPOINT_PATH: &str = ".cosmonaut/checkpoint.jsonl";
+
+/// A line of the checkpoint: a completed review, with the key of the run it was completed in
+#[derive(Serialize, Deserialize)]
+struct CheckpointEntry {
+    run_key: String,
+    file_review: SourceFileReview,
+}
+/// The completed [`SourceFileReview`]s of the run, keyed by the `id_hash` of the file
+#[derive(Debug)]
+pub(crate) struct Checkpoint {
+    path: PathBuf,
+    run_key: String,
+    file_reviews: HashMap<String, SourceFileReview>,
+}
+impl Checkpoint {
+    /// Loads the reviews completed by an earlier run of the same review type and model, if any
+    pub(crate) fn load(path: PathBuf, run_key: String) -> Self {
+        let file_reviews: HashMap<String, SourceFileReview> = fs::read_to_string(&path)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| match serde_json::from_str::<CheckpointEntry>(line) {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    warn!("Ignoring invalid checkpoint entry: {}", e);
+                    None
+                }
+            })
+            .filter(|entry| entry.run_key == run_key)
+            .filter_map(|entry| {
+                let id_hash = entry.file_review.source_file_info.id_hash.clone()?;
+                Some((id_hash, entry.file_review))
+            })
+            .collect();
+        if !file_reviews.is_empty() {
+            info!(
+                "Resuming from the checkpoint: {} file reviews completed",
+                file_reviews.len()
+            );
+        }
+        Self {
+            path,
+            run_key,
+            file_reviews,
+        }
+    }
+    /// Loads the [`Checkpoint`], or [`None`] if `use_checkpoint` is not set in the [`Settings`]
+    pub(crate) fn from_settings(settings: &Settings) -> Option<Self> {
+        if !settings.use_checkpoint {
+            return None;
+        }
+        let path = settings
+            .checkpoint_path
+            .as_deref()
+            .unwrap_or(DEFAULT_CHECKPOINT_PATH);
+        let run_key = calculate_hash_from(&format!(
+            "{}:{}",
+            settings.review_type,
+            get_service_and_model(settings).unwrap_or_default()
+        ));
+        Some(Self::load(PathBuf::from(path), run_key))
+    }
+    /// Gets the completed review of the file, if the file is unchanged since, i.e., has the same `id_hash`
+    pub(crate) fn get(&self, file_info: &SourceFileInfo) -> Option<SourceFileReview> {
+        let file_review = self.file_reviews.get(file_info.id_hash.as_deref()?)?;
+        if file_review.source_file_info.relative_path != file_info.relative_path {
+            return None;
+        }
+        let mut file_review = file_review.clone();
+        file_review.usage = None;
+        Some(file_review)
+    }
+    /// Appends the completed review to the checkpoint
+    pub(crate) fn append(&self, file_review: &SourceFileReview) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let entry = CheckpointEntry {
+            run_key: self.run_key.clone(),
+            file_review: file_review.clone(),
+        };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+        Ok(())
+    }
+    /// Removes the checkpoint, once every file is reviewed
+    pub(crate) fn remove(&self) -> Result<(), Box<dyn Error>> {
+        if self.path.exists() {
+            fs::remove_file(&self.path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retrieval::data::{LanguageType, Statistics};
+    use tempfile::tempdir;
+
+    fn test_file_info(relative_path: &str, id_hash: &str) -> SourceFileInfo {
+        SourceFileInfo::new(
+            "main.rs".to_string(),
+            relative_path.to_string(),
+            LanguageType {
+                name: "Rust".to_string(),
+                extension: "rs".to_string(),
+                statistics: None,
+            },
+            id_hash.to_string(),
+            Statistics::new(),
+        )
+    }
+
+    #[test]
+    fn test_checkpoint_resume() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("checkpoint.jsonl");
+        let file_review: SourceFileReview = serde_json::from_str(
+            r#"{
+                "source_file_info": { "name": "main.rs", "relative_path": "src/main.rs", "id_hash": "abc123" },
+                "summary": "A summary"
+            }"#,
+        )
+        .unwrap();
+
+        let checkpoint = Checkpoint::load(path.clone(), "run".to_string());
+        checkpoint.append(&file_review).unwrap();
+
+        let resumed = Checkpoint::load(path.clone(), "run".to_string());
+        assert_eq!(
+            resumed
+                .get(&test_file_info("src/main.rs", "abc123"))
+                .unwrap()
+                .summary,
+            "A summary"
+        );
+        assert!(resumed
+            .get(&test_file_info("src/main.rs", "def456"))
+            .is_none());
+        assert!(Checkpoint::load(path.clone(), "other run".to_string())
+            .get(&test_file_info("src/main.rs", "abc123"))
+            .is_none());
+
+        resumed.remove().unwrap();
+        assert!(!path.exists());
+    }
+}
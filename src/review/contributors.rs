@@ -0,0 +1,168 @@
+//! The contributors section of the HTML report: each contributor's commits, the recency of their last contribution,
+//! the share of the files they own, and, if attributed by git blame, their findings, in one place.
+//!
+//! The findings are matched to a contributor by name, so anonymised findings, e.g., of 'Contributor 1', are listed
+//! after the contributors, on their own.
+use crate::retrieval::data::Contributor;
+use crate::review::data::{ContributorFindings, RepositoryReview};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// A contributor to the repository, as shown in the report
+///
+/// #Fields:
+/// * `name` - The name of the contributor
+/// * `num_commits` - The number of commits by the contributor
+/// * `percentage_commits` - The percentage of the commits to the repository by the contributor
+/// * `last_contribution` - The date of the last commit by the contributor, if any
+/// * `days_since_last_contribution` - The number of days since the last commit by the contributor, if any
+/// * `files_owned` - The number of files of which the contributor is the owner
+/// * `percentage_files_owned` - The percentage of the owned files of which the contributor is the owner
+/// * `findings` - The [`ContributorFindings`] attributed to the contributor by git blame, if any
+#[derive(Clone, Serialize, Debug, PartialEq)]
+pub(crate) struct ContributorSummary {
+    pub(crate) name: String,
+    pub(crate) num_commits: i32,
+    pub(crate) percentage_commits: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) last_contribution: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) days_since_last_contribution: Option<i64>,
+    pub(crate) files_owned: i32,
+    pub(crate) percentage_files_owned: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) findings: Option<ContributorFindings>,
+}
+
+/// Gets the [`ContributorSummary`] of each contributor, most commits first, then of each contributor to whom findings
+/// are attributed who is not one by name, most findings first
+pub(crate) fn get_contributor_summaries(
+    repository_review: &RepositoryReview,
+    now: DateTime<Utc>,
+) -> Vec<ContributorSummary> {
+    let contributors: &[Contributor] = repository_review.get_contributors();
+    let contributor_findings: &[ContributorFindings] = repository_review
+        .contributor_findings
+        .as_deref()
+        .unwrap_or_default();
+    // Each file has one owner, so the owned files are those of all the contributors
+    let owned_files: i32 = contributors
+        .iter()
+        .map(|contributor| contributor.get_statistics().num_files)
+        .sum();
+
+    let mut summaries: Vec<ContributorSummary> = contributors
+        .iter()
+        .map(|contributor| {
+            let statistics = contributor.get_statistics();
+            let last_contribution: &DateTime<Utc> = contributor.get_last_contribution();
+            ContributorSummary {
+                name: contributor.get_name().to_string(),
+                num_commits: statistics.num_commits,
+                percentage_commits: contributor.get_percentage_contribution(),
+                last_contribution: Some(last_contribution.format("%Y-%m-%d").to_string()),
+                days_since_last_contribution: Some((now - *last_contribution).num_days().max(0)),
+                files_owned: statistics.num_files,
+                percentage_files_owned: if owned_files > 0 {
+                    statistics.num_files as f32 / owned_files as f32 * 100.0
+                } else {
+                    0.0
+                },
+                findings: contributor_findings
+                    .iter()
+                    .find(|findings| findings.contributor == contributor.get_name())
+                    .cloned(),
+            }
+        })
+        .collect();
+    summaries.sort_by(|a, b| {
+        b.num_commits
+            .cmp(&a.num_commits)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    // The contributor findings are already most findings first
+    for findings in contributor_findings.iter().filter(|findings| {
+        !contributors
+            .iter()
+            .any(|contributor| contributor.get_name() == findings.contributor)
+    }) {
+        summaries.push(ContributorSummary {
+            name: findings.contributor.clone(),
+            num_commits: 0,
+            percentage_commits: 0.0,
+            last_contribution: None,
+            days_since_last_contribution: None,
+            files_owned: 0,
+            percentage_files_owned: 0.0,
+            findings: Some(findings.clone()),
+        });
+    }
+    summaries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retrieval::data::Statistics;
+    use chrono::TimeZone;
+
+    fn test_contributor(name: &str, day: u32, num_commits: i32, num_files: i32) -> Contributor {
+        let mut statistics = Statistics::new();
+        statistics.num_commits = num_commits;
+        statistics.num_files = num_files;
+        Contributor::new(
+            name.to_string(),
+            Utc.with_ymd_and_hms(2024, 1, day, 12, 0, 0).unwrap(),
+            num_commits as f32 / 4.0 * 100.0,
+            statistics,
+        )
+    }
+    fn test_findings(contributor: &str, total: i32) -> ContributorFindings {
+        ContributorFindings {
+            contributor: contributor.to_string(),
+            security_issues: 0,
+            errors: total,
+            improvements: 0,
+            total,
+        }
+    }
+
+    #[test]
+    fn test_get_contributor_summaries() {
+        let mut repository_review = RepositoryReview::new("repos_name".to_string());
+        repository_review.contributors(vec![
+            test_contributor("Ana", 20, 1, 1),
+            test_contributor("Bo", 30, 3, 3),
+        ]);
+        repository_review.contributor_findings(Some(vec![
+            test_findings("Contributor 1", 3),
+            test_findings("Ana", 2),
+        ]));
+
+        let summaries = get_contributor_summaries(
+            &repository_review,
+            Utc.with_ymd_and_hms(2024, 1, 31, 0, 0, 0).unwrap(),
+        );
+        assert_eq!(
+            summaries
+                .iter()
+                .map(|summary| (
+                    summary.name.as_str(),
+                    summary.num_commits,
+                    summary.days_since_last_contribution,
+                    summary.percentage_files_owned,
+                    summary.findings.as_ref().map(|findings| findings.total)
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Bo", 3, Some(0), 75.0, None),
+                ("Ana", 1, Some(10), 25.0, Some(2)),
+                ("Contributor 1", 0, None, 0.0, Some(3)),
+            ]
+        );
+        assert_eq!(
+            summaries[0].last_contribution.as_deref(),
+            Some("2024-01-30")
+        );
+    }
+}
@@ -1,23 +1,61 @@
 //! This module contains the data structures that will hold the review data for presentation in a report.
 //! The intent is that the data will be serialised to JSON and then passed to a templating engine to generate the report.
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 use crate::{
     impl_builder_methods,
-    retrieval::data::{Contributor, LanguageType, SourceFileInfo, Statistics},
+    provider::{api::TokenUsage, cost::CostBreakdown},
+    retrieval::data::{CommitActivity, Contributor, LanguageType, SourceFileInfo, Statistics},
+    retrieval::dependencies::Dependency,
+    retrieval::documentation::DocumentationCoverage,
+    retrieval::duplication::Duplication,
+    retrieval::licensing::Licensing,
+    retrieval::markers::MarkerInventory,
+    retrieval::secrets::RedactedLine,
+    retrieval::vulnerabilities::DependencyVulnerability,
+    review::delta::StatisticDelta,
+    review::schema::REPOSITORY_REVIEW_SCHEMA_VERSION,
+    review::trends::TrendPoint,
 };
 
 /// Represents the overall review of the repository
 /// #Fields:
+/// * `schema_version` - The version of the `repository_review.schema.json` the review, as a JSON report, complies with
 /// * `repository_name` - The name of the repository
 /// * `generative_ai_service_and_model` - The name of the generative AI service and model used to generate the review
 /// * `repository_type` - The type of repository, e.g., 'Java', '.Net', etc.
 /// * `date` - The date the review was generated
 /// * `repository_purpose` - The purpose of the repository
+/// * `commit_range` - The commit range assessed, if only the files changed in the range are reviewed
+/// * `repository_ref` - The branch, tag or commit reviewed, if not the working tree
+/// * `head_sha` - The id of the commit reviewed, i.e., 'HEAD' of the working tree, or of the `repository_ref`
+/// * `source_url` - The URL of the files at the `head_sha` on GitHub or GitLab, if the 'origin' remote is hosted on one
 /// * `summary` - A [`ReviewSummary`] of the repository
 /// * `repository_rag_status` - The overall [`RAGStatus`] of the repository
+/// * `cost` - The estimated [`CostBreakdown`] of the requests made to the provider for the review
+/// * `architecture_review` - The [`ArchitectureFinding`]s of a review of the repository as a whole, if requested
+/// * `duplication` - The [`Duplication`] of code across the repository's files
+/// * `complex_files` - The [`ComplexFile`]s, i.e., the most complex files of the repository, most complex first
+/// * `hotspots` - The [`Hotspot`]s, i.e., the files that most change, are largest and have most findings, highest first
+/// * `directories` - The [`DirectorySummary`]s, i.e., the findings and RAG status of each area of the codebase
+/// * `findings` - The [`Finding`]s of all the file reviews, most severe first, then by file
+/// * `contributor_findings` - The [`ContributorFindings`], i.e., the findings attributed to contributors by git blame
+/// * `ownership` - The [`Ownership`] of the files, i.e., the bus factor, and the contributors who own most of them
+/// * `markers` - The [`MarkerInventory`], i.e., the TODO, FIXME and HACK markers, and the files with the most
+/// * `untested_files` - The relative paths of the production files with no corresponding tests, largest first
+/// * `remediation` - The [`Remediation`] of each file re-reviewed from a previous report, i.e., whether it improved
+/// * `licensing` - The [`Licensing`] of the repository, for a compliance review
+/// * `dependencies` - The direct [`Dependency`]s declared in the repository's manifests
+/// * `dependency_review` - The [`DependencyFinding`]s of a review of the hygiene of the dependencies, if requested
+/// * `dependency_vulnerabilities` - The known [`DependencyVulnerability`]s of the dependencies, if looked up
+/// * `trends` - The [`TrendPoint`]s of the recent reviews of the repository, including this one, oldest first, if recorded
+/// * `commit_activity` - The [`CommitActivity`] of each of the last 52 weeks, oldest first, if a git repository
+/// * `executive_summary` - The [`ExecutiveSummary`] of the review, for a non-technical audience, if requested
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub(crate) struct RepositoryReview {
+    #[serde(default)]
+    schema_version: String,
     pub(crate) repository_name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) generative_ai_service_and_model: Option<String>,
@@ -25,9 +63,55 @@ pub(crate) struct RepositoryReview {
     repository_type: Option<String>,
     date: String,
     repository_purpose: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commit_range: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    repository_ref: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    head_sha: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source_url: Option<String>,
     pub(crate) summary: Option<ReviewSummary>,
     repository_rag_status: RAGStatus,
-    #[serde(skip_deserializing)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) cost: Option<CostBreakdown>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) architecture_review: Option<Vec<ArchitectureFinding>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) duplication: Option<Duplication>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) complex_files: Option<Vec<ComplexFile>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) hotspots: Option<Vec<Hotspot>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) directories: Option<Vec<DirectorySummary>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) findings: Option<Vec<Finding>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) contributor_findings: Option<Vec<ContributorFindings>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) ownership: Option<Ownership>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) markers: Option<MarkerInventory>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) untested_files: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) remediation: Option<Vec<Remediation>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) licensing: Option<Licensing>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) dependencies: Option<Vec<Dependency>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) dependency_review: Option<Vec<DependencyFinding>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) dependency_vulnerabilities: Option<Vec<DependencyVulnerability>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) trends: Option<Vec<TrendPoint>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) commit_activity: Option<Vec<CommitActivity>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) executive_summary: Option<ExecutiveSummary>,
+    #[serde(default)]
     pub(crate) statistics: Statistics,
     contributors: Vec<Contributor>,
     language_types: Vec<LanguageType>,
@@ -36,23 +120,74 @@ pub(crate) struct RepositoryReview {
 impl RepositoryReview {
     pub(crate) fn new(repository_name: String) -> Self {
         RepositoryReview {
+            schema_version: REPOSITORY_REVIEW_SCHEMA_VERSION.to_string(),
             repository_name,
             generative_ai_service_and_model: None,
             repository_type: None,
             date: String::new(),
             repository_purpose: None,
+            commit_range: None,
+            repository_ref: None,
+            head_sha: None,
+            source_url: None,
             summary: None,
             repository_rag_status: RAGStatus::Green,
+            cost: None,
+            architecture_review: None,
+            duplication: None,
+            complex_files: None,
+            hotspots: None,
+            directories: None,
+            findings: None,
+            contributor_findings: None,
+            ownership: None,
+            markers: None,
+            untested_files: None,
+            remediation: None,
+            licensing: None,
+            dependencies: None,
+            dependency_review: None,
+            dependency_vulnerabilities: None,
+            trends: None,
+            commit_activity: None,
+            executive_summary: None,
             statistics: Statistics::new(),
             contributors: Vec::new(),
             language_types: Vec::new(),
             file_reviews: Vec::new(),
         }
     }
+    pub(crate) fn get_repository_rag_status(&self) -> &RAGStatus {
+        &self.repository_rag_status
+    }
+    pub(crate) fn get_date(&self) -> &str {
+        &self.date
+    }
+    pub(crate) fn get_head_sha(&self) -> Option<&str> {
+        self.head_sha.as_deref()
+    }
+    pub(crate) fn get_source_url(&self) -> Option<&str> {
+        self.source_url.as_deref()
+    }
+    pub(crate) fn get_repository_purpose(&self) -> Option<&str> {
+        self.repository_purpose.as_deref()
+    }
+    pub(crate) fn get_contributors(&self) -> &[Contributor] {
+        &self.contributors
+    }
+    pub(crate) fn get_language_types(&self) -> &[LanguageType] {
+        &self.language_types
+    }
     /// pushes a [`FileReview`] into the filereviews [`Vec`]
     pub(crate) fn add_source_file_review(&mut self, file_review: SourceFileReview) {
         self.file_reviews.push(file_review);
     }
+    /// Adds the [`CostBreakdown`] to that of the review, and its [`TokenUsage`] to the statistics
+    pub(crate) fn add_cost(&mut self, cost: &CostBreakdown) {
+        let total: &mut CostBreakdown = self.cost.get_or_insert_with(CostBreakdown::new);
+        total.merge(cost);
+        self.statistics.token_usage = Some(total.usage.clone());
+    }
 }
 
 impl_builder_methods!(
@@ -61,8 +196,31 @@ impl_builder_methods!(
     repository_type: Option<String>,
     date: String,
     repository_purpose: Option<String>,
+    commit_range: Option<String>,
+    repository_ref: Option<String>,
+    head_sha: Option<String>,
+    source_url: Option<String>,
     summary: Option<ReviewSummary>,
     repository_rag_status: RAGStatus,
+    cost: Option<CostBreakdown>,
+    architecture_review: Option<Vec<ArchitectureFinding>>,
+    duplication: Option<Duplication>,
+    complex_files: Option<Vec<ComplexFile>>,
+    hotspots: Option<Vec<Hotspot>>,
+    directories: Option<Vec<DirectorySummary>>,
+    findings: Option<Vec<Finding>>,
+    contributor_findings: Option<Vec<ContributorFindings>>,
+    ownership: Option<Ownership>,
+    markers: Option<MarkerInventory>,
+    untested_files: Option<Vec<String>>,
+    remediation: Option<Vec<Remediation>>,
+    licensing: Option<Licensing>,
+    dependencies: Option<Vec<Dependency>>,
+    dependency_review: Option<Vec<DependencyFinding>>,
+    dependency_vulnerabilities: Option<Vec<DependencyVulnerability>>,
+    trends: Option<Vec<TrendPoint>>,
+    commit_activity: Option<Vec<CommitActivity>>,
+    executive_summary: Option<ExecutiveSummary>,
     contributors: Vec<Contributor>,
     language_types: Vec<LanguageType>
 );
@@ -73,14 +231,48 @@ pub(crate) struct ReviewSummary {
     pub(crate) errors: i32,
     pub(crate) improvements: i32,
     pub(crate) documentation: Option<Documentation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) documentation_coverage: Option<DocumentationCoverage>,
 }
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+/// The security issues counted by [`Severity`], and by OWASP Top 10 category and CWE id, where given
+///
+/// #Fields:
+/// * `low`, `medium`, `high`, `critical` - The number of security issues of each [`Severity`]
+/// * `total` - The number of security issues
+/// * `owasp_categories` - The number of security issues per OWASP Top 10 category, e.g., 'A03:2021-Injection'
+/// * `cwes` - The number of security issues per CWE id, e.g., 'CWE-89'
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
 pub(crate) struct SecurityIssueBreakdown {
     pub(crate) low: i32,
     pub(crate) medium: i32,
     pub(crate) high: i32,
     pub(crate) critical: i32,
     pub(crate) total: i32,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) owasp_categories: BTreeMap<String, i32>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) cwes: BTreeMap<String, i32>,
+}
+impl SecurityIssueBreakdown {
+    /// Counts a security issue of the [`Severity`]
+    pub(crate) fn add(&mut self, severity: &Severity) {
+        self.total += 1;
+        match severity {
+            Severity::Low => self.low += 1,
+            Severity::Medium => self.medium += 1,
+            Severity::High => self.high += 1,
+            Severity::Critical => self.critical += 1,
+        }
+    }
+    /// Counts the OWASP Top 10 category and CWE id of the [`SecurityIssue`], if given
+    pub(crate) fn add_categories(&mut self, security_issue: &SecurityIssue) {
+        if let Some(owasp) = &security_issue.owasp {
+            *self.owasp_categories.entry(owasp.clone()).or_default() += 1;
+        }
+        if let Some(cwe) = &security_issue.cwe {
+            *self.cwes.entry(cwe.clone()).or_default() += 1;
+        }
+    }
 }
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub(crate) enum Documentation {
@@ -96,6 +288,28 @@ pub(crate) enum RAGStatus {
     Amber,
     Red,
 }
+impl RAGStatus {
+    /// Gets the rank of the [`RAGStatus`], worst highest
+    pub(crate) fn get_rank(&self) -> i32 {
+        match self {
+            RAGStatus::Green => 0,
+            RAGStatus::Amber => 1,
+            RAGStatus::Red => 2,
+        }
+    }
+    /// Keeps the worse of the worst [`RAGStatus`] so far, if any, and the [`RAGStatus`], if any, e.g., of the reviews
+    /// of the units of a file
+    pub(crate) fn keep_worst(worst: &mut Option<RAGStatus>, rag_status: Option<&RAGStatus>) {
+        if let Some(rag_status) = rag_status {
+            if worst
+                .as_ref()
+                .map_or(true, |worst| rag_status.get_rank() > worst.get_rank())
+            {
+                *worst = Some(rag_status.clone());
+            }
+        }
+    }
+}
 /// Captures retrieved static and review data from an LLM for a specific source file.
 ///
 /// This struct will contain the fields passed back as JSON from the LLM.
@@ -107,6 +321,14 @@ pub(crate) enum RAGStatus {
 /// * `security_issues` - A [`Vec`] of [`SecurityIssue`]s
 /// * `errors` - A [`Vec`] of [`Error`]s
 /// * `improvements` - A [`Vec`] of [`Improvement`]s
+/// * `usage` - The [`TokenUsage`] reported by the provider for the review request
+/// * `schema_violations` - The values normalised, and violations of the review schema repaired, in the response, if any
+/// * `prompt_trims` - How the prompt was trimmed to fit the model's context window, e.g., the file truncated, if it was
+/// * `plan` - The summary of what the file does, requested before the review, if `plan_then_review` is set
+/// * `suppressed_findings` - The findings dropped by a `cosmonaut:ignore-next-finding` annotation, if any
+/// * `known_findings` - The [`KnownFinding`]s, i.e., those in the baseline report, that do not affect the RAG status
+/// * `second_opinion` - The [`SecondOpinion`] of a stronger model, if the file was Red and `escalation` is set
+/// * `link` - The URL of the file at the reviewed commit, on GitHub or GitLab, if the repository is hosted on one
 ///
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub(crate) struct SourceFileReview {
@@ -120,8 +342,43 @@ pub(crate) struct SourceFileReview {
     pub(crate) errors: Option<Vec<Error>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) improvements: Option<Vec<Improvement>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) usage: Option<TokenUsage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) schema_violations: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) prompt_trims: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) plan: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) suppressed_findings: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) known_findings: Option<Vec<KnownFinding>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) second_opinion: Option<SecondOpinion>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) link: Option<String>,
 }
 impl SourceFileReview {
+    /// A review made without the LLM, i.e., with no findings but the summary
+    pub(crate) fn new(source_file_info: SourceFileInfo, summary: String) -> Self {
+        SourceFileReview {
+            source_file_info,
+            summary,
+            file_rag_status: None,
+            security_issues: None,
+            errors: None,
+            improvements: None,
+            usage: None,
+            schema_violations: None,
+            prompt_trims: None,
+            plan: None,
+            suppressed_findings: None,
+            known_findings: None,
+            second_opinion: None,
+            link: None,
+        }
+    }
     #[allow(dead_code)]
     pub(crate) fn get_security_issues(&self) -> &Option<Vec<SecurityIssue>> {
         &self.security_issues
@@ -138,6 +395,58 @@ impl SourceFileReview {
     pub(crate) fn get_file_rag_status(&self) -> &Option<RAGStatus> {
         &self.file_rag_status
     }
+    /// Drops the findings below the minimum [`Severity`]. Only security issues have a severity, so an error is taken
+    /// as Medium, and an improvement as Low
+    pub(crate) fn retain_min_severity(&mut self, min_severity: &Severity) {
+        if let Some(security_issues) = &mut self.security_issues {
+            security_issues.retain(|security_issue| security_issue.severity >= *min_severity);
+        }
+        if Severity::Medium < *min_severity {
+            self.errors = None;
+        }
+        if Severity::Low < *min_severity {
+            self.improvements = None;
+        }
+    }
+    /// Reports each line with a redacted secret as a Critical [`SecurityIssue`], whether or not the LLM did. A security
+    /// issue the LLM reported on the line is raised to Critical, rather than reported twice.
+    pub(crate) fn add_secret_security_issues(&mut self, redacted_lines: &[RedactedLine]) {
+        let line_offset: usize = self
+            .source_file_info
+            .code_unit
+            .as_ref()
+            .map_or(0, |unit| unit.start_line - 1);
+        let security_issues = self.security_issues.get_or_insert_with(Vec::new);
+        for redacted_line in redacted_lines {
+            // Only a finding with the exact line is the same, not one whose code merely contains, or is within, it
+            let reported = security_issues.iter_mut().find(|security_issue| {
+                security_issue
+                    .code
+                    .lines()
+                    .any(|line| line.trim() == redacted_line.line)
+            });
+            match reported {
+                Some(security_issue) => security_issue.severity = Severity::Critical,
+                None => security_issues.push(SecurityIssue {
+                    severity: Severity::Critical,
+                    code: redacted_line.line.clone(),
+                    threat: format!(
+                        "Hardcoded secret ({}) on line {}",
+                        redacted_line.kinds.join(", "),
+                        redacted_line.line_number + line_offset
+                    ),
+                    mitigation: "Remove the secret from the code and its history, rotate it, and load it from a secret store or the environment".to_string(),
+                    cwe: Some("CWE-798".to_string()),
+                    owasp: Some("A07:2021-Identification and Authentication Failures".to_string()),
+                    fingerprint: None,
+                    link: None,
+                }),
+            }
+        }
+        if security_issues.is_empty() {
+            self.security_issues = None;
+        }
+    }
 }
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub(crate) struct SecurityIssue {
@@ -145,21 +454,316 @@ pub(crate) struct SecurityIssue {
     pub(crate) code: String,
     pub(crate) threat: String,
     pub(crate) mitigation: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) cwe: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) owasp: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) fingerprint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) link: Option<String>,
 }
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub(crate) struct Error {
-    code: String,
-    issue: String,
-    resolution: String,
+    pub(crate) code: String,
+    pub(crate) issue: String,
+    pub(crate) resolution: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) fingerprint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) link: Option<String>,
 }
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub(crate) struct Improvement {
-    code: String,
-    suggestion: String,
+    pub(crate) code: String,
+    pub(crate) suggestion: String,
     improvement_details: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) fingerprint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) link: Option<String>,
+}
+impl Improvement {
+    pub(crate) fn get_improvement_details(&self) -> &str {
+        &self.improvement_details
+    }
+}
+/// A finding of a file review, in the list of all the findings of the repository, most severe first
+///
+/// #Fields:
+/// * `relative_path` - The relative path of the file of the finding
+/// * `kind` - The kind of finding, i.e., 'Security issue', 'Error' or 'Improvement'
+/// * `severity` - The [`Severity`] of the finding, if a security issue
+/// * `issue` - The threat, issue or suggestion of the finding
+/// * `code` - The code of the finding
+/// * `resolution` - The mitigation, resolution or improvement details of the finding
+/// * `fingerprint` - The fingerprint of the finding, if set
+/// * `link` - The URL of the finding's code on GitHub or GitLab, if known
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct Finding {
+    pub(crate) relative_path: String,
+    pub(crate) kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) severity: Option<Severity>,
+    pub(crate) issue: String,
+    pub(crate) code: String,
+    pub(crate) resolution: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) fingerprint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) link: Option<String>,
+}
+/// A file of the repository with a high complexity, as calculated statically, i.e., independent of the LLM
+///
+/// #Fields:
+/// * `relative_path` - The relative path of the file
+/// * `complexity` - The cyclomatic-style complexity of the file, i.e., 1 plus its branches
+/// * `loc` - The lines of code of the file
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct ComplexFile {
+    pub(crate) relative_path: String,
+    pub(crate) complexity: i64,
+    pub(crate) loc: i64,
+}
+/// A file of the repository that changes often, is large, and has findings, so is to be refactored first
+///
+/// #Fields:
+/// * `relative_path` - The relative path of the file
+/// * `commits` - The number of commits that changed the file, i.e., its churn
+/// * `loc` - The lines of code of the file
+/// * `findings` - The number of findings of the file's review, i.e., its security issues, errors and improvements
+/// * `score` - The hotspot score, i.e., the commits, times the lines of code, times one plus the findings
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct Hotspot {
+    pub(crate) relative_path: String,
+    pub(crate) commits: i32,
+    pub(crate) loc: i64,
+    pub(crate) findings: i32,
+    pub(crate) score: i64,
+}
+/// The findings and RAG status of the reviewed files of a directory, i.e., an area of the codebase
+///
+/// #Fields:
+/// * `directory` - The directory, e.g., 'src/provider', or '.' for the files at the root
+/// * `files` - The number of reviewed files in the directory
+/// * `security_issues` - The [`SecurityIssueBreakdown`] of the directory's files
+/// * `errors` - The number of errors in the directory's files
+/// * `improvements` - The number of improvements to the directory's files
+/// * `rag_status` - The [`RAGStatus`] of the directory, calculated as that of the repository
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct DirectorySummary {
+    pub(crate) directory: String,
+    pub(crate) files: i32,
+    pub(crate) security_issues: SecurityIssueBreakdown,
+    pub(crate) errors: i32,
+    pub(crate) improvements: i32,
+    pub(crate) rag_status: RAGStatus,
+}
+/// The change in a file re-reviewed from a previous report, i.e., whether the remediation of its findings worked
+///
+/// #Fields:
+/// * `relative_path` - The relative path of the file
+/// * `previous_rag_status` - The [`RAGStatus`] of the file in the previous report
+/// * `rag_status` - The [`RAGStatus`] of the file now, if any, e.g., not if it was removed
+/// * `previous_security_issues` - The [`SecurityIssueBreakdown`] of the file in the previous report
+/// * `security_issues` - The [`SecurityIssueBreakdown`] of the file now
+/// * `previous_errors` - The number of errors in the file in the previous report
+/// * `errors` - The number of errors in the file now
+/// * `outcome` - The [`RemediationOutcome`], i.e., whether the file improved
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct Remediation {
+    pub(crate) relative_path: String,
+    pub(crate) previous_rag_status: Option<RAGStatus>,
+    pub(crate) rag_status: Option<RAGStatus>,
+    pub(crate) previous_security_issues: SecurityIssueBreakdown,
+    pub(crate) security_issues: SecurityIssueBreakdown,
+    pub(crate) previous_errors: i32,
+    pub(crate) errors: i32,
+    pub(crate) outcome: RemediationOutcome,
+}
+/// Whether a file re-reviewed from a previous report improved
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) enum RemediationOutcome {
+    Improved,
+    Unchanged,
+    Worsened,
+    /// The file is no longer in the repository
+    Removed,
+    /// The file is still in the repository, but was not reviewed, e.g., as it is now ignored
+    NotReviewed,
+}
+/// The findings attributed to a contributor, i.e., to whom git blame attributes the first line of their code
+///
+/// #Fields:
+/// * `contributor` - The name of the contributor, or their rank, e.g., 'Contributor 1', if anonymised
+/// * `security_issues` - The number of security issues attributed to the contributor
+/// * `errors` - The number of errors attributed to the contributor
+/// * `improvements` - The number of improvements attributed to the contributor
+/// * `total` - The number of findings attributed to the contributor
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct ContributorFindings {
+    pub(crate) contributor: String,
+    pub(crate) security_issues: i32,
+    pub(crate) errors: i32,
+    pub(crate) improvements: i32,
+    pub(crate) total: i32,
+}
+/// How concentrated the ownership of the files is, where a file is owned by the author who last changed most of its lines
+///
+/// #Fields:
+/// * `bus_factor` - The fewest contributors who, between them, own more than half of the files
+/// * `mean_concentration` - The mean percentage of a file's lines last changed by its owner
+/// * `key_contributors` - The [`KeyContributor`]s, i.e., those of the bus factor, whose departure would orphan the most
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct Ownership {
+    pub(crate) bus_factor: i32,
+    pub(crate) mean_concentration: f32,
+    pub(crate) key_contributors: Vec<KeyContributor>,
+}
+/// A contributor whose departure would orphan a large part of the codebase
+///
+/// #Fields:
+/// * `name` - The name of the contributor
+/// * `files_owned` - The number of files of which the contributor is the owner
+/// * `percentage_files` - The percentage of the files of which the contributor is the owner
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct KeyContributor {
+    pub(crate) name: String,
+    pub(crate) files_owned: i32,
+    pub(crate) percentage_files: f32,
+}
+/// A finding of the review of the architecture of the repository, across files
+///
+/// #Fields:
+/// * `category` - The kind of problem, e.g., 'Layering', 'God module' or 'Missing tests'
+/// * `finding` - The problem, specific to the repository
+/// * `recommendation` - How to resolve the problem
+/// * `files` - The relative paths of the files or directories concerned
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct ArchitectureFinding {
+    pub(crate) category: String,
+    pub(crate) finding: String,
+    pub(crate) recommendation: String,
+    #[serde(default)]
+    pub(crate) files: Vec<String>,
+}
+/// A finding of the review of the hygiene of the repository's dependencies
+///
+/// #Fields:
+/// * `category` - The kind of problem, e.g., 'Unpinned version' or 'Abandoned package'
+/// * `finding` - The problem, specific to the dependencies
+/// * `recommendation` - How to resolve the problem
+/// * `dependencies` - The names of the dependencies concerned
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct DependencyFinding {
+    pub(crate) category: String,
+    pub(crate) finding: String,
+    pub(crate) recommendation: String,
+    #[serde(default)]
+    pub(crate) dependencies: Vec<String>,
+}
+/// The summary of the review for a non-technical audience, e.g., the executive sponsor of the repository
+///
+/// #Fields:
+/// * `verdict` - One paragraph on the state of the repository, and what to do next
+/// * `top_risks` - The [`ExecutiveRisk`]s, i.e., the most serious risks of the findings, at most 5, most serious first
+/// * `trend` - The [`ExecutiveTrend`] since the last review of the repository, if recorded
+/// * `remediation_hours` - The estimated hours to resolve the findings, from their number and severity
+/// * `remediation_effort` - The remediation effort, in plain terms, e.g., who is needed and for how long
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct ExecutiveSummary {
+    pub(crate) verdict: String,
+    #[serde(default)]
+    pub(crate) top_risks: Vec<ExecutiveRisk>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) trend: Option<ExecutiveTrend>,
+    pub(crate) remediation_hours: f64,
+    pub(crate) remediation_effort: String,
+}
+/// A risk of the findings of the review, in plain terms
+///
+/// #Fields:
+/// * `risk` - The risk
+/// * `impact` - The impact on the business if the risk is not addressed
+/// * `files` - The relative paths of the files concerned
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct ExecutiveRisk {
+    pub(crate) risk: String,
+    pub(crate) impact: String,
+    #[serde(default)]
+    pub(crate) files: Vec<String>,
+}
+/// The change in the key metrics of the repository since its last recorded review
+///
+/// #Fields:
+/// * `previous_date` - The date of the last review
+/// * `previous_rag_status` - The overall [`RAGStatus`] of the repository in the last review
+/// * `changes` - The [`StatisticDelta`]s of the key metrics, e.g., the security issues and lines of code
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct ExecutiveTrend {
+    pub(crate) previous_date: String,
+    pub(crate) previous_rag_status: RAGStatus,
+    pub(crate) changes: Vec<StatisticDelta>,
+}
+/// A finding that recurs from the baseline report, so is accepted
+///
+/// #Fields:
+/// * `fingerprint` - The hash of the file's `id_hash`, and the finding's code and issue
+/// * `description` - The kind of finding and its issue, e.g., 'Error: Unchecked unwrap'
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct KnownFinding {
+    pub(crate) fingerprint: String,
+    pub(crate) description: String,
 }
-/// Severity of the security issue as per CVSS v3.1
+/// The opinions of a file review escalated to a stronger model, as the first model found the file Red
+///
+/// #Fields:
+/// * `first_opinion` - The [`Opinion`] of the model that first reviewed the file
+/// * `second_opinion` - The [`Opinion`] of the stronger model, whose findings are those of the file review
+/// * `confirmed` - Whether the stronger model also found the file Red
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct SecondOpinion {
+    pub(crate) first_opinion: Opinion,
+    pub(crate) second_opinion: Opinion,
+    pub(crate) confirmed: bool,
+}
+/// The findings of a model's review of a file
+///
+/// #Fields:
+/// * `model` - The model that reviewed the file
+/// * `rag_status` - The [`RAGStatus`] of the file, by the model's findings
+/// * `security_issues` - The [`SecurityIssueBreakdown`] of the model's findings
+/// * `errors` - The number of errors the model found
+/// * `improvements` - The number of improvements the model suggested
+/// * `summary` - The model's summary of the file
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct Opinion {
+    pub(crate) model: String,
+    pub(crate) rag_status: RAGStatus,
+    pub(crate) security_issues: SecurityIssueBreakdown,
+    pub(crate) errors: i32,
+    pub(crate) improvements: i32,
+    pub(crate) summary: String,
+}
+impl Opinion {
+    /// Gets the opinion of the model from its review of the file, with the [`RAGStatus`] calculated from its findings
+    pub(crate) fn new(model: &str, file_review: &SourceFileReview, rag_status: RAGStatus) -> Self {
+        let mut security_issues = SecurityIssueBreakdown::default();
+        for security_issue in file_review.security_issues.iter().flatten() {
+            security_issues.add(&security_issue.severity);
+        }
+        Self {
+            model: model.to_string(),
+            rag_status,
+            security_issues,
+            errors: file_review.errors.as_ref().map_or(0, Vec::len) as i32,
+            improvements: file_review.improvements.as_ref().map_or(0, Vec::len) as i32,
+            summary: file_review.summary.clone(),
+        }
+    }
+}
+/// Severity of the security issue as per CVSS v3.1, ordered from Low to Critical
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub(crate) enum Severity {
     Low,
     Medium,
@@ -185,10 +789,12 @@ pub(crate) fn deserialize_file_review(
 #[cfg(test)]
 mod tests {
     use crate::{
+        provider::{api::TokenUsage, cost::CostBreakdown, RequestType},
         retrieval::data::{LanguageType, SourceFileInfo, Statistics},
+        retrieval::secrets::RedactedLine,
         review::data::{
-            deserialize_file_review, Error, Improvement, RAGStatus, SecurityIssue, Severity,
-            SourceFileReview,
+            deserialize_file_review, Error, Improvement, Opinion, RAGStatus, RepositoryReview,
+            SecurityIssue, Severity, SourceFileReview,
         },
     };
 
@@ -256,6 +862,10 @@ mod tests {
                         num_files: 0,
                         num_commits: 0,
                         frequency: 0.0,
+                        token_usage: None,
+                        complexity: None,
+                        test_loc: 0,
+                        test_ratio: None,
                     }),
                 }),
                 id_hash: Some("0".to_string()),
@@ -266,7 +876,18 @@ mod tests {
                     num_files: 0,
                     num_commits: 0,
                     frequency: 0.0,
+                    token_usage: None,
+                    complexity: None,
+                    test_loc: 0,
+                    test_ratio: None,
                 },
+                redacted_secrets: None,
+                patch_hunk: None,
+                code_unit: None,
+                coverage: None,
+                diagnostics: None,
+                markers: None,
+                generated: None,
             },
             summary: "This is a review summary".to_string(),
             file_rag_status: Some(RAGStatus::Green),
@@ -275,20 +896,145 @@ mod tests {
                 code: "SEC001".to_string(),
                 threat: "Potential security vulnerability".to_string(),
                 mitigation: "Apply security patch".to_string(),
+                cwe: None,
+                owasp: None,
+                fingerprint: None,
+                link: None,
             }]),
             errors: Some(vec![Error {
                 code: "ERR001".to_string(),
                 issue: "Syntax error".to_string(),
                 resolution: "Fix syntax error".to_string(),
+                fingerprint: None,
+                link: None,
             }]),
             improvements: Some(vec![Improvement {
                 code: "IMP001".to_string(),
                 suggestion: "Refactor code".to_string(),
                 improvement_details: "Improve code readability".to_string(),
+                fingerprint: None,
+                link: None,
             }]),
+            usage: None,
+            schema_violations: None,
+            prompt_trims: None,
+            plan: None,
+            suppressed_findings: None,
+            known_findings: None,
+            second_opinion: None,
+            link: None,
         };
 
         let result = deserialize_file_review(json_str).unwrap();
         assert_eq!(result, expected_result);
+
+        let opinion = Opinion::new("gpt-3.5-turbo", &result, RAGStatus::Red);
+        assert_eq!(opinion.model, "gpt-3.5-turbo");
+        assert_eq!(opinion.security_issues.total, 1);
+        assert_eq!(opinion.errors, 1);
+        assert_eq!(opinion.improvements, 1);
+        assert_eq!(opinion.summary, result.summary);
+    }
+
+    #[test]
+    fn test_retain_min_severity() {
+        let file_review: SourceFileReview = serde_json::from_str(
+            r#"{
+                "source_file_info": { "name": "main.rs", "relative_path": "src/main.rs" },
+                "summary": "A summary",
+                "security_issues": [
+                    { "severity": "Low", "code": "log(input)", "threat": "Log injection", "mitigation": "Escape the input" },
+                    { "severity": "High", "code": "query(input)", "threat": "SQL injection", "mitigation": "Bind parameters" }
+                ],
+                "errors": [{ "code": "parse().unwrap()", "issue": "Unchecked unwrap", "resolution": "Handle the error" }],
+                "improvements": [{ "code": "fn f()", "suggestion": "Rename", "improvement_details": "Use a clear name" }]
+            }"#,
+        )
+        .unwrap();
+
+        let mut medium_review = file_review.clone();
+        medium_review.retain_min_severity(&Severity::Medium);
+        assert_eq!(
+            medium_review.security_issues.as_ref().map(Vec::len),
+            Some(1)
+        );
+        assert_eq!(medium_review.errors.as_ref().map(Vec::len), Some(1));
+        assert_eq!(medium_review.improvements, None);
+
+        let mut high_review = file_review.clone();
+        high_review.retain_min_severity(&Severity::High);
+        assert_eq!(high_review.security_issues.as_ref().map(Vec::len), Some(1));
+        assert_eq!(high_review.errors, None);
+
+        let mut low_review = file_review.clone();
+        low_review.retain_min_severity(&Severity::Low);
+        assert_eq!(low_review, file_review);
+    }
+
+    #[test]
+    fn test_add_secret_security_issues() {
+        let mut file_review: SourceFileReview = serde_json::from_str(
+            r#"{
+                "source_file_info": { "name": "main.rs", "relative_path": "src/main.rs" },
+                "summary": "A summary",
+                "security_issues": [
+                    { "severity": "High", "code": "fn keys() {\n    let key = \"[REDACTED:aws-access-key]\";\n}", "threat": "Hardcoded key", "mitigation": "Remove it" },
+                    { "severity": "Low", "code": "password", "threat": "Weak password policy", "mitigation": "Strengthen it" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        file_review.add_secret_security_issues(&[
+            RedactedLine {
+                line_number: 3,
+                kinds: vec!["aws-access-key".to_string()],
+                line: "let key = \"[REDACTED:aws-access-key]\";".to_string(),
+            },
+            RedactedLine {
+                line_number: 7,
+                kinds: vec!["password".to_string()],
+                line: "let password = \"[REDACTED:password]\";".to_string(),
+            },
+        ]);
+
+        let security_issues = file_review.security_issues.unwrap();
+        assert_eq!(security_issues.len(), 3);
+        assert_eq!(security_issues[0].severity, Severity::Critical);
+        assert_eq!(security_issues[0].threat, "Hardcoded key");
+        // A finding whose code is only within the line is not the same
+        assert_eq!(security_issues[1].severity, Severity::Low);
+        assert_eq!(security_issues[2].severity, Severity::Critical);
+        assert_eq!(
+            security_issues[2].threat,
+            "Hardcoded secret (password) on line 7"
+        );
+    }
+
+    #[test]
+    fn test_add_cost() {
+        let usage = |prompt_tokens: i64, completion_tokens: i64| TokenUsage {
+            prompt_tokens,
+            completion_tokens,
+            cached_prompt_tokens: 0,
+        };
+        let mut review_cost = CostBreakdown::new();
+        review_cost.add(
+            &RequestType::Review,
+            Some("src/main.rs"),
+            &usage(1_000, 100),
+            0.01,
+        );
+        let mut summary_cost = CostBreakdown::new();
+        summary_cost.add(&RequestType::Summarise, None, &usage(500, 50), 0.005);
+
+        let mut review = RepositoryReview::new("test".to_string());
+        review.add_cost(&review_cost);
+        review.add_cost(&summary_cost);
+
+        let cost = review.cost.as_ref().unwrap();
+        assert!((cost.total_cost - 0.015).abs() < 1e-9);
+        assert_eq!(cost.request_types.len(), 2);
+        assert_eq!(cost.files.len(), 1);
+        assert_eq!(review.statistics.token_usage, Some(usage(1_500, 150)));
     }
 }
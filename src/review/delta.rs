@@ -0,0 +1,339 @@
+//! Compares two JSON reports of a repository, e.g., `cosmonaut_code delta last-month.json today.json`, as a
+//! [`ReportDelta`]: the findings new in the later report, those resolved since the earlier one, the change in the RAG
+//! status of each file, and the change in the repository's statistics.
+//!
+//! Findings are matched by fingerprint, i.e., the file's relative path, and the finding's code and kind, or CWE, as for
+//! feedback, so a finding is matched while the rest of its file changes, and however the LLM words it. At function
+//! granularity, a file's units are taken together.
+use crate::retrieval::data::Statistics;
+use crate::review::data::{Finding, RAGStatus, ReviewSummary, SourceFileReview};
+use crate::review::feedback::set_fingerprints;
+use crate::review::findings::get_findings;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+
+/// The change between two JSON reports of a repository
+///
+/// #Fields:
+/// * `repository_name` - The name of the repository, as in the later report
+/// * `previous_date` - The date of the earlier report
+/// * `date` - The date of the later report
+/// * `previous_rag_status` - The overall [`RAGStatus`] of the repository in the earlier report
+/// * `rag_status` - The overall [`RAGStatus`] of the repository in the later report
+/// * `new_findings` - The [`Finding`]s in the later report that were not in the earlier one, most severe first
+/// * `resolved_findings` - The [`Finding`]s in the earlier report that are not in the later one, most severe first
+/// * `rag_changes` - The [`RagChange`] of each file whose RAG status changed, or that was added or removed
+/// * `statistics` - The [`StatisticDelta`]s of the repository, e.g., its lines of code and security issues
+#[derive(Clone, Serialize, Debug, PartialEq)]
+pub(crate) struct ReportDelta {
+    pub(crate) repository_name: String,
+    pub(crate) previous_date: String,
+    pub(crate) date: String,
+    pub(crate) previous_rag_status: RAGStatus,
+    pub(crate) rag_status: RAGStatus,
+    pub(crate) new_findings: Vec<Finding>,
+    pub(crate) resolved_findings: Vec<Finding>,
+    pub(crate) rag_changes: Vec<RagChange>,
+    pub(crate) statistics: Vec<StatisticDelta>,
+}
+/// The change in the RAG status of a file
+///
+/// #Fields:
+/// * `relative_path` - The relative path of the file
+/// * `previous_rag_status` - The [`RAGStatus`] of the file in the earlier report, if it was reviewed
+/// * `rag_status` - The [`RAGStatus`] of the file in the later report, if it was reviewed, e.g., not if it was removed
+#[derive(Clone, Serialize, Debug, PartialEq)]
+pub(crate) struct RagChange {
+    pub(crate) relative_path: String,
+    pub(crate) previous_rag_status: Option<RAGStatus>,
+    pub(crate) rag_status: Option<RAGStatus>,
+}
+/// The change in a statistic of the repository
+///
+/// #Fields:
+/// * `name` - The name of the statistic, e.g., 'Lines of code'
+/// * `previous` - The value of the statistic in the earlier report
+/// * `current` - The value of the statistic in the later report
+/// * `change` - The change in the statistic, i.e., `current` less `previous`
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct StatisticDelta {
+    pub(crate) name: String,
+    pub(crate) previous: i64,
+    pub(crate) current: i64,
+    pub(crate) change: i64,
+}
+impl ReportDelta {
+    /// Loads the JSON reports, and compares the later one with the earlier one
+    pub(crate) fn load(previous_path: &str, path: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Self::compare(
+            &Report::load(previous_path)?,
+            &Report::load(path)?,
+        ))
+    }
+    fn compare(previous: &Report, current: &Report) -> Self {
+        let previous_findings: Vec<Finding> = get_findings(&previous.file_reviews);
+        let findings: Vec<Finding> = get_findings(&current.file_reviews);
+        let new_findings: Vec<Finding> = get_findings_not_in(&findings, &previous_findings);
+        let resolved_findings: Vec<Finding> = get_findings_not_in(&previous_findings, &findings);
+
+        let previous_rag_statuses: BTreeMap<String, Option<RAGStatus>> =
+            previous.get_rag_statuses();
+        let mut rag_statuses: BTreeMap<String, Option<RAGStatus>> = current.get_rag_statuses();
+        let mut rag_changes: Vec<RagChange> = previous_rag_statuses
+            .into_iter()
+            .map(|(relative_path, previous_rag_status)| RagChange {
+                rag_status: rag_statuses.remove(&relative_path).flatten(),
+                relative_path,
+                previous_rag_status,
+            })
+            .collect();
+        // The files only in the later report, i.e., added
+        rag_changes.extend(
+            rag_statuses
+                .into_iter()
+                .map(|(relative_path, rag_status)| RagChange {
+                    relative_path,
+                    previous_rag_status: None,
+                    rag_status,
+                }),
+        );
+        rag_changes.retain(|rag_change| rag_change.previous_rag_status != rag_change.rag_status);
+        rag_changes.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+
+        Self {
+            repository_name: current.repository_name.clone(),
+            previous_date: previous.date.clone(),
+            date: current.date.clone(),
+            previous_rag_status: previous.repository_rag_status.clone(),
+            rag_status: current.repository_rag_status.clone(),
+            new_findings,
+            resolved_findings,
+            rag_changes,
+            statistics: get_statistic_deltas(previous, current),
+        }
+    }
+}
+impl fmt::Display for ReportDelta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {:?} -> {:?}, {} new findings, {} resolved findings, {} files changed RAG status",
+            self.repository_name,
+            self.previous_rag_status,
+            self.rag_status,
+            self.new_findings.len(),
+            self.resolved_findings.len(),
+            self.rag_changes.len()
+        )
+    }
+}
+/// The parts of a JSON report that are compared
+#[derive(Deserialize, Debug)]
+struct Report {
+    repository_name: String,
+    #[serde(default)]
+    date: String,
+    repository_rag_status: RAGStatus,
+    #[serde(default)]
+    summary: Option<ReviewSummary>,
+    #[serde(default)]
+    statistics: Statistics,
+    file_reviews: Vec<SourceFileReview>,
+}
+impl Report {
+    fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read the report: {}: {}", path, e))?;
+        Self::parse(&contents)
+            .map_err(|e| format!("The report is not a JSON report: {}: {}", path, e).into())
+    }
+    /// Parses the JSON report, fingerprinting each finding, as the fingerprints of an older report may not be set
+    fn parse(contents: &str) -> Result<Self, serde_json::Error> {
+        let mut report: Report = serde_json::from_str(contents)?;
+        for file_review in report.file_reviews.iter_mut() {
+            set_fingerprints(file_review);
+        }
+        Ok(report)
+    }
+    /// Gets the worst [`RAGStatus`] of each file reviewed, by relative path
+    fn get_rag_statuses(&self) -> BTreeMap<String, Option<RAGStatus>> {
+        let mut rag_statuses: BTreeMap<String, Option<RAGStatus>> = BTreeMap::new();
+        for file_review in &self.file_reviews {
+            RAGStatus::keep_worst(
+                rag_statuses
+                    .entry(file_review.source_file_info.relative_path.clone())
+                    .or_default(),
+                file_review.file_rag_status.as_ref(),
+            );
+        }
+        rag_statuses
+    }
+}
+/// Gets the findings, with a fingerprint, that are not among the other findings, by fingerprint
+fn get_findings_not_in(findings: &[Finding], others: &[Finding]) -> Vec<Finding> {
+    let other_fingerprints: HashSet<&str> = others
+        .iter()
+        .filter_map(|finding| finding.fingerprint.as_deref())
+        .collect();
+    findings
+        .iter()
+        .filter(|finding| {
+            finding
+                .fingerprint
+                .as_deref()
+                .is_some_and(|fingerprint| !other_fingerprints.contains(fingerprint))
+        })
+        .cloned()
+        .collect()
+}
+/// Gets the [`StatisticDelta`] of each statistic of the repository, and of the counts of its findings
+fn get_statistic_deltas(previous: &Report, current: &Report) -> Vec<StatisticDelta> {
+    let get_statistics = |report: &Report| -> Vec<(&'static str, i64)> {
+        let summary: Option<&ReviewSummary> = report.summary.as_ref();
+        vec![
+            ("Files", report.statistics.num_files as i64),
+            ("Lines of code", report.statistics.loc),
+            ("Lines of test code", report.statistics.test_loc),
+            ("Commits", report.statistics.num_commits as i64),
+            (
+                "Critical security issues",
+                summary.map_or(0, |summary| summary.security_issues.critical as i64),
+            ),
+            (
+                "High security issues",
+                summary.map_or(0, |summary| summary.security_issues.high as i64),
+            ),
+            (
+                "Security issues",
+                summary.map_or(0, |summary| summary.security_issues.total as i64),
+            ),
+            ("Errors", summary.map_or(0, |summary| summary.errors as i64)),
+            (
+                "Improvements",
+                summary.map_or(0, |summary| summary.improvements as i64),
+            ),
+        ]
+    };
+    get_statistics(previous)
+        .into_iter()
+        .zip(get_statistics(current))
+        .map(|((name, previous), (_, current))| StatisticDelta {
+            name: name.to_string(),
+            previous,
+            current,
+            change: current - previous,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::data::Severity;
+
+    const PREVIOUS_REPORT: &str = r#"{
+        "repository_name": "repos_name",
+        "date": "2024-01-01",
+        "repository_rag_status": "Red",
+        "summary": {
+            "text": "", "errors": 1, "improvements": 0,
+            "security_issues": { "low": 0, "medium": 0, "high": 1, "critical": 0, "total": 1 }
+        },
+        "statistics": { "size": 100, "loc": 40, "num_files": 2, "num_commits": 10, "frequency": 1.0 },
+        "file_reviews": [
+            {
+                "source_file_info": { "name": "main.rs", "relative_path": "src/main.rs", "id_hash": "abc123" },
+                "summary": "A summary",
+                "file_rag_status": "Red",
+                "security_issues": [
+                    { "severity": "High", "code": "query(input)", "threat": "SQL injection", "mitigation": "Bind" }
+                ],
+                "errors": [{ "code": "parse().unwrap()", "issue": "Unchecked unwrap", "resolution": "Handle it" }]
+            },
+            {
+                "source_file_info": { "name": "old.rs", "relative_path": "src/old.rs", "id_hash": "def456" },
+                "summary": "A summary",
+                "file_rag_status": "Green"
+            }
+        ]
+    }"#;
+    const REPORT: &str = r#"{
+        "repository_name": "repos_name",
+        "date": "2024-02-01",
+        "repository_rag_status": "Amber",
+        "summary": {
+            "text": "", "errors": 2, "improvements": 0,
+            "security_issues": { "low": 0, "medium": 0, "high": 0, "critical": 0, "total": 0 }
+        },
+        "statistics": { "size": 120, "loc": 55, "num_files": 2, "num_commits": 14, "frequency": 1.0 },
+        "file_reviews": [
+            {
+                "source_file_info": { "name": "main.rs", "relative_path": "src/main.rs", "id_hash": "ghi789" },
+                "summary": "A summary",
+                "file_rag_status": "Amber",
+                "errors": [
+                    { "code": "parse().unwrap()", "issue": "Unchecked unwrap", "resolution": "Handle it" },
+                    { "code": "v[0]", "issue": "Unchecked index", "resolution": "Use get" }
+                ]
+            },
+            {
+                "source_file_info": { "name": "new.rs", "relative_path": "src/new.rs", "id_hash": "jkl012" },
+                "summary": "A summary",
+                "file_rag_status": "Green"
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_report_delta() {
+        let report_delta = ReportDelta::compare(
+            &Report::parse(PREVIOUS_REPORT).unwrap(),
+            &Report::parse(REPORT).unwrap(),
+        );
+        assert_eq!(
+            (&report_delta.previous_rag_status, &report_delta.rag_status),
+            (&RAGStatus::Red, &RAGStatus::Amber)
+        );
+        // The unwrap recurs, though the rest of the file changed
+        assert_eq!(report_delta.new_findings.len(), 1);
+        assert_eq!(report_delta.new_findings[0].issue, "Unchecked index");
+        assert_eq!(report_delta.resolved_findings.len(), 1);
+        assert_eq!(
+            report_delta.resolved_findings[0].severity,
+            Some(Severity::High)
+        );
+        assert_eq!(
+            report_delta
+                .rag_changes
+                .iter()
+                .map(|rag_change| (
+                    rag_change.relative_path.as_str(),
+                    rag_change.previous_rag_status.clone(),
+                    rag_change.rag_status.clone()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("src/main.rs", Some(RAGStatus::Red), Some(RAGStatus::Amber)),
+                ("src/new.rs", None, Some(RAGStatus::Green)),
+                ("src/old.rs", Some(RAGStatus::Green), None),
+            ]
+        );
+        let lines_of_code = &report_delta.statistics[1];
+        assert_eq!(
+            (
+                lines_of_code.name.as_str(),
+                lines_of_code.previous,
+                lines_of_code.current,
+                lines_of_code.change
+            ),
+            ("Lines of code", 40, 55, 15)
+        );
+        assert_eq!(
+            report_delta.to_string(),
+            "repos_name: Red -> Amber, 1 new findings, 1 resolved findings, 3 files changed RAG status"
+        );
+    }
+}
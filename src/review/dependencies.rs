@@ -0,0 +1,84 @@
+//! A review, after the file reviews, of the hygiene of the repository's direct dependencies: the dependencies listed
+//! from its manifests are sent to the LLM for findings, e.g., unpinned versions or obviously abandoned packages.
+use super::strip_artifacts_from;
+use crate::provider::models::warn_if_exceeds_context_window;
+use crate::provider::prompts::PromptData;
+use crate::provider::{get_provider, get_service, review_or_summarise, RequestType};
+use crate::retrieval::dependencies::Dependency;
+use crate::review::data::DependencyFinding;
+use crate::settings::Settings;
+use log::info;
+use serde::Deserialize;
+use std::error::Error;
+
+/// The response of the LLM to the dependency review
+#[derive(Deserialize)]
+struct DependencyReviewResponse {
+    #[serde(default)]
+    findings: Vec<DependencyFinding>,
+}
+
+/// Reviews the hygiene of the [`Dependency`]s, returning the [`DependencyFinding`]s
+pub(crate) async fn review_dependencies(
+    settings: &Settings,
+    dependencies: &[Dependency],
+) -> Result<Vec<DependencyFinding>, Box<dyn Error>> {
+    info!("Reviewing {} dependencies", dependencies.len());
+    let provider = get_provider(settings);
+    let mut prompt_data: PromptData =
+        PromptData::get_dependency_review_prompt(settings.prompts_path.as_deref())?;
+    prompt_data.add_user_message_prompt(get_dependency_review_request(dependencies));
+    warn_if_exceeds_context_window(settings, &get_service(provider).model, &prompt_data);
+
+    let response =
+        review_or_summarise(RequestType::Summarise, settings, provider, &prompt_data).await?;
+    parse_dependency_review(&response.choices[0].message.content)
+}
+/// Gets the user message with each dependency, by manifest
+fn get_dependency_review_request(dependencies: &[Dependency]) -> String {
+    let mut request = String::from("Dependencies:\n");
+    for dependency in dependencies {
+        request.push_str(&format!(
+            " {} ({}): {} {}{}\n",
+            dependency.manifest,
+            dependency.ecosystem,
+            dependency.name,
+            dependency.version,
+            if dependency.dev { " (dev)" } else { "" }
+        ));
+    }
+    request
+}
+/// Parses the [`DependencyFinding`]s from the response, ignoring any text around the JSON
+fn parse_dependency_review(content: &str) -> Result<Vec<DependencyFinding>, Box<dyn Error>> {
+    let json: String = strip_artifacts_from(content)?;
+    let response: DependencyReviewResponse = serde_json::from_str(&json)?;
+    Ok(response.findings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dependency_review_request_and_response() {
+        let dependencies = vec![Dependency {
+            manifest: "Cargo.toml".to_string(),
+            ecosystem: "crates.io".to_string(),
+            name: "tempfile".to_string(),
+            version: "*".to_string(),
+            dev: true,
+        }];
+        assert_eq!(
+            get_dependency_review_request(&dependencies),
+            "Dependencies:\n Cargo.toml (crates.io): tempfile * (dev)\n"
+        );
+
+        let findings = parse_dependency_review(
+            r#"{"findings": [{"category": "Unpinned version", "finding": "tempfile accepts any version", "recommendation": "Require '3'", "dependencies": ["tempfile"]}]}"#,
+        )
+        .unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].dependencies, vec!["tempfile".to_string()]);
+    }
+}
@@ -0,0 +1,129 @@
+//! Directory summaries: the findings and RAG status of each area of the codebase, e.g., 'src/provider', so that a big
+//! report can be read by area.
+//!
+//! A file is in the area of its directory, up to [`DIRECTORY_DEPTH`] levels deep, e.g., 'src/provider/google.rs' is in
+//! 'src/provider', 'src/main.rs' in 'src', and a file at the root in '.'. An area's RAG status is calculated from its
+//! findings as the repository's is, see [`super::get_rag_status_for`].
+use super::get_rag_status_for;
+use crate::review::data::{DirectorySummary, SecurityIssueBreakdown, SourceFileReview};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+/// The number of levels of directories summarised, e.g., 2 for 'src/provider'
+const DIRECTORY_DEPTH: usize = 2;
+
+/// Gets the [`DirectorySummary`] of each directory with reviewed files, ordered by directory
+pub(crate) fn get_directory_summaries(file_reviews: &[SourceFileReview]) -> Vec<DirectorySummary> {
+    let mut directories: BTreeMap<String, (HashSet<&str>, DirectorySummary)> = BTreeMap::new();
+    for file_review in file_reviews {
+        let relative_path: &str = &file_review.source_file_info.relative_path;
+        let directory: String = get_directory(relative_path);
+        let (files, summary) = directories.entry(directory.clone()).or_insert_with(|| {
+            (
+                HashSet::new(),
+                DirectorySummary {
+                    directory,
+                    files: 0,
+                    security_issues: SecurityIssueBreakdown::default(),
+                    errors: 0,
+                    improvements: 0,
+                    rag_status: Default::default(),
+                },
+            )
+        });
+        // The units of a file, at function granularity, are one file of the directory
+        files.insert(relative_path);
+        summary.errors += file_review.errors.as_ref().map_or(0, Vec::len) as i32;
+        summary.improvements += file_review.improvements.as_ref().map_or(0, Vec::len) as i32;
+        for security_issue in file_review.security_issues.iter().flatten() {
+            summary.security_issues.add(&security_issue.severity);
+        }
+    }
+    directories
+        .into_values()
+        .map(|(files, mut summary)| {
+            summary.files = files.len() as i32;
+            summary.rag_status = get_rag_status_for(
+                summary.files,
+                &summary.security_issues,
+                summary.errors,
+                summary.improvements,
+            );
+            summary
+        })
+        .collect()
+}
+/// Gets the directory of the file, up to [`DIRECTORY_DEPTH`] levels deep, or '.' for a file at the root
+pub(crate) fn get_directory(relative_path: &str) -> String {
+    let directories: Vec<String> = Path::new(relative_path)
+        .parent()
+        .map(|parent| {
+            parent
+                .components()
+                .take(DIRECTORY_DEPTH)
+                .map(|component| component.as_os_str().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    if directories.is_empty() {
+        ".".to_string()
+    } else {
+        directories.join("/")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::data::RAGStatus;
+
+    fn test_file_review(relative_path: &str, findings: &str) -> SourceFileReview {
+        serde_json::from_str(&format!(
+            r#"{{
+                "source_file_info": {{ "name": "file", "relative_path": "{}" }},
+                "summary": "A summary",
+                {}
+            }}"#,
+            relative_path, findings
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_get_directory_summaries() {
+        let security_issue = r#""security_issues": [
+            { "severity": "High", "code": "query(input)", "threat": "SQL injection", "mitigation": "Bind it" }
+        ]"#;
+        let error = r#""errors": [{ "code": "x.unwrap()", "issue": "May panic", "resolution": "Handle it" }]"#;
+        let file_reviews = vec![
+            test_file_review("src/review/mod.rs", security_issue),
+            test_file_review("src/provider/google.rs", error),
+            test_file_review("src/provider/api/mod.rs", error),
+            test_file_review("src/provider/api/mod.rs", error),
+            test_file_review("src/main.rs", r#""errors": []"#),
+            test_file_review("build.rs", r#""errors": []"#),
+        ];
+
+        let summaries = get_directory_summaries(&file_reviews);
+        let directories: Vec<(&str, i32, &RAGStatus)> = summaries
+            .iter()
+            .map(|summary| {
+                (
+                    summary.directory.as_str(),
+                    summary.files,
+                    &summary.rag_status,
+                )
+            })
+            .collect();
+        assert_eq!(
+            directories,
+            vec![
+                (".", 1, &RAGStatus::Green),
+                ("src", 1, &RAGStatus::Green),
+                ("src/provider", 2, &RAGStatus::Amber),
+                ("src/review", 1, &RAGStatus::Red),
+            ]
+        );
+        assert_eq!(summaries[2].errors, 3);
+    }
+}
@@ -0,0 +1,206 @@
+//! Estimates the scope and cost of a review without making any requests to a provider, i.e., a dry run.
+//!
+//! The repository is walked with every filter of a real review applied, including skipping files with secrets if set,
+//! and the tokens of each file's review prompt estimated. The cost is then estimated for each configured provider and
+//! service, from its pricing.
+use super::{
+    get_exceeded_max_file_size, get_prompt_data_based_on_review_type, get_review_request,
+    get_scoped_files, get_skipped_generated, get_skipped_secrets, get_source_files,
+    initialise_repository_review, open_review_worktree, remove_ignored_files,
+    split_source_files_into_units, validate_repository,
+};
+use crate::provider::api::TokenUsage;
+use crate::provider::cost::get_pricing;
+use crate::retrieval::data::SourceFileInfo;
+use crate::retrieval::git::worktree::ReviewWorktree;
+use crate::review::data::RepositoryReview;
+use crate::review::remediation::PreviousReport;
+use crate::settings::{ReviewGranularity, Settings};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+
+/// The number of completion tokens assumed per file review, as the length of the response cannot be known in advance
+pub(crate) const ESTIMATED_COMPLETION_TOKENS_PER_FILE: u64 = 1_000;
+
+/// A file that would be reviewed, with the estimated number of tokens of its review prompt
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct DryRunFile {
+    pub(crate) relative_path: String,
+    pub(crate) prompt_tokens: u64,
+}
+/// The estimated cost of the review with a configured provider and service, or [`None`] if its pricing is unknown
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct DryRunCost {
+    pub(crate) provider: String,
+    pub(crate) service: String,
+    pub(crate) model: String,
+    pub(crate) estimated_cost: Option<f64>,
+}
+/// The estimated scope and cost of a review
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DryRun {
+    pub(crate) files: Vec<DryRunFile>,
+    pub(crate) costs: Vec<DryRunCost>,
+}
+impl DryRun {
+    /// The estimated [`TokenUsage`] of reviewing every file
+    pub(crate) fn estimate_usage(&self) -> TokenUsage {
+        TokenUsage {
+            prompt_tokens: self
+                .files
+                .iter()
+                .map(|file| file.prompt_tokens)
+                .sum::<u64>() as i64,
+            completion_tokens: (self.files.len() as u64 * ESTIMATED_COMPLETION_TOKENS_PER_FILE)
+                as i64,
+            cached_prompt_tokens: 0,
+        }
+    }
+    /// Gets the dry run as JSON, i.e., the files, the estimated usage and the costs, e.g., for `--output -`
+    pub(crate) fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&json!({
+            "files": self.files,
+            "estimated_usage": self.estimate_usage(),
+            "costs": self.costs,
+        }))
+    }
+}
+impl fmt::Display for DryRun {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<60} {:>10}", "FILE", "TOKENS")?;
+        for file in &self.files {
+            writeln!(f, "{:<60} {:>10}", file.relative_path, file.prompt_tokens)?;
+        }
+        let usage = self.estimate_usage();
+        writeln!(
+            f,
+            "\n{} files, ~{} prompt tokens and ~{} completion tokens\n",
+            self.files.len(),
+            usage.prompt_tokens,
+            usage.completion_tokens
+        )?;
+        writeln!(
+            f,
+            "{:<30} {:<30} {:>10}",
+            "PROVIDER / SERVICE", "MODEL", "COST (USD)"
+        )?;
+        for cost in &self.costs {
+            writeln!(
+                f,
+                "{:<30} {:<30} {:>10}",
+                format!("{} / {}", cost.provider, cost.service),
+                cost.model,
+                cost.estimated_cost
+                    .map_or("unknown".to_string(), |estimated_cost| format!(
+                        "{:.4}",
+                        estimated_cost
+                    ))
+            )?;
+        }
+        Ok(())
+    }
+}
+/// Walks the repository, applying the filters of a review, and estimates the tokens and the cost, per configured
+/// provider and service, of reviewing the files in scope
+pub(crate) fn estimate_review(settings: &Settings) -> Result<DryRun, Box<dyn Error>> {
+    let repository_root: PathBuf = validate_repository(
+        PathBuf::from(&settings.repository_path),
+        settings.require_git,
+    )?;
+    let worktree: Option<ReviewWorktree> = open_review_worktree(settings)?;
+    let repository_root: PathBuf = match &worktree {
+        Some(worktree) => worktree.path.clone(),
+        None => repository_root,
+    };
+    let mut review: RepositoryReview = initialise_repository_review(settings)?;
+    let previous_report: Option<PreviousReport> = PreviousReport::from_settings(settings)?;
+    let scoped_files: Option<HashSet<String>> = get_scoped_files(
+        settings,
+        &repository_root.to_string_lossy(),
+        &mut review,
+        previous_report.as_ref(),
+    )?;
+    let source_files: Vec<SourceFileInfo> =
+        get_source_files(settings, &repository_root, scoped_files.as_ref());
+    let source_files: Vec<SourceFileInfo> = remove_ignored_files(source_files);
+    let source_files: Vec<SourceFileInfo> = match settings.review_granularity {
+        ReviewGranularity::File => source_files,
+        ReviewGranularity::Function => split_source_files_into_units(settings, source_files),
+    };
+
+    let mut dry_run = DryRun::default();
+    for file_info in &source_files {
+        if get_skipped_secrets(settings, file_info).is_some()
+            || get_exceeded_max_file_size(settings, file_info).is_some()
+            || get_skipped_generated(settings, file_info).is_some()
+        {
+            continue;
+        }
+        let Some(mut prompt_data) = get_prompt_data_based_on_review_type(settings, file_info)?
+        else {
+            return Err(format!(
+                "The '{}' review type does not use a provider to estimate",
+                settings.review_type
+            )
+            .into());
+        };
+        prompt_data.add_user_message_prompt(get_review_request(file_info));
+        dry_run.files.push(DryRunFile {
+            relative_path: match &file_info.code_unit {
+                Some(unit) => format!("{} ({})", file_info.relative_path, unit.name),
+                None => file_info.relative_path.clone(),
+            },
+            prompt_tokens: prompt_data.estimate_tokens(),
+        });
+    }
+
+    let usage = dry_run.estimate_usage();
+    for provider in &settings.providers {
+        for service in &provider.services {
+            dry_run.costs.push(DryRunCost {
+                provider: provider.name.clone(),
+                service: service.name.clone(),
+                model: service.model.clone(),
+                estimated_cost: get_pricing(settings, service)
+                    .map(|pricing| pricing.estimate_cost(&usage)),
+            });
+        }
+    }
+    Ok(dry_run)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dry_run_estimate_usage() {
+        let dry_run = DryRun {
+            files: vec![
+                DryRunFile {
+                    relative_path: "src/main.rs".to_string(),
+                    prompt_tokens: 1_200,
+                },
+                DryRunFile {
+                    relative_path: "src/lib.rs".to_string(),
+                    prompt_tokens: 800,
+                },
+            ],
+            costs: Vec::new(),
+        };
+        let usage = dry_run.estimate_usage();
+        assert_eq!(usage.prompt_tokens, 2_000);
+        assert_eq!(
+            usage.completion_tokens,
+            2 * ESTIMATED_COMPLETION_TOKENS_PER_FILE as i64
+        );
+        assert!(dry_run.to_string().contains("2 files"));
+        let json: serde_json::Value = serde_json::from_str(&dry_run.to_json().unwrap()).unwrap();
+        assert_eq!(json["files"][1]["relative_path"], "src/lib.rs");
+        assert_eq!(json["estimated_usage"]["prompt_tokens"], 2_000);
+    }
+}
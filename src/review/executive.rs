@@ -0,0 +1,292 @@
+//! The executive summary of the review: a verdict on the repository, its top risks, the trend since its last recorded
+//! review and the estimated effort to remediate its findings, written by the LLM for a non-technical audience, with its
+//! own prompt, distinct from that of the technical summary.
+//!
+//! The remediation hours are estimated from the number and severity of the findings, not by the LLM, so that they are
+//! comparable across reviews; the LLM only puts them in plain terms.
+use super::strip_artifacts_from;
+use crate::provider::models::warn_if_exceeds_context_window;
+use crate::provider::prompts::PromptData;
+use crate::provider::{get_provider, get_service, review_or_summarise, RequestType};
+use crate::review::data::{
+    ExecutiveRisk, ExecutiveSummary, ExecutiveTrend, RepositoryReview, ReviewSummary, Severity,
+};
+use crate::review::delta::StatisticDelta;
+use crate::review::trends::TrendPoint;
+use crate::settings::Settings;
+use log::info;
+use serde::Deserialize;
+use std::error::Error;
+
+/// The most risks given in the executive summary
+const MAX_TOP_RISKS: usize = 5;
+/// The most findings given to the LLM, most serious first
+const MAX_FINDINGS: usize = 20;
+
+/// The response of the LLM to the executive summary
+#[derive(Deserialize)]
+struct ExecutiveSummaryResponse {
+    verdict: String,
+    #[serde(default)]
+    top_risks: Vec<ExecutiveRisk>,
+    #[serde(default)]
+    remediation_effort: String,
+}
+
+/// Summarises the review for a non-technical audience, with the trend since the `previous` recorded review, if any
+pub(crate) async fn summarise_for_executives(
+    settings: &Settings,
+    repository_review: &RepositoryReview,
+    previous: Option<&TrendPoint>,
+) -> Result<ExecutiveSummary, Box<dyn Error>> {
+    info!("Summarising the review of the repository for executives");
+    let remediation_hours: f64 = repository_review
+        .summary
+        .as_ref()
+        .map_or(0.0, estimate_remediation_hours);
+    let trend: Option<ExecutiveTrend> = previous
+        .map(|previous| get_executive_trend(previous, &TrendPoint::from_review(repository_review)));
+
+    let provider = get_provider(settings);
+    let mut prompt_data: PromptData = PromptData::get_executive_summary_prompt(
+        settings.prompts_path.as_deref(),
+        settings.get_summary_language(),
+    )?;
+    prompt_data.add_user_message_prompt(get_executive_summary_request(
+        repository_review,
+        trend.as_ref(),
+        remediation_hours,
+    ));
+    warn_if_exceeds_context_window(settings, &get_service(provider).model, &prompt_data);
+
+    let response =
+        review_or_summarise(RequestType::Summarise, settings, provider, &prompt_data).await?;
+    let response = parse_executive_summary(&response.choices[0].message.content)?;
+    Ok(ExecutiveSummary {
+        verdict: response.verdict,
+        top_risks: response.top_risks,
+        trend,
+        remediation_hours,
+        remediation_effort: response.remediation_effort,
+    })
+}
+/// Estimates the hours to resolve the findings: 8 for each Critical security issue, 4 for each High, 2 for each Medium
+/// and 1 for each Low, 1 for each error, and half an hour for each improvement
+fn estimate_remediation_hours(summary: &ReviewSummary) -> f64 {
+    let security_issues = &summary.security_issues;
+    (security_issues.critical * 8
+        + security_issues.high * 4
+        + security_issues.medium * 2
+        + security_issues.low
+        + summary.errors) as f64
+        + summary.improvements as f64 * 0.5
+}
+/// Gets the change in the key metrics of the repository from the previous review to the current one
+fn get_executive_trend(previous: &TrendPoint, current: &TrendPoint) -> ExecutiveTrend {
+    let get_metrics = |point: &TrendPoint| -> Vec<(&'static str, i64)> {
+        vec![
+            ("Critical security issues", point.critical),
+            ("High security issues", point.high),
+            ("Security issues", point.security_issues),
+            ("Errors", point.errors),
+            ("Improvements", point.improvements),
+            ("Lines of code", point.loc),
+        ]
+    };
+    ExecutiveTrend {
+        previous_date: previous.date.clone(),
+        previous_rag_status: previous.rag_status.clone(),
+        changes: get_metrics(previous)
+            .into_iter()
+            .zip(get_metrics(current))
+            .map(|((name, previous), (_, current))| StatisticDelta {
+                name: name.to_string(),
+                previous,
+                current,
+                change: current - previous,
+            })
+            .collect(),
+    }
+}
+/// Gets the user message with the key metrics, the trend, the remediation hours, the most serious findings, i.e., the
+/// security issues, most severe first, then the errors, and the technical summary
+fn get_executive_summary_request(
+    repository_review: &RepositoryReview,
+    trend: Option<&ExecutiveTrend>,
+    remediation_hours: f64,
+) -> String {
+    let mut request = format!(
+        "Repository: {}\nOverall status: {:?}\nFiles reviewed: {}\nLines of code: {}\n",
+        repository_review.repository_name,
+        repository_review.get_repository_rag_status(),
+        repository_review.statistics.num_files,
+        repository_review.statistics.loc
+    );
+    if let Some(summary) = &repository_review.summary {
+        let security_issues = &summary.security_issues;
+        request.push_str(&format!(
+            "Security issues: {} ({} Critical, {} High, {} Medium, {} Low)\nErrors: {}\nImprovements: {}\n",
+            security_issues.total,
+            security_issues.critical,
+            security_issues.high,
+            security_issues.medium,
+            security_issues.low,
+            summary.errors,
+            summary.improvements
+        ));
+    }
+    match trend {
+        Some(trend) => {
+            request.push_str(&format!(
+                "Since the last review, of {}, when the overall status was {:?}:\n",
+                trend.previous_date, trend.previous_rag_status
+            ));
+            for change in &trend.changes {
+                request.push_str(&format!(
+                    " {}: {} to {} ({:+})\n",
+                    change.name, change.previous, change.current, change.change
+                ));
+            }
+        }
+        None => request.push_str("There is no previous review.\n"),
+    }
+    request.push_str(&format!(
+        "Estimated remediation effort: {} hours\n",
+        remediation_hours
+    ));
+
+    let mut findings: Vec<(Option<&Severity>, &str, &str)> = repository_review
+        .file_reviews
+        .iter()
+        .flat_map(|file_review| {
+            let relative_path = file_review.source_file_info.relative_path.as_str();
+            let security_issues = file_review
+                .security_issues
+                .iter()
+                .flatten()
+                .map(move |issue| (Some(&issue.severity), relative_path, issue.threat.as_str()));
+            let errors = file_review
+                .errors
+                .iter()
+                .flatten()
+                .map(move |error| (None, relative_path, error.issue.as_str()));
+            security_issues.chain(errors)
+        })
+        .collect();
+    // Security issues, most severe first, then errors, each in the order of the file reviews
+    findings.sort_by(|a, b| b.0.cmp(&a.0));
+    if !findings.is_empty() {
+        request.push_str("Most serious findings:\n");
+        for (severity, relative_path, issue) in findings.into_iter().take(MAX_FINDINGS) {
+            match severity {
+                Some(severity) => request.push_str(&format!(
+                    " Security issue ({:?}), in {}: {}\n",
+                    severity, relative_path, issue
+                )),
+                None => request.push_str(&format!(" Error, in {}: {}\n", relative_path, issue)),
+            }
+        }
+    }
+    if let Some(summary) = &repository_review.summary {
+        request.push_str(&format!("Technical summary:\n{}\n", summary.text.trim()));
+    }
+    request
+}
+/// Parses the [`ExecutiveSummaryResponse`] from the response, ignoring any text around the JSON, keeping the top risks
+fn parse_executive_summary(content: &str) -> Result<ExecutiveSummaryResponse, Box<dyn Error>> {
+    let json: String = strip_artifacts_from(content)?;
+    let mut response: ExecutiveSummaryResponse = serde_json::from_str(&json)?;
+    response.top_risks.truncate(MAX_TOP_RISKS);
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::data::RAGStatus;
+
+    #[test]
+    fn test_executive_summary_request_and_response() {
+        let mut repository_review: RepositoryReview = serde_json::from_str(
+            r#"{
+                "repository_name": "repos_name",
+                "date": "2024-02-01",
+                "repository_purpose": null,
+                "summary": {
+                    "text": "The repository is well structured.",
+                    "security_issues": { "low": 0, "medium": 1, "high": 1, "critical": 0, "total": 2 },
+                    "errors": 1,
+                    "improvements": 2
+                },
+                "repository_rag_status": "Red",
+                "contributors": [],
+                "language_types": [],
+                "file_reviews": [
+                    {
+                        "source_file_info": { "name": "main.rs", "relative_path": "src/main.rs" },
+                        "summary": "Runs the CLI",
+                        "security_issues": [
+                            { "severity": "Medium", "code": "log(token)", "threat": "Token logged", "mitigation": "Redact it" },
+                            { "severity": "High", "code": "query(input)", "threat": "SQL injection", "mitigation": "Bind it" }
+                        ],
+                        "errors": [{ "code": "parse().unwrap()", "issue": "Unchecked unwrap", "resolution": "Handle it" }]
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        repository_review.statistics.loc = 120;
+        repository_review.statistics.num_files = 1;
+        let summary = repository_review.summary.as_ref().unwrap();
+        assert_eq!(estimate_remediation_hours(summary), 8.0);
+
+        let previous = TrendPoint {
+            date: "2024-01-01".to_string(),
+            head_sha: None,
+            rag_status: RAGStatus::Amber,
+            critical: 0,
+            high: 0,
+            security_issues: 1,
+            errors: 1,
+            improvements: 2,
+            loc: 100,
+            cost: None,
+        };
+        let trend = get_executive_trend(&previous, &TrendPoint::from_review(&repository_review));
+        assert_eq!(
+            trend
+                .changes
+                .iter()
+                .map(|change| (change.name.as_str(), change.change))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Critical security issues", 0),
+                ("High security issues", 1),
+                ("Security issues", 1),
+                ("Errors", 0),
+                ("Improvements", 0),
+                ("Lines of code", 20)
+            ]
+        );
+        let request = get_executive_summary_request(&repository_review, Some(&trend), 8.0);
+        assert!(request.contains(
+            "Most serious findings:\n Security issue (High), in src/main.rs: SQL injection\n Security issue (Medium), in src/main.rs: Token logged\n Error, in src/main.rs: Unchecked unwrap\n"
+        ));
+        assert!(request.contains("Files reviewed: 1\nLines of code: 120\n"));
+        assert!(request.contains(" Lines of code: 100 to 120 (+20)\n"));
+
+        let response = parse_executive_summary(
+            r#"```json
+            {"verdict": "The repository is at risk.", "top_risks": [
+                {"risk": "Customer data could be read", "impact": "A breach", "files": ["src/main.rs"]},
+                {"risk": "2", "impact": ""}, {"risk": "3", "impact": ""}, {"risk": "4", "impact": ""},
+                {"risk": "5", "impact": ""}, {"risk": "6", "impact": ""}
+            ], "remediation_effort": "One developer for a day."}
+            ```"#,
+        )
+        .unwrap();
+        assert_eq!(response.top_risks.len(), MAX_TOP_RISKS);
+        assert_eq!(response.top_risks[0].files, vec!["src/main.rs".to_string()]);
+        assert!(parse_executive_summary("The repository is fine").is_err());
+    }
+}
@@ -0,0 +1,325 @@
+//! Feedback on findings: a persisted store of the findings marked as false positives, by fingerprint, e.g.,
+//! `cosmonaut_code feedback false-positive 3f2a... "The input is validated by the caller"`.
+//!
+//! A finding's fingerprint is the hash of the file's relative path, the finding's code, with whitespace collapsed, and
+//! its kind, or, for a security issue, its CWE, if any. The LLM words an issue differently from one review to the next,
+//! so the wording is not fingerprinted. Unlike the fingerprint of a [`super::baseline::Baseline`], it holds while the
+//! rest of the file changes. A false positive is dropped from later reviews, and listed as suppressed. If
+//! `feedback_counter_examples` is set, the false positives are also given to the LLM, as examples of what not to
+//! report.
+use crate::retrieval::code::calculate_hash_from;
+use crate::review::data::SourceFileReview;
+use crate::review::normalise::normalise_cwe;
+use crate::settings::Settings;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+/// The location of the feedback store used if `feedback_path` is not set, relative to the working directory
+pub(crate) const DEFAULT_FEEDBACK_PATH: &str = ".cosmonaut/feedback.json";
+/// The most false positives given to the LLM as counter-examples
+const MAX_COUNTER_EXAMPLES: usize = 20;
+
+/// A finding marked as a false positive
+///
+/// #Fields:
+/// * `fingerprint` - The fingerprint of the finding, as in the report
+/// * `reason` - Why the finding is a false positive, if given
+/// * `relative_path` - The relative path of the file of the finding, once it has been seen in a review
+/// * `code` - The code of the finding, once it has been seen in a review
+/// * `description` - The kind of finding and its issue, e.g., 'Error: Unchecked unwrap', once it has been seen
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct FalsePositive {
+    pub(crate) fingerprint: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) relative_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) code: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) description: Option<String>,
+}
+/// The findings marked as false positives, persisted as JSON
+#[derive(Debug)]
+pub(crate) struct FeedbackStore {
+    path: PathBuf,
+    false_positives: Vec<FalsePositive>,
+    changed: bool,
+}
+impl FeedbackStore {
+    /// Loads the feedback store from the path, if any, otherwise starts with none
+    pub(crate) fn load(path: PathBuf) -> Self {
+        let false_positives: Vec<FalsePositive> = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                warn!("Ignoring invalid feedback: {}: {}", path.display(), e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        if !false_positives.is_empty() {
+            info!("Feedback: {} false positives", false_positives.len());
+        }
+        Self {
+            path,
+            false_positives,
+            changed: false,
+        }
+    }
+    /// Loads the [`FeedbackStore`] at the `feedback_path` of the [`Settings`], or the default
+    pub(crate) fn from_settings(settings: &Settings) -> Self {
+        let path = settings
+            .feedback_path
+            .as_deref()
+            .unwrap_or(DEFAULT_FEEDBACK_PATH);
+        Self::load(PathBuf::from(path))
+    }
+    /// Marks the finding with the fingerprint as a false positive, with the reason, if any, returning false if it
+    /// already was
+    pub(crate) fn mark_false_positive(
+        &mut self,
+        fingerprint: &str,
+        reason: Option<String>,
+    ) -> bool {
+        if let Some(false_positive) = self.get_mut(fingerprint) {
+            if reason.is_some() {
+                false_positive.reason = reason;
+                self.changed = true;
+            }
+            return false;
+        }
+        self.false_positives.push(FalsePositive {
+            fingerprint: fingerprint.to_string(),
+            reason,
+            relative_path: None,
+            code: None,
+            description: None,
+        });
+        self.changed = true;
+        true
+    }
+    /// Drops the findings of the [`SourceFileReview`] marked as false positives, recording each as suppressed, and the
+    /// details of each, e.g., its code, for the counter-examples
+    pub(crate) fn apply(&mut self, file_review: &mut SourceFileReview) {
+        if self.false_positives.is_empty() {
+            return;
+        }
+        let relative_path: String = file_review.source_file_info.relative_path.clone();
+        let mut found: Vec<(String, String, String)> = Vec::new();
+        let mut is_false_positive =
+            |fingerprint: &Option<String>, code: &str, description: String| {
+                let Some(fingerprint) = fingerprint else {
+                    return false;
+                };
+                let is_marked = self
+                    .false_positives
+                    .iter()
+                    .any(|false_positive| false_positive.fingerprint == *fingerprint);
+                if is_marked {
+                    found.push((fingerprint.clone(), code.to_string(), description));
+                }
+                is_marked
+            };
+        if let Some(security_issues) = &mut file_review.security_issues {
+            security_issues.retain(|security_issue| {
+                !is_false_positive(
+                    &security_issue.fingerprint,
+                    &security_issue.code,
+                    format!("Security issue: {}", security_issue.threat),
+                )
+            });
+        }
+        if let Some(errors) = &mut file_review.errors {
+            errors.retain(|error| {
+                !is_false_positive(
+                    &error.fingerprint,
+                    &error.code,
+                    format!("Error: {}", error.issue),
+                )
+            });
+        }
+        if let Some(improvements) = &mut file_review.improvements {
+            improvements.retain(|improvement| {
+                !is_false_positive(
+                    &improvement.fingerprint,
+                    &improvement.code,
+                    format!("Improvement: {}", improvement.suggestion),
+                )
+            });
+        }
+        for (fingerprint, code, description) in found {
+            file_review
+                .suppressed_findings
+                .get_or_insert_with(Vec::new)
+                .push(format!("False positive: {}", description));
+            if let Some(false_positive) = self.get_mut(&fingerprint) {
+                if false_positive.description.is_none() {
+                    false_positive.relative_path = Some(relative_path.clone());
+                    false_positive.code = Some(code);
+                    false_positive.description = Some(description);
+                    self.changed = true;
+                }
+            }
+        }
+    }
+    /// Gets the false positives seen in a review as counter-examples for the prompt, i.e., findings not to report, or
+    /// [`None`] if there are none
+    pub(crate) fn get_counter_examples(&self) -> Option<String> {
+        let examples: Vec<String> = self
+            .false_positives
+            .iter()
+            .filter_map(|false_positive| {
+                let description = false_positive.description.as_ref()?;
+                let code = false_positive.code.as_deref().unwrap_or_default();
+                Some(match &false_positive.reason {
+                    Some(reason) => format!(
+                        "- {}, for code: '{}', as: {}",
+                        description,
+                        code.trim(),
+                        reason
+                    ),
+                    None => format!("- {}, for code: '{}'", description, code.trim()),
+                })
+            })
+            .take(MAX_COUNTER_EXAMPLES)
+            .collect();
+        (!examples.is_empty()).then(|| {
+            format!(
+                "These findings were reported in earlier reviews, but are false positives, so do not report them, or \
+                 findings like them:\n{}",
+                examples.join("\n")
+            )
+        })
+    }
+    /// Saves the feedback store, if changed
+    pub(crate) fn save(&self) -> Result<(), Box<dyn Error>> {
+        if !self.changed {
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(
+            &self.path,
+            serde_json::to_string_pretty(&self.false_positives)?,
+        )?;
+        Ok(())
+    }
+    fn get_mut(&mut self, fingerprint: &str) -> Option<&mut FalsePositive> {
+        self.false_positives
+            .iter_mut()
+            .find(|false_positive| false_positive.fingerprint == fingerprint)
+    }
+}
+/// Sets the fingerprint of each finding of the [`SourceFileReview`], by which it can be marked as a false positive
+pub(crate) fn set_fingerprints(file_review: &mut SourceFileReview) {
+    let relative_path: &str = &file_review.source_file_info.relative_path;
+    for security_issue in file_review.security_issues.iter_mut().flatten() {
+        let kind: String = security_issue
+            .cwe
+            .as_deref()
+            .and_then(normalise_cwe)
+            .unwrap_or_else(|| "Security issue".to_string());
+        security_issue.fingerprint =
+            Some(get_fingerprint(relative_path, &security_issue.code, &kind));
+    }
+    for error in file_review.errors.iter_mut().flatten() {
+        error.fingerprint = Some(get_fingerprint(relative_path, &error.code, "Error"));
+    }
+    for improvement in file_review.improvements.iter_mut().flatten() {
+        improvement.fingerprint = Some(get_fingerprint(
+            relative_path,
+            &improvement.code,
+            "Improvement",
+        ));
+    }
+}
+/// Gets the fingerprint of a finding from the file's relative path, the finding's code, with whitespace collapsed, and
+/// its kind, e.g., 'Error', or CWE
+fn get_fingerprint(relative_path: &str, code: &str, kind: &str) -> String {
+    let code: String = code.split_whitespace().collect::<Vec<_>>().join(" ");
+    calculate_hash_from(&format!("{}:{}:{}", relative_path, code, kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const FILE_REVIEW: &str = r#"{
+        "source_file_info": { "name": "main.rs", "relative_path": "src/main.rs", "id_hash": "abc123" },
+        "summary": "A summary",
+        "security_issues": [
+            { "severity": "High", "code": "query(input)", "threat": "SQL injection", "mitigation": "Bind parameters" }
+        ],
+        "errors": [{ "code": "parse().unwrap()", "issue": "Unchecked unwrap", "resolution": "Handle the error" }]
+    }"#;
+
+    #[test]
+    fn test_feedback_store_apply() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("feedback.json");
+        let mut file_review: SourceFileReview = serde_json::from_str(FILE_REVIEW).unwrap();
+        set_fingerprints(&mut file_review);
+        let fingerprint: String = file_review.security_issues.as_ref().unwrap()[0]
+            .fingerprint
+            .clone()
+            .unwrap();
+
+        let mut feedback = FeedbackStore::load(path.clone());
+        assert_eq!(feedback.get_counter_examples(), None);
+        assert!(
+            feedback.mark_false_positive(&fingerprint, Some("The input is a constant".to_string()))
+        );
+        assert!(!feedback.mark_false_positive(&fingerprint, None));
+        feedback.save().unwrap();
+
+        // The false positive is dropped in a later run, and its details kept for the counter-examples
+        let mut feedback = FeedbackStore::load(path.clone());
+        feedback.apply(&mut file_review);
+        assert_eq!(file_review.security_issues, Some(Vec::new()));
+        assert_eq!(file_review.errors.as_ref().map(Vec::len), Some(1));
+        assert_eq!(
+            file_review.suppressed_findings,
+            Some(vec![
+                "False positive: Security issue: SQL injection".to_string()
+            ])
+        );
+        feedback.save().unwrap();
+        let counter_examples = FeedbackStore::load(path).get_counter_examples().unwrap();
+        assert!(counter_examples.ends_with(
+            "- Security issue: SQL injection, for code: 'query(input)', as: The input is a constant"
+        ));
+    }
+
+    #[test]
+    fn test_set_fingerprints() {
+        let mut file_review: SourceFileReview = serde_json::from_str(FILE_REVIEW).unwrap();
+        set_fingerprints(&mut file_review);
+        let mut reworded: SourceFileReview = serde_json::from_str(FILE_REVIEW).unwrap();
+        let security_issue = &mut reworded.security_issues.as_mut().unwrap()[0];
+        security_issue.code = "query(\n    input\n)".to_string();
+        security_issue.threat = "Injection of SQL".to_string();
+        reworded.errors.as_mut().unwrap()[0].issue = "An unwrap that may panic".to_string();
+        set_fingerprints(&mut reworded);
+        // Neither the wording of the issue, nor the layout of the code, changes the fingerprint
+        assert_eq!(
+            file_review.security_issues.as_ref().unwrap()[0].fingerprint,
+            reworded.security_issues.as_ref().unwrap()[0].fingerprint
+        );
+        assert_eq!(
+            file_review.errors.as_ref().unwrap()[0].fingerprint,
+            reworded.errors.as_ref().unwrap()[0].fingerprint
+        );
+
+        // A security issue is fingerprinted by its CWE, if any
+        reworded.security_issues.as_mut().unwrap()[0].cwe = Some("CWE-89".to_string());
+        set_fingerprints(&mut reworded);
+        assert_ne!(
+            file_review.security_issues.as_ref().unwrap()[0].fingerprint,
+            reworded.security_issues.as_ref().unwrap()[0].fingerprint
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! The findings of every file review in one list, most severe first, then by file, so that a security team can work
+//! through them top-down, rather than file by file.
+//!
+//! The security issues are ordered by [`Severity`], Critical first, then come the errors, then the improvements.
+use crate::review::data::{Finding, Severity, SourceFileReview};
+
+/// Gets each finding of the file reviews as a [`Finding`], most severe first, then by relative path
+pub(crate) fn get_findings(file_reviews: &[SourceFileReview]) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = Vec::new();
+    for file_review in file_reviews {
+        let relative_path: &str = &file_review.source_file_info.relative_path;
+        for security_issue in file_review.security_issues.iter().flatten() {
+            findings.push(Finding {
+                relative_path: relative_path.to_string(),
+                kind: "Security issue".to_string(),
+                severity: Some(security_issue.severity.clone()),
+                issue: security_issue.threat.clone(),
+                code: security_issue.code.clone(),
+                resolution: security_issue.mitigation.clone(),
+                fingerprint: security_issue.fingerprint.clone(),
+                link: security_issue.link.clone(),
+            });
+        }
+        for error in file_review.errors.iter().flatten() {
+            findings.push(Finding {
+                relative_path: relative_path.to_string(),
+                kind: "Error".to_string(),
+                severity: None,
+                issue: error.issue.clone(),
+                code: error.code.clone(),
+                resolution: error.resolution.clone(),
+                fingerprint: error.fingerprint.clone(),
+                link: error.link.clone(),
+            });
+        }
+        for improvement in file_review.improvements.iter().flatten() {
+            findings.push(Finding {
+                relative_path: relative_path.to_string(),
+                kind: "Improvement".to_string(),
+                severity: None,
+                issue: improvement.suggestion.clone(),
+                code: improvement.code.clone(),
+                resolution: improvement.get_improvement_details().to_string(),
+                fingerprint: improvement.fingerprint.clone(),
+                link: improvement.link.clone(),
+            });
+        }
+    }
+    // Stable, so the findings of a file keep the order of its review
+    findings.sort_by(|a, b| {
+        get_rank(b)
+            .cmp(&get_rank(a))
+            .then_with(|| a.relative_path.cmp(&b.relative_path))
+    });
+    findings
+}
+/// Gets the rank of the [`Finding`], most severe highest, i.e., a security issue by its [`Severity`], then an error,
+/// then an improvement
+fn get_rank(finding: &Finding) -> i32 {
+    match (&finding.severity, finding.kind.as_str()) {
+        (Some(Severity::Critical), _) => 5,
+        (Some(Severity::High), _) => 4,
+        (Some(Severity::Medium), _) => 3,
+        (Some(Severity::Low), _) => 2,
+        (None, "Error") => 1,
+        (None, _) => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_findings() {
+        let file_reviews: Vec<SourceFileReview> = serde_json::from_str(
+            r#"[
+                {
+                    "source_file_info": { "name": "main.rs", "relative_path": "src/main.rs" },
+                    "summary": "Runs the CLI",
+                    "security_issues": [
+                        { "severity": "Low", "code": "debug!(input)", "threat": "Input logged", "mitigation": "Redact it" }
+                    ],
+                    "improvements": [
+                        { "code": "fn main()", "suggestion": "Split main", "improvement_details": "It is long" }
+                    ]
+                },
+                {
+                    "source_file_info": { "name": "db.rs", "relative_path": "src/db.rs" },
+                    "summary": "Queries the database",
+                    "security_issues": [
+                        { "severity": "Low", "code": "println!(row)", "threat": "Row printed", "mitigation": "Remove it" },
+                        { "severity": "Critical", "code": "query(input)", "threat": "SQL injection", "mitigation": "Bind it" }
+                    ],
+                    "errors": [{ "code": "conn.unwrap()", "issue": "Unchecked unwrap", "resolution": "Handle it" }]
+                }
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            get_findings(&file_reviews)
+                .iter()
+                .map(|finding| (finding.relative_path.as_str(), finding.issue.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("src/db.rs", "SQL injection"),
+                ("src/db.rs", "Row printed"),
+                ("src/main.rs", "Input logged"),
+                ("src/db.rs", "Unchecked unwrap"),
+                ("src/main.rs", "Split main"),
+            ]
+        );
+    }
+}
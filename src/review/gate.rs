@@ -0,0 +1,134 @@
+//! A quality gate on the [`RepositoryReview`], so that a review can block a CI pipeline.
+//!
+//! Each threshold of the [`QualityGate`] that is exceeded has a distinct exit code, so that a pipeline can tell why the
+//! review failed. Known findings, i.e., those in a baseline, are not counted.
+use crate::review::data::{RAGStatus, RepositoryReview};
+use crate::settings::QualityGate;
+use std::fmt;
+
+/// The exit code when the repository is Red
+pub(crate) const RED_EXIT_CODE: i32 = 3;
+/// The exit code when the critical and high security issues exceed the maximum
+pub(crate) const SECURITY_ISSUES_EXIT_CODE: i32 = 4;
+/// The exit code when the errors exceed the maximum
+pub(crate) const ERRORS_EXIT_CODE: i32 = 5;
+
+/// The first threshold of the [`QualityGate`] that the review exceeded
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum GateFailure {
+    Red,
+    SecurityIssues { count: i32, max: i32 },
+    Errors { count: i32, max: i32 },
+}
+impl GateFailure {
+    /// The exit code of the process for the failure
+    pub(crate) fn exit_code(&self) -> i32 {
+        match self {
+            GateFailure::Red => RED_EXIT_CODE,
+            GateFailure::SecurityIssues { .. } => SECURITY_ISSUES_EXIT_CODE,
+            GateFailure::Errors { .. } => ERRORS_EXIT_CODE,
+        }
+    }
+}
+impl fmt::Display for GateFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GateFailure::Red => write!(f, "the repository is Red"),
+            GateFailure::SecurityIssues { count, max } => write!(
+                f,
+                "{} critical and high security issues, over the maximum of {}",
+                count, max
+            ),
+            GateFailure::Errors { count, max } => {
+                write!(f, "{} errors, over the maximum of {}", count, max)
+            }
+        }
+    }
+}
+/// Checks the [`RepositoryReview`] against the [`QualityGate`], in order: the RAG status, the critical and high security
+/// issues, then the errors, returning the first [`GateFailure`], or [`None`] if the review passed
+pub(crate) fn check_quality_gate(
+    quality_gate: &QualityGate,
+    review: &RepositoryReview,
+) -> Option<GateFailure> {
+    if quality_gate.fail_on_red && *review.get_repository_rag_status() == RAGStatus::Red {
+        return Some(GateFailure::Red);
+    }
+    let summary = review.summary.as_ref()?;
+    if let Some(max) = quality_gate.max_critical_high_security_issues {
+        let count = summary.security_issues.critical + summary.security_issues.high;
+        if count > max {
+            return Some(GateFailure::SecurityIssues { count, max });
+        }
+    }
+    if let Some(max) = quality_gate.max_errors {
+        if summary.errors > max {
+            return Some(GateFailure::Errors {
+                count: summary.errors,
+                max,
+            });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::data::{ReviewSummary, SecurityIssueBreakdown};
+
+    fn test_review(rag_status: RAGStatus, high: i32, errors: i32) -> RepositoryReview {
+        let mut review = RepositoryReview::new("repos_name".to_string());
+        review.repository_rag_status(rag_status);
+        review.summary(Some(ReviewSummary {
+            text: String::new(),
+            security_issues: SecurityIssueBreakdown {
+                low: 0,
+                medium: 0,
+                high,
+                critical: 0,
+                total: high,
+                ..Default::default()
+            },
+            errors,
+            improvements: 0,
+            documentation: None,
+            documentation_coverage: None,
+        }));
+        review
+    }
+
+    #[test]
+    fn test_check_quality_gate() {
+        let quality_gate = QualityGate {
+            fail_on_red: true,
+            max_critical_high_security_issues: Some(0),
+            max_errors: Some(10),
+        };
+        assert_eq!(
+            check_quality_gate(&quality_gate, &test_review(RAGStatus::Red, 1, 20)),
+            Some(GateFailure::Red)
+        );
+        let failure = check_quality_gate(&quality_gate, &test_review(RAGStatus::Amber, 1, 20));
+        assert_eq!(
+            failure,
+            Some(GateFailure::SecurityIssues { count: 1, max: 0 })
+        );
+        assert_eq!(failure.unwrap().exit_code(), SECURITY_ISSUES_EXIT_CODE);
+        assert_eq!(
+            check_quality_gate(&quality_gate, &test_review(RAGStatus::Amber, 0, 11)),
+            Some(GateFailure::Errors { count: 11, max: 10 })
+        );
+        assert_eq!(
+            check_quality_gate(&quality_gate, &test_review(RAGStatus::Green, 0, 10)),
+            None
+        );
+        assert_eq!(
+            check_quality_gate(
+                &QualityGate::default(),
+                &test_review(RAGStatus::Amber, 5, 50)
+            ),
+            None
+        );
+    }
+}
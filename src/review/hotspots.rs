@@ -0,0 +1,104 @@
+//! Hotspots: the files that change often, are large, and have findings, i.e., those to refactor first.
+//!
+//! A file's hotspot score is the product of its churn, i.e., the commits that changed it, its lines of code, and one
+//! plus its findings, so a file with no findings, e.g., in a static review, is still ranked by its churn and size. At
+//! function granularity, the units of a file are combined.
+use crate::retrieval::data::SourceFileInfo;
+use crate::review::data::{Hotspot, SourceFileReview};
+use std::collections::HashMap;
+
+/// The number of hotspots listed in the report
+const MAX_HOTSPOTS: usize = 10;
+
+/// Gets the [`MAX_HOTSPOTS`] files with the highest hotspot score, highest first. A file with no commits or lines of
+/// code, e.g., outside git, is not a hotspot.
+pub(crate) fn get_hotspots(
+    source_files: &[SourceFileInfo],
+    file_reviews: &[SourceFileReview],
+) -> Vec<Hotspot> {
+    let mut hotspots: HashMap<&str, Hotspot> = HashMap::new();
+    for file_info in source_files {
+        let hotspot = hotspots
+            .entry(&file_info.relative_path)
+            .or_insert_with(|| Hotspot {
+                relative_path: file_info.relative_path.clone(),
+                commits: 0,
+                loc: 0,
+                findings: 0,
+                score: 0,
+            });
+        // The units of a file each have the file's commits, and their own lines of code
+        hotspot.commits = hotspot.commits.max(file_info.statistics.num_commits);
+        hotspot.loc += file_info.statistics.loc;
+    }
+    for file_review in file_reviews {
+        if let Some(hotspot) = hotspots.get_mut(file_review.source_file_info.relative_path.as_str())
+        {
+            hotspot.findings += count_findings(file_review);
+        }
+    }
+    let mut hotspots: Vec<Hotspot> = hotspots
+        .into_values()
+        .filter_map(|mut hotspot| {
+            hotspot.score = hotspot.commits as i64 * hotspot.loc * (1 + hotspot.findings as i64);
+            (hotspot.score > 0).then_some(hotspot)
+        })
+        .collect();
+    hotspots.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.relative_path.cmp(&b.relative_path))
+    });
+    hotspots.truncate(MAX_HOTSPOTS);
+    hotspots
+}
+/// Counts the findings of the review, i.e., its security issues, errors and improvements
+fn count_findings(file_review: &SourceFileReview) -> i32 {
+    let security_issues = file_review.security_issues.as_ref().map_or(0, Vec::len);
+    let errors = file_review.errors.as_ref().map_or(0, Vec::len);
+    let improvements = file_review.improvements.as_ref().map_or(0, Vec::len);
+    (security_issues + errors + improvements) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retrieval::data::Statistics;
+
+    fn test_file_info(relative_path: &str, num_commits: i32, loc: i64) -> SourceFileInfo {
+        let mut statistics = Statistics::new();
+        statistics.num_commits = num_commits;
+        statistics.loc = loc;
+        SourceFileInfo::new_rust(relative_path, statistics)
+    }
+
+    #[test]
+    fn test_get_hotspots() {
+        let source_files = vec![
+            test_file_info("src/main.rs", 10, 100),
+            test_file_info("src/lib.rs", 2, 300),
+            test_file_info("src/lib.rs", 2, 100),
+            test_file_info("src/new.rs", 0, 500),
+        ];
+        let file_review: SourceFileReview = serde_json::from_str(
+            r#"{
+                "source_file_info": { "name": "lib.rs", "relative_path": "src/lib.rs", "id_hash": "0" },
+                "summary": "A summary",
+                "errors": [{ "code": "E1", "issue": "An issue", "resolution": "A resolution" }],
+                "improvements": [
+                    { "code": "I1", "suggestion": "A suggestion", "improvement_details": "Details" },
+                    { "code": "I2", "suggestion": "A suggestion", "improvement_details": "Details" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let hotspots = get_hotspots(&source_files, &[file_review]);
+        let scores: Vec<(&str, i64)> = hotspots
+            .iter()
+            .map(|hotspot| (hotspot.relative_path.as_str(), hotspot.score))
+            .collect();
+        // 'src/lib.rs' is split in two units, with 400 lines of code and 3 findings in all
+        assert_eq!(scores, vec![("src/lib.rs", 3200), ("src/main.rs", 1000)]);
+        assert_eq!(hotspots[0].findings, 3);
+    }
+}
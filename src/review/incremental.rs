@@ -0,0 +1,200 @@
+//! Incremental reviews: the [`SourceFileReview`]s of the last run are kept, with the `id_hash` of each file, so that the
+//! next run only sends new or changed files to the LLM, carrying forward the reviews of unchanged files.
+//!
+//! Unlike the [`super::cache::ReviewCache`], a review is carried forward regardless of a change to the prompt or model,
+//! and only the last run is kept, so files removed from the repository drop out.
+use crate::retrieval::data::SourceFileInfo;
+use crate::review::data::SourceFileReview;
+use crate::settings::Settings;
+use log::{info, warn};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+/// The location of the last run's reviews used if `last_review_path` is not set, relative to the working directory
+pub(crate) const DEFAULT_LAST_REVIEW_PATH: &str = ".cosmonaut/last_review.json";
+
+/// The [`SourceFileReview`]s of the last run, keyed by the relative path of the file, and its unit, if any
+#[derive(Debug)]
+pub(crate) struct LastReview {
+    path: PathBuf,
+    file_reviews: HashMap<String, SourceFileReview>,
+}
+impl LastReview {
+    /// Loads the reviews of the last run from the path, if any, otherwise starts with none
+    pub(crate) fn load(path: PathBuf) -> Self {
+        let file_reviews: Vec<SourceFileReview> = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                warn!("Ignoring invalid last review: {}: {}", path.display(), e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        info!(
+            "Incremental review: {} file reviews from the last run",
+            file_reviews.len()
+        );
+        Self {
+            path,
+            file_reviews: file_reviews
+                .into_iter()
+                .map(|file_review| (get_key(&file_review.source_file_info), file_review))
+                .collect(),
+        }
+    }
+    /// Loads the [`LastReview`], or [`None`] if `incremental_review` is not set in the [`Settings`]
+    pub(crate) fn from_settings(settings: &Settings) -> Option<Self> {
+        if !settings.incremental_review {
+            return None;
+        }
+        let path = settings
+            .last_review_path
+            .as_deref()
+            .unwrap_or(DEFAULT_LAST_REVIEW_PATH);
+        Some(Self::load(PathBuf::from(path)))
+    }
+    /// Gets the last run's review of the file, if the file is unchanged since, i.e., has the same `id_hash`.
+    /// A carried forward review has no [`crate::provider::api::TokenUsage`], as no request was made.
+    pub(crate) fn get(&self, file_info: &SourceFileInfo) -> Option<SourceFileReview> {
+        let file_review = self.file_reviews.get(&get_key(file_info))?;
+        if file_info.id_hash.is_none() || file_review.source_file_info.id_hash != file_info.id_hash
+        {
+            return None;
+        }
+        let mut file_review = file_review.clone();
+        file_review.usage = None;
+        Some(file_review)
+    }
+    /// Saves the reviews of this run, replacing those of the last
+    pub(crate) fn save(&self, file_reviews: &[SourceFileReview]) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string(file_reviews)?)?;
+        Ok(())
+    }
+}
+/// Gets the key of the file's review, i.e., its relative path, and the name and first line of its unit, if the file was
+/// split into units at function granularity, as every unit of a file has the same path, and units may share a name,
+/// e.g., methods of different types
+fn get_key(file_info: &SourceFileInfo) -> String {
+    match &file_info.code_unit {
+        Some(unit) => format!(
+            "{}#{}:{}",
+            file_info.relative_path, unit.name, unit.start_line
+        ),
+        None => file_info.relative_path.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retrieval::data::{LanguageType, Statistics};
+    use tempfile::tempdir;
+
+    fn test_file_info(id_hash: &str) -> SourceFileInfo {
+        SourceFileInfo::new(
+            "main.rs".to_string(),
+            "src/main.rs".to_string(),
+            LanguageType {
+                name: "Rust".to_string(),
+                extension: "rs".to_string(),
+                statistics: None,
+            },
+            id_hash.to_string(),
+            Statistics::new(),
+        )
+    }
+
+    #[test]
+    fn test_last_review_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("last_review.json");
+        let file_review: SourceFileReview = serde_json::from_str(
+            r#"{
+                "source_file_info": { "name": "main.rs", "relative_path": "src/main.rs", "id_hash": "abc123" },
+                "summary": "A summary",
+                "usage": { "prompt_tokens": 100, "completion_tokens": 50 }
+            }"#,
+        )
+        .unwrap();
+
+        let last_review = LastReview::load(path.clone());
+        assert!(last_review.get(&test_file_info("abc123")).is_none());
+        last_review.save(&[file_review]).unwrap();
+
+        let last_review = LastReview::load(path);
+        let carried_forward = last_review.get(&test_file_info("abc123")).unwrap();
+        assert_eq!(carried_forward.summary, "A summary");
+        assert_eq!(carried_forward.usage, None);
+        assert!(last_review.get(&test_file_info("def456")).is_none());
+    }
+
+    #[test]
+    fn test_last_review_of_units() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("last_review.json");
+        let file_reviews: Vec<SourceFileReview> = serde_json::from_str(
+            r#"[
+                {
+                    "source_file_info": {
+                        "name": "main.rs", "relative_path": "src/main.rs", "id_hash": "abc123",
+                        "code_unit": { "name": "main", "start_line": 1, "end_line": 3 }
+                    },
+                    "summary": "The main function"
+                },
+                {
+                    "source_file_info": {
+                        "name": "main.rs", "relative_path": "src/main.rs", "id_hash": "def456",
+                        "code_unit": { "name": "run", "start_line": 5, "end_line": 9 }
+                    },
+                    "summary": "The run function"
+                }
+            ]"#,
+        )
+        .unwrap();
+        LastReview::load(path.clone()).save(&file_reviews).unwrap();
+
+        let last_review = LastReview::load(path);
+        for file_review in &file_reviews {
+            let carried_forward = last_review.get(&file_review.source_file_info).unwrap();
+            assert_eq!(carried_forward.summary, file_review.summary);
+        }
+        assert!(last_review.get(&test_file_info("abc123")).is_none());
+    }
+
+    #[test]
+    fn test_last_review_of_units_with_same_name() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("last_review.json");
+        let file_reviews: Vec<SourceFileReview> = serde_json::from_str(
+            r#"[
+                {
+                    "source_file_info": {
+                        "name": "shapes.rs", "relative_path": "src/shapes.rs", "id_hash": "abc123",
+                        "code_unit": { "name": "area", "start_line": 3, "end_line": 5 }
+                    },
+                    "summary": "The area of a circle"
+                },
+                {
+                    "source_file_info": {
+                        "name": "shapes.rs", "relative_path": "src/shapes.rs", "id_hash": "def456",
+                        "code_unit": { "name": "area", "start_line": 9, "end_line": 11 }
+                    },
+                    "summary": "The area of a square"
+                }
+            ]"#,
+        )
+        .unwrap();
+        LastReview::load(path.clone()).save(&file_reviews).unwrap();
+
+        // Each unit is carried forward with its own review, neither replacing the other
+        let last_review = LastReview::load(path);
+        for file_review in &file_reviews {
+            let carried_forward = last_review.get(&file_review.source_file_info).unwrap();
+            assert_eq!(carried_forward.summary, file_review.summary);
+        }
+    }
+}
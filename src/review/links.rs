@@ -0,0 +1,157 @@
+//! Deep links from the findings to the code on the platform hosting the repository, i.e., GitHub or GitLab, so that a
+//! reviewer can go straight to the file and line of a finding at the commit reviewed.
+//!
+//! The platform is taken from the URL of the 'origin' remote, in any of its forms, e.g.,
+//! 'git@github.com:owner/repo.git', or 'https://gitlab.com/group/repo'. A finding is located as it is for attribution,
+//! i.e., by the first line of its code snippet; a finding whose code is not found is linked to its file.
+//!
+//! The links are to the code at the commit reviewed, so a file with uncommitted changes, or any file of a review of the
+//! staged contents, is not linked, as its code at the commit is not that reviewed.
+use crate::retrieval::notebook::is_notebook;
+use crate::review::attribution::find_code_line;
+use crate::review::data::SourceFileReview;
+use url::Url;
+
+/// The platforms hosting a repository to which findings are linked
+enum Platform {
+    GitHub,
+    GitLab,
+}
+impl Platform {
+    /// Gets the platform from the host of a remote's URL, e.g., 'github.com', or a self-hosted 'gitlab.example.com'
+    fn from_host(host: &str) -> Option<Self> {
+        let host: String = host.to_lowercase();
+        if host.contains("github") {
+            Some(Platform::GitHub)
+        } else if host.contains("gitlab") {
+            Some(Platform::GitLab)
+        } else {
+            None
+        }
+    }
+    /// The path segment, after the repository's path, of the URL of a file at a commit
+    fn blob_path(&self) -> &'static str {
+        match self {
+            Platform::GitHub => "blob",
+            Platform::GitLab => "-/blob",
+        }
+    }
+}
+
+/// Gets the URL of the files of the repository at the commit, e.g., 'https://github.com/owner/repo/blob/3f2a...', from
+/// the URL of its 'origin' remote, or [`None`] if it is not hosted on GitHub or GitLab
+pub(crate) fn get_source_url(origin_url: &str, head_sha: &str) -> Option<String> {
+    let origin_url: &str = origin_url.trim().trim_end_matches('/');
+    // Either a URL, e.g., 'https://github.com/owner/repo.git', or 'ssh://git@github.com:22/owner/repo.git', or the
+    // scp-like form, e.g., 'git@github.com:owner/repo.git'
+    let (authority, path): (&str, &str) = match origin_url.split_once("://") {
+        Some((_, rest)) => rest.split_once('/')?,
+        None => origin_url.split_once(':')?,
+    };
+    let host: &str = authority.rsplit('@').next()?.split(':').next()?;
+    let platform: Platform = Platform::from_host(host)?;
+    let path: &str = path.trim_end_matches(".git").trim_matches('/');
+    if path.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "https://{}/{}/{}/{}",
+        host,
+        path,
+        platform.blob_path(),
+        head_sha
+    ))
+}
+/// Sets the link of the [`SourceFileReview`] to its file, and of each finding to its line, where found, under the
+/// source URL. The lines of a notebook's code are not those of the file, so its findings are linked to the file.
+pub(crate) fn set_links(file_review: &mut SourceFileReview, source_url: &str) {
+    let relative_path: &str = &file_review.source_file_info.relative_path;
+    let Some(file_link) = get_file_link(source_url, relative_path) else {
+        return;
+    };
+    let contents: Option<String> = (!is_notebook(relative_path))
+        .then(|| file_review.source_file_info.get_source_file_contents());
+    // The lines of a unit, at function granularity, are from its start line in the file
+    let line_offset: usize = file_review
+        .source_file_info
+        .code_unit
+        .as_ref()
+        .map_or(0, |unit| unit.start_line - 1);
+    let get_link = |code: &str| -> Option<String> {
+        let line: Option<usize> = contents
+            .as_deref()
+            .and_then(|contents| find_code_line(contents, code));
+        Some(match line {
+            Some(line) => format!("{}#L{}", file_link, line + line_offset),
+            None => file_link.clone(),
+        })
+    };
+    for security_issue in file_review.security_issues.iter_mut().flatten() {
+        security_issue.link = get_link(&security_issue.code);
+    }
+    for error in file_review.errors.iter_mut().flatten() {
+        error.link = get_link(&error.code);
+    }
+    for improvement in file_review.improvements.iter_mut().flatten() {
+        improvement.link = get_link(&improvement.code);
+    }
+    file_review.link = Some(file_link);
+}
+/// Gets the URL of the file under the source URL, with each segment of its relative path percent-encoded, e.g., a '#'
+/// or '?' in a file name, which would otherwise end the path
+fn get_file_link(source_url: &str, relative_path: &str) -> Option<String> {
+    let mut file_link: Url = Url::parse(source_url).ok()?;
+    file_link
+        .path_segments_mut()
+        .ok()?
+        .extend(relative_path.split(['/', '\\']));
+    Some(file_link.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHA: &str = "3f2a1b";
+
+    #[test]
+    fn test_get_source_url() {
+        for origin_url in [
+            "git@github.com:cosmonaut-nz/cosmonaut-code.git",
+            "https://github.com/cosmonaut-nz/cosmonaut-code.git",
+            "https://user@github.com/cosmonaut-nz/cosmonaut-code/",
+            "ssh://git@github.com:22/cosmonaut-nz/cosmonaut-code.git",
+        ] {
+            assert_eq!(
+                get_source_url(origin_url, SHA),
+                Some("https://github.com/cosmonaut-nz/cosmonaut-code/blob/3f2a1b".to_string()),
+                "{}",
+                origin_url
+            );
+        }
+        assert_eq!(
+            get_source_url("git@gitlab.example.com:group/sub/repo.git", SHA),
+            Some("https://gitlab.example.com/group/sub/repo/-/blob/3f2a1b".to_string())
+        );
+        assert_eq!(
+            get_source_url("https://bitbucket.org/owner/repo.git", SHA),
+            None
+        );
+        assert_eq!(get_source_url("/srv/git/repo.git", SHA), None);
+    }
+
+    #[test]
+    fn test_get_file_link() {
+        let source_url = "https://github.com/cosmonaut-nz/cosmonaut-code/blob/3f2a1b";
+        assert_eq!(
+            get_file_link(source_url, "src/review/mod.rs").as_deref(),
+            Some("https://github.com/cosmonaut-nz/cosmonaut-code/blob/3f2a1b/src/review/mod.rs")
+        );
+        assert_eq!(
+            get_file_link(source_url, "docs\\a #1?%.md").as_deref(),
+            Some(
+                "https://github.com/cosmonaut-nz/cosmonaut-code/blob/3f2a1b/docs/a%20%231%3F%25.md"
+            )
+        );
+    }
+}
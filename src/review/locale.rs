@@ -0,0 +1,179 @@
+//! The headings and labels of the HTML report in the `report_locale`, e.g., 'de', so that non-English stakeholders can
+//! read it. Each label of the built-in templates is looked up by its English text with the `t` helper, e.g.,
+//! `{{t "File reviews"}}`, and is left in English if the locale has none. A '{}' in a label is replaced by the next
+//! parameter of the helper, e.g., `{{t "Over the last {} reviews" trends.length}}`.
+//!
+//! The built-in locales are 'de', 'fr' and 'es'. The labels of any other locale, or overrides of those built in, are
+//! read from the `report_locale_path`, a JSON file of the same form, i.e., `{"language": "...", "labels": {...}}`.
+use handlebars::{
+    html_escape, Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+
+/// The built-in locales, as (locale, compiled in labels)
+const BUILT_IN_LOCALES: [(&str, &str); 3] = [
+    ("de", include_str!("./locales/de.json")),
+    ("fr", include_str!("./locales/fr.json")),
+    ("es", include_str!("./locales/es.json")),
+];
+
+/// The labels of the HTML report in a locale, registered as the `t` Handlebars helper
+///
+/// #Fields:
+/// * `language` - The name of the language of the locale, e.g., 'German', in which the LLM may write the summaries
+/// * `labels` - The label in the locale of each English label
+#[derive(Deserialize, Debug, Default, Clone)]
+pub(crate) struct Locale {
+    #[serde(default)]
+    pub(crate) language: Option<String>,
+    #[serde(default)]
+    labels: HashMap<String, String>,
+}
+impl Locale {
+    /// Gets the label in the locale, or the English label if the locale has none
+    fn get_label<'a>(&'a self, label: &'a str) -> &'a str {
+        self.labels.get(label).map_or(label, String::as_str)
+    }
+}
+impl HelperDef for Locale {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let label = h.param(0).and_then(|v| v.value().as_str()).unwrap_or("");
+        let mut label: String = self.get_label(label).to_string();
+        for param in h.params().iter().skip(1) {
+            let value: String = match param.value() {
+                serde_json::Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+            label = label.replacen("{}", &value, 1);
+        }
+        write!(out, "{}", html_escape(&label))?;
+        Ok(())
+    }
+}
+
+/// Gets the [`Locale`] of the `report_locale`, i.e., its built-in labels, if any, overridden by those in the
+/// `report_locale_path`, if set, or English, if neither is set
+pub(crate) fn get_locale(
+    report_locale: Option<&str>,
+    report_locale_path: Option<&str>,
+) -> Result<Locale, Box<dyn Error>> {
+    let built_in: Option<&str> = report_locale.and_then(|report_locale| {
+        // A regional locale, e.g., 'de-AT', has the labels of its language
+        let language: &str = report_locale.split(['-', '_']).next().unwrap_or_default();
+        BUILT_IN_LOCALES
+            .iter()
+            .find(|(locale, _)| locale.eq_ignore_ascii_case(language))
+            .map(|(_, labels)| *labels)
+    });
+    let mut locale: Locale = match built_in {
+        Some(labels) => serde_json::from_str(labels)?,
+        None => Locale::default(),
+    };
+    match report_locale_path {
+        Some(report_locale_path) => {
+            let overrides: Locale = serde_json::from_str(
+                &std::fs::read_to_string(report_locale_path)
+                    .map_err(|e| format!("Failed to read locale: {}: {}", report_locale_path, e))?,
+            )
+            .map_err(|e| format!("Invalid locale: {}: {}", report_locale_path, e))?;
+            if overrides.language.is_some() {
+                locale.language = overrides.language;
+            }
+            locale.labels.extend(overrides.labels);
+        }
+        None => {
+            if let (Some(report_locale), None) = (report_locale, built_in) {
+                return Err(format!(
+                    "The report_locale is not built in, so needs a report_locale_path: {}",
+                    report_locale
+                )
+                .into());
+            }
+        }
+    }
+    Ok(locale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_get_locale() {
+        assert!(get_locale(None, None).unwrap().labels.is_empty());
+        let locale = get_locale(Some("de-AT"), None).unwrap();
+        assert_eq!(locale.language.as_deref(), Some("German"));
+        assert_eq!(locale.get_label("File reviews"), "Dateiprüfungen");
+        assert_eq!(locale.get_label("Not a label"), "Not a label");
+        assert!(get_locale(Some("mi"), None).is_err());
+
+        let dir = tempdir().unwrap();
+        let report_locale_path = dir.path().join("mi.json");
+        std::fs::write(
+            &report_locale_path,
+            r#"{"language": "Māori", "labels": {"Over the last {} reviews": "I ngā arotake {} kua hipa"}}"#,
+        )
+        .unwrap();
+        let locale = get_locale(Some("mi"), report_locale_path.to_str()).unwrap();
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("t", Box::new(locale));
+        let render = |template: &str| handlebars.render_template(template, &()).unwrap();
+        assert_eq!(
+            render(r#"{{t "Over the last {} reviews" 5}}"#),
+            "I ngā arotake 5 kua hipa"
+        );
+        assert_eq!(render(r#"{{t "Top risks"}}"#), "Top risks");
+    }
+    #[test]
+    fn test_built_in_locales_have_every_label() {
+        let templates = [
+            include_str!("./templates/report_template.html"),
+            include_str!("./templates/report_template_executive.html"),
+            include_str!("./templates/site_index_template.html"),
+            include_str!("./templates/site_directory_template.html"),
+            include_str!("./templates/site_file_template.html"),
+            include_str!("./templates/delta_template.html"),
+        ];
+        let label_regex = regex::Regex::new(r#"\{\{t "([^"]*)""#).unwrap();
+        for (name, _) in BUILT_IN_LOCALES {
+            let locale = get_locale(Some(name), None).unwrap();
+            for template in templates {
+                for captures in label_regex.captures_iter(template) {
+                    assert!(
+                        locale.labels.contains_key(&captures[1]),
+                        "{} has no label: {}",
+                        name,
+                        &captures[1]
+                    );
+                }
+            }
+            // The values labelled by the templates, e.g., `{{t rag_status}}`
+            for label in [
+                "Red",
+                "Amber",
+                "Green",
+                "Improved",
+                "NotReviewed",
+                "Security issue",
+                "Excellent",
+            ] {
+                assert!(
+                    locale.labels.contains_key(label),
+                    "{} has no label: {}",
+                    name,
+                    label
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,552 @@
+//! Merges the JSON reports of partial reviews of a repository, e.g., of each sub-project of a monorepo, or each shard
+//! of its files in CI, into one consolidated [`RepositoryReview`], e.g., `cosmonaut_code merge a.json b.json`.
+//!
+//! The file reviews of the reports are combined, a file reviewed in more than one report keeping its review from the
+//! last, and the statistics, summary, languages, cost and RAG status are recomputed from those of the reports. A report
+//! whose every file is reviewed again in a later one, e.g., a shard merged twice, is superseded, so the sections of its
+//! files, e.g., its test code, contributor findings, complex files and summary text, are not counted again. The
+//! sections of the repository as a whole, e.g., its contributors, ownership and duplication, cannot be recomputed
+//! without the repository, so are taken from the first report that has them, except its trends, which are taken from
+//! the newest report, as they run up to it.
+use crate::provider::cost::CostBreakdown;
+use crate::retrieval::data::{LanguageType, Statistics};
+use crate::retrieval::documentation::DocumentationCoverage;
+use crate::retrieval::test_files::TestStatistics;
+use crate::review::data::{ContributorFindings, RepositoryReview, ReviewSummary, SourceFileReview};
+use crate::review::directories::get_directory_summaries;
+use crate::review::findings::get_findings;
+use crate::review::trends::TrendPoint;
+use crate::review::{get_overall_rag_for, get_review_date, REVIEW_DATE_FORMAT};
+use chrono::NaiveDateTime;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+
+/// Loads the JSON reports and merges them, in order, into one [`RepositoryReview`]
+pub(crate) fn merge_reports(paths: &[String]) -> Result<RepositoryReview, Box<dyn Error>> {
+    if paths.is_empty() {
+        return Err("No reports to merge".into());
+    }
+    let reviews: Vec<RepositoryReview> = paths
+        .iter()
+        .map(|path| load_report(path))
+        .collect::<Result<_, _>>()?;
+    Ok(merge(reviews))
+}
+fn load_report(path: &str) -> Result<RepositoryReview, Box<dyn Error>> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read the report: {}: {}", path, e))?;
+    serde_json::from_str(&contents)
+        .map_err(|e| format!("The report is not a JSON report: {}: {}", path, e).into())
+}
+/// Merges the reviews, of which there is at least one, named as the first
+fn merge(mut reviews: Vec<RepositoryReview>) -> RepositoryReview {
+    let mut merged: RepositoryReview = RepositoryReview::new(reviews[0].repository_name.clone());
+    let superseded: Vec<bool> = get_superseded(&reviews);
+    merged.file_reviews = merge_file_reviews(&mut reviews);
+    // The reviews counted in the sums of the sections that cannot be recomputed from the merged file reviews
+    let counted: Vec<&RepositoryReview> = reviews
+        .iter()
+        .zip(&superseded)
+        .filter(|(_, superseded)| !**superseded)
+        .map(|(review, _)| review)
+        .collect();
+    merged.statistics = merge_statistics(&reviews, &counted, &merged.file_reviews);
+
+    let mut services: Vec<String> = Vec::new();
+    for service in reviews
+        .iter()
+        .filter_map(|review| review.generative_ai_service_and_model.clone())
+    {
+        if !services.contains(&service) {
+            services.push(service);
+        }
+    }
+    merged.generative_ai_service_and_model((!services.is_empty()).then(|| services.join(", ")));
+    merged.repository_purpose(
+        reviews
+            .iter()
+            .find_map(|review| review.get_repository_purpose().map(str::to_string)),
+    );
+    merged.head_sha(
+        reviews
+            .iter()
+            .find_map(|review| review.get_head_sha().map(str::to_string)),
+    );
+    merged.source_url(
+        reviews
+            .iter()
+            .find_map(|review| review.get_source_url().map(str::to_string)),
+    );
+
+    let mut language_types: Vec<LanguageType> = merge_language_types(&merged.file_reviews);
+    LanguageType::calculate_percentage_distribution(&mut language_types);
+    merged.repository_type(Some(LanguageType::get_predominant_language(
+        &language_types,
+    )));
+    merged.language_types(language_types);
+    merged.cost(merge_cost(&reviews));
+    merged.contributors(
+        reviews
+            .iter()
+            .map(|review| review.get_contributors())
+            .find(|contributors| !contributors.is_empty())
+            .unwrap_or_default()
+            .to_vec(),
+    );
+    merged.contributor_findings(merge_contributor_findings(&counted));
+    merged.trends(get_newest_trends(&reviews));
+
+    // The sections of the repository as a whole, then those of each counted report's files, in the order of the reports
+    merged.duplication(reviews.iter().find_map(|review| review.duplication.clone()));
+    merged.ownership(reviews.iter().find_map(|review| review.ownership.clone()));
+    merged.markers(reviews.iter().find_map(|review| review.markers.clone()));
+    merged.licensing(reviews.iter().find_map(|review| review.licensing.clone()));
+    merged.commit_activity(
+        reviews
+            .iter()
+            .find_map(|review| review.commit_activity.clone()),
+    );
+    merged.architecture_review(concat(counted.iter().copied(), |review| {
+        &review.architecture_review
+    }));
+    merged.untested_files(concat(counted.iter().copied(), |review| {
+        &review.untested_files
+    }));
+    merged.remediation(concat(counted.iter().copied(), |review| {
+        &review.remediation
+    }));
+    merged.dependency_review(concat(&reviews, |review| &review.dependency_review));
+    merged.dependencies(concat(&reviews, |review| &review.dependencies).map(dedup));
+    merged.dependency_vulnerabilities(
+        concat(&reviews, |review| &review.dependency_vulnerabilities).map(dedup),
+    );
+    let mut complex_files = concat(counted.iter().copied(), |review| &review.complex_files);
+    if let Some(complex_files) = &mut complex_files {
+        complex_files.sort_by(|a, b| b.complexity.cmp(&a.complexity));
+    }
+    merged.complex_files(complex_files);
+    let mut hotspots = concat(counted.iter().copied(), |review| &review.hotspots);
+    if let Some(hotspots) = &mut hotspots {
+        hotspots.sort_by(|a, b| b.score.cmp(&a.score));
+    }
+    merged.hotspots(hotspots);
+    merged.directories(Some(get_directory_summaries(&merged.file_reviews)));
+    merged.findings(Some(get_findings(&merged.file_reviews)));
+
+    merged.summary(Some(merge_summary(&counted, &merged)));
+    merged.date(get_review_date());
+    merged.repository_rag_status(get_overall_rag_for(&merged));
+    merged
+}
+/// Gets whether each review is superseded, i.e., it has file reviews, and every file of them is reviewed again in a
+/// later review
+fn get_superseded(reviews: &[RepositoryReview]) -> Vec<bool> {
+    let mut later_paths: HashSet<&str> = HashSet::new();
+    let mut superseded: Vec<bool> = Vec::with_capacity(reviews.len());
+    for review in reviews.iter().rev() {
+        let relative_paths: Vec<&str> = review
+            .file_reviews
+            .iter()
+            .map(|file_review| file_review.source_file_info.relative_path.as_str())
+            .collect();
+        superseded.push(
+            !relative_paths.is_empty()
+                && relative_paths
+                    .iter()
+                    .all(|relative_path| later_paths.contains(relative_path)),
+        );
+        later_paths.extend(relative_paths);
+    }
+    superseded.reverse();
+    superseded
+}
+/// Gets the trends of the newest review, by its date, or of the last given, if as new, as each review's trends run up
+/// to it
+fn get_newest_trends(reviews: &[RepositoryReview]) -> Option<Vec<TrendPoint>> {
+    reviews
+        .iter()
+        .filter(|review| review.trends.is_some())
+        .max_by_key(|review| {
+            NaiveDateTime::parse_from_str(review.get_date(), REVIEW_DATE_FORMAT).ok()
+        })
+        .and_then(|review| review.trends.clone())
+}
+/// Takes the file reviews of each review, a file, or unit of a file, reviewed more than once keeping its last review
+fn merge_file_reviews(reviews: &mut [RepositoryReview]) -> Vec<SourceFileReview> {
+    let mut file_reviews: Vec<SourceFileReview> = Vec::new();
+    let mut positions: HashMap<(String, Option<usize>), usize> = HashMap::new();
+    for review in reviews.iter_mut() {
+        for file_review in std::mem::take(&mut review.file_reviews) {
+            let key = (
+                file_review.source_file_info.relative_path.clone(),
+                file_review
+                    .source_file_info
+                    .code_unit
+                    .as_ref()
+                    .map(|unit| unit.start_line),
+            );
+            match positions.get(&key) {
+                Some(&position) => file_reviews[position] = file_review,
+                None => {
+                    positions.insert(key, file_reviews.len());
+                    file_reviews.push(file_review);
+                }
+            }
+        }
+    }
+    file_reviews
+}
+/// Gets the [`Statistics`] of the merged file reviews, a file, even if reviewed by unit, counted once, with the test
+/// code of the reviews counted, and the commits and token usage of all of the reviews, the commits being those of the
+/// same repository
+fn merge_statistics(
+    reviews: &[RepositoryReview],
+    counted: &[&RepositoryReview],
+    file_reviews: &[SourceFileReview],
+) -> Statistics {
+    let mut statistics: Statistics = Statistics::new();
+    let mut relative_paths: HashSet<&str> = HashSet::new();
+    for file_info in file_reviews
+        .iter()
+        .map(|file_review| &file_review.source_file_info)
+    {
+        if relative_paths.insert(&file_info.relative_path) {
+            statistics.size += file_info.statistics.size;
+            statistics.loc += file_info.statistics.loc;
+            statistics.num_files += 1;
+        }
+    }
+    statistics.test_loc = counted
+        .iter()
+        .map(|review| review.statistics.test_loc)
+        .sum();
+    for review in reviews {
+        statistics.num_commits = statistics.num_commits.max(review.statistics.num_commits);
+        if let Some(token_usage) = &review.statistics.token_usage {
+            statistics
+                .token_usage
+                .get_or_insert_with(Default::default)
+                .add(token_usage);
+        }
+    }
+    statistics.test_ratio = TestStatistics {
+        test_loc: statistics.test_loc,
+        production_loc: statistics.loc - statistics.test_loc,
+        untested_files: Vec::new(),
+    }
+    .get_test_ratio();
+    statistics
+}
+/// Gets the [`LanguageType`]s of the merged file reviews, with the [`Statistics`] of their files, a file, even if
+/// reviewed by unit, counted once, as in the [`Statistics`] of the merged review
+fn merge_language_types(file_reviews: &[SourceFileReview]) -> Vec<LanguageType> {
+    let mut language_types: Vec<LanguageType> = Vec::new();
+    let mut relative_paths: HashSet<&str> = HashSet::new();
+    for file_info in file_reviews
+        .iter()
+        .map(|file_review| &file_review.source_file_info)
+    {
+        let Some(language) = &file_info.language else {
+            continue;
+        };
+        if !relative_paths.insert(&file_info.relative_path) {
+            continue;
+        }
+        let position = match language_types
+            .iter()
+            .position(|existing| existing.name == language.name)
+        {
+            Some(position) => position,
+            None => {
+                language_types.push(LanguageType {
+                    statistics: None,
+                    ..language.clone()
+                });
+                language_types.len() - 1
+            }
+        };
+        let statistics = language_types[position]
+            .statistics
+            .get_or_insert_with(Statistics::new);
+        statistics.size += file_info.statistics.size;
+        statistics.loc += file_info.statistics.loc;
+        statistics.num_files += 1;
+    }
+    language_types
+}
+/// Sums the [`CostBreakdown`]s of the reviews, if any has one
+fn merge_cost(reviews: &[RepositoryReview]) -> Option<CostBreakdown> {
+    let mut merged: Option<CostBreakdown> = None;
+    for cost in reviews.iter().filter_map(|review| review.cost.as_ref()) {
+        merged.get_or_insert_with(CostBreakdown::new).merge(cost);
+    }
+    merged
+}
+/// Sums the [`ContributorFindings`] of the reviews, by contributor, most findings first
+fn merge_contributor_findings(reviews: &[&RepositoryReview]) -> Option<Vec<ContributorFindings>> {
+    let mut merged: Vec<ContributorFindings> = Vec::new();
+    for findings in concat(reviews.iter().copied(), |review| {
+        &review.contributor_findings
+    })? {
+        match merged
+            .iter_mut()
+            .find(|existing| existing.contributor == findings.contributor)
+        {
+            Some(existing) => {
+                existing.security_issues += findings.security_issues;
+                existing.errors += findings.errors;
+                existing.improvements += findings.improvements;
+                existing.total += findings.total;
+            }
+            None => merged.push(findings),
+        }
+    }
+    merged.sort_by(|a, b| b.total.cmp(&a.total));
+    Some(merged)
+}
+/// Recounts the findings of the merged review's files and dependencies, with the summary text, and documentation
+/// coverage, of each counted review
+fn merge_summary(reviews: &[&RepositoryReview], merged: &RepositoryReview) -> ReviewSummary {
+    let mut summary: ReviewSummary = ReviewSummary {
+        text: String::new(),
+        security_issues: Default::default(),
+        errors: 0,
+        improvements: 0,
+        documentation: None,
+        documentation_coverage: None,
+    };
+    for file_review in &merged.file_reviews {
+        summary.errors += file_review.errors.as_ref().map_or(0, Vec::len) as i32;
+        summary.improvements += file_review.improvements.as_ref().map_or(0, Vec::len) as i32;
+        for security_issue in file_review.security_issues.iter().flatten() {
+            summary.security_issues.add(&security_issue.severity);
+            summary.security_issues.add_categories(security_issue);
+        }
+    }
+    for vulnerability in merged.dependency_vulnerabilities.iter().flatten() {
+        summary.security_issues.add(&vulnerability.severity);
+    }
+    summary.text = reviews
+        .iter()
+        .filter_map(|review| review.summary.as_ref())
+        .map(|review_summary| review_summary.text.trim())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<&str>>()
+        .join("\n\n");
+
+    let mut coverage: Option<DocumentationCoverage> = None;
+    for review_coverage in reviews.iter().filter_map(|review| {
+        review
+            .summary
+            .as_ref()
+            .and_then(|review_summary| review_summary.documentation_coverage.as_ref())
+    }) {
+        let coverage = coverage.get_or_insert_with(|| DocumentationCoverage {
+            language: None,
+            public_items: 0,
+            documented_items: 0,
+            doc_files: 0,
+            languages: Vec::new(),
+        });
+        coverage.public_items += review_coverage.public_items;
+        coverage.documented_items += review_coverage.documented_items;
+        coverage.doc_files = coverage.doc_files.max(review_coverage.doc_files);
+        coverage
+            .languages
+            .extend(review_coverage.languages.iter().cloned());
+    }
+    summary.documentation = coverage
+        .as_ref()
+        .map(DocumentationCoverage::get_documentation);
+    summary.documentation_coverage = coverage;
+    summary
+}
+/// Concatenates a section of the reviews, or [`None`] if none of them has it
+fn concat<'a, T: Clone + 'a>(
+    reviews: impl IntoIterator<Item = &'a RepositoryReview>,
+    section: impl Fn(&'a RepositoryReview) -> &'a Option<Vec<T>>,
+) -> Option<Vec<T>> {
+    reviews
+        .into_iter()
+        .filter_map(|review| section(review).as_ref())
+        .fold(None, |merged: Option<Vec<T>>, items| {
+            let mut merged = merged.unwrap_or_default();
+            merged.extend(items.iter().cloned());
+            Some(merged)
+        })
+}
+/// Removes the repeats of items, e.g., the dependencies found by more than one review, keeping the first
+fn dedup<T: PartialEq>(items: Vec<T>) -> Vec<T> {
+    let mut unique: Vec<T> = Vec::with_capacity(items.len());
+    for item in items {
+        if !unique.contains(&item) {
+            unique.push(item);
+        }
+    }
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retrieval::data::SourceFileInfo;
+    use crate::review::data::{
+        ArchitectureFinding, ComplexFile, Error as ReviewError, Hotspot, RAGStatus, SecurityIssue,
+        Severity,
+    };
+
+    fn test_review(relative_paths: &[&str], loc: i64) -> RepositoryReview {
+        let mut review = RepositoryReview::new("repos_name".to_string());
+        for relative_path in relative_paths {
+            let mut statistics = Statistics::new();
+            statistics.loc = loc;
+            review.add_source_file_review(SourceFileReview::new(
+                SourceFileInfo::new_rust(relative_path, statistics),
+                "A summary".to_string(),
+            ));
+        }
+        review
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut backend = test_review(&["backend/main.rs", "src/lib.rs"], 10);
+        backend.file_reviews[0].errors = Some(vec![ReviewError {
+            code: "parse().unwrap()".to_string(),
+            issue: "Unchecked unwrap".to_string(),
+            resolution: "Handle it".to_string(),
+            fingerprint: None,
+            link: None,
+        }]);
+        let mut frontend = test_review(&["frontend/app.rs", "src/lib.rs"], 10);
+        frontend.file_reviews[1].security_issues = Some(vec![SecurityIssue {
+            severity: Severity::High,
+            code: "query(input)".to_string(),
+            threat: "SQL injection".to_string(),
+            mitigation: "Bind the input".to_string(),
+            cwe: None,
+            owasp: None,
+            fingerprint: None,
+            link: None,
+        }]);
+
+        let merged = merge(vec![backend, frontend]);
+        assert_eq!(
+            merged
+                .file_reviews
+                .iter()
+                .map(|file_review| file_review.source_file_info.relative_path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["backend/main.rs", "src/lib.rs", "frontend/app.rs"]
+        );
+        // The file in both reports keeps its review from the last
+        assert!(merged.file_reviews[1].security_issues.is_some());
+        assert_eq!(
+            (merged.statistics.loc, merged.statistics.num_files),
+            (30, 3)
+        );
+        let summary = merged.summary.as_ref().unwrap();
+        assert_eq!((summary.errors, summary.security_issues.high), (1, 1));
+        assert_eq!(*merged.get_repository_rag_status(), RAGStatus::Red);
+    }
+
+    #[test]
+    fn test_merge_superseded() {
+        let shard = |date: &str, trend_date: &str| {
+            let mut review = test_review(&["backend/main.rs", "backend/tests/main.rs"], 10);
+            review.statistics.test_loc = 10;
+            let mut statistics = Statistics::new();
+            statistics.loc = 20;
+            statistics.num_files = 2;
+            review.language_types(vec![LanguageType {
+                name: "Rust".to_string(),
+                extension: "rs".to_string(),
+                statistics: Some(statistics),
+            }]);
+            review.contributor_findings(Some(vec![ContributorFindings {
+                contributor: "Ana Smith".to_string(),
+                security_issues: 0,
+                errors: 1,
+                improvements: 0,
+                total: 1,
+            }]));
+            review.date(date.to_string());
+            review.trends(Some(vec![serde_json::from_str(&format!(
+                r#"{{"date": "{}", "rag_status": "Green", "critical": 0, "high": 0, "security_issues": 0,
+                    "errors": 1, "improvements": 0, "loc": 20}}"#,
+                trend_date
+            ))
+            .unwrap()]));
+            review.summary(Some(
+                serde_json::from_str(
+                    r#"{"text": "The backend is well structured.", "security_issues": { "low": 0, "medium": 0,
+                    "high": 0, "critical": 0, "total": 0 }, "errors": 1, "improvements": 0, "documentation": null,
+                    "documentation_coverage": { "public_items": 10, "documented_items": 5, "doc_files": 1 }}"#,
+                )
+                .unwrap(),
+            ));
+            review.architecture_review(Some(vec![ArchitectureFinding {
+                category: "Coupling".to_string(),
+                finding: "The tests reach into the backend".to_string(),
+                recommendation: "Test through its API".to_string(),
+                files: vec!["backend/tests/main.rs".to_string()],
+            }]));
+            review.untested_files(Some(vec!["backend/main.rs".to_string()]));
+            review.remediation(Some(
+                serde_json::from_str(
+                    r#"[{"relative_path": "backend/main.rs", "previous_rag_status": "Amber", "rag_status": "Green",
+                    "previous_security_issues": { "low": 0, "medium": 0, "high": 0, "critical": 0, "total": 0 },
+                    "security_issues": { "low": 0, "medium": 0, "high": 0, "critical": 0, "total": 0 },
+                    "previous_errors": 2, "errors": 1, "outcome": "Improved"}]"#,
+                )
+                .unwrap(),
+            ));
+            review.complex_files(Some(vec![ComplexFile {
+                relative_path: "backend/main.rs".to_string(),
+                complexity: 30,
+                loc: 10,
+            }]));
+            review.hotspots(Some(vec![Hotspot {
+                relative_path: "backend/main.rs".to_string(),
+                commits: 5,
+                loc: 10,
+                findings: 1,
+                score: 100,
+            }]));
+            review
+        };
+        // The same shard merged twice, the newer given first, with another shard
+        let merged = merge(vec![
+            shard("09:00, 02/02/2024", "newest"),
+            shard("09:00, 01/02/2024", "oldest"),
+            test_review(&["frontend/app.rs"], 10),
+        ]);
+        assert_eq!(
+            (
+                merged.statistics.loc,
+                merged.statistics.num_files,
+                merged.statistics.test_loc
+            ),
+            (30, 3, 10)
+        );
+        // The languages are those of the merged files, not the sums of those of the reports
+        let language_statistics = merged.get_language_types()[0].statistics.as_ref().unwrap();
+        assert_eq!(
+            (language_statistics.loc, language_statistics.num_files),
+            (30, 3)
+        );
+        assert_eq!(merged.contributor_findings.as_ref().unwrap()[0].total, 1);
+        // The sections of the superseded shard's files are not repeated
+        assert_eq!(merged.architecture_review.as_ref().unwrap().len(), 1);
+        assert_eq!(merged.untested_files.as_ref().unwrap().len(), 1);
+        assert_eq!(merged.remediation.as_ref().unwrap().len(), 1);
+        assert_eq!(merged.complex_files.as_ref().unwrap().len(), 1);
+        assert_eq!(merged.hotspots.as_ref().unwrap().len(), 1);
+        let summary = merged.summary.as_ref().unwrap();
+        assert_eq!(summary.text, "The backend is well structured.");
+        let coverage = summary.documentation_coverage.as_ref().unwrap();
+        assert_eq!((coverage.public_items, coverage.documented_items), (10, 5));
+        assert_eq!(merged.trends.as_ref().unwrap()[0].date, "newest");
+    }
+}
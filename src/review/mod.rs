@@ -6,30 +6,134 @@
 //! Produces a human readable report.
 // TODO Complete refactor! The file is hard to manage, and oftentimes does not meet DRY or SOLID principles
 //      refactor extract non-review aspects into other modules.
+pub(crate) mod anonymise;
+pub(crate) mod architecture;
+pub(crate) mod attribution;
+pub(crate) mod baseline;
+pub(crate) mod benchmark;
+pub(crate) mod cache;
+pub(crate) mod checkpoint;
+pub(crate) mod contributors;
 pub(crate) mod data;
+pub(crate) mod delta;
+pub(crate) mod dependencies;
+pub(crate) mod directories;
+pub(crate) mod dry_run;
+pub(crate) mod executive;
+pub(crate) mod feedback;
+pub(crate) mod findings;
+pub(crate) mod gate;
+pub(crate) mod hotspots;
+pub(crate) mod incremental;
+pub(crate) mod links;
+pub(crate) mod locale;
+pub(crate) mod merge;
+pub(crate) mod normalise;
+pub(crate) mod notify;
+pub(crate) mod ownership;
+pub(crate) mod progress;
+pub(crate) mod remediation;
 pub(crate) mod report;
+pub(crate) mod schema;
+pub(crate) mod site;
+pub(crate) mod suppression;
+pub(crate) mod trends;
 use crate::provider::api::ProviderCompletionResponse;
-use crate::provider::prompts::PromptData;
-use crate::provider::{get_provider, get_service_and_model, review_or_summarise, RequestType};
+use crate::provider::cost::{log_cost_breakdown, take_cost_breakdown};
+use crate::provider::models::{trim_to_context_window, warn_if_exceeds_context_window};
+use crate::provider::prompts::{estimate_tokens, PromptData};
+use crate::provider::retry::RetryPolicy;
+use crate::provider::scheduler::{schedule, ScheduledProvider};
+use crate::provider::{
+    get_provider, get_service, get_service_and_model, review_or_summarise,
+    review_or_summarise_with_service, RequestType,
+};
 use crate::retrieval::code::{
-    analyse_file_language, calculate_hash_from, calculate_rag_status_for_reviewed_file,
+    analyse_file_language, calculate_complexity, calculate_hash_from,
+    calculate_rag_status_for_reviewed_file, count_lines_of_code,
+};
+use crate::retrieval::context::RepositoryContext;
+use crate::retrieval::coverage::Coverage;
+use crate::retrieval::data::{LanguageType, SourceFileError, SourceFileInfo, Statistics};
+use crate::retrieval::dependencies::{get_dependencies, DEPENDENCY_MANIFESTS};
+use crate::retrieval::documentation::{
+    get_documentation_coverage, is_documentation_file, read_readme, DocumentationCoverage,
+};
+use crate::retrieval::duplication::detect_duplication;
+use crate::retrieval::git::blame::{get_author_lines, get_file_owners, AuthorLines, FileOwner};
+use crate::retrieval::git::contributor::get_git_contributors;
+use crate::retrieval::git::diff::{
+    get_changed_files, get_commit_range, get_files_changed_since, get_staged_contents,
+    get_staged_files, get_uncommitted_files,
+};
+use crate::retrieval::git::repository::{
+    get_commit_activity, get_head_sha, get_origin_url, get_total_commits, get_unignored_files,
+    is_bare_repository, open_repository,
 };
-use crate::retrieval::data::{LanguageType, SourceFileInfo, Statistics};
-use crate::retrieval::git::repository::{get_blacklist_dirs, get_total_commits};
 use crate::retrieval::git::source_file::get_source_file_change_frequency;
-use crate::retrieval::git::{contributor::get_git_contributors, repository::is_not_blacklisted};
+use crate::retrieval::git::worktree::ReviewWorktree;
+use crate::retrieval::licensing::{get_licensing, Licensing, LICENCE_FILES, MANIFEST_FILES};
+use crate::retrieval::linters::{Diagnostic, Diagnostics};
+use crate::retrieval::markers::{count_markers, get_marker_inventory, MarkerInventory};
+use crate::retrieval::notebook::{get_notebook_code, is_notebook};
+use crate::retrieval::patch::{parse_patch, PatchHunk};
+use crate::retrieval::secrets::{get_redacted_lines, scrub_secrets};
+use crate::retrieval::test_files::{get_test_statistics, TestStatistics};
+use crate::retrieval::units::{split_into_units, TOP_LEVEL_UNIT};
+use crate::retrieval::vulnerabilities::{lookup_vulnerabilities, LOCKFILES};
+use crate::review::anonymise::anonymise_contributors;
+use crate::review::architecture::review_architecture;
+use crate::review::attribution::get_contributor_findings;
+use crate::review::baseline::Baseline;
+use crate::review::cache::ReviewCache;
+use crate::review::checkpoint::Checkpoint;
 use crate::review::data::{
-    RAGStatus, RepositoryReview, ReviewSummary, SecurityIssueBreakdown, Severity, SourceFileReview,
+    ComplexFile, Opinion, RAGStatus, RepositoryReview, ReviewSummary, SecondOpinion,
+    SecurityIssueBreakdown, SourceFileReview,
 };
+use crate::review::dependencies::review_dependencies;
+use crate::review::directories::get_directory_summaries;
+use crate::review::executive::summarise_for_executives;
+use crate::review::feedback::{set_fingerprints, FeedbackStore};
+use crate::review::findings::get_findings;
+use crate::review::hotspots::get_hotspots;
+use crate::review::incremental::LastReview;
+use crate::review::links::{get_source_url, set_links};
+use crate::review::normalise::{dedup_findings, normalise_file_review};
+use crate::review::notify::notify_slack;
+use crate::review::ownership::get_ownership;
+use crate::review::progress::ReviewProgress;
+use crate::review::remediation::PreviousReport;
 use crate::review::report::create_report;
-use crate::settings::{ProviderSettings, ReviewType, Settings};
+use crate::review::schema::{get_review_schema, SchemaValidationError, SchemaValidator};
+use crate::review::suppression::{apply_suppressions, is_file_ignored};
+use crate::review::trends::{get_previous_point, record_trend};
+use crate::settings::{
+    Escalation, ProviderSettings, ReviewGranularity, ReviewScope, ReviewType, ServiceSettings,
+    Settings,
+};
 use chrono::{DateTime, Local, Utc};
+use futures::stream::{self, StreamExt};
+use ignore::DirEntry;
 use log::{debug, error, info, warn};
-use regex::Regex;
+use regex::{Regex, RegexSet};
+use std::collections::{BTreeSet, HashSet};
 use std::error::Error;
 use std::path::{Path, PathBuf};
 use std::{fmt, fs};
-use walkdir::{DirEntry, WalkDir};
+
+/// The number of the most complex files listed in the report
+const MAX_COMPLEX_FILES: usize = 10;
+/// The maximum number of a file's linter diagnostics given with its review
+const MAX_PROMPT_DIAGNOSTICS: usize = 50;
+/// The maximum number of functions listed in the summary of a file too large to review
+const MAX_SUMMARY_FUNCTIONS: usize = 20;
+/// The number of weeks of the commit activity of the repository, up to the review
+const COMMIT_ACTIVITY_WEEKS: usize = 52;
+/// The number of seconds in a day, for the `changed_within_days`
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+/// The format of the date of a review, e.g., '14:05, 01/02/2024'
+pub(crate) const REVIEW_DATE_FORMAT: &str = "%H:%M, %d/%m/%Y";
 
 /// Takes the filepath to a repository and iterates over the code, gaining stats, and sending each relevant file for review.
 ///
@@ -41,14 +145,76 @@ use walkdir::{DirEntry, WalkDir};
 pub(crate) async fn assess_codebase(
     settings: Settings,
 ) -> Result<String, Box<dyn std::error::Error>> {
+    let mut review: RepositoryReview = review_codebase(&settings).await?;
+    if settings.executive_summary
+        && settings.review_type.uses_provider()
+        && !review.file_reviews.is_empty()
+    {
+        // The trend is since the last recorded review, so is read before this one is recorded
+        let previous = get_previous_point(&settings, &review.repository_name).unwrap_or_else(|e| {
+            warn!("Failed to read the last review of the repository: {}", e);
+            None
+        });
+        match summarise_for_executives(&settings, &review, previous.as_ref()).await {
+            Ok(executive_summary) => {
+                review.executive_summary(Some(executive_summary));
+            }
+            Err(e) => warn!("Failed to summarise the review for executives: {}", e),
+        }
+        // The review was finalised, with its cost, before the summary was requested
+        let cost = take_cost_breakdown();
+        log_cost_breakdown(&cost);
+        review.add_cost(&cost);
+    }
+    match record_trend(&settings, &review) {
+        Ok(trends) => {
+            review.trends(trends);
+        }
+        Err(e) => warn!("Failed to record the trend of the review: {}", e),
+    }
+
+    // Should be good to go now, so create the report
+    let report_output: String = create_report(&settings, &review)?;
+    if let Some(slack_webhook_url) = &settings.slack_webhook_url {
+        if let Err(e) = notify_slack(slack_webhook_url, &review, &report_output).await {
+            warn!("Failed to notify Slack of the review: {}", e);
+        }
+    }
+    Ok(report_output)
+}
+/// Reviews the files of the repository in the scope of the review, returning the [`RepositoryReview`]
+pub(crate) async fn review_codebase(
+    settings: &Settings,
+) -> Result<RepositoryReview, Box<dyn std::error::Error>> {
     // Check whether this a valid git repository
-    let repository_root: PathBuf = validate_repository(PathBuf::from(&settings.repository_path))?;
+    let repository_root: PathBuf = validate_repository(
+        PathBuf::from(&settings.repository_path),
+        settings.require_git,
+    )?;
+    // A ref, if set, is reviewed in a temporary worktree, which is removed when dropped, i.e., when the review is done
+    let worktree: Option<ReviewWorktree> = open_review_worktree(settings)?;
+    let repository_root: PathBuf = match &worktree {
+        Some(worktree) => worktree.path.clone(),
+        None => repository_root,
+    };
+    let repository_path: String = repository_root.to_string_lossy().to_string();
 
     // Initialise the RepositoryReview data struct
-    let mut review: RepositoryReview = initialise_repository_review(&settings)?;
+    let mut review: RepositoryReview = initialise_repository_review(settings)?;
+    review.repository_ref(settings.repository_ref.clone());
+    let head_sha: Option<String> = get_head_sha(&repository_path).ok();
+    // Findings are linked to their code on GitHub or GitLab, if the repository is hosted on one, unless the staged
+    // contents are reviewed, which are in no commit
+    review.source_url(
+        head_sha
+            .as_ref()
+            .filter(|_| settings.review_scope != ReviewScope::Staged)
+            .and_then(|head_sha| get_source_url(&get_origin_url(&repository_path)?, head_sha)),
+    );
+    review.head_sha(head_sha);
 
     // Add the service and model to the RepositoryReview
-    review.generative_ai_service_and_model(get_service_and_model(&settings));
+    review.generative_ai_service_and_model(get_service_and_model(settings));
 
     info!(
         "Reviewing: {}, with {}",
@@ -58,80 +224,609 @@ pub(crate) async fn assess_codebase(
     // Initialise the RepositoryReview::ReviewSummary
     let mut review_summary_section: ReviewSummary = initialise_review_summary_section();
 
-    // Initialise a file count that we will use to show how many files were processed
-    #[cfg(debug_assertions)]
-    let mut overall_processed_files = 0;
-
     // The RepositoryReview has a Vec<LanguageTypes>, initialise
     let mut lang_type_breakdown: Vec<LanguageType> = Vec::new();
 
-    // The review of source files begins.
-    // Iterate over the files in the repository that are not blacklisted
-    for entry in valid_files_from_repository(&repository_root) {
-        #[cfg(debug_assertions)]
-        if settings.is_developer_mode() {
-            if let Some(max_count) = settings.developer_mode.as_ref().unwrap().max_file_count {
-                if max_count >= 0 && overall_processed_files >= max_count {
-                    continue;
-                }
-            }
-        }
+    // Reviews of unchanged files are taken from the cache, if enabled
+    let review_cache: Option<ReviewCache> = ReviewCache::from_settings(settings);
+    // Reviews of files unchanged since the last run are carried forward, if incremental reviews are enabled
+    let last_review: Option<LastReview> = LastReview::from_settings(settings);
 
-        let result: Option<SourceFileInfo> =
-            // Get the file info, including the file contents
-            get_initial_source_file_info(&entry, &repository_root);
+    // The Red files of a previous report, if set, are the only files re-reviewed, to show whether they improved
+    let previous_report: Option<PreviousReport> = PreviousReport::from_settings(settings)?;
 
-        if let Some(file_info) = result {
-            #[cfg(debug_assertions)]
-            if settings.is_developer_mode() {
-                overall_processed_files += 1;
-            }
+    // The files in the scope of the review, e.g., those changed between two refs, if not all of the repository's files
+    let scoped_files: Option<HashSet<String>> = get_scoped_files(
+        settings,
+        &repository_path,
+        &mut review,
+        previous_report.as_ref(),
+    )?;
 
-            // Add the LanguageType to the Vec<LanguageType>
-            update_language_type_statistics(&mut lang_type_breakdown, &file_info);
+    // Gather the source files that are not ignored, with their contents and statistics
+    let mut source_files: Vec<SourceFileInfo> =
+        get_source_files(settings, &repository_root, scoped_files.as_ref());
+    if let Some(coverage_path) = &settings.coverage_path {
+        Coverage::from_path(coverage_path)?.apply(&mut source_files);
+    }
+    if !settings.linter_reports.is_empty() || !settings.run_linters.is_empty() {
+        Diagnostics::from_settings(
+            &settings.linter_reports,
+            &settings.run_linters,
+            &repository_root,
+        )?
+        .apply(&mut source_files);
+    }
+    for file_info in &source_files {
+        // Add the LanguageType to the Vec<LanguageType>
+        update_language_type_statistics(&mut lang_type_breakdown, file_info);
+    }
+    let documentation_coverage: DocumentationCoverage = get_documentation_coverage(
+        &source_files,
+        count_documentation_files(settings, &repository_root),
+    );
+    review_summary_section.documentation = Some(documentation_coverage.get_documentation());
+    review_summary_section.documentation_coverage = Some(documentation_coverage);
+    let markers: MarkerInventory = get_marker_inventory(&source_files);
+    if settings.markers_in_summary {
+        if let Some(note) = markers.get_summary_note() {
+            review_summary_section.text.push_str(&note);
+            review_summary_section.text.push('\n');
+        }
+    }
+    review.markers(Some(markers));
+    let test_statistics: TestStatistics = get_test_statistics(&source_files);
+    review.statistics.test_loc = test_statistics.test_loc;
+    review.statistics.test_ratio = test_statistics.get_test_ratio();
+    review.untested_files(Some(test_statistics.untested_files));
+    // Duplicated code is detected across the whole files, before any is split into units
+    review.duplication(Some(detect_duplication(&source_files)));
+    review.complex_files(Some(get_most_complex_files(&source_files)));
+    let dependencies = get_dependencies(&read_repository_files_named(
+        settings,
+        &repository_root,
+        &DEPENDENCY_MANIFESTS,
+    ));
+    if !dependencies.is_empty() {
+        review.dependencies(Some(dependencies));
+    }
+    if settings.review_type == ReviewType::Compliance {
+        review.licensing(Some(get_repository_licensing(
+            settings,
+            &repository_root,
+            &source_files,
+        )));
+    }
 
-            let file_name_str = file_info.relative_path.clone();
-            let contents_str = file_info.get_source_file_contents();
-            // Actually review the file via the LLM, returns a SourceFileReview
-            match review_file(
-                &settings,
-                &file_name_str.to_string(),
-                &contents_str.to_string(),
+    // The purpose of the repository, from the README, which is also part of the context of each file review
+    let repository_purpose: Option<String> = if settings.review_type.uses_provider() {
+        match summarise_readme(settings, &repository_root).await {
+            Ok(purpose) => purpose,
+            Err(e) => {
+                warn!("Failed to summarise the README: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let repository_context: Option<RepositoryContext> =
+        settings.use_repository_context.then(|| {
+            LanguageType::calculate_percentage_distribution(&mut lang_type_breakdown);
+            RepositoryContext::new(
+                LanguageType::get_predominant_language(&lang_type_breakdown),
+                repository_purpose.clone(),
+                &source_files,
             )
-            .await
-            {
-                Ok(Some(mut reviewed_file)) => {
-                    update_repository_review_statistics(&mut review, &file_info);
+        });
 
-                    reviewed_file.source_file_info = file_info.clone();
-                    update_review_summary(&mut review_summary_section, &mut reviewed_file);
+    // At function granularity, each function of a file is reviewed on its own, so whole files are ignored beforehand
+    let source_files: Vec<SourceFileInfo> = remove_ignored_files(source_files);
+    let source_files: Vec<SourceFileInfo> = match settings.review_granularity {
+        ReviewGranularity::File => source_files,
+        ReviewGranularity::Function => split_source_files_into_units(settings, source_files),
+    };
+
+    review_source_files(
+        settings,
+        &mut review,
+        &mut review_summary_section,
+        &source_files,
+        review_cache.as_ref(),
+        last_review.as_ref(),
+        repository_context.as_ref(),
+    )
+    .await?;
+    if let Some(source_url) = review.get_source_url().map(str::to_string) {
+        // Only the files as they are at the commit reviewed are linked
+        match get_uncommitted_files(&repository_path) {
+            Ok(uncommitted_files) => {
+                for file_review in review.file_reviews.iter_mut() {
+                    if !uncommitted_files.contains(&file_review.source_file_info.relative_path) {
+                        set_links(file_review, &source_url);
+                    }
+                }
+            }
+            Err(e) => warn!(
+                "Failed to get the uncommitted files, so none is linked: {}",
+                e
+            ),
+        }
+    }
+    review.hotspots(Some(get_hotspots(&source_files, &review.file_reviews)));
+    review.directories(Some(get_directory_summaries(&review.file_reviews)));
+    review.findings(Some(get_findings(&review.file_reviews)));
+    if let Some(previous_report) = &previous_report {
+        review.remediation(Some(
+            previous_report.get_remediation(&repository_root, &review.file_reviews),
+        ));
+    }
 
-                    // Add SourceFileReview to the RepositoryReview
-                    review.add_source_file_review(reviewed_file);
+    // The known vulnerabilities of the dependencies are security issues of the repository, so are in its RAG status
+    if settings.vulnerability_lookup {
+        if let Some(dependencies) = &review.dependencies {
+            let lockfiles: Vec<(String, String)> = read_repository_files_named(
+                settings,
+                &repository_root,
+                &LOCKFILES.map(|(lockfile, _)| lockfile),
+            );
+            match lookup_vulnerabilities(dependencies, &lockfiles).await {
+                Ok(vulnerabilities) => {
+                    for vulnerability in &vulnerabilities {
+                        review_summary_section
+                            .security_issues
+                            .add(&vulnerability.severity);
+                    }
+                    review.dependency_vulnerabilities(Some(vulnerabilities));
                 }
-                Ok(None) => warn!("No review actioned. None returned from 'review_file'"),
-                Err(e) => return Err(e),
+                Err(e) => warn!(
+                    "Failed to look up the vulnerabilities of the dependencies: {}",
+                    e
+                ),
+            }
+        }
+    }
+
+    // The architecture and dependencies are reviewed before the review is finalised, so that their requests are in
+    // the cost
+    if settings.architecture_review
+        && settings.review_type.uses_provider()
+        && !review.file_reviews.is_empty()
+    {
+        match review_architecture(settings, &review.file_reviews).await {
+            Ok(findings) => review.architecture_review(Some(findings)),
+            Err(e) => warn!("Failed to review the architecture of the repository: {}", e),
+        }
+    }
+    if settings.dependency_review && settings.review_type.uses_provider() {
+        if let Some(dependencies) = &review.dependencies {
+            match review_dependencies(settings, dependencies).await {
+                Ok(findings) => review.dependency_review(Some(findings)),
+                Err(e) => warn!("Failed to review the dependencies of the repository: {}", e),
+            }
+        }
+    }
+    finalise_review(
+        &mut review,
+        &mut review_summary_section,
+        &mut lang_type_breakdown,
+        repository_purpose,
+        settings,
+    )
+    .await?;
+    // A plain directory, if git is not required, has no commits or contributors
+    if open_repository(&repository_root).is_ok() {
+        review.statistics.num_commits = get_total_commits(&repository_path)?;
+        match get_commit_activity(&repository_path, Utc::now(), COMMIT_ACTIVITY_WEEKS) {
+            Ok(commit_activity) => {
+                review.commit_activity(Some(commit_activity));
             }
+            Err(e) => warn!("Failed to get the commit activity of the repository: {}", e),
+        }
+        // Each file is owned by its main author, once, whether reviewed whole or by unit. Blaming every file is slow
+        // for a large repository, so is only done if set, as for the contributor findings.
+        let file_owners: Vec<FileOwner> = if settings.file_ownership {
+            let relative_paths: BTreeSet<&str> = source_files
+                .iter()
+                .map(|file_info| file_info.relative_path.as_str())
+                .collect();
+            let relative_paths: Vec<&str> = relative_paths.into_iter().collect();
+            get_file_owners(&repository_path, &relative_paths)
+        } else {
+            Vec::new()
+        };
+        review.contributors(get_git_contributors(&repository_path, &file_owners));
+        review.ownership(get_ownership(&file_owners));
+        if settings.contributor_findings {
+            review.contributor_findings(Some(get_contributor_findings(
+                &repository_path,
+                &settings.review_scope,
+                &review.file_reviews,
+                // Named consistently with the other contributors, if they are all anonymised
+                settings.anonymise_contributor_findings && !settings.anonymise_contributors,
+            )));
+        }
+        if settings.anonymise_contributors {
+            anonymise_contributors(&mut review);
+        }
+    }
+
+    if let Some(last_review) = &last_review {
+        if let Err(e) = last_review.save(&review.file_reviews) {
+            warn!(
+                "Failed to save the reviews for the next incremental review: {}",
+                e
+            );
         }
-    } // end get_files_from_repository
+    }
+
+    Ok(review)
+}
+
+/// Reviews only the hunks of a unified diff, e.g., from `git format-patch` or a GitHub pull request, each with the
+/// context lines around it, so that there are findings per hunk. Returns the path(s) of the report(s) created.
+pub(crate) async fn assess_patch(
+    settings: Settings,
+    patch_path: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let patch: String = fs::read_to_string(patch_path)
+        .map_err(|e| format!("Failed to read the patch: {}: {}", patch_path, e))?;
+    let patch_name: String = Path::new(patch_path)
+        .file_stem()
+        .map_or(patch_path.to_string(), |stem| {
+            stem.to_string_lossy().to_string()
+        });
+
+    let mut review: RepositoryReview = RepositoryReview::new(patch_name);
+    review.generative_ai_service_and_model(get_service_and_model(&settings));
+    info!("Reviewing the patch: {}", patch_path);
+
+    let mut review_summary_section: ReviewSummary = initialise_review_summary_section();
+    let mut lang_type_breakdown: Vec<LanguageType> = Vec::new();
+    let review_cache: Option<ReviewCache> = ReviewCache::from_settings(&settings);
+
+    let source_files: Vec<SourceFileInfo> = parse_patch(&patch)
+        .iter()
+        .filter_map(|hunk| get_patch_hunk_source_file_info(&settings, hunk))
+        .collect();
+    for file_info in &source_files {
+        update_language_type_statistics(&mut lang_type_breakdown, file_info);
+    }
 
+    review_source_files(
+        &settings,
+        &mut review,
+        &mut review_summary_section,
+        &source_files,
+        review_cache.as_ref(),
+        None,
+        None,
+    )
+    .await?;
     finalise_review(
         &mut review,
         &mut review_summary_section,
         &mut lang_type_breakdown,
+        None,
         &settings,
     )
     .await?;
 
-    // Should be good to go now, so create the report
     create_report(&settings, &review)
 }
+/// Reviews the source files, adding each [`SourceFileReview`] to the [`RepositoryReview`], and its findings to the
+/// [`ReviewSummary`]
+async fn review_source_files(
+    settings: &Settings,
+    review: &mut RepositoryReview,
+    review_summary: &mut ReviewSummary,
+    source_files: &[SourceFileInfo],
+    review_cache: Option<&ReviewCache>,
+    last_review: Option<&LastReview>,
+    repository_context: Option<&RepositoryContext>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Findings in the baseline report, if any, are known, so do not affect the RAG status
+    let baseline: Option<Baseline> = Baseline::from_settings(settings)?;
+
+    // Findings marked as false positives are dropped, and, if set, given to the LLM as counter-examples
+    let mut feedback: FeedbackStore = FeedbackStore::from_settings(settings);
+    let counter_examples: Option<String> = if settings.feedback_counter_examples {
+        feedback.get_counter_examples()
+    } else {
+        None
+    };
+
+    // Reviews completed by an earlier run that stopped part way, if any, are resumed
+    let checkpoint: Option<Checkpoint> = Checkpoint::from_settings(settings);
+
+    // The review of source files begins. Up to 'max_concurrent_reviews' files are reviewed at once, and the reviews
+    // ordered as the files were found, so the report is the same whatever order the reviews complete in
+    let progress = ReviewProgress::new(settings.progress_bar, source_files.len());
+    let mut reviews = stream::iter(source_files.iter().enumerate())
+        .map(|(index, file_info)| {
+            let checkpoint = checkpoint.as_ref();
+            let counter_examples = counter_examples.as_deref();
+            let progress = &progress;
+            // Actually review the file via the LLM, returns a SourceFileReview
+            async move {
+                if let Some(file_review) =
+                    checkpoint.and_then(|checkpoint| checkpoint.get(file_info))
+                {
+                    info!("Resumed from the checkpoint: {}", file_info.relative_path);
+                    return (index, Ok(Some(file_review)));
+                }
+                if let Some(file_review) = last_review.and_then(|last| last.get(file_info)) {
+                    info!(
+                        "Unchanged since the last review: {}",
+                        file_info.relative_path
+                    );
+                    return (index, Ok(Some(file_review)));
+                }
+                if let Some(max_file_size_kb) = get_exceeded_max_file_size(settings, file_info) {
+                    warn!(
+                        "Not sent for review, as it is over {} KB: {}",
+                        max_file_size_kb, file_info.relative_path
+                    );
+                    return (
+                        index,
+                        Ok(Some(SourceFileReview::new(
+                            file_info.clone(),
+                            get_structural_summary(file_info, max_file_size_kb),
+                        ))),
+                    );
+                }
+                if let Some(reason) = get_skipped_generated(settings, file_info) {
+                    info!(
+                        "Not sent for review, as it is generated: {}",
+                        file_info.relative_path
+                    );
+                    let summary = format!("Not sent for review, as it is generated: {}", reason);
+                    return (
+                        index,
+                        Ok(Some(SourceFileReview::new(file_info.clone(), summary))),
+                    );
+                }
+                if let Some(redacted_secrets) = get_skipped_secrets(settings, file_info) {
+                    warn!(
+                        "Not sent for review, as it contains secrets: {}",
+                        file_info.relative_path
+                    );
+                    let summary = format!(
+                        "Not sent for review, as it contains secrets: {}",
+                        redacted_secrets.join(", ")
+                    );
+                    return (
+                        index,
+                        Ok(Some(SourceFileReview::new(file_info.clone(), summary))),
+                    );
+                }
+                progress.start_file(&file_info.relative_path);
+                (
+                    index,
+                    review_file(
+                        settings,
+                        file_info,
+                        review_cache,
+                        repository_context,
+                        counter_examples,
+                    )
+                    .await,
+                )
+            }
+        })
+        .buffer_unordered(settings.max_concurrent_reviews.max(1));
+    let mut reviewed_files: Vec<(usize, SourceFileReview)> = Vec::new();
+    while let Some((index, result)) = reviews.next().await {
+        progress.finish_file();
+        match result {
+            Ok(Some(mut reviewed_file)) => {
+                if let Some(checkpoint) = &checkpoint {
+                    reviewed_file.source_file_info = source_files[index].clone();
+                    if let Err(e) = checkpoint.append(&reviewed_file) {
+                        warn!("Failed to checkpoint the review: {}", e);
+                    }
+                }
+                reviewed_files.push((index, reviewed_file));
+            }
+            Ok(None) => warn!("No review actioned. None returned from 'review_file'"),
+            Err(e) => return Err(e),
+        }
+    }
+    // The progress bar is cleared once the reviews are done, before the summary is logged
+    drop(reviews);
+    drop(progress);
+    // Every file is reviewed, so there is nothing to resume
+    if let Some(checkpoint) = &checkpoint {
+        if let Err(e) = checkpoint.remove() {
+            warn!("Failed to remove the checkpoint: {}", e);
+        }
+    }
+    reviewed_files.sort_by_key(|(index, _)| *index);
+
+    for (index, mut reviewed_file) in reviewed_files {
+        let file_info: &SourceFileInfo = &source_files[index];
+        update_repository_review_statistics(review, file_info);
 
+        reviewed_file.source_file_info = file_info.clone();
+        if file_info.redacted_secrets.is_some() {
+            reviewed_file.add_secret_security_issues(&get_redacted_lines(
+                &file_info.get_source_file_contents(),
+            ));
+        }
+        let num_duplicates: usize = dedup_findings(&mut reviewed_file);
+        if num_duplicates > 0 {
+            debug!(
+                "Removed {} repeated findings: {}",
+                num_duplicates, file_info.relative_path
+            );
+        }
+        set_fingerprints(&mut reviewed_file);
+        feedback.apply(&mut reviewed_file);
+        apply_suppressions(&mut reviewed_file, &file_info.get_source_file_contents());
+        if let Some(baseline) = &baseline {
+            baseline.apply(&mut reviewed_file);
+        }
+        if let Some(min_severity) = &settings.min_severity {
+            reviewed_file.retain_min_severity(min_severity);
+        }
+        update_review_summary(settings, review_summary, &mut reviewed_file);
+
+        // Add SourceFileReview to the RepositoryReview
+        review.add_source_file_review(reviewed_file);
+    }
+    if let Err(e) = feedback.save() {
+        warn!("Failed to save the feedback: {}", e);
+    }
+    Ok(())
+}
+/// Gets the paths of the files in the [`ReviewScope`], changed within the `changed_within_days`, if set, and to be
+/// re-reviewed from the [`PreviousReport`], if any, or [`None`] if all files are in scope, annotating the [`RepositoryReview`] with the commit range assessed
+fn get_scoped_files(
+    settings: &Settings,
+    repository_path: &str,
+    review: &mut RepositoryReview,
+    previous_report: Option<&PreviousReport>,
+) -> Result<Option<HashSet<String>>, Box<dyn std::error::Error>> {
+    let scoped_files: Option<HashSet<String>> = match settings.review_scope {
+        ReviewScope::All => None,
+        ReviewScope::Diff => {
+            let base_ref = settings
+                .base_ref
+                .as_deref()
+                .ok_or("A base_ref must be set for the 'diff' review_scope")?;
+            let head_ref = settings.get_head_ref();
+            let commit_range = get_commit_range(repository_path, base_ref, head_ref)?;
+            let changed_files = get_changed_files(repository_path, base_ref, head_ref)?;
+            info!(
+                "Reviewing the {} files changed in: {}",
+                changed_files.len(),
+                commit_range
+            );
+            review.commit_range(Some(commit_range));
+            Some(changed_files)
+        }
+        ReviewScope::Staged => {
+            let staged_files = get_staged_files(repository_path)?;
+            info!(
+                "Reviewing the {} files staged for commit",
+                staged_files.len()
+            );
+            Some(staged_files)
+        }
+    };
+    let mut scoped_files: Option<HashSet<String>> = scoped_files;
+    if let Some(days) = settings.changed_within_days {
+        let since: i64 = Utc::now().timestamp() - i64::from(days) * SECONDS_PER_DAY;
+        let recent_files: HashSet<String> = get_files_changed_since(repository_path, since)?;
+        info!(
+            "{} files were changed in the last {} days",
+            recent_files.len(),
+            days
+        );
+        scoped_files = Some(narrow_scoped_files(scoped_files, recent_files));
+    }
+    if let Some(previous_report) = previous_report {
+        scoped_files = Some(narrow_scoped_files(
+            scoped_files,
+            previous_report.get_files_to_rereview(),
+        ));
+    }
+    Ok(scoped_files)
+}
+/// Narrows the files in scope, if any, to the files passed, otherwise the files passed are in scope
+fn narrow_scoped_files(
+    scoped_files: Option<HashSet<String>>,
+    files: HashSet<String>,
+) -> HashSet<String> {
+    match scoped_files {
+        Some(scoped_files) => scoped_files.intersection(&files).cloned().collect(),
+        None => files,
+    }
+}
+/// Gets the [`SourceFileInfo`], including the file contents, of each source file in the repository that is not
+/// ignored, is in the scope of the review, and is predominantly by the `authors`, if set, riskiest first, limited
+/// to the `max_file_count` in developer mode
+#[cfg_attr(not(debug_assertions), allow(unused_variables))]
+fn get_source_files(
+    settings: &Settings,
+    repository_root: &PathBuf,
+    scoped_files: Option<&HashSet<String>>,
+) -> Vec<SourceFileInfo> {
+    let mut source_files: Vec<SourceFileInfo> = Vec::new();
+    // Only the files within the review paths, if any, are reviewed, though found from the repository root
+    for entry in reviewed_files_from_repository(settings, repository_root) {
+        if scoped_files.is_some_and(|scoped_files| {
+            !entry
+                .path()
+                .strip_prefix(repository_root)
+                .ok()
+                .and_then(|relative_path| relative_path.to_str())
+                .is_some_and(|relative_path| scoped_files.contains(relative_path))
+        }) {
+            continue;
+        }
+        if let Some(file_info) = get_initial_source_file_info(settings, &entry, repository_root) {
+            source_files.push(file_info);
+        }
+    }
+    if let Some(authors) = settings.get_author_patterns().ok().flatten() {
+        let repository_path: String = repository_root.to_string_lossy().to_string();
+        source_files.retain(|file_info| {
+            get_author_lines(&repository_path, &file_info.relative_path)
+                .is_ok_and(|author_lines| is_predominantly_by(&authors, &author_lines))
+        });
+        info!(
+            "Reviewing the {} files predominantly written by the authors",
+            source_files.len()
+        );
+    }
+    // The riskiest files are reviewed first, so a run cut short has already reviewed them
+    sort_by_risk(&mut source_files);
+    #[cfg(debug_assertions)]
+    if settings.is_developer_mode() {
+        if let Some(max_count) = settings.developer_mode.as_ref().unwrap().max_file_count {
+            if max_count >= 0 {
+                source_files.truncate(max_count as usize);
+            }
+        }
+    }
+    source_files
+}
+/// Whether more than half of the lines of a file were last changed by authors whose name or email match the patterns
+fn is_predominantly_by(authors: &RegexSet, author_lines: &[AuthorLines]) -> bool {
+    let total_lines: usize = author_lines.iter().map(|author| author.lines).sum();
+    let authored_lines: usize = author_lines
+        .iter()
+        .filter(|author| authors.is_match(&author.name) || authors.is_match(&author.email))
+        .map(|author| author.lines)
+        .sum();
+    authored_lines * 2 > total_lines
+}
+/// Sorts the source files by their risk, highest first, i.e., one plus the number of commits that changed the file,
+/// times its size, so that a file that changes often, or is large, is reviewed before one that is not. Files of the
+/// same risk are sorted by relative path.
+fn sort_by_risk(source_files: &mut [SourceFileInfo]) {
+    source_files.sort_by(|a, b| {
+        let risk = |file_info: &SourceFileInfo| {
+            (1 + file_info.statistics.num_commits as i64) * file_info.statistics.size
+        };
+        risk(b)
+            .cmp(&risk(a))
+            .then_with(|| a.relative_path.cmp(&b.relative_path))
+    });
+}
 /// Updates the [`RepositoryReview`] statistics per [`SourceFileInfo`] processed
 fn update_repository_review_statistics(review: &mut RepositoryReview, file_info: &SourceFileInfo) {
     review.statistics.size += file_info.statistics.size;
     review.statistics.loc += file_info.statistics.loc;
-    review.statistics.num_files += 1;
+    // A file split into units is counted once, on its first unit
+    let is_next_unit = file_info.code_unit.is_some()
+        && review.file_reviews.last().is_some_and(|file_review| {
+            file_review.source_file_info.relative_path == file_info.relative_path
+        });
+    if !is_next_unit {
+        review.statistics.num_files += 1;
+    }
 }
 /// Updates the language type statistics, adding a new one if it doesn't exist
 /// Note that the [`LanguageType`].statistics.frequency is not updated here, but at the end
@@ -177,54 +872,99 @@ fn initialise_repository_review(
 
     Ok(RepositoryReview::new(repository_name.to_string()))
 }
-/// gets files from non-blacklisted dirs (that are not symlinks)
-fn valid_files_from_repository(repository_root: &PathBuf) -> Vec<DirEntry> {
-    let blacklisted_dirs = get_blacklist_dirs(repository_root);
-    WalkDir::new(repository_root)
+/// Inventories the licences of the repository, from its licence file, the dependency manifests that are not ignored and
+/// are within the review paths, if any, and the headers of the source files. The licence file at the repository root
+/// is that of every file, so is read whatever the review paths.
+fn get_repository_licensing(
+    settings: &Settings,
+    repository_root: &PathBuf,
+    source_files: &[SourceFileInfo],
+) -> Licensing {
+    let licence_file: Option<String> = LICENCE_FILES
+        .iter()
+        .find_map(|name| fs::read_to_string(repository_root.join(name)).ok());
+    let manifests: Vec<(String, String)> =
+        read_repository_files_named(settings, repository_root, &MANIFEST_FILES);
+    get_licensing(licence_file.as_deref(), &manifests, source_files)
+}
+/// Reads the files with any of the names, e.g., 'Cargo.toml', that are not ignored and are within the review paths, if
+/// any, each as its relative path and contents
+fn read_repository_files_named(
+    settings: &Settings,
+    repository_root: &PathBuf,
+    names: &[&str],
+) -> Vec<(String, String)> {
+    reviewed_files_from_repository(settings, repository_root)
+        .iter()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| names.contains(&name))
+        })
+        .filter_map(|entry| {
+            let relative_path = entry.path().strip_prefix(repository_root).ok()?.to_str()?;
+            let contents = fs::read_to_string(entry.path()).ok()?;
+            Some((relative_path.to_string(), contents))
+        })
+        .collect()
+}
+/// Counts the documentation files, e.g., Markdown, that are not ignored and are within the review paths, if any
+fn count_documentation_files(settings: &Settings, repository_root: &PathBuf) -> usize {
+    reviewed_files_from_repository(settings, repository_root)
+        .iter()
+        .filter_map(|entry| entry.path().strip_prefix(repository_root).ok()?.to_str())
+        .filter(|relative_path| is_documentation_file(relative_path))
+        .count()
+}
+/// Gets the files of the repository that are not ignored, and are within the review paths, if any
+fn reviewed_files_from_repository(settings: &Settings, repository_root: &PathBuf) -> Vec<DirEntry> {
+    valid_files_from_repository(repository_root)
         .into_iter()
-        .filter_entry(|e| is_not_blacklisted(e, &blacklisted_dirs) && !e.file_type().is_symlink())
-        .filter_map(|e| e.ok())
-        .filter(|e| e.file_type().is_file())
+        .filter(|entry| {
+            entry
+                .path()
+                .strip_prefix(repository_root)
+                .is_ok_and(|relative_path| settings.is_path_reviewed(relative_path))
+        })
         .collect()
 }
-/// TODO: to implement the review of the state of documentation, etc.
+/// Gets the files of the repository that are not ignored, e.g., by a '.gitignore', and are not symlinks
+fn valid_files_from_repository(repository_root: &PathBuf) -> Vec<DirEntry> {
+    get_unignored_files(repository_root)
+}
+/// Initialises the [`ReviewSummary`], whose documentation is set from the documentation coverage of the source files
 fn initialise_review_summary_section() -> ReviewSummary {
     ReviewSummary {
         text: String::new(),
-        security_issues: SecurityIssueBreakdown {
-            low: 0,
-            medium: 0,
-            high: 0,
-            critical: 0,
-            total: 0,
-        },
+        security_issues: SecurityIssueBreakdown::default(),
         errors: 0,
         improvements: 0,
         documentation: None,
+        documentation_coverage: None,
     }
 }
 
-/// Updates the [`ReviewSummary`] with the results of the [`SourceFileReview`]
-fn update_review_summary(review_summary: &mut ReviewSummary, reviewed_file: &mut SourceFileReview) {
+/// Updates the [`ReviewSummary`] with the results of the [`SourceFileReview`], and sets the file's RAG status within the
+/// [`crate::settings::RagThresholds`] of its language
+fn update_review_summary(
+    settings: &Settings,
+    review_summary: &mut ReviewSummary,
+    reviewed_file: &mut SourceFileReview,
+) {
     review_summary.errors += reviewed_file.errors.as_ref().map_or(0, Vec::len) as i32;
     review_summary.improvements += reviewed_file.improvements.as_ref().map_or(0, Vec::len) as i32;
 
     if let Some(issues) = &reviewed_file.security_issues {
         for issue in issues {
-            review_summary.security_issues.total += 1;
-            match issue.severity {
-                Severity::Low => review_summary.security_issues.low += 1,
-                Severity::Medium => review_summary.security_issues.medium += 1,
-                Severity::High => review_summary.security_issues.high += 1,
-                Severity::Critical => review_summary.security_issues.critical += 1,
-            }
+            review_summary.security_issues.add(&issue.severity);
+            review_summary.security_issues.add_categories(issue);
         }
     }
     review_summary.text.push_str(&reviewed_file.summary);
     review_summary.text.push('\n');
 
-    reviewed_file.file_rag_status =
-        Some(calculate_rag_status_for_reviewed_file(reviewed_file).unwrap_or_default());
+    reviewed_file.file_rag_status = Some(get_file_rag_status(settings, reviewed_file));
 }
 
 /// Finalise the [`RepositoryReview`] by adding the [`ReviewSummary`], Vec<LanguageType>, and other data
@@ -232,6 +972,7 @@ async fn finalise_review(
     review: &mut RepositoryReview,
     review_summary: &mut ReviewSummary,
     breakdown: &mut [LanguageType],
+    repository_purpose: Option<String>,
     settings: &Settings,
 ) -> Result<(), Box<dyn std::error::Error>> {
     if !review.file_reviews.is_empty() {
@@ -247,6 +988,13 @@ async fn finalise_review(
         };
     }
     review.summary(Some(review_summary.clone()));
+    review.repository_purpose(repository_purpose);
+
+    // The requests of the review are complete, so the cost can be totalled; any later request, e.g., the executive
+    // summary, is added to it
+    let cost = take_cost_breakdown();
+    log_cost_breakdown(&cost);
+    review.add_cost(&cost);
 
     // Handle the statistics for the language types
     LanguageType::calculate_percentage_distribution(breakdown);
@@ -254,10 +1002,7 @@ async fn finalise_review(
     review.repository_type(Some(predominant_language));
 
     review.date(get_review_date());
-    review.repository_purpose(None); // TODO Implement this and incorporate the documentation status
     review.repository_rag_status(get_overall_rag_for(review));
-    review.statistics.num_commits = get_total_commits(&settings.repository_path)?;
-    review.contributors(get_git_contributors(&settings.repository_path));
     review.language_types(breakdown.to_vec());
 
     Ok(())
@@ -268,8 +1013,11 @@ async fn finalise_review(
 /// # Parameters
 ///
 /// * `settings` - A [`Settings`] that contains information for the LLM
-/// * `code_file_path` - The path (as [`String`]) of the file to process
-/// * `code_file_contents` - The contents (as [`String`]) of the file to process
+/// * `file_info` - The [`SourceFileInfo`] of the file to process, including its contents
+/// * `review_cache` - An [`Option`]al [`ReviewCache`] that is checked before, and updated after, the review
+/// * `repository_context` - An [`Option`]al [`RepositoryContext`] given with the file, which is not part of the cache key
+/// * `counter_examples` - The [`Option`]al findings marked as false positives, given with the file, which are not part of
+///   the cache key
 ///
 /// # Returns
 ///
@@ -277,91 +1025,431 @@ async fn finalise_review(
 ///
 async fn review_file(
     settings: &Settings,
-    code_file_path: &String,
-    code_file_contents: &String,
+    file_info: &SourceFileInfo,
+    review_cache: Option<&ReviewCache>,
+    repository_context: Option<&RepositoryContext>,
+    counter_examples: Option<&str>,
 ) -> Result<Option<SourceFileReview>, Box<dyn std::error::Error>> {
-    info!("Reviewing file: {}", code_file_path);
+    let code_file_path: &str = &file_info.relative_path;
 
-    if let Some(mut prompt_data) = get_prompt_data_based_on_review_type(settings)? {
-        let provider: &ProviderSettings = get_provider(settings);
-        let review_request: String = format!(
-            "Source file to review:\n file name: {}\n contents: \n{}\n",
-            code_file_path, code_file_contents
-        );
+    if let Some(mut prompt_data) = get_prompt_data_based_on_review_type(settings, file_info)? {
+        // The provider is scheduled for the whole of the file's review, e.g., its plan and any repair
+        let estimated_tokens: u64 =
+            prompt_data.estimate_tokens() + estimate_tokens(&file_info.get_source_file_contents());
+        let scheduled: ScheduledProvider = schedule(settings, estimated_tokens)?;
+        let provider: &ProviderSettings = scheduled.provider;
+        let service: &ServiceSettings = get_service(provider);
+
+        prompt_data.id = Some(code_file_path.to_string());
+        if let Some(repository_context) = repository_context {
+            prompt_data.add_repository_context(&repository_context.to_prompt());
+        }
+        if let Some(counter_examples) = counter_examples {
+            prompt_data.add_counter_examples(counter_examples);
+        }
+        let review_request: String = get_review_request(file_info);
+        // The review is cached by the prompt as sent, i.e., with its review request, and the coverage and diagnostics
+        // notes of that, to the model it is scheduled on. A plan is the model's own read of that same prompt, so only
+        // whether one is made is in the key, not its text, or every planned review would miss the cache.
+        let cache_key: Option<String> = match (review_cache, file_info.id_hash.as_deref()) {
+            (Some(cache), Some(id_hash)) => {
+                let mut key_prompt_data: PromptData = prompt_data.clone();
+                if settings.plan_then_review {
+                    key_prompt_data.add_file_plan("");
+                }
+                key_prompt_data.add_user_message_prompt(review_request.clone());
+                Some(cache.get_key(id_hash, &service.model, &key_prompt_data))
+            }
+            _ => None,
+        };
+        if let (Some(cache), Some(key)) = (review_cache, &cache_key) {
+            if let Some(cached_review) = cache.get(key) {
+                info!("Using cached review for file: {}", code_file_path);
+                return Ok(Some(cached_review));
+            }
+        }
+        info!("Reviewing file: {}", code_file_path);
 
+        let plan: Option<ProviderCompletionResponse> = if settings.plan_then_review {
+            plan_file_review(settings, provider, file_info).await
+        } else {
+            None
+        };
+        if let Some(plan) = &plan {
+            prompt_data.add_file_plan(&plan.choices[0].message.content);
+        }
         prompt_data.add_user_message_prompt(review_request);
-        perform_review(settings, provider, &prompt_data).await
+        let trims: Vec<String> = trim_to_context_window(settings, &service.model, &mut prompt_data);
+        let mut file_review = perform_review(settings, provider, service, &prompt_data).await?;
+        if let Some(escalation) = &settings.escalation {
+            if let Some(first_review) = file_review.take() {
+                file_review = Some(
+                    escalate_red_file_review(
+                        settings,
+                        escalation,
+                        &service.model,
+                        &prompt_data,
+                        file_info,
+                        first_review,
+                    )
+                    .await?,
+                );
+            }
+        }
+        if let Some(file_review) = &mut file_review {
+            if !trims.is_empty() {
+                file_review.prompt_trims = Some(trims);
+            }
+        }
+        if let (Some(file_review), Some(plan)) = (&mut file_review, plan) {
+            if let Some(plan_usage) = &plan.usage {
+                file_review
+                    .usage
+                    .get_or_insert_with(Default::default)
+                    .add(plan_usage);
+            }
+            file_review.plan = Some(plan.choices[0].message.content.trim().to_string());
+        }
+
+        if let (Some(cache), Some(key), Some(file_review)) =
+            (review_cache, &cache_key, &file_review)
+        {
+            if let Err(e) = cache.put(key, file_review) {
+                warn!("Failed to cache review for file: {}: {}", code_file_path, e);
+            }
+        }
+        Ok(file_review)
     } else {
         Ok(None)
     }
 }
-/// Fetches the correct [`PromptData`] according to the [`ReviewType`] passed
+/// Escalates the file review, if Red, to the stronger model of the [`Escalation`], for a second opinion, whose findings
+/// are kept, with both opinions recorded. A failure is logged, and the first review kept.
+async fn escalate_red_file_review(
+    settings: &Settings,
+    escalation: &Escalation,
+    first_model: &str,
+    prompt_data: &PromptData,
+    file_info: &SourceFileInfo,
+    first_review: SourceFileReview,
+) -> Result<SourceFileReview, Box<dyn std::error::Error>> {
+    // The RAG status is of the file as found, not as the model described it
+    let mut reviewed_file: SourceFileReview = first_review.clone();
+    reviewed_file.source_file_info = file_info.clone();
+    let first_rag_status: RAGStatus = get_file_rag_status(settings, &reviewed_file);
+    if first_rag_status != RAGStatus::Red {
+        return Ok(first_review);
+    }
+    let (provider, service) = escalation.get_provider_and_service(settings)?;
+    info!(
+        "Escalating the Red review of: {} to model: {}",
+        file_info.relative_path, service.model
+    );
+    let mut second_review: SourceFileReview =
+        match perform_review(settings, provider, service, prompt_data).await {
+            Ok(Some(second_review)) => second_review,
+            Ok(None) => return Ok(first_review),
+            Err(e) => {
+                warn!(
+                    "Failed to escalate, so keeping the first review of: {}: {}",
+                    file_info.relative_path, e
+                );
+                return Ok(first_review);
+            }
+        };
+    let first_opinion = Opinion::new(first_model, &reviewed_file, first_rag_status);
+    reviewed_file = second_review.clone();
+    reviewed_file.source_file_info = file_info.clone();
+    let second_rag_status: RAGStatus = get_file_rag_status(settings, &reviewed_file);
+    if let Some(first_usage) = &first_review.usage {
+        second_review
+            .usage
+            .get_or_insert_with(Default::default)
+            .add(first_usage);
+    }
+    second_review.second_opinion = Some(SecondOpinion {
+        first_opinion,
+        second_opinion: Opinion::new(&service.model, &reviewed_file, second_rag_status.clone()),
+        confirmed: second_rag_status == RAGStatus::Red,
+    });
+    Ok(second_review)
+}
+/// Gets the [`RAGStatus`] of the file review, within the [`crate::settings::RagThresholds`] of its language
+fn get_file_rag_status(settings: &Settings, file_review: &SourceFileReview) -> RAGStatus {
+    let rag_thresholds = settings.get_rag_thresholds(
+        file_review
+            .source_file_info
+            .language
+            .as_ref()
+            .map(|language| language.name.as_str()),
+    );
+    calculate_rag_status_for_reviewed_file(file_review, &rag_thresholds).unwrap_or_default()
+}
+/// Asks the LLM to summarise what the file does, before it is reviewed, i.e., the 'plan' of a two stage review.
+/// A failure is logged, and the file reviewed without a plan.
+async fn plan_file_review(
+    settings: &Settings,
+    provider: &ProviderSettings,
+    file_info: &SourceFileInfo,
+) -> Option<ProviderCompletionResponse> {
+    let mut plan_prompt = match PromptData::get_file_plan_prompt(settings.prompts_path.as_deref()) {
+        Ok(plan_prompt) => plan_prompt,
+        Err(e) => {
+            warn!("Failed to get the plan prompt: {}", e);
+            return None;
+        }
+    };
+    plan_prompt.id = Some(file_info.relative_path.clone());
+    plan_prompt.add_user_message_prompt(get_review_request(file_info));
+    match review_or_summarise(RequestType::Plan, settings, provider, &plan_prompt).await {
+        Ok(plan) => Some(plan),
+        Err(e) => {
+            warn!(
+                "Failed to plan the review of: {}, reviewing without: {}",
+                file_info.relative_path, e
+            );
+            None
+        }
+    }
+}
+/// Gets the user message that asks for the review of the file, noting any secrets redacted from its contents, its
+/// test coverage, if known, and its linter diagnostics, if any
+fn get_review_request(file_info: &SourceFileInfo) -> String {
+    let coverage_note: String = match file_info.coverage {
+        Some(coverage) => format!(
+            " note: this file has {:.0}% test coverage. Weigh the risk of its findings accordingly, and suggest tests for the untested logic.\n",
+            coverage
+        ),
+        None => String::new(),
+    };
+    let diagnostics_note: String = match &file_info.diagnostics {
+        Some(diagnostics) => {
+            let mut note = String::from(
+                " note: static linters reported these diagnostics for the file. Build on them, e.g., their impact and fix, rather than repeat them:\n",
+            );
+            for diagnostic in diagnostics.iter().take(MAX_PROMPT_DIAGNOSTICS) {
+                note.push_str(&format!(
+                    "  line {}: {} {} ({}): {}\n",
+                    diagnostic.line,
+                    diagnostic.tool,
+                    diagnostic.rule.as_deref().unwrap_or("-"),
+                    diagnostic.level,
+                    diagnostic.message
+                ));
+            }
+            note
+        }
+        None => String::new(),
+    };
+    let redaction_note: String = match &file_info.redacted_secrets {
+        Some(redacted_secrets) => format!(
+            " note: secrets were redacted from the contents, as '[REDACTED:{{kind}}]', of kinds: {}. Each is a secret hardcoded in the original file, so report it as a security issue.\n",
+            redacted_secrets.join(", ")
+        ),
+        None => String::new(),
+    };
+    let patch_hunk_note: String = match &file_info.patch_hunk {
+        Some(header) => format!(
+            " note: the contents are only the hunk: '{}', of a unified diff of the file. Lines starting with '+' were added, '-' removed, and ' ' are unchanged context. Review the change, i.e., the added lines, in its context.\n",
+            header
+        ),
+        None => String::new(),
+    };
+    let notebook_note: &str = if is_notebook(&file_info.relative_path) {
+        " note: the contents are the code cells of a Jupyter notebook, in order, each headed by its execution count, without their outputs. Review them as one script.\n"
+    } else {
+        ""
+    };
+    let code_unit_note: String = match &file_info.code_unit {
+        Some(unit) => format!(
+            " note: the contents are only the unit: '{}', lines {} to {}, of the file. Review only this unit.\n",
+            unit.name, unit.start_line, unit.end_line
+        ),
+        None => String::new(),
+    };
+    format!(
+        "Source file to review:\n file name: {}\n{}{}{}{}{}{} contents: \n{}\n",
+        file_info.relative_path,
+        patch_hunk_note,
+        notebook_note,
+        code_unit_note,
+        coverage_note,
+        diagnostics_note,
+        redaction_note,
+        file_info.get_source_file_contents()
+    )
+}
+/// Fetches the correct [`PromptData`] according to the [`ReviewType`] passed, with the guidance for the file's language
 fn get_prompt_data_based_on_review_type(
     settings: &Settings,
+    file_info: &SourceFileInfo,
 ) -> Result<Option<PromptData>, Box<dyn std::error::Error>> {
-    match settings.review_type {
-        ReviewType::General => PromptData::get_code_review_prompt().map(Some),
-        ReviewType::Security => PromptData::get_security_review_prompt().map(Some),
+    let prompts_path: Option<&str> = settings.prompts_path.as_deref();
+    let mut prompt_data = match &settings.review_type {
+        ReviewType::General => PromptData::get_code_review_prompt(prompts_path)?,
+        ReviewType::Security => PromptData::get_security_review_prompt(prompts_path)?,
+        ReviewType::Custom(name) => {
+            let custom_review_type = settings
+                .get_custom_review_type()
+                .ok_or_else(|| format!("No custom_review_types entry named: '{}'", name))?;
+            PromptData::get_custom_review_prompt(
+                &custom_review_type.prompt_path,
+                &get_review_schema(settings)?,
+            )?
+        }
         ReviewType::CodeStats => {
             info!("CODE STATISTICS ONLY. Only running code statistics, no review run.");
-            Ok(None)
+            return Ok(None);
         }
-    }
+        ReviewType::Compliance => {
+            info!("COMPLIANCE ONLY. Only inventorying licences, no review run.");
+            return Ok(None);
+        }
+    };
+    let language: Option<&str> = file_info
+        .language
+        .as_ref()
+        .map(|language| language.name.as_str());
+    prompt_data.add_language_guidance(prompts_path, language)?;
+    Ok(Some(prompt_data))
 }
 
-/// passes to the LLM the required review via a preconfigured [`PromptData`]
+/// passes to the LLM, of the passed service of the provider, the required review via a preconfigured [`PromptData`]
+/// A response that cannot be parsed into a [`SourceFileReview`] is sent back to the LLM, with the error, to be corrected.
+/// If that fails, the review is retried under the provider's [`RetryPolicy`]
 async fn perform_review(
     settings: &Settings,
     provider: &ProviderSettings,
+    service: &ServiceSettings,
     prompt_data: &PromptData,
 ) -> Result<Option<SourceFileReview>, Box<dyn std::error::Error>> {
-    let max_retries = provider.max_retries.unwrap_or(0);
-    let mut attempts = 0;
-
-    loop {
-        match review_or_summarise(RequestType::Review, settings, provider, prompt_data).await {
-            Ok(response) => match process_llm_response(&response) {
-                Ok(file_review) => return Ok(Some(file_review)),
-                Err(e) if attempts < max_retries => {
-                    error!("Error processing response: {}", e);
-                    attempts += 1;
+    let file_review = RetryPolicy::from_provider_settings(provider)
+        .retry("Review", move || async move {
+            let response = review_or_summarise_with_service(
+                RequestType::Review,
+                settings,
+                provider,
+                service,
+                prompt_data,
+            )
+            .await?;
+            let file_review = match process_llm_response(settings, &response) {
+                Ok(mut file_review) => {
+                    file_review.usage = response.usage.clone();
+                    file_review
                 }
-                Err(e) => return Err(e),
-            },
-            Err(e) if attempts < max_retries => {
-                error!("Error in review: {}", e);
-                attempts += 1;
-            }
-            Err(e) => {
-                error!(
-                    "Failed after {} attempts. Cannot recover from error ",
-                    max_retries
-                );
-                return Err(e);
+                Err(e) => {
+                    repair_llm_response(settings, provider, service, prompt_data, &response, e)
+                        .await?
+                }
+            };
+            Ok::<_, Box<dyn std::error::Error>>(file_review)
+        })
+        .await
+        .map_err(|e| {
+            error!("Cannot recover from error: {}", e);
+            e
+        })?;
+    Ok(Some(file_review))
+}
+/// Asks the LLM to correct a response that could not be processed, by sending back the response along with the error.
+/// If the correction also fails, the original error is returned, so that the review is retried.
+async fn repair_llm_response(
+    settings: &Settings,
+    provider: &ProviderSettings,
+    service: &ServiceSettings,
+    prompt_data: &PromptData,
+    response: &ProviderCompletionResponse,
+    error: Box<dyn std::error::Error>,
+) -> Result<SourceFileReview, Box<dyn std::error::Error>> {
+    info!(
+        "Asking model: {} to correct its response for: {}",
+        response.model,
+        prompt_data.id.as_deref().unwrap_or_default()
+    );
+    let repair_prompt = prompt_data.get_json_repair_prompt(
+        settings.prompts_path.as_deref(),
+        &response.choices[0].message.content,
+        &error.to_string(),
+    )?;
+    let repaired_response = match review_or_summarise_with_service(
+        RequestType::Review,
+        settings,
+        provider,
+        service,
+        &repair_prompt,
+    )
+    .await
+    {
+        Ok(repaired_response) => repaired_response,
+        Err(e) => {
+            warn!("Failed to get a corrected response: {}", e);
+            return Err(error);
+        }
+    };
+    match process_llm_response(settings, &repaired_response) {
+        Ok(mut file_review) => {
+            let mut usage = response.usage.clone().unwrap_or_default();
+            if let Some(repair_usage) = &repaired_response.usage {
+                usage.add(repair_usage);
             }
+            file_review.usage = Some(usage);
+            Ok(file_review)
+        }
+        Err(e) => {
+            warn!("Corrected response could not be processed: {}", e);
+            Err(error)
         }
     }
 }
 /// processes the response returned by the LLM, stripping any artefacts, or illegal chars, then loading the JSON into a [`SourceFileReview`]
+///
+/// The JSON is validated against the review schema. If not valid, it is repaired where possible, with the violations
+/// recorded on the [`SourceFileReview`], otherwise a [`SchemaValidationError`] is returned.
 fn process_llm_response(
+    settings: &Settings,
     response: &ProviderCompletionResponse,
 ) -> Result<SourceFileReview, Box<dyn std::error::Error>> {
     let orig_response_json = response.choices[0].message.content.to_string();
 
-    strip_artifacts_from(&orig_response_json)
-        .map_err(|e| {
-            Box::new(std::io::Error::new(std::io::ErrorKind::Other, e))
-                as Box<dyn std::error::Error>
-        })
-        .and_then(|stripped_json| {
-            data::deserialize_file_review(&stripped_json).map_err(|e| {
-                info!(
-                    "Failed to deserialize ProviderCompletionResponse from model: {};  JSON: {:?}",
-                    response.model, &stripped_json
-                );
-                Box::new(e) as Box<dyn std::error::Error>
-            })
-        })
+    let stripped_json = strip_artifacts_from(&orig_response_json)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+    let mut response_json: serde_json::Value =
+        serde_json::from_str(&stripped_json).map_err(|e| {
+            info!(
+                "Failed to deserialize ProviderCompletionResponse from model: {};  JSON: {:?}",
+                response.model, &stripped_json
+            );
+            e
+        })?;
+    let normalisations: Vec<String> = normalise_file_review(&mut response_json);
+
+    let validator = SchemaValidator::for_review_type(settings)?;
+    let violations: Vec<String> = validator.validate(&response_json);
+    if !violations.is_empty() {
+        let repairs = validator.repair(&mut response_json);
+        let remaining: Vec<String> = validator.validate(&response_json);
+        if !remaining.is_empty() {
+            info!(
+                "Response from model: {} is not valid against the schema, after repair: {:?}",
+                response.model, remaining
+            );
+            return Err(Box::new(SchemaValidationError {
+                violations: remaining,
+            }));
+        }
+        warn!(
+            "Repaired response from model: {}: {:?}",
+            response.model, repairs
+        );
+    }
+    let mut file_review = data::deserialize_file_review(&response_json.to_string())?;
+    // The values normalised are recorded with the violations repaired, so that each coercion can be reviewed
+    let violations: Vec<String> = normalisations.into_iter().chain(violations).collect();
+    if !violations.is_empty() {
+        file_review.schema_violations = Some(violations);
+    }
+    Ok(file_review)
 }
 /// Asks the LLM to summarise a concat text of [`SourceFileReview`] summaries (in `review_summary.text`) into a concise overall repository summary
 /// # Parameters:
@@ -374,12 +1462,16 @@ pub(crate) async fn summarise_review_summaries(
     info!("Creating repository summary statement");
 
     let provider: &ProviderSettings = get_provider(settings);
-    let mut prompt_data: PromptData = PromptData::get_overall_summary_prompt()?;
+    let mut prompt_data: PromptData = PromptData::get_overall_summary_prompt(
+        settings.prompts_path.as_deref(),
+        settings.get_summary_language(),
+    )?;
 
     debug!("Input review summaries: {}", review_summary.text);
 
     let summary_request: String = review_summary.text.to_string();
     prompt_data.add_user_message_prompt(summary_request);
+    warn_if_exceeds_context_window(settings, &get_service(provider).model, &prompt_data);
 
     let response_result: Result<ProviderCompletionResponse, Box<dyn Error>> =
         review_or_summarise(RequestType::Summarise, settings, provider, &prompt_data).await;
@@ -395,8 +1487,42 @@ pub(crate) async fn summarise_review_summaries(
         Err(e) => Err(e),
     }
 }
-/// validates the provided [`Path`] as being a directory that holds a '.git' subdirectory - i.e. is a valid git repository
-fn validate_repository(repository_root: PathBuf) -> Result<PathBuf, PathError> {
+/// Asks the LLM to summarise the README of the repository, if it has one, as the purpose of the repository
+async fn summarise_readme(
+    settings: &Settings,
+    repository_root: &Path,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let Some((readme_path, readme)) = read_readme(repository_root) else {
+        info!("No README found to summarise the purpose of the repository");
+        return Ok(None);
+    };
+    info!("Summarising the README: {}", readme_path.display());
+
+    let provider: &ProviderSettings = get_provider(settings);
+    let mut prompt_data: PromptData = PromptData::get_readme_summary_prompt(
+        settings.prompts_path.as_deref(),
+        settings.get_summary_language(),
+    )?;
+    prompt_data.add_user_message_prompt(format!("Documentation to summarise:\n{}", readme));
+    warn_if_exceeds_context_window(settings, &get_service(provider).model, &prompt_data);
+
+    let response =
+        review_or_summarise(RequestType::Summarise, settings, provider, &prompt_data).await?;
+    Ok(Some(response.choices[0].message.content.trim().to_string()))
+}
+/// Checks out the `repository_ref`, if set, into a temporary [`ReviewWorktree`]. A bare repository has no working tree
+/// to review, so its 'HEAD' is checked out if no ref is set.
+fn open_review_worktree(settings: &Settings) -> Result<Option<ReviewWorktree>, SourceFileError> {
+    settings
+        .repository_ref
+        .as_deref()
+        .or_else(|| is_bare_repository(&settings.repository_path).then_some("HEAD"))
+        .map(|reference| ReviewWorktree::new(&settings.repository_path, reference))
+        .transpose()
+}
+/// validates the provided [`Path`] as being a directory that is a git repository, i.e., a working tree, with a '.git'
+/// directory, a linked worktree, with a '.git' file, or a bare repository. If git is not required, any directory is valid.
+fn validate_repository(repository_root: PathBuf, require_git: bool) -> Result<PathBuf, PathError> {
     if !repository_root.is_dir() {
         return Err(PathError {
             message: format!(
@@ -405,11 +1531,19 @@ fn validate_repository(repository_root: PathBuf) -> Result<PathBuf, PathError> {
             ),
         });
     }
-    if !repository_root.join(".git").is_dir() {
+    if let Err(e) = open_repository(&repository_root) {
+        if !require_git {
+            warn!(
+                "Not a Git repository, so its commits, contributors and change frequencies are not reviewed: {}",
+                repository_root.display()
+            );
+            return Ok(repository_root);
+        }
         return Err(PathError {
             message: format!(
-                "Provided path is not a valid Git repository: {}",
-                repository_root.display()
+                "Provided path is not a valid Git repository: {}: {}",
+                repository_root.display(),
+                e.message()
             ),
         });
     }
@@ -417,17 +1551,34 @@ fn validate_repository(repository_root: PathBuf) -> Result<PathBuf, PathError> {
     Ok(repository_root)
 }
 
+/// Reads the contents of the file at the relative path that are reviewed in the [`ReviewScope`], i.e., for a staged
+/// review, those in the index, not any unstaged edits in the working tree, otherwise those of the working tree
+fn read_reviewed_contents(
+    review_scope: &ReviewScope,
+    repo_root: &Path,
+    relative_path: &str,
+) -> Option<String> {
+    match review_scope {
+        ReviewScope::Staged => get_staged_contents(repo_root.to_str()?, relative_path).ok(),
+        _ => fs::read_to_string(repo_root.join(relative_path)).ok(),
+    }
+}
 /// Builds the initial [`SourceFileInfo`]
 /// At his point it is not known if the file is a source file, so this is determined by the [`LanguageType`] returned from the Linguist crate
 /// There is cost in doing all this work here, but the contents of the file needs to be assessed for accuracy.
 ///
 /// # Parameters:
+/// * `settings` - The [`Settings`], e.g., of the languages reviewed
 /// * `entry` - A [`DirEntry`] that represents the file to be assessed
 /// * `repo_root` - A [`PathBuf`] that represents the root of the repository
 ///
 /// # Returns:
 /// * A [`SourceFileInfo`] if the file is a source file, otherwise None
-fn get_initial_source_file_info(entry: &DirEntry, repo_root: &PathBuf) -> Option<SourceFileInfo> {
+fn get_initial_source_file_info(
+    settings: &Settings,
+    entry: &DirEntry,
+    repo_root: &PathBuf,
+) -> Option<SourceFileInfo> {
     let path = entry.path();
     let relative_path = path.strip_prefix(repo_root).ok()?.to_path_buf();
 
@@ -435,14 +1586,31 @@ fn get_initial_source_file_info(entry: &DirEntry, repo_root: &PathBuf) -> Option
     let file_name = path.file_name().unwrap().to_str().unwrap().to_string();
     let relative_path_str = relative_path.to_str()?.to_string();
 
-    let contents = fs::read_to_string(path).ok()?;
+    let contents = read_reviewed_contents(&settings.review_scope, repo_root, &relative_path_str)?;
+    // Only the code cells of a notebook are reviewed, not its JSON, e.g., the outputs
+    let contents = if is_notebook(&relative_path_str) {
+        match get_notebook_code(&contents) {
+            Some(code) => code,
+            None => {
+                debug!("Not a valid notebook: {}", relative_path_str);
+                return None;
+            }
+        }
+    } else {
+        contents
+    };
     let id_hash = calculate_hash_from(&contents);
     let ext = path.extension()?.to_str()?.to_string();
 
+    // A file that is not in git, e.g., of a plain directory, has no change frequency
     let stats: Statistics =
-        get_source_file_change_frequency(repo_root.to_str()?, &relative_path_str)
-            .ok()?
-            .get_as_statistics();
+        match get_source_file_change_frequency(repo_root.to_str()?, &relative_path_str) {
+            Ok(change_frequency) => change_frequency.get_as_statistics(),
+            Err(e) => {
+                debug!("No change frequency for: {}: {}", relative_path_str, e);
+                Statistics::new()
+            }
+        };
 
     let language = LanguageType {
         name: String::new(), // Don't know this yet
@@ -458,25 +1626,244 @@ fn get_initial_source_file_info(entry: &DirEntry, repo_root: &PathBuf) -> Option
     );
     source_file_info.set_source_file_contents(contents);
 
-    analyse_file_language(source_file_info).cloned()
+    let mut file_info: SourceFileInfo =
+        analyse_file_language(settings, source_file_info).cloned()?;
+    scrub_source_file_secrets(&mut file_info);
+    Some(file_info)
+}
+/// Gets the [`MAX_COMPLEX_FILES`] files with the highest complexity, most complex first
+fn get_most_complex_files(source_files: &[SourceFileInfo]) -> Vec<ComplexFile> {
+    let mut complex_files: Vec<ComplexFile> = source_files
+        .iter()
+        .filter_map(|file_info| {
+            Some(ComplexFile {
+                relative_path: file_info.relative_path.clone(),
+                complexity: file_info.statistics.complexity?,
+                loc: file_info.statistics.loc,
+            })
+        })
+        .collect();
+    complex_files.sort_by_key(|complex_file| std::cmp::Reverse(complex_file.complexity));
+    complex_files.truncate(MAX_COMPLEX_FILES);
+    complex_files
+}
+/// Removes the files ignored by annotation, i.e., 'cosmonaut:ignore', checked once per whole file, before any is split
+/// into units, as only the unit with the annotation would have it
+fn remove_ignored_files(source_files: Vec<SourceFileInfo>) -> Vec<SourceFileInfo> {
+    source_files
+        .into_iter()
+        .filter(|file_info| {
+            let ignored = is_file_ignored(&file_info.get_source_file_contents());
+            if ignored {
+                info!("Ignored by annotation: {}", file_info.relative_path);
+            }
+            !ignored
+        })
+        .collect()
+}
+/// Splits each [`SourceFileInfo`] into one per [`crate::retrieval::units::CodeUnit`], i.e., function, of the file, with the
+/// unit as its contents and its own hash and statistics. A file in which no function is found, or that is over the
+/// 'max_file_size_kb', so is not reviewed, is kept whole.
+fn split_source_files_into_units(
+    settings: &Settings,
+    source_files: Vec<SourceFileInfo>,
+) -> Vec<SourceFileInfo> {
+    let mut unit_files: Vec<SourceFileInfo> = Vec::new();
+    for file_info in source_files {
+        if get_exceeded_max_file_size(settings, &file_info).is_some() {
+            unit_files.push(file_info);
+            continue;
+        }
+        let language: Option<&str> = file_info
+            .language
+            .as_ref()
+            .map(|language| language.name.as_str());
+        let units = split_into_units(&file_info.get_source_file_contents(), language);
+        if units.is_empty() {
+            unit_files.push(file_info);
+            continue;
+        }
+        debug!(
+            "Split into {} units: {}",
+            units.len(),
+            file_info.relative_path
+        );
+        for (unit, contents) in units {
+            let mut unit_info: SourceFileInfo = file_info.clone();
+            unit_info.id_hash = Some(calculate_hash_from(&format!(
+                "{}:{}:{}",
+                file_info.relative_path, unit.name, contents
+            )));
+            unit_info.statistics.size = contents.len() as i64;
+            unit_info.statistics.loc = count_lines_of_code(contents.clone()).unwrap_or_default();
+            unit_info.statistics.complexity =
+                language.map(|language| calculate_complexity(&contents, language));
+            unit_info.markers = count_markers(&contents);
+            // Only the unit's own diagnostics are given with its review
+            unit_info.diagnostics = file_info.diagnostics.as_ref().and_then(|diagnostics| {
+                let diagnostics: Vec<Diagnostic> = diagnostics
+                    .iter()
+                    .filter(|diagnostic| {
+                        (unit.start_line..=unit.end_line).contains(&diagnostic.line)
+                    })
+                    .cloned()
+                    .collect();
+                (!diagnostics.is_empty()).then_some(diagnostics)
+            });
+            unit_info.code_unit = Some(unit);
+            unit_info.set_source_file_contents(contents);
+            unit_files.push(unit_info);
+        }
+    }
+    unit_files
 }
+/// Builds the [`SourceFileInfo`] of a [`PatchHunk`], with the hunk as its contents, or [`None`] if the file is not a
+/// source file, as determined by the [`LanguageType`] returned from the Linguist crate
+fn get_patch_hunk_source_file_info(
+    settings: &Settings,
+    hunk: &PatchHunk,
+) -> Option<SourceFileInfo> {
+    let path = Path::new(&hunk.file_path);
+    let file_name = path.file_name()?.to_str()?.to_string();
+    let ext = path.extension()?.to_str()?.to_string();
+    let id_hash = calculate_hash_from(&format!("{}:{}", hunk.file_path, hunk.contents));
 
-/// Gets an overall [`RAGStatus`] for the passed [`RepositoryReview`]
-fn get_overall_rag_for(review: &RepositoryReview) -> RAGStatus {
-    if let Some(breakdown) = &review.summary {
-        let num_total_files = review.file_reviews.len() as i32;
+    let language = LanguageType {
+        name: String::new(), // Don't know this yet
+        extension: ext,
+        statistics: None,
+    };
+    let source_file_info: &mut SourceFileInfo = &mut SourceFileInfo::new(
+        file_name,
+        hunk.file_path.clone(),
+        language,
+        id_hash,
+        Statistics::new(),
+    );
+    source_file_info.set_source_file_contents(hunk.contents.clone());
 
-        if breakdown.security_issues.high > 0 || breakdown.security_issues.critical > 0 {
-            return RAGStatus::Red;
-        }
+    let mut file_info: SourceFileInfo =
+        analyse_file_language(settings, source_file_info).cloned()?;
+    file_info.patch_hunk = Some(hunk.header.clone());
+    scrub_source_file_secrets(&mut file_info);
+    Some(file_info)
+}
+/// Scrubs the secrets from the contents of the file once the statistics are taken, so that the contents are never sent
+/// to a provider with them
+fn scrub_source_file_secrets(file_info: &mut SourceFileInfo) {
+    let (scrubbed_contents, redacted_secrets) =
+        scrub_secrets(&file_info.get_source_file_contents());
+    if !redacted_secrets.is_empty() {
+        warn!(
+            "Redacted secrets from: {}, before review: {}",
+            file_info.relative_path,
+            redacted_secrets.join(", ")
+        );
+        file_info.set_source_file_contents(scrubbed_contents);
+        file_info.redacted_secrets = Some(redacted_secrets);
+    }
+}
 
-        let security_issues_ratio = breakdown.security_issues.total as f64 / num_total_files as f64;
-        let errors_ratio = breakdown.errors as f64 / num_total_files as f64;
-        let improvements_ratio = breakdown.improvements as f64 / num_total_files as f64;
+/// Gets the kinds of secret redacted from the file, if the file is not to be sent to the provider because of them, i.e.,
+/// 'skip_files_with_secrets' is set
+fn get_skipped_secrets<'a>(
+    settings: &Settings,
+    file_info: &'a SourceFileInfo,
+) -> Option<&'a Vec<String>> {
+    file_info
+        .redacted_secrets
+        .as_ref()
+        .filter(|_| settings.skip_files_with_secrets)
+}
 
-        if security_issues_ratio > 0.05 || errors_ratio > 0.08 || improvements_ratio > 0.80 {
-            return RAGStatus::Amber;
-        }
+/// Gets the reason the file is taken as generated, if generated files are skipped, so is not to be sent to the provider
+fn get_skipped_generated<'a>(
+    settings: &Settings,
+    file_info: &'a SourceFileInfo,
+) -> Option<&'a str> {
+    file_info
+        .generated
+        .as_deref()
+        .filter(|_| settings.skip_generated_files)
+}
+
+/// Gets the 'max_file_size_kb', if the file is over it, so is not to be sent to the provider
+fn get_exceeded_max_file_size(settings: &Settings, file_info: &SourceFileInfo) -> Option<u64> {
+    settings
+        .max_file_size_kb
+        .filter(|max_file_size_kb| file_info.statistics.size as u64 > max_file_size_kb * 1024)
+}
+/// Gets the summary of a file not sent for review as it is too large, i.e., its size and, as its structure, its
+/// functions, if any are found
+fn get_structural_summary(file_info: &SourceFileInfo, max_file_size_kb: u64) -> String {
+    let mut summary = format!(
+        "Not sent for review, as it is {} KB, over the maximum of {} KB.",
+        file_info.statistics.size / 1024,
+        max_file_size_kb
+    );
+    let language: Option<&str> = file_info
+        .language
+        .as_ref()
+        .map(|language| language.name.as_str());
+    let functions: Vec<String> = split_into_units(&file_info.get_source_file_contents(), language)
+        .into_iter()
+        .map(|(unit, _)| unit)
+        .filter(|unit| unit.name != TOP_LEVEL_UNIT)
+        .map(|unit| {
+            format!(
+                "{} (lines {} to {})",
+                unit.name, unit.start_line, unit.end_line
+            )
+        })
+        .collect();
+    if !functions.is_empty() {
+        summary.push_str(&format!(
+            " It has {} functions: {}{}",
+            functions.len(),
+            functions
+                .iter()
+                .take(MAX_SUMMARY_FUNCTIONS)
+                .cloned()
+                .collect::<Vec<String>>()
+                .join(", "),
+            if functions.len() > MAX_SUMMARY_FUNCTIONS {
+                ", ..."
+            } else {
+                ""
+            }
+        ));
+    }
+    summary
+}
+/// Gets an overall [`RAGStatus`] for the passed [`RepositoryReview`]
+pub(crate) fn get_overall_rag_for(review: &RepositoryReview) -> RAGStatus {
+    match &review.summary {
+        Some(breakdown) => get_rag_status_for(
+            review.statistics.num_files,
+            &breakdown.security_issues,
+            breakdown.errors,
+            breakdown.improvements,
+        ),
+        None => RAGStatus::Green,
+    }
+}
+/// Gets the [`RAGStatus`] of the findings of a number of files, e.g., of the repository, or a directory of it
+pub(crate) fn get_rag_status_for(
+    num_files: i32,
+    security_issues: &SecurityIssueBreakdown,
+    errors: i32,
+    improvements: i32,
+) -> RAGStatus {
+    if security_issues.high > 0 || security_issues.critical > 0 {
+        return RAGStatus::Red;
+    }
+
+    let security_issues_ratio = security_issues.total as f64 / num_files as f64;
+    let errors_ratio = errors as f64 / num_files as f64;
+    let improvements_ratio = improvements as f64 / num_files as f64;
+
+    if security_issues_ratio > 0.05 || errors_ratio > 0.08 || improvements_ratio > 0.80 {
+        return RAGStatus::Amber;
     }
 
     RAGStatus::Green
@@ -539,11 +1926,11 @@ fn strip_artifacts_from(orig_json_str: &str) -> Result<String, &'static str> {
     }
 }
 /// Gets the current time and date as a string
-fn get_review_date() -> String {
+pub(crate) fn get_review_date() -> String {
     // Date stamp the review
     let now_utc: DateTime<Utc> = Utc::now();
     let now_local = now_utc.with_timezone(&Local);
-    let review_date = now_local.format("%H:%M, %d/%m/%Y").to_string();
+    let review_date = now_local.format(REVIEW_DATE_FORMAT).to_string();
     review_date
 }
 
@@ -568,9 +1955,33 @@ impl Error for PathError {}
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::provider::api::TokenUsage;
+    use crate::provider::cost::record_usage;
 
     const JSON_OPENING: &str = "```json";
     const JSON_CLOSE: &str = "```";
+
+    fn test_settings() -> Settings {
+        serde_json::from_str(
+            r#"{
+                "providers": [{
+                    "name": "openai",
+                    "services": [{
+                        "name": "gpt-4",
+                        "model": "gpt-4",
+                        "pricing": { "prompt_per_million": 10.0, "completion_per_million": 30.0 }
+                    }],
+                    "default_service": "gpt-4",
+                    "api_url": "https://api.openai.com"
+                }],
+                "default_provider": "openai",
+                "repository_path": "path/to/repo",
+                "report_output_path": "path/to/report"
+            }"#,
+        )
+        .unwrap()
+    }
+
     #[test]
     fn test_strip_json_markers() {
         let json_str_with_markers =
@@ -594,6 +2005,155 @@ mod tests {
         assert_eq!(result.unwrap(), expected_json);
     }
 
+    #[test]
+    fn test_sort_by_risk() {
+        let source_file = |relative_path: &str, num_commits: i32, size: i64| {
+            let mut statistics = Statistics::new();
+            statistics.num_commits = num_commits;
+            statistics.size = size;
+            SourceFileInfo::new_rust(relative_path, statistics)
+        };
+        let mut source_files = vec![
+            source_file("src/a.rs", 0, 1000),
+            source_file("src/b.rs", 9, 500),
+            source_file("src/c.rs", 1, 500),
+            source_file("src/d.rs", 0, 1000),
+        ];
+        sort_by_risk(&mut source_files);
+        let relative_paths: Vec<&str> = source_files
+            .iter()
+            .map(|file_info| file_info.relative_path.as_str())
+            .collect();
+        assert_eq!(
+            relative_paths,
+            vec!["src/b.rs", "src/a.rs", "src/c.rs", "src/d.rs"]
+        );
+    }
+
+    #[test]
+    fn test_max_file_size() {
+        let mut file_info = SourceFileInfo::new_rust("src/lib.rs", Statistics::new());
+        file_info.set_source_file_contents(
+            "use std::fs;\n\nfn read() {\n    fs::read(\"a\");\n}\n\nfn write() {}\n".to_string(),
+        );
+        file_info.statistics.size = 3 * 1024;
+        let mut settings: Settings = test_settings();
+        assert_eq!(get_exceeded_max_file_size(&settings, &file_info), None);
+        settings.max_file_size_kb = Some(2);
+        assert_eq!(get_exceeded_max_file_size(&settings, &file_info), Some(2));
+        assert_eq!(
+            get_structural_summary(&file_info, 2),
+            "Not sent for review, as it is 3 KB, over the maximum of 2 KB. It has 2 functions: read (lines 3 to 5), write (lines 7 to 7)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_finalise_review_cost() {
+        let settings: Settings = test_settings();
+        let provider: &ProviderSettings = get_provider(&settings);
+        let response = ProviderCompletionResponse {
+            id: String::new(),
+            model: "gpt-4".to_string(),
+            choices: Vec::new(),
+            usage: Some(TokenUsage {
+                prompt_tokens: 1_000,
+                completion_tokens: 500,
+                cached_prompt_tokens: 0,
+            }),
+        };
+        // The architecture review, which is requested before the review is finalised
+        record_usage(
+            &settings,
+            &RequestType::Summarise,
+            None,
+            provider,
+            get_service(provider),
+            &response,
+        );
+        let mut review = RepositoryReview::new("repos_name".to_string());
+        finalise_review(
+            &mut review,
+            &mut initialise_review_summary_section(),
+            &mut [],
+            None,
+            &settings,
+        )
+        .await
+        .unwrap();
+        let cost = review.cost.unwrap();
+        assert!(cost
+            .request_types
+            .iter()
+            .any(|request_cost| request_cost.name == "summarise"));
+        assert!(cost.total_cost > 0.0);
+        assert_eq!(review.statistics.token_usage.unwrap().prompt_tokens, 1_000);
+    }
+
+    #[test]
+    fn test_is_predominantly_by() {
+        let author_lines = |lines: [usize; 2]| {
+            vec![
+                AuthorLines {
+                    name: "Ana Smith".to_string(),
+                    email: "ana@example.com".to_string(),
+                    lines: lines[0],
+                },
+                AuthorLines {
+                    name: "Ben".to_string(),
+                    email: "ben@example.org".to_string(),
+                    lines: lines[1],
+                },
+            ]
+        };
+        let authors = RegexSet::new([r"(?i)^ana\b", r"@example\.net$"]).unwrap();
+        assert!(is_predominantly_by(&authors, &author_lines([6, 4])));
+        assert!(!is_predominantly_by(&authors, &author_lines([5, 5])));
+        let authors = RegexSet::new([r"@example\.org$"]).unwrap();
+        assert!(is_predominantly_by(&authors, &author_lines([1, 9])));
+        assert!(!is_predominantly_by(&authors, &[]));
+    }
+
+    #[test]
+    fn test_validate_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let plain_path = dir.path().to_path_buf();
+        assert!(validate_repository(plain_path.clone(), true).is_err());
+        assert_eq!(
+            validate_repository(plain_path.clone(), false).unwrap(),
+            plain_path
+        );
+        assert!(validate_repository(plain_path.join("missing"), false).is_err());
+    }
+
+    #[test]
+    fn test_read_repository_files_named() {
+        let dir = tempfile::tempdir().unwrap();
+        for relative_path in [
+            "Cargo.toml",
+            "backend/Cargo.toml",
+            "backend/README.md",
+            "docs/guide.md",
+        ] {
+            let path = dir.path().join(relative_path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        let mut settings: Settings = test_settings();
+        let repository_root: PathBuf = dir.path().to_path_buf();
+        assert_eq!(
+            read_repository_files_named(&settings, &repository_root, &["Cargo.toml"]).len(),
+            2
+        );
+        assert_eq!(count_documentation_files(&settings, &repository_root), 2);
+        // Only the manifests and documentation within the review paths are read
+        settings.review_paths = vec!["backend/".to_string()];
+        assert_eq!(
+            read_repository_files_named(&settings, &repository_root, &["Cargo.toml"]),
+            vec![("backend/Cargo.toml".to_string(), String::new())]
+        );
+        assert_eq!(count_documentation_files(&settings, &repository_root), 1);
+    }
+
     #[test]
     fn test_normal_directory_path() {
         let path_str = "/location/dirname/cosmonaut-code";
@@ -0,0 +1,376 @@
+//! Normalises the enum values in a review response before it is validated and deserialised, as different models return
+//! them in different casings and vocabularies, e.g., "sev: HIGH", "critical!" or "Major".
+//!
+//! A known synonym is mapped to its enum value. An unknown severity is mapped to 'Medium', and an unknown RAG status
+//! removed, as it is calculated from the findings anyway. The CWE id and OWASP Top 10 category of a security issue are
+//! normalised in the same way, e.g., "cwe 89" to 'CWE-89', and removed if not known. Each value changed is recorded
+//! with the review.
+//!
+//! Models also often repeat a finding in a review, which would inflate its counts and RAG status, so repeated findings
+//! are removed before the statistics are calculated.
+use crate::review::data::{SecurityIssue, SourceFileReview};
+use log::{debug, warn};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+/// The OWASP Top 10 (2021) categories, in order, i.e., 'A01' first
+const OWASP_CATEGORIES: [&str; 10] = [
+    "A01:2021-Broken Access Control",
+    "A02:2021-Cryptographic Failures",
+    "A03:2021-Injection",
+    "A04:2021-Insecure Design",
+    "A05:2021-Security Misconfiguration",
+    "A06:2021-Vulnerable and Outdated Components",
+    "A07:2021-Identification and Authentication Failures",
+    "A08:2021-Software and Data Integrity Failures",
+    "A09:2021-Security Logging and Monitoring Failures",
+    "A10:2021-Server-Side Request Forgery",
+];
+/// The index of the OWASP Top 10 (2021) category of each OWASP Top 10 (2017) category, in the order of the latter, as
+/// mapped by OWASP, e.g., 'A7:2017-Cross-Site Scripting' to 'A03:2021-Injection'
+const OWASP_2017_CATEGORIES: [usize; 10] = [2, 6, 1, 4, 0, 4, 2, 7, 5, 8];
+/// The words of the name of each OWASP Top 10 category, lowercased without spaces, in the order of the categories
+const OWASP_CATEGORY_NAMES: [&[&str]; 10] = [
+    &["brokenaccesscontrol", "accesscontrol"],
+    &["cryptographic", "sensitivedataexposure"],
+    &["injection", "crosssitescripting", "xss"],
+    &["insecuredesign"],
+    &["misconfiguration", "xmlexternalentities", "xxe"],
+    &[
+        "vulnerableandoutdated",
+        "vulnerablecomponents",
+        "outdatedcomponents",
+    ],
+    &[
+        "identificationandauthentication",
+        "brokenauthentication",
+        "authenticationfailures",
+    ],
+    &["integrity", "insecuredeserialization"],
+    &["loggingandmonitoring", "insufficientlogging"],
+    &["serversiderequestforgery", "ssrf"],
+];
+
+/// Normalises the `severity` of each security issue, its `owasp` category and `cwe` id, removing either if it is not
+/// recognised, and the `file_rag_status`, in the review response, returning a description of each value changed, e.g.,
+/// for the `schema_violations` of the review
+pub(crate) fn normalise_file_review(response_json: &mut Value) -> Vec<String> {
+    let mut normalisations: Vec<String> = Vec::new();
+    if let Some(security_issues) = response_json["security_issues"].as_array_mut() {
+        for security_issue in security_issues {
+            if let Some(severity) = security_issue.get_mut("severity") {
+                normalisations.extend(normalise_value(
+                    severity,
+                    "severity",
+                    normalise_severity,
+                    Some("Medium"),
+                ));
+            }
+            if let Some(owasp) = security_issue.get_mut("owasp") {
+                normalisations.extend(normalise_value(
+                    owasp,
+                    "owasp",
+                    normalise_owasp_category,
+                    None,
+                ));
+            }
+            if let Some(cwe) = security_issue.get_mut("cwe") {
+                if let Some(original) = cwe.as_str() {
+                    let normalised: Option<String> = normalise_cwe(original);
+                    if normalised.as_deref() != Some(original) {
+                        normalisations.push(get_normalisation(
+                            "cwe",
+                            original,
+                            normalised.as_deref(),
+                        ));
+                        *cwe = normalised.map_or(Value::Null, Value::from);
+                    }
+                }
+            }
+        }
+    }
+    if let Some(rag_status) = response_json.get_mut("file_rag_status") {
+        normalisations.extend(normalise_value(
+            rag_status,
+            "file_rag_status",
+            normalise_rag_status,
+            None,
+        ));
+    }
+    for normalisation in &normalisations {
+        debug!("{}", normalisation);
+    }
+    normalisations
+}
+/// Removes the findings of the review that repeat an earlier one, i.e., with the same code and issue, regardless of
+/// case and whitespace. Of a repeated security issue, the highest severity is kept. Returns the number removed.
+pub(crate) fn dedup_findings(file_review: &mut SourceFileReview) -> usize {
+    let mut removed: usize = 0;
+    if let Some(security_issues) = &mut file_review.security_issues {
+        let mut kept: Vec<SecurityIssue> = Vec::new();
+        let mut kept_indices: HashMap<String, usize> = HashMap::new();
+        for security_issue in security_issues.drain(..) {
+            let key = get_finding_key(&security_issue.code, &security_issue.threat);
+            match kept_indices.get(&key) {
+                Some(&index) => {
+                    if security_issue.severity > kept[index].severity {
+                        kept[index].severity = security_issue.severity;
+                    }
+                    removed += 1;
+                }
+                None => {
+                    kept_indices.insert(key, kept.len());
+                    kept.push(security_issue);
+                }
+            }
+        }
+        *security_issues = kept;
+    }
+    if let Some(errors) = &mut file_review.errors {
+        removed += dedup_by_key(errors, |error| get_finding_key(&error.code, &error.issue));
+    }
+    if let Some(improvements) = &mut file_review.improvements {
+        removed += dedup_by_key(improvements, |improvement| {
+            get_finding_key(&improvement.code, &improvement.suggestion)
+        });
+    }
+    removed
+}
+/// Removes the findings whose key is that of an earlier one, returning the number removed
+fn dedup_by_key<T>(findings: &mut Vec<T>, get_key: impl Fn(&T) -> String) -> usize {
+    let mut keys: HashSet<String> = HashSet::new();
+    let num_findings: usize = findings.len();
+    findings.retain(|finding| keys.insert(get_key(finding)));
+    num_findings - findings.len()
+}
+/// Gets the key of a finding from its code and issue, lowercased and with whitespace collapsed
+fn get_finding_key(code: &str, issue: &str) -> String {
+    let collapse = |text: &str| text.split_whitespace().collect::<Vec<_>>().join(" ");
+    format!("{}\n{}", collapse(code), collapse(issue)).to_lowercase()
+}
+/// Normalises a severity, e.g., "sev: HIGH" to 'High', or [`None`] if not known. A whole synonym is matched before any
+/// prefix is removed, so that, e.g., "severe" is not read as "sev" and "ere".
+pub(crate) fn normalise_severity(severity: &str) -> Option<&'static str> {
+    get_severity(&simplify(severity, &[]))
+        .or_else(|| get_severity(&simplify(severity, &["severity", "sev"])))
+}
+/// Gets the severity of a simplified synonym, see [`simplify`]
+fn get_severity(severity: &str) -> Option<&'static str> {
+    match severity {
+        "critical" | "crit" | "blocker" | "severe" | "veryhigh" => Some("Critical"),
+        "high" | "major" | "important" => Some("High"),
+        "medium" | "med" | "moderate" | "warning" | "normal" => Some("Medium"),
+        "low" | "minor" | "trivial" | "info" | "informational" | "note" => Some("Low"),
+        _ => None,
+    }
+}
+/// Normalises a RAG status, e.g., "RED" to 'Red', or [`None`] if not known
+pub(crate) fn normalise_rag_status(rag_status: &str) -> Option<&'static str> {
+    match simplify(rag_status, &["ragstatus", "rag", "status"]).as_str() {
+        "red" | "r" => Some("Red"),
+        "amber" | "a" | "yellow" | "orange" => Some("Amber"),
+        "green" | "g" => Some("Green"),
+        _ => None,
+    }
+}
+/// Normalises an OWASP Top 10 category to that of 2021, e.g., 'A03:2021-Injection', or [`None`] if not known. A code
+/// of 2021, or with no year, e.g., "a3", is taken as is, and one of 2017, e.g., "A7:2017", is translated by OWASP's
+/// mapping. A code of any other year is not known, so the category is found by its name, e.g., "Injection", if any.
+pub(crate) fn normalise_owasp_category(owasp: &str) -> Option<&'static str> {
+    static CODE: OnceLock<Regex> = OnceLock::new();
+    let code: &Regex = CODE.get_or_init(|| {
+        Regex::new(r"(?i)\bA(0?[1-9]|10)\b(?:\s*:\s*(\d{4})\b)?")
+            .expect("OWASP code should be valid")
+    });
+    let index: Option<usize> = code.captures(owasp).and_then(|captures| {
+        let index: usize = captures[1].parse::<usize>().ok()? - 1;
+        match captures.get(2).map(|year| year.as_str()) {
+            None | Some("2021") => Some(index),
+            Some("2017") => Some(OWASP_2017_CATEGORIES[index]),
+            Some(_) => None,
+        }
+    });
+    index
+        .or_else(|| {
+            let name: String = simplify(owasp, &["owasp"]);
+            OWASP_CATEGORY_NAMES
+                .iter()
+                .position(|names| names.iter().any(|words| name.contains(words)))
+        })
+        .map(|index| OWASP_CATEGORIES[index])
+}
+/// Normalises a CWE id, e.g., "cwe 89" or "CWE-89: SQL Injection", to 'CWE-89', or [`None`] if there is no id, or it is
+/// not a valid one, e.g., "CWE-0"
+pub(crate) fn normalise_cwe(cwe: &str) -> Option<String> {
+    static ID: OnceLock<Regex> = OnceLock::new();
+    let id: &Regex = ID.get_or_init(|| {
+        Regex::new(r"(?i)^\s*(?:cwe\s*[-_:]?\s*)?(\d+)\b").expect("CWE id should be valid")
+    });
+    let id: u32 = id.captures(cwe)?[1].parse().ok().filter(|id| *id > 0)?;
+    Some(format!("CWE-{}", id))
+}
+/// Replaces a string value with its normalised form. An unknown value is replaced with the default, or null if none.
+/// Returns a description of the change, if any.
+fn normalise_value(
+    value: &mut Value,
+    field: &str,
+    normalise: fn(&str) -> Option<&'static str>,
+    default: Option<&'static str>,
+) -> Option<String> {
+    let original: &str = value.as_str()?;
+    let normalised = match normalise(original) {
+        Some(normalised) => Some(normalised),
+        None => {
+            warn!(
+                "Unknown {} in response: '{}', replaced with: {}",
+                field,
+                original,
+                default.unwrap_or("null")
+            );
+            default
+        }
+    };
+    if normalised == Some(original) {
+        return None;
+    }
+    let normalisation: String = get_normalisation(field, original, normalised);
+    *value = normalised.map_or(Value::Null, Value::from);
+    Some(normalisation)
+}
+/// Gets the description of a value normalised, e.g., "severity: 'sev: HIGH' normalised to 'High'"
+fn get_normalisation(field: &str, original: &str, normalised: Option<&str>) -> String {
+    match normalised {
+        Some(normalised) => format!("{}: '{}' normalised to '{}'", field, original, normalised),
+        None => format!("{}: '{}' is not known, so removed", field, original),
+    }
+}
+/// Lowercases the value, keeping only the letters, and removing any of the prefixes, e.g., "Sev: HIGH!" to 'high'
+fn simplify(value: &str, prefixes: &[&str]) -> String {
+    let letters: String = value
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .flat_map(char::to_lowercase)
+        .collect();
+    prefixes
+        .iter()
+        .find_map(|prefix| letters.strip_prefix(prefix).filter(|rest| !rest.is_empty()))
+        .map_or(letters.clone(), str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::data::Severity;
+    use serde_json::json;
+
+    #[test]
+    fn test_normalise_severity() {
+        assert_eq!(normalise_severity("sev: HIGH"), Some("High"));
+        assert_eq!(normalise_severity("critical!"), Some("Critical"));
+        assert_eq!(normalise_severity("Moderate"), Some("Medium"));
+        assert_eq!(normalise_severity(" low "), Some("Low"));
+        assert_eq!(normalise_severity("Severity: Minor"), Some("Low"));
+        assert_eq!(normalise_severity("severe"), Some("Critical"));
+        assert_eq!(normalise_severity("Severity: severe"), Some("Critical"));
+        assert_eq!(normalise_severity("catastrophic"), None);
+    }
+
+    #[test]
+    fn test_normalise_security_categories() {
+        assert_eq!(
+            normalise_owasp_category("A03:2021-Injection"),
+            Some("A03:2021-Injection")
+        );
+        assert_eq!(
+            normalise_owasp_category("a1"),
+            Some("A01:2021-Broken Access Control")
+        );
+        assert_eq!(
+            normalise_owasp_category("OWASP: Server-Side Request Forgery (SSRF)"),
+            Some("A10:2021-Server-Side Request Forgery")
+        );
+        assert_eq!(
+            normalise_owasp_category("A7:2017-Cross-Site Scripting (XSS)"),
+            Some("A03:2021-Injection")
+        );
+        assert_eq!(
+            normalise_owasp_category("A03:2017"),
+            Some("A02:2021-Cryptographic Failures")
+        );
+        assert_eq!(normalise_owasp_category("A03:2013"), None);
+        assert_eq!(normalise_owasp_category("Bad vibes"), None);
+        assert_eq!(
+            normalise_cwe("CWE-89: SQL Injection"),
+            Some("CWE-89".to_string())
+        );
+        assert_eq!(normalise_cwe("cwe 079"), Some("CWE-79".to_string()));
+        assert_eq!(normalise_cwe("89"), Some("CWE-89".to_string()));
+        assert_eq!(normalise_cwe("SQL injection"), None);
+        assert_eq!(normalise_cwe("cwe 0"), None);
+    }
+
+    #[test]
+    fn test_dedup_findings() {
+        let mut file_review: SourceFileReview = serde_json::from_str(
+            r#"{
+                "source_file_info": { "name": "main.rs", "relative_path": "src/main.rs" },
+                "summary": "A summary",
+                "security_issues": [
+                    { "severity": "Low", "code": "query(input)", "threat": "SQL injection", "mitigation": "Bind it" },
+                    { "severity": "High", "code": "query( input )", "threat": "sql  injection", "mitigation": "Escape it" }
+                ],
+                "errors": [
+                    { "code": "x.unwrap()", "issue": "May panic", "resolution": "Handle it" },
+                    { "code": "y.unwrap()", "issue": "May panic", "resolution": "Handle it" }
+                ],
+                "improvements": [
+                    { "code": "let a", "suggestion": "Rename a", "improvement_details": "Details" },
+                    { "code": "let a", "suggestion": "Rename a", "improvement_details": "Other details" },
+                    { "code": "let a", "suggestion": "Rename a", "improvement_details": "Details" }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(dedup_findings(&mut file_review), 3);
+        let security_issues = file_review.security_issues.unwrap();
+        assert_eq!(security_issues.len(), 1);
+        assert_eq!(security_issues[0].severity, Severity::High);
+        assert_eq!(security_issues[0].mitigation, "Bind it");
+        assert_eq!(file_review.errors.unwrap().len(), 2);
+        assert_eq!(file_review.improvements.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_normalise_file_review() {
+        let mut response_json = json!({
+            "file_rag_status": "purple",
+            "summary": "A summary",
+            "security_issues": [
+                { "severity": "HIGH", "cwe": "cwe 89", "owasp": "Injection" },
+                { "severity": "catastrophic", "cwe": "unknown", "owasp": "A3" }
+            ]
+        });
+
+        let normalisations = normalise_file_review(&mut response_json);
+        assert_eq!(normalisations.len(), 7);
+        assert!(normalisations.contains(&"severity: 'HIGH' normalised to 'High'".to_string()));
+        assert!(normalisations
+            .contains(&"file_rag_status: 'purple' is not known, so removed".to_string()));
+        assert_eq!(response_json["security_issues"][0]["severity"], "High");
+        assert_eq!(response_json["security_issues"][1]["severity"], "Medium");
+        assert_eq!(response_json["security_issues"][0]["cwe"], "CWE-89");
+        assert_eq!(
+            response_json["security_issues"][0]["owasp"],
+            "A03:2021-Injection"
+        );
+        assert_eq!(response_json["security_issues"][1]["cwe"], Value::Null);
+        assert_eq!(
+            response_json["security_issues"][1]["owasp"],
+            "A03:2021-Injection"
+        );
+        assert_eq!(response_json["file_rag_status"], Value::Null);
+    }
+}
@@ -0,0 +1,107 @@
+//! Notifies a Slack channel, via an incoming webhook, when a review of the repository is done: its RAG status, the
+//! number of its findings, and the path of its report, so that the team need not watch for the report.
+//!
+//! The review and its report are already done, so a failed notification is only logged by the caller.
+use crate::review::data::{RAGStatus, RepositoryReview};
+use crate::settings::APIKey;
+use log::info;
+use serde_json::{json, Value};
+use std::error::Error;
+use std::time::Duration;
+
+/// The time allowed for Slack to accept the notification, so that a slow webhook does not hold up the exit
+const SLACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Posts the summary of the review, and the path of its report, to the Slack incoming webhook. The URL is the secret
+/// of the webhook, so is removed from any error.
+pub(crate) async fn notify_slack(
+    slack_webhook_url: &APIKey,
+    repository_review: &RepositoryReview,
+    report_output: &str,
+) -> Result<(), Box<dyn Error>> {
+    info!("Notifying Slack of the review");
+    let client = reqwest::Client::builder().timeout(SLACK_TIMEOUT).build()?;
+    slack_webhook_url
+        .use_key(|url| client.post(url))
+        .json(&get_slack_message(repository_review, report_output))
+        .send()
+        .await
+        .map_err(|e| e.without_url())?
+        .error_for_status()
+        .map_err(|e| e.without_url())?;
+    Ok(())
+}
+/// Gets the Slack message of the review, i.e., the repository, its RAG status, its findings and the path of its report
+fn get_slack_message(repository_review: &RepositoryReview, report_output: &str) -> Value {
+    let rag_status: &RAGStatus = repository_review.get_repository_rag_status();
+    let emoji: &str = match rag_status {
+        RAGStatus::Green => ":large_green_circle:",
+        RAGStatus::Amber => ":large_orange_circle:",
+        RAGStatus::Red => ":red_circle:",
+    };
+    let mut text: String = format!(
+        "{} *cosmonaut review of {}*: {:?}",
+        emoji,
+        escape_slack_text(&repository_review.repository_name),
+        rag_status
+    );
+    if let Some(summary) = &repository_review.summary {
+        let security_issues = &summary.security_issues;
+        text.push_str(&format!(
+            "\nSecurity issues: {} ({} critical, {} high, {} medium, {} low), errors: {}, improvements: {}",
+            security_issues.total,
+            security_issues.critical,
+            security_issues.high,
+            security_issues.medium,
+            security_issues.low,
+            summary.errors,
+            summary.improvements
+        ));
+    }
+    text.push_str(&format!(
+        "\nFiles reviewed: {}\nReport: {}",
+        repository_review.statistics.num_files,
+        escape_slack_text(report_output)
+    ));
+    json!({ "text": text })
+}
+/// Escapes the characters that Slack reads as markup, i.e., '&', '<' and '>'
+fn escape_slack_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_slack_message() {
+        let mut repository_review: RepositoryReview = serde_json::from_str(
+            r#"{
+                "repository_name": "<repos_name>",
+                "date": "2024-02-01",
+                "repository_purpose": null,
+                "summary": {
+                    "text": "The repository is well structured.",
+                    "security_issues": { "low": 1, "medium": 0, "high": 1, "critical": 0, "total": 2 },
+                    "errors": 3,
+                    "improvements": 4
+                },
+                "repository_rag_status": "Amber",
+                "contributors": [],
+                "language_types": [],
+                "file_reviews": []
+            }"#,
+        )
+        .unwrap();
+        repository_review.statistics.num_files = 12;
+        assert_eq!(
+            get_slack_message(&repository_review, "reports/repos_name.json")["text"],
+            ":large_orange_circle: *cosmonaut review of &lt;repos_name&gt;*: Amber\n\
+            Security issues: 2 (0 critical, 1 high, 0 medium, 1 low), errors: 3, improvements: 4\n\
+            Files reviewed: 12\nReport: reports/repos_name.json"
+        );
+    }
+}
@@ -0,0 +1,92 @@
+//! Ownership: how concentrated the knowledge of the codebase is, i.e., its bus factor, and the contributors whose
+//! departure would orphan large parts of it.
+//!
+//! A file is owned by the author who last changed most of its lines, by git blame. The bus factor is the fewest
+//! contributors who, between them, own more than half of the files, i.e., who, if they left, would leave most of the
+//! codebase without its main author.
+use crate::retrieval::git::blame::FileOwner;
+use crate::review::data::{KeyContributor, Ownership};
+use std::collections::HashMap;
+
+/// The percentage of the files that, if orphaned, is taken as losing the codebase
+const ORPHANED_PERCENTAGE: f32 = 50.0;
+
+/// Gets the [`Ownership`] of the files, or [`None`] if no file has an owner, e.g., outside git
+pub(crate) fn get_ownership(file_owners: &[FileOwner]) -> Option<Ownership> {
+    if file_owners.is_empty() {
+        return None;
+    }
+    let num_files: f32 = file_owners.len() as f32;
+    let mut files_owned: HashMap<&str, usize> = HashMap::new();
+    for file_owner in file_owners {
+        *files_owned.entry(&file_owner.owner).or_default() += 1;
+    }
+    let mut files_owned: Vec<(&str, usize)> = files_owned.into_iter().collect();
+    files_owned.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+
+    // The owners of the most files are taken until they own more than the orphaned percentage
+    let mut key_contributors: Vec<KeyContributor> = Vec::new();
+    let mut orphaned_percentage: f32 = 0.0;
+    for (name, files_owned) in files_owned {
+        if orphaned_percentage > ORPHANED_PERCENTAGE {
+            break;
+        }
+        let percentage_files: f32 = files_owned as f32 / num_files * 100.0;
+        orphaned_percentage += percentage_files;
+        key_contributors.push(KeyContributor {
+            name: name.to_string(),
+            files_owned: files_owned as i32,
+            percentage_files,
+        });
+    }
+    let mean_concentration: f32 = file_owners
+        .iter()
+        .map(|file_owner| file_owner.concentration)
+        .sum::<f32>()
+        / num_files;
+    Some(Ownership {
+        bus_factor: key_contributors.len() as i32,
+        mean_concentration,
+        key_contributors,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_file_owner(relative_path: &str, owner: &str, concentration: f32) -> FileOwner {
+        FileOwner {
+            relative_path: relative_path.to_string(),
+            owner: owner.to_string(),
+            concentration,
+        }
+    }
+
+    #[test]
+    fn test_get_ownership() {
+        assert_eq!(get_ownership(&[]), None);
+
+        let file_owners = vec![
+            test_file_owner("src/main.rs", "ana", 100.0),
+            test_file_owner("src/lib.rs", "ana", 80.0),
+            test_file_owner("src/review.rs", "ben", 60.0),
+            test_file_owner("src/provider.rs", "ben", 60.0),
+            test_file_owner("build.rs", "cy", 100.0),
+        ];
+        let ownership = get_ownership(&file_owners).unwrap();
+        // 'ana' owns 40% of the files, so 'ben' is needed to orphan more than half of them
+        assert_eq!(ownership.bus_factor, 2);
+        let names: Vec<&str> = ownership
+            .key_contributors
+            .iter()
+            .map(|contributor| contributor.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["ana", "ben"]);
+        assert_eq!(ownership.key_contributors[0].files_owned, 2);
+        assert_eq!(ownership.mean_concentration, 80.0);
+
+        let ownership = get_ownership(&file_owners[..2]).unwrap();
+        assert_eq!(ownership.bus_factor, 1);
+    }
+}
@@ -0,0 +1,75 @@
+//! A progress bar of the file reviews, showing the files reviewed of the total, the file last started, the elapsed
+//! time, the estimated time remaining and the estimated cost so far.
+//!
+//! The bar is drawn to stderr only if it is a terminal, so it is not written to, e.g., a CI log, and may be switched
+//! off with the `progress_bar` setting.
+use crate::provider::cost::get_total_cost;
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::time::Duration;
+
+/// The layout of the progress bar, with the estimated cost as its prefix, and the file last started as its message
+const PROGRESS_TEMPLATE: &str =
+    "{spinner} [{elapsed_precise}] {bar:30} {pos}/{len} files, ETA {eta} | {prefix} | {wide_msg}";
+/// How often the spinner and elapsed time are redrawn while waiting on a review
+const TICK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The progress of the reviews of the source files
+pub(crate) struct ReviewProgress {
+    progress_bar: ProgressBar,
+}
+impl ReviewProgress {
+    /// Starts the progress of the review of the number of files, drawn only if enabled and stderr is a terminal
+    pub(crate) fn new(enabled: bool, num_files: usize) -> Self {
+        let progress_bar = ProgressBar::with_draw_target(
+            Some(num_files as u64),
+            if enabled {
+                ProgressDrawTarget::stderr()
+            } else {
+                ProgressDrawTarget::hidden()
+            },
+        );
+        if let Ok(style) = ProgressStyle::with_template(PROGRESS_TEMPLATE) {
+            progress_bar.set_style(style.progress_chars("=> "));
+        }
+        progress_bar.set_prefix(format_cost(0.0));
+        progress_bar.enable_steady_tick(TICK_INTERVAL);
+        Self { progress_bar }
+    }
+    /// Shows the file as the one being reviewed
+    pub(crate) fn start_file(&self, relative_path: &str) {
+        self.progress_bar.set_message(relative_path.to_string());
+    }
+    /// Counts a file as reviewed, updating the estimated cost so far
+    pub(crate) fn finish_file(&self) {
+        self.progress_bar.set_prefix(format_cost(get_total_cost()));
+        self.progress_bar.inc(1);
+    }
+    /// The number of files reviewed so far
+    pub(crate) fn position(&self) -> u64 {
+        self.progress_bar.position()
+    }
+}
+impl Drop for ReviewProgress {
+    /// Clears the bar, so the log lines that follow are not interleaved with it
+    fn drop(&mut self) {
+        self.progress_bar.finish_and_clear();
+    }
+}
+/// Formats the estimated cost so far, in USD
+fn format_cost(cost: f64) -> String {
+    format!("${:.4} USD", cost)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_review_progress() {
+        let progress = ReviewProgress::new(false, 2);
+        progress.start_file("src/main.rs");
+        progress.finish_file();
+        assert_eq!(progress.position(), 1);
+        assert_eq!(format_cost(0.12345), "$0.1235 USD");
+    }
+}
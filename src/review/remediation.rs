@@ -0,0 +1,236 @@
+//! Re-reviews of remediation work: only the files of a previous JSON report that were Red, or had a Critical or High
+//! security issue, are reviewed again, e.g., `"rereview_report_path": "previous-report.json"`, and the report shows, for
+//! each, whether it improved.
+//!
+//! A file improved if its RAG status is better, or, if the same, it has fewer Critical and High security issues, then
+//! fewer security issues in all, then fewer errors. At function granularity, a file's units are taken together. A file
+//! with no review in this run was either removed, or, if still in the repository, not reviewed, e.g., as now ignored.
+use crate::review::data::{
+    RAGStatus, Remediation, RemediationOutcome, SecurityIssueBreakdown, SourceFileReview,
+};
+use crate::settings::Settings;
+use log::info;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+/// The findings of the files of a report to be re-reviewed
+#[derive(Debug, Default)]
+pub(crate) struct PreviousReport {
+    files: BTreeMap<String, FileFindings>,
+}
+/// The RAG status and findings of a file, taken together across its units
+#[derive(Debug, Default)]
+struct FileFindings {
+    rag_status: Option<RAGStatus>,
+    security_issues: SecurityIssueBreakdown,
+    errors: i32,
+}
+impl FileFindings {
+    /// Adds the findings of a review of the file, or of one of its units, keeping the worst RAG status
+    fn add(&mut self, file_review: &SourceFileReview) {
+        RAGStatus::keep_worst(&mut self.rag_status, file_review.file_rag_status.as_ref());
+        for security_issue in file_review.security_issues.iter().flatten() {
+            self.security_issues.add(&security_issue.severity);
+        }
+        self.errors += file_review.errors.as_ref().map_or(0, Vec::len) as i32;
+    }
+    /// Whether the file is to be re-reviewed, i.e., it was Red, or had a Critical or High security issue
+    fn is_to_rereview(&self) -> bool {
+        self.rag_status == Some(RAGStatus::Red)
+            || self.security_issues.critical > 0
+            || self.security_issues.high > 0
+    }
+    /// Compares the findings, less being better, by RAG status, Critical and High security issues, all security issues,
+    /// then errors
+    fn compare(&self, other: &FileFindings) -> Ordering {
+        let key = |findings: &FileFindings| {
+            (
+                findings.rag_status.as_ref().map_or(0, RAGStatus::get_rank),
+                findings.security_issues.critical + findings.security_issues.high,
+                findings.security_issues.total,
+                findings.errors,
+            )
+        };
+        key(self).cmp(&key(other))
+    }
+}
+impl PreviousReport {
+    /// Loads the findings of each file in the file reviews of the JSON report
+    pub(crate) fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read the report to re-review: {}: {}", path, e))?;
+        let report: serde_json::Value = serde_json::from_str(&contents)?;
+        let file_reviews: Vec<SourceFileReview> =
+            serde_json::from_value(report.get("file_reviews").cloned().ok_or_else(|| {
+                format!("The report to re-review is not a JSON report: {}", path)
+            })?)?;
+        let previous_report = Self {
+            files: get_file_findings(&file_reviews),
+        };
+        info!(
+            "Re-reviewing {} of the {} files of: {}",
+            previous_report.get_files_to_rereview().len(),
+            previous_report.files.len(),
+            path
+        );
+        Ok(previous_report)
+    }
+    /// Loads the [`PreviousReport`], or [`None`] if `rereview_report_path` is not set in the [`Settings`]
+    pub(crate) fn from_settings(settings: &Settings) -> Result<Option<Self>, Box<dyn Error>> {
+        settings
+            .rereview_report_path
+            .as_deref()
+            .map(Self::load)
+            .transpose()
+    }
+    /// Gets the relative paths of the files to be re-reviewed
+    pub(crate) fn get_files_to_rereview(&self) -> HashSet<String> {
+        self.files
+            .iter()
+            .filter(|(_, findings)| findings.is_to_rereview())
+            .map(|(relative_path, _)| relative_path.clone())
+            .collect()
+    }
+    /// Gets the [`Remediation`] of each file re-reviewed, from its reviews in this run, ordered by relative path
+    pub(crate) fn get_remediation(
+        &self,
+        repository_root: &Path,
+        file_reviews: &[SourceFileReview],
+    ) -> Vec<Remediation> {
+        let mut reviewed: BTreeMap<String, FileFindings> = get_file_findings(file_reviews);
+        self.files
+            .iter()
+            .filter(|(_, previous)| previous.is_to_rereview())
+            .map(|(relative_path, previous)| {
+                let current: Option<FileFindings> = reviewed.remove(relative_path);
+                let outcome = match &current {
+                    None if repository_root.join(relative_path).exists() => {
+                        RemediationOutcome::NotReviewed
+                    }
+                    None => RemediationOutcome::Removed,
+                    Some(current) => match current.compare(previous) {
+                        Ordering::Less => RemediationOutcome::Improved,
+                        Ordering::Equal => RemediationOutcome::Unchanged,
+                        Ordering::Greater => RemediationOutcome::Worsened,
+                    },
+                };
+                let current: FileFindings = current.unwrap_or_default();
+                Remediation {
+                    relative_path: relative_path.clone(),
+                    previous_rag_status: previous.rag_status.clone(),
+                    rag_status: current.rag_status,
+                    previous_security_issues: previous.security_issues.clone(),
+                    security_issues: current.security_issues,
+                    previous_errors: previous.errors,
+                    errors: current.errors,
+                    outcome,
+                }
+            })
+            .collect()
+    }
+}
+/// Gets the [`FileFindings`] of each file reviewed, by relative path
+fn get_file_findings(file_reviews: &[SourceFileReview]) -> BTreeMap<String, FileFindings> {
+    let mut files: BTreeMap<String, FileFindings> = BTreeMap::new();
+    for file_review in file_reviews {
+        files
+            .entry(file_review.source_file_info.relative_path.clone())
+            .or_default()
+            .add(file_review);
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retrieval::data::{SourceFileInfo, Statistics};
+    use crate::review::data::{SecurityIssue, Severity};
+    use tempfile::tempdir;
+
+    fn test_file_review(
+        relative_path: &str,
+        rag_status: RAGStatus,
+        severities: &[Severity],
+    ) -> SourceFileReview {
+        let source_file_info = SourceFileInfo::new_rust(relative_path, Statistics::new());
+        let mut file_review = SourceFileReview::new(source_file_info, String::new());
+        file_review.file_rag_status = Some(rag_status);
+        file_review.security_issues = Some(
+            severities
+                .iter()
+                .map(|severity| SecurityIssue {
+                    severity: severity.clone(),
+                    code: "query(input)".to_string(),
+                    threat: "SQL injection".to_string(),
+                    mitigation: "Use a prepared statement".to_string(),
+                    cwe: None,
+                    owasp: None,
+                    fingerprint: None,
+                    link: None,
+                })
+                .collect(),
+        );
+        file_review
+    }
+
+    #[test]
+    fn test_get_remediation() {
+        let previous_report = PreviousReport {
+            files: get_file_findings(&[
+                test_file_review("src/db.rs", RAGStatus::Red, &[Severity::High]),
+                test_file_review("src/api.rs", RAGStatus::Amber, &[Severity::Critical]),
+                test_file_review("src/auth.rs", RAGStatus::Red, &[]),
+                test_file_review("src/old.rs", RAGStatus::Red, &[]),
+                test_file_review("src/skipped.rs", RAGStatus::Red, &[]),
+                test_file_review("src/main.rs", RAGStatus::Amber, &[Severity::Medium]),
+            ]),
+        };
+        let mut files: Vec<String> = previous_report
+            .get_files_to_rereview()
+            .into_iter()
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                "src/api.rs",
+                "src/auth.rs",
+                "src/db.rs",
+                "src/old.rs",
+                "src/skipped.rs"
+            ]
+        );
+
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/skipped.rs"), "fn main() {}\n").unwrap();
+        let remediation = previous_report.get_remediation(
+            dir.path(),
+            &[
+                test_file_review("src/db.rs", RAGStatus::Green, &[]),
+                test_file_review("src/api.rs", RAGStatus::Red, &[Severity::Critical]),
+                test_file_review("src/auth.rs", RAGStatus::Red, &[]),
+            ],
+        );
+        let outcomes: Vec<(&str, &RemediationOutcome)> = remediation
+            .iter()
+            .map(|remediation| (remediation.relative_path.as_str(), &remediation.outcome))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                ("src/api.rs", &RemediationOutcome::Worsened),
+                ("src/auth.rs", &RemediationOutcome::Unchanged),
+                ("src/db.rs", &RemediationOutcome::Improved),
+                ("src/old.rs", &RemediationOutcome::Removed),
+                ("src/skipped.rs", &RemediationOutcome::NotReviewed),
+            ]
+        );
+        assert_eq!(remediation[2].previous_security_issues.high, 1);
+        assert_eq!(remediation[2].security_issues.total, 0);
+    }
+}
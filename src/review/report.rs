@@ -1,9 +1,14 @@
 //! Produces reports in various formats according to [`OutputType`].
-use super::data::RepositoryReview;
+use super::contributors::{get_contributor_summaries, ContributorSummary};
+use super::data::{RAGStatus, RepositoryReview};
+use super::delta::ReportDelta;
+use super::locale::{get_locale, Locale};
+use super::schema::{SchemaValidator, REPOSITORY_REVIEW_SCHEMA_VERSION};
+use super::site::create_site;
 use crate::settings::Settings;
 use chrono::DateTime;
 use chrono::{Local, Utc};
-use handlebars::{Context, Handlebars, Helper, HelperResult, Output, RenderContext};
+use handlebars::{html_escape, Context, Handlebars, Helper, HelperResult, Output, RenderContext};
 use serde::{Deserialize, Serialize};
 use std::error::Error;
 use std::fmt::{self, Display, Formatter};
@@ -12,6 +17,30 @@ use std::io::Write;
 use std::path::{Path, PathBuf};
 
 const HTML_TEMPLATE: &str = include_str!("./templates/report_template.html");
+const EXECUTIVE_HTML_TEMPLATE: &str = include_str!("./templates/report_template_executive.html");
+/// The styles of the dark theme, added to those of the template as the `theme_styles` partial
+const DARK_THEME_STYLES: &str = include_str!("./templates/report_theme_dark.css");
+/// The name the HTML report template is registered by
+const HTML_TEMPLATE_NAME: &str = "repository review";
+const DELTA_HTML_TEMPLATE: &str = include_str!("./templates/delta_template.html");
+/// The name the HTML delta report template is registered by
+const DELTA_HTML_TEMPLATE_NAME: &str = "report delta";
+/// The extension of a partial in the `report_partials_path`, whose file stem is the name it is used by, e.g.,
+/// 'header.hbs' as `{{> header}}`
+const PARTIAL_EXTENSION: &str = "hbs";
+/// The width and height, in pixels, of a trend chart, and the margin within it
+const TREND_CHART_SIZE: (f64, f64, f64) = (300.0, 60.0, 4.0);
+/// The width, in pixels, of the language chart, and the height of its bar and of each row of its legend
+const LANGUAGE_CHART_SIZE: (f64, f64, f64) = (600.0, 10.0, 20.0);
+/// The colours of the languages of the language chart, in order of their share of the code, repeated if need be
+const LANGUAGE_CHART_COLOURS: [&str; 8] = [
+    "#1C2A38", "#2E86AB", "#F18F01", "#C73E1D", "#3B8B5A", "#8E6C8A", "#A8B3BF", "#E4C65B",
+];
+/// The label of the badge of the review, as shown on the left of it
+const BADGE_LABEL: &str = "cosmonaut review";
+/// The `report_output_path` to write only the JSON report to stdout, rather than the reports to files, e.g., to pipe it
+/// to jq
+pub(crate) const STDOUT_OUTPUT_PATH: &str = "-";
 
 /// Creates and outputs a report for the [`Settings`] and [`RepositoryReview`] passed in
 /// The function the renders according to [`OutputType`]
@@ -19,6 +48,9 @@ pub(crate) fn create_report(
     settings: &Settings,
     repository_review: &RepositoryReview,
 ) -> Result<String, Box<dyn std::error::Error>> {
+    if settings.report_output_path == STDOUT_OUTPUT_PATH {
+        return write_to_stdout(&render_json(repository_review, settings)?);
+    }
     let mut report_paths = Vec::new();
 
     let render_functions = [
@@ -40,13 +72,14 @@ pub(crate) fn create_report(
     ];
 
     for (file_extension, render_fn) in render_functions {
-        if settings
-            .developer_mode
-            .as_ref()
-            .map_or(false, |dev| dev.verbose_data_output)
-            || settings.output_type.to_string() == file_extension
-        {
-            match create_specific_report(repository_review, render_fn, settings, file_extension) {
+        if is_output(settings, file_extension) {
+            match create_specific_report(
+                repository_review,
+                &repository_review.repository_name,
+                render_fn,
+                settings,
+                file_extension,
+            ) {
                 Ok(path) => report_paths.push(path),
                 Err(_) if file_extension == "pdf" => {
                     log::warn!("PDF report generation is not implemented yet.");
@@ -55,30 +88,96 @@ pub(crate) fn create_report(
             }
         }
     }
+    // The site is a directory of pages, rather than a file, so is not rendered as the other formats
+    if is_output(settings, "site") {
+        report_paths.push(create_site(settings, repository_review)?);
+    }
+    // The badge is not timestamped, so a README can show the latest review's status from a fixed URL
+    match create_badge(settings, repository_review) {
+        Ok(path) => report_paths.push(path),
+        Err(e) => log::warn!("Failed to create the badge: {}", e),
+    }
+
+    Ok(report_paths.join(", "))
+}
+
+/// Creates and outputs a report of the [`ReportDelta`] between two JSON reports, in the formats of the [`Settings`], as
+/// for a review
+pub(crate) fn create_delta_report(
+    settings: &Settings,
+    report_delta: &ReportDelta,
+) -> Result<String, Box<dyn std::error::Error>> {
+    if settings.report_output_path == STDOUT_OUTPUT_PATH {
+        return write_to_stdout(&render_delta_json(report_delta, settings)?);
+    }
+    let mut report_paths = Vec::new();
+
+    let render_functions = [
+        (
+            "json",
+            render_delta_json
+                as fn(&ReportDelta, &Settings) -> Result<String, Box<dyn std::error::Error>>,
+        ),
+        (
+            "html",
+            render_delta_html
+                as fn(&ReportDelta, &Settings) -> Result<String, Box<dyn std::error::Error>>,
+        ),
+    ];
+    let name: String = format!("{}-delta", report_delta.repository_name);
+    for (file_extension, render_fn) in render_functions {
+        if is_output(settings, file_extension) {
+            report_paths.push(create_specific_report(
+                report_delta,
+                &name,
+                render_fn,
+                settings,
+                file_extension,
+            )?);
+        }
+    }
+    if settings.output_type == OutputType::Pdf {
+        log::warn!("PDF report generation is not implemented yet.");
+    }
 
     Ok(report_paths.join(", "))
 }
 
+/// Writes the report to stdout, the only output, so that it can be piped, e.g., to jq, the logs being on stderr
+fn write_to_stdout(content: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let mut stdout = std::io::stdout().lock();
+    writeln!(stdout, "{}", content)
+        .and_then(|_| stdout.flush())
+        .map_err(|e| format!("Error writing to stdout: {}", e))?;
+    Ok("stdout".to_string())
+}
+/// Whether the report is output in the format of the file extension, i.e., the `output_type`, or every format, if
+/// `verbose_data_output` is set
+fn is_output(settings: &Settings, file_extension: &str) -> bool {
+    settings
+        .developer_mode
+        .as_ref()
+        .map_or(false, |dev| dev.verbose_data_output)
+        || settings.output_type.to_string() == file_extension
+}
+
 /// There may be multiple report formats, so here we handle according, according to `render_fn`
-fn create_specific_report<F>(
-    repository_review: &RepositoryReview,
+fn create_specific_report<T, F>(
+    report: &T,
+    name: &str,
     render_fn: F,
     settings: &Settings,
     file_extension: &str,
 ) -> Result<String, Box<dyn std::error::Error>>
 where
-    F: Fn(&RepositoryReview, &Settings) -> Result<String, Box<dyn std::error::Error>>,
+    F: Fn(&T, &Settings) -> Result<String, Box<dyn std::error::Error>>,
 {
     let output_dir: PathBuf = PathBuf::from(&settings.report_output_path);
-    let output_file_path: PathBuf = create_named_timestamped_filename(
-        &output_dir,
-        &repository_review.repository_name,
-        file_extension,
-        Local::now(),
-    );
+    let output_file_path: PathBuf =
+        create_named_timestamped_filename(&output_dir, name, file_extension, Local::now());
     let report_filepath = output_file_path.clone().to_string_lossy().into_owned();
 
-    let output_content = render_fn(repository_review, settings)?;
+    let output_content = render_fn(report, settings)?;
 
     let mut output_file = fs::File::create(output_file_path)
         .map_err(|e| format!("Error creating output file: {}", e))?;
@@ -93,6 +192,16 @@ fn render_json(
     repository_review: &RepositoryReview,
     _settings: &Settings,
 ) -> Result<String, Box<dyn std::error::Error>> {
+    // A violation is a change to the data not made to the schema, so is no reason to lose the report
+    let violations: Vec<String> = SchemaValidator::for_repository_review()?
+        .validate(&serde_json::to_value(repository_review)?);
+    if !violations.is_empty() {
+        log::warn!(
+            "The JSON report is not valid against its schema, version {}: {}",
+            REPOSITORY_REVIEW_SCHEMA_VERSION,
+            violations.join("; ")
+        );
+    }
     serde_json::to_string_pretty(repository_review).map_err(|e| {
         Box::new(std::io::Error::new(
             std::io::ErrorKind::Other,
@@ -103,22 +212,25 @@ fn render_json(
 
 fn render_html(
     repository_review: &RepositoryReview,
-    _settings: &Settings,
+    settings: &Settings,
 ) -> Result<String, Box<dyn std::error::Error>> {
     let current_year = Utc::now().format("%Y").to_string();
-    let mut handlebars = Handlebars::new();
-    handlebars.register_helper("format_percentage", Box::new(format_percentage));
-    handlebars.register_helper("newline_to_br", Box::new(newline_to_br));
-
-    handlebars
-        .register_template_string("repository review", HTML_TEMPLATE)
-        .unwrap();
+    let handlebars = get_html_handlebars(
+        &settings.report_theme,
+        settings.report_template_path.as_deref(),
+        settings.report_partials_path.as_deref(),
+        get_locale(
+            settings.report_locale.as_deref(),
+            settings.report_locale_path.as_deref(),
+        )?,
+    )?;
     let context = ReportContext {
         repository_review,
+        contributors: get_contributor_summaries(repository_review, Utc::now()),
         current_year,
     };
     handlebars
-        .render("repository review", &context)
+        .render(HTML_TEMPLATE_NAME, &context)
         .map_err(|e| {
             Box::new(std::io::Error::new(
                 std::io::ErrorKind::Other,
@@ -127,6 +239,106 @@ fn render_html(
         })
 }
 
+fn render_delta_json(
+    report_delta: &ReportDelta,
+    _settings: &Settings,
+) -> Result<String, Box<dyn std::error::Error>> {
+    serde_json::to_string_pretty(report_delta)
+        .map_err(|e| format!("Error serializing delta: {}", e).into())
+}
+
+fn render_delta_html(
+    report_delta: &ReportDelta,
+    settings: &Settings,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut handlebars = Handlebars::new();
+    handlebars.register_helper("format_change", Box::new(format_change));
+    handlebars.register_helper(
+        "t",
+        Box::new(get_locale(
+            settings.report_locale.as_deref(),
+            settings.report_locale_path.as_deref(),
+        )?),
+    );
+    handlebars.register_template_string(DELTA_HTML_TEMPLATE_NAME, DELTA_HTML_TEMPLATE)?;
+    let context = DeltaReportContext {
+        report_delta,
+        current_year: Utc::now().format("%Y").to_string(),
+    };
+    handlebars
+        .render(DELTA_HTML_TEMPLATE_NAME, &context)
+        .map_err(|e| format!("Error rendering HTML: {}", e).into())
+}
+
+/// Gets the [`Handlebars`] registry of the HTML report, with the user's template, if set, otherwise the built-in one of the
+/// [`ReportTheme`], and each partial in the partials directory, if set, e.g., for an organisation's own branding and
+/// layout, with the labels of the [`Locale`] of the report as the `t` helper.
+pub(crate) fn get_html_handlebars(
+    theme: &ReportTheme,
+    template_path: Option<&str>,
+    partials_path: Option<&str>,
+    locale: Locale,
+) -> Result<Handlebars<'static>, Box<dyn std::error::Error>> {
+    let mut handlebars = Handlebars::new();
+    handlebars.register_helper("format_percentage", Box::new(format_percentage));
+    handlebars.register_helper("newline_to_br", Box::new(newline_to_br));
+    handlebars.register_helper("format_cost", Box::new(format_cost));
+    handlebars.register_helper("trend_chart", Box::new(trend_chart));
+    handlebars.register_helper("directory_of", Box::new(directory_of));
+    handlebars.register_helper("format_change", Box::new(format_change));
+    handlebars.register_helper("language_chart", Box::new(language_chart));
+    handlebars.register_helper("t", Box::new(locale));
+
+    let theme_styles: &str = match theme {
+        ReportTheme::Dark => DARK_THEME_STYLES,
+        ReportTheme::Detail | ReportTheme::Executive => "",
+    };
+    handlebars.register_partial("theme_styles", theme_styles)?;
+    if let Some(partials_path) = partials_path {
+        for entry in fs::read_dir(partials_path)? {
+            let path: PathBuf = entry?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some(PARTIAL_EXTENSION)
+            {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            handlebars
+                .register_partial(name, fs::read_to_string(&path)?)
+                .map_err(|e| format!("Invalid report partial: {}: {}", path.display(), e))?;
+        }
+    }
+    match template_path {
+        Some(template_path) => handlebars
+            .register_template_string(HTML_TEMPLATE_NAME, fs::read_to_string(template_path)?)
+            .map_err(|e| format!("Invalid report template: {}: {}", template_path, e))?,
+        None => handlebars.register_template_string(
+            HTML_TEMPLATE_NAME,
+            match theme {
+                ReportTheme::Executive => EXECUTIVE_HTML_TEMPLATE,
+                ReportTheme::Detail | ReportTheme::Dark => HTML_TEMPLATE,
+            },
+        )?,
+    }
+    Ok(handlebars)
+}
+
+/// Writes the [`Badge`] of the [`RepositoryReview`] to '{repository_name}-badge.json' in the `report_output_path`,
+/// returning its path
+fn create_badge(
+    settings: &Settings,
+    repository_review: &RepositoryReview,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let badge_path: PathBuf = PathBuf::from(&settings.report_output_path)
+        .join(format!("{}-badge.json", repository_review.repository_name));
+    fs::write(
+        &badge_path,
+        serde_json::to_string_pretty(&Badge::from(repository_review))?,
+    )
+    .map_err(|e| format!("Error writing to output file: {}", e))?;
+    Ok(badge_path.to_string_lossy().into_owned())
+}
 fn render_pdf(
     _repository_review: &RepositoryReview,
     _settings: &Settings,
@@ -135,6 +347,39 @@ fn render_pdf(
     Err(Box::new(ReportError::NotImplemented))
 }
 
+/// Renders the findings of the [`RepositoryReview`] as plain text, e.g., for the terminal: the [`super::data::RAGStatus`],
+/// security issues and errors of each file, then the overall [`super::data::RAGStatus`]
+pub(crate) fn render_findings(repository_review: &RepositoryReview) -> String {
+    let mut findings = String::new();
+    for file_review in &repository_review.file_reviews {
+        findings.push_str(&format!(
+            "{}: {}\n",
+            file_review.source_file_info.relative_path,
+            file_review.file_rag_status.as_ref().map_or(
+                "Unknown".to_string(),
+                |rag_status| format!("{:?}", rag_status)
+            )
+        ));
+        for security_issue in file_review.security_issues.iter().flatten() {
+            findings.push_str(&format!(
+                "  [{:?}] {}: {}\n",
+                security_issue.severity, security_issue.threat, security_issue.mitigation
+            ));
+        }
+        for error in file_review.errors.iter().flatten() {
+            findings.push_str(&format!(
+                "  [Error] {}: {}\n",
+                error.issue, error.resolution
+            ));
+        }
+    }
+    findings.push_str(&format!(
+        "Overall: {:?}\n",
+        repository_review.get_repository_rag_status()
+    ));
+    findings
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
 #[serde(rename_all = "lowercase")]
 
@@ -143,6 +388,7 @@ pub(crate) enum OutputType {
     Json,
     Pdf,
     Html,
+    Site,
     All,
 }
 impl fmt::Display for OutputType {
@@ -154,11 +400,22 @@ impl fmt::Display for OutputType {
                 OutputType::Json => "json",
                 OutputType::Pdf => "pdf",
                 OutputType::Html => "html",
+                OutputType::Site => "site",
                 OutputType::All => "all",
             }
         )
     }
 }
+/// The built-in theme of the HTML report: 'detail', i.e., every finding of every file, for engineers, 'executive', i.e.,
+/// the overall status, key figures and the files needing immediate action, or 'dark', i.e., 'detail' in dark mode
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ReportTheme {
+    #[default]
+    Detail,
+    Executive,
+    Dark,
+}
 
 /// Handlebars [`Helper`] to round a `f64` to two decimal places
 fn format_percentage(
@@ -172,6 +429,145 @@ fn format_percentage(
     write!(out, "{:.2}", param)?;
     Ok(())
 }
+/// Handlebars [`Helper`] to round a `f64` cost to four decimal places
+fn format_cost(
+    h: &Helper<'_>,
+    _: &Handlebars<'_>,
+    _: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let param = h.param(0).and_then(|v| v.value().as_f64()).unwrap_or(0.0);
+    write!(out, "{:.4}", param)?;
+    Ok(())
+}
+/// Handlebars [`Helper`] to render a field of each point of a series as an SVG line chart, e.g., the 'security_issues'
+/// of the trend of the repository, or the 'commits' of its commit activity
+fn trend_chart(
+    h: &Helper<'_>,
+    _: &Handlebars,
+    _: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let field = h.param(1).and_then(|v| v.value().as_str()).unwrap_or("");
+    let values: Vec<f64> = h
+        .param(0)
+        .and_then(|v| v.value().as_array())
+        .into_iter()
+        .flatten()
+        .map(|point| point[field].as_f64().unwrap_or(0.0))
+        .collect();
+    write!(out, "{}", get_trend_chart(&values))?;
+    Ok(())
+}
+/// Gets an SVG line chart of the values, in order, from the least at the bottom to the greatest at the top
+fn get_trend_chart(values: &[f64]) -> String {
+    let (width, height, margin) = TREND_CHART_SIZE;
+    let min: f64 = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max: f64 = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let step: f64 = match values.len() {
+        0 | 1 => 0.0,
+        num_values => (width - 2.0 * margin) / (num_values - 1) as f64,
+    };
+    let points: Vec<String> = values
+        .iter()
+        .enumerate()
+        .map(|(index, value)| {
+            let y: f64 = if max > min {
+                height - margin - (value - min) / (max - min) * (height - 2.0 * margin)
+            } else {
+                height / 2.0
+            };
+            format!("{:.1},{:.1}", margin + index as f64 * step, y)
+        })
+        .collect();
+    format!(
+        r#"<svg class="trend-chart" width="{0}" height="{1}" viewBox="0 0 {0} {1}"><polyline fill="none" stroke="currentColor" stroke-width="2" points="{2}"/></svg>"#,
+        width,
+        height,
+        points.join(" ")
+    )
+}
+/// Handlebars [`Helper`] to render the share of the lines of code of each language of the [`LanguageType`]s as an SVG
+/// bar chart, GitHub style, the languages of more than one extension, e.g., '.h' and '.c', taken together
+///
+/// [`LanguageType`]: crate::retrieval::data::LanguageType
+fn language_chart(
+    h: &Helper<'_>,
+    _: &Handlebars,
+    _: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let mut languages: Vec<(String, f64)> = Vec::new();
+    for language_type in h
+        .param(0)
+        .and_then(|v| v.value().as_array())
+        .into_iter()
+        .flatten()
+    {
+        let name: &str = language_type["name"].as_str().unwrap_or_default();
+        let frequency: f64 = language_type["statistics"]["frequency"]
+            .as_f64()
+            .unwrap_or(0.0);
+        match languages.iter_mut().find(|(existing, _)| existing == name) {
+            Some((_, total)) => *total += frequency,
+            None => languages.push((name.to_string(), frequency)),
+        }
+    }
+    languages.sort_by(|a, b| b.1.total_cmp(&a.1));
+    write!(out, "{}", get_language_chart(&languages))?;
+    Ok(())
+}
+/// Gets an SVG bar of the languages, each a segment as wide as its percentage of the code, with a legend of each below
+fn get_language_chart(languages: &[(String, f64)]) -> String {
+    let (width, bar_height, row_height) = LANGUAGE_CHART_SIZE;
+    let total: f64 = languages.iter().map(|(_, percentage)| percentage).sum();
+    let height: f64 = bar_height + languages.len() as f64 * row_height;
+    let mut segments = String::new();
+    let mut legend = String::new();
+    let mut x: f64 = 0.0;
+    for (index, (name, percentage)) in languages.iter().enumerate() {
+        let colour: &str = LANGUAGE_CHART_COLOURS[index % LANGUAGE_CHART_COLOURS.len()];
+        let name: String = html_escape(name);
+        let segment_width: f64 = if total > 0.0 {
+            percentage / total * width
+        } else {
+            0.0
+        };
+        segments.push_str(&format!(
+            r#"<rect x="{:.1}" y="0" width="{:.1}" height="{}" fill="{}"><title>{} {:.2}%</title></rect>"#,
+            x, segment_width, bar_height, colour, name, percentage
+        ));
+        x += segment_width;
+        let y: f64 = bar_height + index as f64 * row_height + row_height / 2.0;
+        legend.push_str(&format!(
+            r#"<circle cx="6" cy="{0:.1}" r="5" fill="{1}"/><text x="16" y="{0:.1}" dominant-baseline="middle" font-size="12">{2} {3:.2}%</text>"#,
+            y, colour, name, percentage
+        ));
+    }
+    format!(
+        r#"<svg class="language-chart" width="{0}" height="{1}" viewBox="0 0 {0} {1}">{2}{3}</svg>"#,
+        width, height, segments, legend
+    )
+}
+/// Handlebars [`Helper`] to render a change in a statistic with its sign, e.g., '+3', '-2' or '0'
+fn format_change(
+    h: &Helper<'_>,
+    _: &Handlebars,
+    _: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let change = h.param(0).and_then(|v| v.value().as_i64()).unwrap_or(0);
+    if change > 0 {
+        write!(out, "+{}", change)?;
+    } else {
+        write!(out, "{}", change)?;
+    }
+    Ok(())
+}
 /// Handlebars [`Helper`] to render a '\n' character to "<br" HTML
 fn newline_to_br(
     h: &Helper<'_>,
@@ -185,6 +581,24 @@ fn newline_to_br(
     write!(out, "{}", replaced_text)?;
     Ok(())
 }
+/// Handlebars [`Helper`] to render the directory of a relative path, e.g., 'src/review' of 'src/review/mod.rs', or '.'
+/// if it is at the root
+fn directory_of(
+    h: &Helper<'_>,
+    _: &Handlebars,
+    _: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let path = h.param(0).and_then(|v| v.value().as_str()).unwrap_or("");
+    let directory = Path::new(path)
+        .parent()
+        .and_then(|directory| directory.to_str())
+        .filter(|directory| !directory.is_empty())
+        .unwrap_or(".");
+    write!(out, "{}", directory)?;
+    Ok(())
+}
 
 /// Creates a timestamped file
 ///
@@ -207,12 +621,51 @@ fn create_named_timestamped_filename(
     ))
 }
 
+/// A shields.io endpoint badge, i.e., the JSON from which 'https://img.shields.io/endpoint?url=...' renders a badge, of
+/// the overall [`RAGStatus`] of a review
+///
+/// #Fields:
+/// * `schema_version` - The version of the endpoint schema, always 1
+/// * `label` - The text on the left of the badge, i.e., 'cosmonaut review'
+/// * `message` - The text on the right of the badge, i.e., the [`RAGStatus`]
+/// * `color` - The colour of the right of the badge, from the [`RAGStatus`]
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct Badge {
+    schema_version: u8,
+    label: &'static str,
+    message: String,
+    color: &'static str,
+}
+impl From<&RepositoryReview> for Badge {
+    fn from(repository_review: &RepositoryReview) -> Self {
+        let rag_status: &RAGStatus = repository_review.get_repository_rag_status();
+        Badge {
+            schema_version: 1,
+            label: BADGE_LABEL,
+            message: format!("{:?}", rag_status),
+            color: match rag_status {
+                RAGStatus::Green => "brightgreen",
+                RAGStatus::Amber => "orange",
+                RAGStatus::Red => "red",
+            },
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub(crate) struct ReportContext<'a> {
     pub repository_review: &'a RepositoryReview,
+    pub contributors: Vec<ContributorSummary>,
     pub current_year: String,
 }
 
+#[derive(Serialize)]
+struct DeltaReportContext<'a> {
+    report_delta: &'a ReportDelta,
+    current_year: String,
+}
+
 #[derive(Debug)]
 pub(crate) enum ReportError {
     NotImplemented,
@@ -231,6 +684,138 @@ impl Error for ReportError {}
 mod tests {
     use super::*;
     use chrono::TimeZone;
+    use tempfile::tempdir;
+    #[test]
+    fn test_render_findings() {
+        let mut repository_review = RepositoryReview::new("repos_name".to_string());
+        repository_review.add_source_file_review(
+            serde_json::from_str(
+                r#"{
+                    "source_file_info": { "name": "main.rs", "relative_path": "src/main.rs", "id_hash": "abc123" },
+                    "summary": "A summary",
+                    "file_rag_status": "Red",
+                    "security_issues": [
+                        { "severity": "High", "code": "", "threat": "SQL injection", "mitigation": "Bind parameters" }
+                    ],
+                    "errors": [{ "code": "", "issue": "Unchecked unwrap", "resolution": "Handle the error" }]
+                }"#,
+            )
+            .unwrap(),
+        );
+
+        assert_eq!(
+            render_findings(&repository_review),
+            "src/main.rs: Red\n  [High] SQL injection: Bind parameters\n  [Error] Unchecked unwrap: Handle the error\nOverall: Green\n"
+        );
+    }
+    #[test]
+    fn test_badge() {
+        let mut repository_review = RepositoryReview::new("repos_name".to_string());
+        assert_eq!(
+            serde_json::to_string(&Badge::from(&repository_review)).unwrap(),
+            r#"{"schemaVersion":1,"label":"cosmonaut review","message":"Green","color":"brightgreen"}"#
+        );
+        repository_review.repository_rag_status(RAGStatus::Amber);
+        let badge = Badge::from(&repository_review);
+        assert_eq!((badge.message.as_str(), badge.color), ("Amber", "orange"));
+    }
+    #[test]
+    fn test_get_trend_chart() {
+        assert_eq!(
+            get_trend_chart(&[0.0, 5.0, 10.0]),
+            r#"<svg class="trend-chart" width="300" height="60" viewBox="0 0 300 60"><polyline fill="none" stroke="currentColor" stroke-width="2" points="4.0,56.0 150.0,30.0 296.0,4.0"/></svg>"#
+        );
+        assert!(get_trend_chart(&[3.0]).contains(r#"points="4.0,30.0""#));
+    }
+    #[test]
+    fn test_get_language_chart() {
+        assert_eq!(
+            get_language_chart(&[("Rust".to_string(), 75.0), ("C & C++".to_string(), 25.0)]),
+            concat!(
+                r#"<svg class="language-chart" width="600" height="50" viewBox="0 0 600 50">"#,
+                r##"<rect x="0.0" y="0" width="450.0" height="10" fill="#1C2A38"><title>Rust 75.00%</title></rect>"##,
+                r##"<rect x="450.0" y="0" width="150.0" height="10" fill="#2E86AB"><title>C &amp; C++ 25.00%</title></rect>"##,
+                r##"<circle cx="6" cy="20.0" r="5" fill="#1C2A38"/><text x="16" y="20.0" dominant-baseline="middle" font-size="12">Rust 75.00%</text>"##,
+                r##"<circle cx="6" cy="40.0" r="5" fill="#2E86AB"/><text x="16" y="40.0" dominant-baseline="middle" font-size="12">C &amp; C++ 25.00%</text>"##,
+                "</svg>"
+            )
+        );
+        let handlebars =
+            get_html_handlebars(&ReportTheme::Detail, None, None, Locale::default()).unwrap();
+        let language_types = serde_json::json!([
+            { "name": "C", "extension": "c", "statistics": { "frequency": 20.0 } },
+            { "name": "Rust", "extension": "rs", "statistics": { "frequency": 70.0 } },
+            { "name": "C", "extension": "h", "statistics": { "frequency": 10.0 } }
+        ]);
+        let chart = handlebars
+            .render_template("{{language_chart this}}", &language_types)
+            .unwrap();
+        assert!(
+            chart.contains("<title>Rust 70.00%</title>")
+                && chart.contains("<title>C 30.00%</title>")
+        );
+        assert!(chart.find("Rust 70.00%") < chart.find("C 30.00%"));
+    }
+    #[test]
+    fn test_get_html_handlebars_with_user_template() {
+        let dir = tempdir().unwrap();
+        let template_path = dir.path().join("branded.hbs");
+        fs::write(
+            &template_path,
+            "{{> header}}<p>{{repository_review.repository_name}}, {{format_cost 1.5}}</p>",
+        )
+        .unwrap();
+        let partials_path = dir.path().join("partials");
+        fs::create_dir(&partials_path).unwrap();
+        fs::write(partials_path.join("header.hbs"), "<h1>Acme</h1>").unwrap();
+        fs::write(partials_path.join("notes.txt"), "Not a partial").unwrap();
+
+        let handlebars = get_html_handlebars(
+            &ReportTheme::Detail,
+            template_path.to_str(),
+            partials_path.to_str(),
+            Locale::default(),
+        )
+        .unwrap();
+        let repository_review = RepositoryReview::new("repos_name".to_string());
+        let context = ReportContext {
+            repository_review: &repository_review,
+            contributors: Vec::new(),
+            current_year: "2024".to_string(),
+        };
+        assert_eq!(
+            handlebars.render(HTML_TEMPLATE_NAME, &context).unwrap(),
+            "<h1>Acme</h1><p>repos_name, 1.5000</p>"
+        );
+    }
+    #[test]
+    fn test_get_html_handlebars_themes() {
+        let repository_review = RepositoryReview::new("repos_name".to_string());
+        let context = ReportContext {
+            repository_review: &repository_review,
+            contributors: Vec::new(),
+            current_year: "2024".to_string(),
+        };
+        let render = |theme: ReportTheme| {
+            get_html_handlebars(&theme, None, None, Locale::default())
+                .unwrap()
+                .render(HTML_TEMPLATE_NAME, &context)
+                .unwrap()
+        };
+        let detail = render(ReportTheme::Detail);
+        assert!(!detail.contains("#161B22"));
+        assert!(!detail.contains("executive summary"));
+        assert!(render(ReportTheme::Executive).contains("executive summary"));
+        assert!(render(ReportTheme::Dark).contains("#161B22"));
+    }
+    #[test]
+    fn test_directory_of() {
+        let handlebars =
+            get_html_handlebars(&ReportTheme::Detail, None, None, Locale::default()).unwrap();
+        let render = |template: &str| handlebars.render_template(template, &()).unwrap();
+        assert_eq!(render("{{directory_of 'src/review/mod.rs'}}"), "src/review");
+        assert_eq!(render("{{directory_of 'main.rs'}}"), ".");
+    }
     #[test]
     fn test_create_named_timestamped_filename() {
         let base_path = PathBuf::from("/some/path");
@@ -0,0 +1,400 @@
+//! Validates a review response from the LLM against the `file_review.schema.json` it was asked to comply with, rather
+//! than relying on deserialisation alone, e.g., an enum value in the wrong case, or a missing required field.
+//!
+//! If a response is not valid, a repair is attempted before it is counted as a failure: missing required fields are
+//! filled with defaults, enum values are matched regardless of case, and scalar values are coerced to the expected type.
+//!
+//! The JSON report, i.e., the serialised [`super::data::RepositoryReview`], is validated against the versioned
+//! `repository_review.schema.json`, so that a field renamed or added without the schema being updated is caught.
+//!
+//! Only the subset of JSON Schema used by the review schemas is supported: `type`, `enum`, `properties`, `required`,
+//! `items`, `additionalProperties: false` and local `$ref`s. Custom types, e.g., 'languageType', are not checked.
+use crate::provider::prompts::FILE_REVIEW_SCHEMA;
+use crate::settings::Settings;
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// The JSON schema of the JSON report, i.e., of a [`super::data::RepositoryReview`], printed by `cosmonaut_code schema`
+pub(crate) const REPOSITORY_REVIEW_SCHEMA: &str =
+    include_str!("./specification/repository_review.schema.json");
+/// The version of the [`REPOSITORY_REVIEW_SCHEMA`], given in each report as its `schema_version`. The major version
+/// changes when a field is renamed or removed, or its type changes, and the minor version when a field is added.
+pub(crate) const REPOSITORY_REVIEW_SCHEMA_VERSION: &str = "1.4";
+
+/// Validates, and repairs, a JSON value against a JSON schema
+pub(crate) struct SchemaValidator {
+    schema: Value,
+}
+impl SchemaValidator {
+    pub(crate) fn new(schema: Value) -> Self {
+        Self { schema }
+    }
+    /// Creates the [`SchemaValidator`] for a [`super::data::SourceFileReview`]
+    pub(crate) fn for_file_review() -> Result<Self, serde_json::Error> {
+        Ok(Self::new(serde_json::from_str(FILE_REVIEW_SCHEMA)?))
+    }
+    /// Creates the [`SchemaValidator`] for a [`super::data::RepositoryReview`], i.e., the JSON report
+    pub(crate) fn for_repository_review() -> Result<Self, serde_json::Error> {
+        Ok(Self::new(serde_json::from_str(REPOSITORY_REVIEW_SCHEMA)?))
+    }
+    /// Creates the [`SchemaValidator`] for the review type in the settings, i.e., the schema of the selected
+    /// [`crate::settings::CustomReviewType`], if it has one, otherwise that of a [`super::data::SourceFileReview`]
+    pub(crate) fn for_review_type(settings: &Settings) -> Result<Self, Box<dyn std::error::Error>> {
+        match get_custom_schema_path(settings) {
+            Some(schema_path) => Ok(Self::new(serde_json::from_str(&read_schema(schema_path)?)?)),
+            None => Ok(Self::for_file_review()?),
+        }
+    }
+    /// Gets the violations of the schema in the value, as 'path: violation', or an empty [`Vec`] if valid
+    pub(crate) fn validate(&self, value: &Value) -> Vec<String> {
+        let mut violations: Vec<String> = Vec::new();
+        self.validate_node(&self.schema, value, "$", &mut violations);
+        violations
+    }
+    /// Repairs the value, where possible, returning a description of each repair made
+    pub(crate) fn repair(&self, value: &mut Value) -> Vec<String> {
+        let mut repairs: Vec<String> = Vec::new();
+        self.repair_node(&self.schema, value, "$", &mut repairs);
+        repairs
+    }
+    fn validate_node(
+        &self,
+        schema: &Value,
+        value: &Value,
+        path: &str,
+        violations: &mut Vec<String>,
+    ) {
+        let schema = self.resolve(schema);
+        if let Some(expected) = schema["type"].as_str() {
+            if !is_of_type(value, expected) {
+                violations.push(format!("{}: expected {}, found {}", path, expected, value));
+                return;
+            }
+        }
+        if let Some(allowed) = schema["enum"].as_array() {
+            if !allowed.contains(value) {
+                violations.push(format!("{}: {} is not one of {:?}", path, value, allowed));
+            }
+        }
+        match value {
+            Value::Object(object) => {
+                for required in get_required(schema) {
+                    if is_missing(object, required) {
+                        violations.push(format!("{}: missing required field '{}'", path, required));
+                    }
+                }
+                if schema["additionalProperties"] == Value::Bool(false) {
+                    for name in object.keys() {
+                        if get_properties(schema)
+                            .map_or(true, |properties| !properties.contains_key(name))
+                        {
+                            violations.push(format!("{}: unexpected field '{}'", path, name));
+                        }
+                    }
+                }
+                for (name, property) in get_properties(schema).into_iter().flatten() {
+                    match object.get(name) {
+                        Some(Value::Null) | None => {}
+                        Some(field) => self.validate_node(
+                            property,
+                            field,
+                            &format!("{}.{}", path, name),
+                            violations,
+                        ),
+                    }
+                }
+            }
+            Value::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    self.validate_node(
+                        &schema["items"],
+                        item,
+                        &format!("{}[{}]", path, index),
+                        violations,
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+    fn repair_node(
+        &self,
+        schema: &Value,
+        value: &mut Value,
+        path: &str,
+        repairs: &mut Vec<String>,
+    ) {
+        let schema = self.resolve(schema);
+        if let Some(expected) = schema["type"].as_str() {
+            if !is_of_type(value, expected) {
+                if let Some(coerced) = coerce(value, expected) {
+                    repairs.push(format!("{}: coerced {} to {}", path, value, expected));
+                    *value = coerced;
+                }
+            }
+        }
+        if let (Some(allowed), Some(text)) = (schema["enum"].as_array(), value.as_str()) {
+            if let Some(matched) = allowed.iter().find(|allowed| {
+                allowed
+                    .as_str()
+                    .is_some_and(|allowed| allowed.eq_ignore_ascii_case(text.trim()))
+            }) {
+                if matched != value {
+                    repairs.push(format!("{}: replaced {} with {}", path, value, matched));
+                    *value = matched.clone();
+                }
+            }
+        }
+        match value {
+            Value::Object(object) => {
+                for required in get_required(schema) {
+                    if is_missing(object, required) {
+                        let default = get_properties(schema)
+                            .and_then(|properties| properties.get(required))
+                            .map_or_else(
+                                || Value::String(String::new()),
+                                |property| default_for(self.resolve(property)),
+                            );
+                        repairs.push(format!(
+                            "{}: filled missing '{}' with {}",
+                            path, required, default
+                        ));
+                        object.insert(required.to_string(), default);
+                    }
+                }
+                for (name, property) in get_properties(schema).into_iter().flatten() {
+                    if let Some(field) = object.get_mut(name).filter(|field| !field.is_null()) {
+                        self.repair_node(property, field, &format!("{}.{}", path, name), repairs);
+                    }
+                }
+            }
+            Value::Array(items) => {
+                let item_schema = self.resolve(&schema["items"]);
+                let num_items = items.len();
+                if let Some(expected) = item_schema["type"].as_str() {
+                    items.retain(|item| {
+                        is_of_type(item, expected) || coerce(item, expected).is_some()
+                    });
+                }
+                if items.len() < num_items {
+                    repairs.push(format!(
+                        "{}: removed {} invalid items",
+                        path,
+                        num_items - items.len()
+                    ));
+                }
+                for (index, item) in items.iter_mut().enumerate() {
+                    self.repair_node(item_schema, item, &format!("{}[{}]", path, index), repairs);
+                }
+            }
+            _ => {}
+        }
+    }
+    /// Resolves a local `$ref`, e.g., '#/$defs/error', unless the schema defines its own properties
+    fn resolve<'a>(&'a self, schema: &'a Value) -> &'a Value {
+        match schema["$ref"].as_str() {
+            Some(reference) if schema.get("properties").is_none() => reference
+                .strip_prefix('#')
+                .and_then(|pointer| self.schema.pointer(pointer))
+                .unwrap_or(schema),
+            _ => schema,
+        }
+    }
+}
+/// Gets the JSON schema a review must comply with, for the review type in the settings
+pub(crate) fn get_review_schema(settings: &Settings) -> Result<String, Box<dyn std::error::Error>> {
+    match get_custom_schema_path(settings) {
+        Some(schema_path) => read_schema(schema_path),
+        None => Ok(FILE_REVIEW_SCHEMA.to_string()),
+    }
+}
+fn get_custom_schema_path(settings: &Settings) -> Option<&str> {
+    settings
+        .get_custom_review_type()
+        .and_then(|custom_review_type| custom_review_type.schema_path.as_deref())
+}
+fn read_schema(schema_path: &str) -> Result<String, Box<dyn std::error::Error>> {
+    std::fs::read_to_string(schema_path)
+        .map_err(|e| format!("Failed to read review schema: {}: {}", schema_path, e).into())
+}
+/// The error returned when a response is not valid against the schema, even after repair
+#[derive(Debug)]
+pub(crate) struct SchemaValidationError {
+    pub(crate) violations: Vec<String>,
+}
+impl fmt::Display for SchemaValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Response is not valid against the schema: {}",
+            self.violations.join("; ")
+        )
+    }
+}
+impl std::error::Error for SchemaValidationError {}
+
+/// Whether the value is of the schema type. Unknown, i.e., custom, types are not checked.
+fn is_of_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" | "float" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        _ => true,
+    }
+}
+/// Coerces a value to the schema type, if it can be done without loss, e.g., '"42"' to '42'
+fn coerce(value: &Value, expected: &str) -> Option<Value> {
+    match (expected, value) {
+        ("string", Value::Number(number)) => Some(Value::String(number.to_string())),
+        ("string", Value::Bool(boolean)) => Some(Value::String(boolean.to_string())),
+        ("integer", Value::String(text)) => text.trim().parse::<i64>().ok().map(Value::from),
+        ("integer", Value::Number(number)) => number
+            .as_f64()
+            .filter(|number| number.fract() == 0.0)
+            .map(|number| Value::from(number as i64)),
+        ("number" | "float", Value::String(text)) => {
+            text.trim().parse::<f64>().ok().map(Value::from)
+        }
+        ("array", Value::Object(_)) => Some(Value::Array(vec![value.clone()])),
+        _ => None,
+    }
+}
+/// The default value for a missing required field of the schema type
+fn default_for(schema: &Value) -> Value {
+    match schema["type"].as_str() {
+        Some("object") => Value::Object(Map::new()),
+        Some("array") => Value::Array(Vec::new()),
+        Some("integer" | "number" | "float") => Value::from(0),
+        Some("boolean") => Value::Bool(false),
+        _ => Value::String(String::new()),
+    }
+}
+fn get_required(schema: &Value) -> impl Iterator<Item = &str> {
+    schema["required"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+}
+fn get_properties(schema: &Value) -> Option<&Map<String, Value>> {
+    schema["properties"].as_object()
+}
+/// Whether the field is missing from the object, or is null
+fn is_missing(object: &Map<String, Value>, name: &str) -> bool {
+    object.get(name).filter(|field| !field.is_null()).is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::data::{RepositoryReview, SourceFileReview};
+    use crate::review::feedback::set_fingerprints;
+    use serde_json::json;
+
+    fn valid_review() -> Value {
+        json!({
+            "source_file_info": { "name": "main.rs", "relative_path": "src/main.rs", "language": null },
+            "file_rag_status": null,
+            "summary": "A summary",
+            "security_issues": [
+                { "severity": "High", "code": "general", "threat": "A threat", "mitigation": "A mitigation" }
+            ],
+            "errors": [],
+            "improvements": null
+        })
+    }
+
+    #[test]
+    fn test_validate_file_review() {
+        let validator = SchemaValidator::for_file_review().unwrap();
+        assert!(validator.validate(&valid_review()).is_empty());
+
+        let mut invalid = valid_review();
+        invalid["security_issues"][0]["severity"] = json!("high");
+        invalid["errors"] = json!([{ "code": "general", "issue": "An issue" }]);
+        invalid.as_object_mut().unwrap().remove("summary");
+        let violations = validator.validate(&invalid);
+        assert_eq!(violations.len(), 3);
+        assert!(violations
+            .iter()
+            .any(|violation| violation.starts_with("$.security_issues[0].severity")));
+    }
+
+    #[test]
+    fn test_repair_file_review() {
+        let validator = SchemaValidator::for_file_review().unwrap();
+        let mut invalid = valid_review();
+        invalid["security_issues"][0]["severity"] = json!(" critical");
+        invalid["errors"] = json!({ "code": 42, "issue": "An issue", "resolution": "A fix" });
+        invalid["improvements"] = json!(["not an improvement"]);
+        invalid.as_object_mut().unwrap().remove("summary");
+
+        let repairs = validator.repair(&mut invalid);
+        assert_eq!(repairs.len(), 5);
+        assert!(validator.validate(&invalid).is_empty());
+        assert_eq!(invalid["security_issues"][0]["severity"], "Critical");
+        assert_eq!(invalid["errors"][0]["code"], "42");
+        assert_eq!(invalid["improvements"], json!([]));
+        assert_eq!(invalid["summary"], "");
+    }
+
+    #[test]
+    fn test_validate_repository_review() {
+        let validator = SchemaValidator::for_repository_review().unwrap();
+        let schema: Value = serde_json::from_str(REPOSITORY_REVIEW_SCHEMA).unwrap();
+        // The schema accepts the reports of each earlier minor version, the current one last
+        assert_eq!(
+            schema["properties"]["schema_version"]["enum"]
+                .as_array()
+                .and_then(|versions| versions.last()),
+            Some(&json!(REPOSITORY_REVIEW_SCHEMA_VERSION))
+        );
+
+        let mut repository_review = RepositoryReview::new("repos_name".to_string());
+        let mut file_review: SourceFileReview = serde_json::from_value(json!({
+            "source_file_info": { "name": "main.rs", "relative_path": "src/main.rs", "id_hash": "abc123" },
+            "summary": "A summary",
+            "file_rag_status": "Red",
+            "security_issues": [{
+                "severity": "High", "code": "query(input)", "threat": "SQL injection", "mitigation": "Bind parameters",
+                "cwe": "CWE-89", "owasp": "A03:2021-Injection"
+            }],
+            "errors": [{ "code": "parse().unwrap()", "issue": "Unchecked unwrap", "resolution": "Handle the error" }],
+            "improvements": [{ "code": "let x", "suggestion": "Rename", "improvement_details": "let count" }]
+        }))
+        .unwrap();
+        set_fingerprints(&mut file_review);
+        repository_review.add_source_file_review(file_review);
+        let mut report: Value = serde_json::to_value(&repository_review).unwrap();
+        assert_eq!(validator.validate(&report), Vec::<String>::new());
+
+        // A field renamed without the schema being updated is caught
+        let threat = report["file_reviews"][0]["security_issues"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("threat")
+            .unwrap();
+        report["file_reviews"][0]["security_issues"][0]["threat_description"] = threat;
+        assert_eq!(
+            validator.validate(&report),
+            vec![
+                "$.file_reviews[0].security_issues[0]: missing required field 'threat'".to_string(),
+                "$.file_reviews[0].security_issues[0]: unexpected field 'threat_description'"
+                    .to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_repair_cannot_fix_unknown_enum_value() {
+        let validator = SchemaValidator::for_file_review().unwrap();
+        let mut invalid = valid_review();
+        invalid["security_issues"][0]["severity"] = json!("Severe");
+
+        validator.repair(&mut invalid);
+        assert_eq!(validator.validate(&invalid).len(), 1);
+    }
+}
@@ -0,0 +1,237 @@
+//! Renders the review as a static site, i.e., an index page, and a page per file review and per directory, rather than
+//! one HTML report, so that the report of a large repository can be published, e.g., to GitHub Pages or an internal
+//! portal, and each file linked to on its own.
+//!
+//! The site is written to '{repository_name}-site-{timestamp}' in the `report_output_path`, with the file pages in
+//! 'files', and the directory pages in 'directories', so it can be copied as is.
+use crate::review::data::{DirectorySummary, RepositoryReview, SourceFileReview};
+use crate::review::directories::{get_directory, get_directory_summaries};
+use crate::review::locale::get_locale;
+use crate::review::report::get_html_handlebars;
+use crate::settings::Settings;
+use chrono::{Local, Utc};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+const SITE_INDEX_TEMPLATE: &str = include_str!("./templates/site_index_template.html");
+const SITE_FILE_TEMPLATE: &str = include_str!("./templates/site_file_template.html");
+const SITE_DIRECTORY_TEMPLATE: &str = include_str!("./templates/site_directory_template.html");
+/// The styles of the pages of the site, used by each as the `site_styles` partial
+const SITE_STYLES: &str = include_str!("./templates/site_styles.css");
+/// The names the site templates are registered by
+const SITE_INDEX_TEMPLATE_NAME: &str = "site index";
+const SITE_FILE_TEMPLATE_NAME: &str = "site file";
+const SITE_DIRECTORY_TEMPLATE_NAME: &str = "site directory";
+/// The directories of the site of the file and directory pages
+const FILES_DIRECTORY: &str = "files";
+const DIRECTORIES_DIRECTORY: &str = "directories";
+
+/// The page of a file review
+///
+/// #Fields:
+/// * `page` - The file name of the page, in the 'files' directory of the site
+/// * `directory` - The directory of the file, as in the [`DirectorySummary`]s
+/// * `directory_page` - The file name of the page of the directory, in the 'directories' directory of the site
+/// * `file_review` - The [`SourceFileReview`]
+#[derive(Serialize, Debug)]
+struct FilePage<'a> {
+    page: String,
+    directory: String,
+    directory_page: String,
+    file_review: &'a SourceFileReview,
+}
+/// The page of a directory, with its files
+#[derive(Serialize, Debug)]
+struct DirectoryPage<'a> {
+    page: String,
+    directory: &'a DirectorySummary,
+    files: Vec<&'a FilePage<'a>>,
+}
+#[derive(Serialize)]
+struct SiteIndexContext<'a> {
+    repository_review: &'a RepositoryReview,
+    files: &'a [FilePage<'a>],
+    directories: &'a [DirectoryPage<'a>],
+    current_year: &'a str,
+}
+#[derive(Serialize)]
+struct SiteFileContext<'a> {
+    repository_name: &'a str,
+    file: &'a FilePage<'a>,
+    current_year: &'a str,
+}
+#[derive(Serialize)]
+struct SiteDirectoryContext<'a> {
+    repository_name: &'a str,
+    directory: &'a DirectoryPage<'a>,
+    current_year: &'a str,
+}
+
+/// Writes the site of the [`RepositoryReview`] to the `report_output_path`, returning the path of its index page
+pub(crate) fn create_site(
+    settings: &Settings,
+    repository_review: &RepositoryReview,
+) -> Result<String, Box<dyn Error>> {
+    let mut handlebars = get_html_handlebars(
+        &settings.report_theme,
+        None,
+        settings.report_partials_path.as_deref(),
+        get_locale(
+            settings.report_locale.as_deref(),
+            settings.report_locale_path.as_deref(),
+        )?,
+    )?;
+    handlebars.register_partial("site_styles", SITE_STYLES)?;
+    handlebars.register_template_string(SITE_INDEX_TEMPLATE_NAME, SITE_INDEX_TEMPLATE)?;
+    handlebars.register_template_string(SITE_FILE_TEMPLATE_NAME, SITE_FILE_TEMPLATE)?;
+    handlebars.register_template_string(SITE_DIRECTORY_TEMPLATE_NAME, SITE_DIRECTORY_TEMPLATE)?;
+
+    let site_path: PathBuf = PathBuf::from(&settings.report_output_path).join(format!(
+        "{}-site-{}",
+        repository_review.repository_name,
+        Local::now().format("%Y%m%d_%H%M%S")
+    ));
+    fs::create_dir_all(site_path.join(FILES_DIRECTORY))?;
+    fs::create_dir_all(site_path.join(DIRECTORIES_DIRECTORY))?;
+
+    let directories: Vec<DirectorySummary> =
+        get_directory_summaries(&repository_review.file_reviews);
+    let files: Vec<FilePage> = get_file_pages(&repository_review.file_reviews);
+    let directory_pages: Vec<DirectoryPage> = directories
+        .iter()
+        .map(|directory| DirectoryPage {
+            page: format!("{}.html", get_page_name(&directory.directory)),
+            directory,
+            files: files
+                .iter()
+                .filter(|file| file.directory == directory.directory)
+                .collect(),
+        })
+        .collect();
+    let current_year: String = Utc::now().format("%Y").to_string();
+
+    for file in &files {
+        let context = SiteFileContext {
+            repository_name: &repository_review.repository_name,
+            file,
+            current_year: &current_year,
+        };
+        fs::write(
+            site_path.join(FILES_DIRECTORY).join(&file.page),
+            handlebars.render(SITE_FILE_TEMPLATE_NAME, &context)?,
+        )?;
+    }
+    for directory in &directory_pages {
+        let context = SiteDirectoryContext {
+            repository_name: &repository_review.repository_name,
+            directory,
+            current_year: &current_year,
+        };
+        fs::write(
+            site_path.join(DIRECTORIES_DIRECTORY).join(&directory.page),
+            handlebars.render(SITE_DIRECTORY_TEMPLATE_NAME, &context)?,
+        )?;
+    }
+    let index_path: PathBuf = site_path.join("index.html");
+    let context = SiteIndexContext {
+        repository_review,
+        files: &files,
+        directories: &directory_pages,
+        current_year: &current_year,
+    };
+    fs::write(
+        &index_path,
+        handlebars.render(SITE_INDEX_TEMPLATE_NAME, &context)?,
+    )
+    .map_err(|e| format!("Error writing to output file: {}", e))?;
+    Ok(index_path.to_string_lossy().into_owned())
+}
+/// Gets the [`FilePage`] of each file review, each named from its relative path, and the start line of its unit, if
+/// reviewed by function, e.g., 'src-main-rs-L12.html', and numbered if the name is taken
+fn get_file_pages(file_reviews: &[SourceFileReview]) -> Vec<FilePage> {
+    let mut pages: HashSet<String> = HashSet::new();
+    file_reviews
+        .iter()
+        .map(|file_review| {
+            let file_info = &file_review.source_file_info;
+            let mut name: String = get_page_name(&file_info.relative_path);
+            if let Some(unit) = &file_info.code_unit {
+                name.push_str(&format!("-L{}", unit.start_line));
+            }
+            let mut page: String = format!("{}.html", name);
+            let mut number: usize = 1;
+            while !pages.insert(page.clone()) {
+                number += 1;
+                page = format!("{}-{}.html", name, number);
+            }
+            let directory: String = get_directory(&file_info.relative_path);
+            FilePage {
+                page,
+                directory_page: format!("{}.html", get_page_name(&directory)),
+                directory,
+                file_review,
+            }
+        })
+        .collect()
+}
+/// Gets the name of a page from a path, i.e., its letters, digits, '-' and '_', with any other character as '-', or
+/// 'root' for the root directory, '.'
+fn get_page_name(path: &str) -> String {
+    let name: String = path
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let name: &str = name.trim_matches('-');
+    if name.is_empty() {
+        "root".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_file_review(relative_path: &str) -> SourceFileReview {
+        serde_json::from_str(&format!(
+            r#"{{
+                "source_file_info": {{ "name": "", "relative_path": "{}", "id_hash": "abc123" }},
+                "summary": "A summary"
+            }}"#,
+            relative_path
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_get_file_pages() {
+        let file_reviews = vec![
+            test_file_review("src/review/mod.rs"),
+            test_file_review("src/review-mod.rs"),
+            test_file_review("src/review/mod.rs"),
+            test_file_review("main.rs"),
+        ];
+        assert_eq!(
+            get_file_pages(&file_reviews)
+                .iter()
+                .map(|file| (file.page.as_str(), file.directory_page.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("src-review-mod-rs.html", "src-review.html"),
+                ("src-review-mod-rs-2.html", "src.html"),
+                ("src-review-mod-rs-3.html", "src-review.html"),
+                ("main-rs.html", "root.html"),
+            ]
+        );
+    }
+}
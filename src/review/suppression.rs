@@ -0,0 +1,128 @@
+//! Inline suppression annotations, in a comment of any style, e.g., `// cosmonaut:ignore`:
+//! - `cosmonaut:ignore` - the whole file is not reviewed
+//! - `cosmonaut:ignore-next-finding` - findings on the next line of code are dropped from the review, and so from the
+//!   RAG status, but recorded as suppressed
+use crate::review::data::SourceFileReview;
+
+/// The annotation that excludes the whole file from review
+const IGNORE_FILE_ANNOTATION: &str = "cosmonaut:ignore";
+/// The annotation that suppresses the findings on the next line of code
+const IGNORE_NEXT_FINDING_ANNOTATION: &str = "cosmonaut:ignore-next-finding";
+
+/// Whether the file is annotated with `cosmonaut:ignore`, so is not reviewed
+pub(crate) fn is_file_ignored(contents: &str) -> bool {
+    contents.lines().any(|line| {
+        line.match_indices(IGNORE_FILE_ANNOTATION)
+            .any(|(start, annotation)| !line[start + annotation.len()..].starts_with('-'))
+    })
+}
+/// Drops the findings of the [`SourceFileReview`] whose code is on the line after a `cosmonaut:ignore-next-finding`,
+/// recording each as a suppressed finding
+pub(crate) fn apply_suppressions(file_review: &mut SourceFileReview, contents: &str) {
+    let suppressed_lines: Vec<&str> = get_suppressed_lines(contents);
+    if suppressed_lines.is_empty() {
+        return;
+    }
+    let is_suppressed = |code: &str| {
+        code.lines().map(str::trim).any(|code_line| {
+            !code_line.is_empty()
+                && suppressed_lines
+                    .iter()
+                    .any(|suppressed_line| code_line.contains(suppressed_line))
+        })
+    };
+    let mut suppressed_findings: Vec<String> = Vec::new();
+    if let Some(security_issues) = &mut file_review.security_issues {
+        security_issues.retain(|security_issue| {
+            let suppressed = is_suppressed(&security_issue.code);
+            if suppressed {
+                suppressed_findings.push(format!("Security issue: {}", security_issue.threat));
+            }
+            !suppressed
+        });
+    }
+    if let Some(errors) = &mut file_review.errors {
+        errors.retain(|error| {
+            let suppressed = is_suppressed(&error.code);
+            if suppressed {
+                suppressed_findings.push(format!("Error: {}", error.issue));
+            }
+            !suppressed
+        });
+    }
+    if let Some(improvements) = &mut file_review.improvements {
+        improvements.retain(|improvement| {
+            let suppressed = is_suppressed(&improvement.code);
+            if suppressed {
+                suppressed_findings.push(format!("Improvement: {}", improvement.suggestion));
+            }
+            !suppressed
+        });
+    }
+    if !suppressed_findings.is_empty() {
+        file_review.suppressed_findings = Some(suppressed_findings);
+    }
+}
+/// Gets the first non-blank line of code after each `cosmonaut:ignore-next-finding`, trimmed
+fn get_suppressed_lines(contents: &str) -> Vec<&str> {
+    let lines: Vec<&str> = contents.lines().collect();
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.contains(IGNORE_NEXT_FINDING_ANNOTATION))
+        .filter_map(|(index, _)| {
+            lines[index + 1..]
+                .iter()
+                .map(|line| line.trim())
+                .find(|line| !line.is_empty() && !line.contains(IGNORE_NEXT_FINDING_ANNOTATION))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_file_ignored() {
+        assert!(is_file_ignored("// cosmonaut:ignore\nfn main() {}\n"));
+        assert!(is_file_ignored("# cosmonaut:ignore generated code\n"));
+        assert!(!is_file_ignored(
+            "// cosmonaut:ignore-next-finding\nfn main() {}\n"
+        ));
+        assert!(!is_file_ignored("fn main() {}\n"));
+    }
+
+    #[test]
+    fn test_apply_suppressions() {
+        let contents = "\
+fn main() {
+    // cosmonaut:ignore-next-finding
+
+    let password = \"[REDACTED:password]\";
+    let value = parse().unwrap();
+}
+";
+        let mut file_review: SourceFileReview = serde_json::from_str(
+            r#"{
+                "source_file_info": { "name": "main.rs", "relative_path": "src/main.rs", "id_hash": "abc123" },
+                "summary": "A summary",
+                "security_issues": [
+                    { "severity": "High", "code": "let password = \"[REDACTED:password]\";", "threat": "Hardcoded password", "mitigation": "Use a secret store" }
+                ],
+                "errors": [
+                    { "code": "let value = parse().unwrap();", "issue": "Unchecked unwrap", "resolution": "Handle the error" }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        apply_suppressions(&mut file_review, contents);
+        assert_eq!(file_review.security_issues, Some(Vec::new()));
+        assert_eq!(file_review.errors.as_ref().map(Vec::len), Some(1));
+        assert_eq!(
+            file_review.suppressed_findings,
+            Some(vec!["Security issue: Hardcoded password".to_string()])
+        );
+    }
+}
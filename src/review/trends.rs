@@ -0,0 +1,231 @@
+//! The history of a repository's reviews: the summary metrics of each review of the whole repository, e.g., its RAG
+//! status, security issues, lines of code and cost, recorded in a local SQLite database, so the report can chart the
+//! quality of the repository over time.
+//!
+//! Only reviews of the whole repository are recorded, i.e., not those of a diff, or a re-review of a previous report,
+//! as their metrics are of only some of its files.
+use crate::review::data::{RAGStatus, RepositoryReview};
+use crate::settings::{ReviewScope, Settings};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+/// The location of the trend store used if `trends_path` is not set, relative to the working directory
+pub(crate) const DEFAULT_TRENDS_PATH: &str = ".cosmonaut/trends.db";
+/// The most recent reviews charted in the report
+const MAX_TREND_POINTS: i64 = 30;
+
+/// The summary metrics of a review of the repository
+///
+/// #Fields:
+/// * `date` - The date the review was created
+/// * `head_sha` - The id of the commit reviewed, if known
+/// * `rag_status` - The overall [`RAGStatus`] of the repository
+/// * `critical`, `high` - The number of Critical and High security issues
+/// * `security_issues` - The number of security issues
+/// * `errors` - The number of errors
+/// * `improvements` - The number of improvements
+/// * `loc` - The lines of code of the repository
+/// * `cost` - The estimated cost of the review, in USD, if known
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct TrendPoint {
+    pub(crate) date: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) head_sha: Option<String>,
+    pub(crate) rag_status: RAGStatus,
+    pub(crate) critical: i64,
+    pub(crate) high: i64,
+    pub(crate) security_issues: i64,
+    pub(crate) errors: i64,
+    pub(crate) improvements: i64,
+    pub(crate) loc: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) cost: Option<f64>,
+}
+impl TrendPoint {
+    pub(crate) fn from_review(repository_review: &RepositoryReview) -> Self {
+        let summary = repository_review.summary.as_ref();
+        Self {
+            date: repository_review.get_date().to_string(),
+            head_sha: repository_review.get_head_sha().map(str::to_string),
+            rag_status: repository_review.get_repository_rag_status().clone(),
+            critical: summary.map_or(0, |summary| summary.security_issues.critical as i64),
+            high: summary.map_or(0, |summary| summary.security_issues.high as i64),
+            security_issues: summary.map_or(0, |summary| summary.security_issues.total as i64),
+            errors: summary.map_or(0, |summary| summary.errors as i64),
+            improvements: summary.map_or(0, |summary| summary.improvements as i64),
+            loc: repository_review.statistics.loc,
+            cost: repository_review.cost.as_ref().map(|cost| cost.total_cost),
+        }
+    }
+}
+/// The SQLite database of the [`TrendPoint`]s of the reviews of each repository
+pub(crate) struct TrendStore {
+    connection: Connection,
+}
+impl TrendStore {
+    /// Opens the trend store at the path, creating it if it does not exist
+    pub(crate) fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)?;
+        }
+        let connection = Connection::open(path)?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS reviews (
+                id INTEGER PRIMARY KEY,
+                repository_name TEXT NOT NULL,
+                date TEXT NOT NULL,
+                head_sha TEXT,
+                rag_status TEXT NOT NULL,
+                critical INTEGER NOT NULL,
+                high INTEGER NOT NULL,
+                security_issues INTEGER NOT NULL,
+                errors INTEGER NOT NULL,
+                improvements INTEGER NOT NULL,
+                loc INTEGER NOT NULL,
+                cost REAL
+            );
+            CREATE INDEX IF NOT EXISTS reviews_repository_name ON reviews (repository_name);",
+        )?;
+        Ok(Self { connection })
+    }
+    /// Records the [`TrendPoint`] of the review
+    pub(crate) fn record(
+        &self,
+        repository_review: &RepositoryReview,
+    ) -> Result<(), Box<dyn Error>> {
+        let point = TrendPoint::from_review(repository_review);
+        self.connection.execute(
+            "INSERT INTO reviews (repository_name, date, head_sha, rag_status, critical, high, security_issues, errors,
+                improvements, loc, cost)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                repository_review.repository_name,
+                point.date,
+                point.head_sha,
+                format!("{:?}", point.rag_status),
+                point.critical,
+                point.high,
+                point.security_issues,
+                point.errors,
+                point.improvements,
+                point.loc,
+                point.cost,
+            ],
+        )?;
+        Ok(())
+    }
+    /// Gets the [`TrendPoint`]s of the most recent reviews of the repository, oldest first
+    pub(crate) fn get_trend(
+        &self,
+        repository_name: &str,
+    ) -> Result<Vec<TrendPoint>, Box<dyn Error>> {
+        let mut statement = self.connection.prepare(
+            "SELECT date, head_sha, rag_status, critical, high, security_issues, errors, improvements, loc, cost
+            FROM reviews WHERE repository_name = ?1 ORDER BY id DESC LIMIT ?2",
+        )?;
+        let mut trend: Vec<TrendPoint> = statement
+            .query_map(params![repository_name, MAX_TREND_POINTS], |row| {
+                Ok(TrendPoint {
+                    date: row.get(0)?,
+                    head_sha: row.get(1)?,
+                    rag_status: match row.get::<_, String>(2)?.as_str() {
+                        "Red" => RAGStatus::Red,
+                        "Amber" => RAGStatus::Amber,
+                        _ => RAGStatus::Green,
+                    },
+                    critical: row.get(3)?,
+                    high: row.get(4)?,
+                    security_issues: row.get(5)?,
+                    errors: row.get(6)?,
+                    improvements: row.get(7)?,
+                    loc: row.get(8)?,
+                    cost: row.get(9)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        trend.reverse();
+        Ok(trend)
+    }
+}
+/// Records the review in the trend store of the [`Settings`], if `record_trends` is set and the whole repository was
+/// reviewed, returning the trend of the repository, including the review, or [`None`] if not recorded
+pub(crate) fn record_trend(
+    settings: &Settings,
+    repository_review: &RepositoryReview,
+) -> Result<Option<Vec<TrendPoint>>, Box<dyn Error>> {
+    if !is_recorded(settings) {
+        return Ok(None);
+    }
+    let trend_store = open_trend_store(settings)?;
+    trend_store.record(repository_review)?;
+    Ok(Some(
+        trend_store.get_trend(&repository_review.repository_name)?,
+    ))
+}
+/// Gets the [`TrendPoint`] of the last recorded review of the repository, i.e., before the one in progress, or [`None`]
+/// if the review is not recorded, or the repository has no recorded review
+pub(crate) fn get_previous_point(
+    settings: &Settings,
+    repository_name: &str,
+) -> Result<Option<TrendPoint>, Box<dyn Error>> {
+    if !is_recorded(settings) {
+        return Ok(None);
+    }
+    Ok(open_trend_store(settings)?
+        .get_trend(repository_name)?
+        .pop())
+}
+/// Whether the review is recorded, i.e., `record_trends` is set and the whole repository is reviewed
+fn is_recorded(settings: &Settings) -> bool {
+    settings.record_trends
+        && settings.review_scope == ReviewScope::All
+        && settings.rereview_report_path.is_none()
+}
+fn open_trend_store(settings: &Settings) -> Result<TrendStore, Box<dyn Error>> {
+    let path = settings
+        .trends_path
+        .as_deref()
+        .unwrap_or(DEFAULT_TRENDS_PATH);
+    TrendStore::open(Path::new(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_trend_store() {
+        let dir = tempdir().unwrap();
+        let trend_store = TrendStore::open(&dir.path().join("trends").join("trends.db")).unwrap();
+        let mut repository_review = RepositoryReview::new("repos_name".to_string());
+        repository_review.date("2024-01-01".to_string());
+        repository_review.repository_rag_status(RAGStatus::Red);
+        trend_store.record(&repository_review).unwrap();
+        repository_review.date("2024-02-01".to_string());
+        repository_review.repository_rag_status(RAGStatus::Amber);
+        trend_store.record(&repository_review).unwrap();
+        trend_store
+            .record(&RepositoryReview::new("other_repos".to_string()))
+            .unwrap();
+
+        let trend = trend_store.get_trend("repos_name").unwrap();
+        assert_eq!(
+            trend
+                .iter()
+                .map(|point| (point.date.as_str(), point.rag_status.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("2024-01-01", RAGStatus::Red),
+                ("2024-02-01", RAGStatus::Amber)
+            ]
+        );
+        assert_eq!((trend[0].security_issues, trend[0].cost), (0, None));
+    }
+}
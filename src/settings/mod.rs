@@ -5,14 +5,25 @@
 // TODO refactor so that the settings are self-contained and are safe once loaded via the 'new' function
 use config::FileFormat;
 use config::{Config, ConfigError, File};
+use regex::{RegexSet, RegexSetBuilder};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
 use std::fmt;
+use std::sync::OnceLock;
 
-use crate::review::report::OutputType;
+use crate::provider::cost::ModelPricing;
+use crate::provider::models::ModelInfo;
+use crate::provider::RequestType;
+use crate::review::data::Severity;
+use crate::review::locale::get_locale;
+use crate::review::report::{OutputType, ReportTheme};
 
 const DEFAULT_CONFIG: &str = include_str!("../../settings/default.json");
 pub(crate) const ENV_SENSITIVE_SETTINGS_PATH: &str = "SENSITIVE_SETTINGS_PATH";
+const VERTEX_AI_PROVIDER: &str = "vertex-ai";
+pub(crate) const REPLAY_PROVIDER: &str = "replay";
+const DEFAULT_HEAD_REF: &str = "HEAD";
 
 #[derive(Serialize, Deserialize, PartialEq)]
 pub(crate) struct Settings {
@@ -26,13 +37,133 @@ pub(crate) struct Settings {
     pub(crate) output_type: OutputType,
     #[serde(default)]
     pub(crate) review_type: ReviewType,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) custom_review_types: Vec<CustomReviewType>,
+    #[serde(default)]
+    pub(crate) review_scope: ReviewScope,
+    #[serde(default)]
+    pub(crate) review_granularity: ReviewGranularity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) base_ref: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) head_ref: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) review_cycles: Option<i32>,
     pub(crate) repository_path: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) review_paths: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) repository_ref: Option<String>,
+    #[serde(default = "default_true")]
+    pub(crate) require_git: bool,
     pub(crate) report_output_path: String,
+    #[serde(default)]
+    pub(crate) report_theme: ReportTheme,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) report_template_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) report_partials_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) report_locale: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) report_locale_path: Option<String>,
+    #[serde(default = "default_false")]
+    pub(crate) localise_summaries: bool,
+    #[serde(default = "default_max_concurrent_reviews")]
+    pub(crate) max_concurrent_reviews: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) scheduled_providers: Vec<String>,
+    #[serde(default = "default_true")]
+    pub(crate) progress_bar: bool,
+    #[serde(default = "default_false")]
+    pub(crate) plan_then_review: bool,
+    #[serde(default = "default_true")]
+    pub(crate) use_repository_context: bool,
+    #[serde(default = "default_false")]
+    pub(crate) architecture_review: bool,
+    #[serde(default = "default_true")]
+    pub(crate) use_review_cache: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) review_cache_path: Option<String>,
+    #[serde(default = "default_true")]
+    pub(crate) use_checkpoint: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) checkpoint_path: Option<String>,
+    #[serde(default = "default_false")]
+    pub(crate) incremental_review: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) last_review_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) baseline_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) rereview_report_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) feedback_path: Option<String>,
+    #[serde(default = "default_false")]
+    pub(crate) feedback_counter_examples: bool,
+    #[serde(default = "default_true")]
+    pub(crate) record_trends: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) trends_path: Option<String>,
+    #[serde(default = "default_false")]
+    pub(crate) executive_summary: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) min_severity: Option<Severity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) quality_gate: Option<QualityGate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) escalation: Option<Escalation>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub(crate) rag_thresholds: HashMap<String, RagThresholds>,
+    #[serde(default = "default_false")]
+    pub(crate) skip_files_with_secrets: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) max_file_size_kb: Option<u64>,
+    #[serde(default = "default_true")]
+    pub(crate) skip_generated_files: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) languages: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) excluded_languages: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) authors: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) changed_within_days: Option<u32>,
+    #[serde(default = "default_false")]
+    pub(crate) contributor_findings: bool,
+    #[serde(default = "default_false")]
+    pub(crate) anonymise_contributor_findings: bool,
+    #[serde(default = "default_false")]
+    pub(crate) file_ownership: bool,
+    #[serde(default = "default_false")]
+    pub(crate) anonymise_contributors: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) coverage_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) linter_reports: Vec<LinterReport>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) run_linters: Vec<Linter>,
+    #[serde(default = "default_false")]
+    pub(crate) dependency_review: bool,
+    #[serde(default = "default_false")]
+    pub(crate) vulnerability_lookup: bool,
+    #[serde(default = "default_false")]
+    pub(crate) markers_in_summary: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) transcript_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) prompts_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) slack_webhook_url: Option<APIKey>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) models: Vec<ModelInfo>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) developer_mode: Option<DeveloperMode>,
+    #[serde(default)]
     pub(crate) sensitive: SensitiveSettings,
+    /// The language of the summaries, resolved from the locale once, on first use
+    #[serde(skip)]
+    summary_language: OnceLock<Option<String>>,
 }
 /// Custom Debug implementation for Settings
 impl fmt::Debug for Settings {
@@ -79,11 +210,69 @@ impl fmt::Display for Settings {
 /// - `providers`: The set of organizations providing the language model service (e.g., openai, google, anthropic, meta, etc.).
 /// - `default_provider`: Default is openai.
 /// - `chosen_provider`: The user selected provider from the configured list.
-/// - `sensitive settings`: Inc. API key for authentication, org_id and org_name.
+/// - `sensitive settings`: Inc. API key for authentication, org_id and org_name. Each provider may set its own instead.
 /// - `repository_path`: The user selected path to the folder containing repository and code for analysis.
-/// - `report_output_path`: The user selected path where analysis output report will be stored.
-/// - `output_type`: The user selected format/type of the output (e.g., json, pdf). Default is JSON.
+/// - `review_paths`: The directories or files, relative to the `repository_path`, e.g., 'backend/', to which the review is limited, while the git statistics are still of the whole repository. All files, if not set.
+/// - `repository_ref`: The branch, tag or commit to review, checked out into a temporary worktree. The working tree, if not set.
+/// - `require_git`: Whether the `repository_path` must be a git repository. If false, a plain directory is reviewed without git statistics. Default is true.
+/// - `report_output_path`: The user selected path where analysis output report will be stored, or '-' to write only the JSON report to stdout.
+/// - `report_theme`: The built-in [`ReportTheme`] of the HTML report, i.e., 'detail', 'executive' or 'dark'. Default is 'detail'.
+/// - `report_template_path`: A Handlebars template of the HTML report, e.g., with an organisation's own branding and layout. The built-in template, if not set.
+/// - `report_partials_path`: A directory of Handlebars partials used by the `report_template_path`, each '{name}.hbs' used as `{{> name}}`. None, if not set.
+/// - `report_locale`: The locale of the headings and labels of the HTML report, e.g., 'de', 'fr' or 'es', each used as `{{t "label"}}`. English, if not set.
+/// - `report_locale_path`: A JSON file of the labels of the `report_locale`, e.g., of a locale that is not built in, each overriding the built-in label. None, if not set.
+/// - `localise_summaries`: Whether the LLM writes the summaries of the repository, of its README and for executives in the language of the `report_locale`. Default is false.
+/// - `output_type`: The user selected format/type of the output (e.g., json, html, or site, i.e., a static site of pages). Default is JSON.
 /// - `review_type`: The user selected numeric code indicating the type of review (e.g., 1 for general, 2 for security; default is 1).
+/// - `custom_review_types`: The [`CustomReviewType`]s that may be selected as the `review_type`, as 'custom:{name}'.
+/// - `review_scope`: The [`ReviewScope`], i.e., 'all' files, only those changed in a 'diff' between refs, or those 'staged'. Default is 'all'.
+/// - `review_granularity`: The [`ReviewGranularity`], i.e., whether each 'file' is reviewed whole, or each 'function' of it. Default is 'file'.
+/// - `base_ref`: For a 'diff' review scope, the branch, tag or commit the changes are from, e.g., 'main'.
+/// - `head_ref`: For a 'diff' review scope, the branch, tag or commit the changes are to. Default is 'HEAD'.
+/// - `max_concurrent_reviews`: The maximum number of files reviewed at once. Default is 1, i.e., one file at a time.
+/// - `scheduled_providers`: The names of other providers, each with its active service, across which the file reviews are spread with the active provider, each to the one with room within its rate limits.
+/// - `progress_bar`: Whether a progress bar of the file reviews is shown, if the output is a terminal. Default is true.
+/// - `plan_then_review`: Whether the LLM is first asked to summarise what each file does, before it is reviewed. Default is false.
+/// - `use_repository_context`: Whether the context of the repository, e.g., its purpose and layout, is given with each file review. Default is true.
+/// - `architecture_review`: Whether, after the file reviews, the repository as a whole is reviewed for architecture-level findings. Default is false.
+/// - `use_review_cache`: Whether file reviews are cached on disk, keyed by file hash, prompt and model. Default is true.
+/// - `review_cache_path`: The user selected path to the review cache. Default is '.cosmonaut/cache'.
+/// - `use_checkpoint`: Whether each file review is checkpointed as it completes, so a run that stops part way is resumed. Default is true.
+/// - `checkpoint_path`: The user selected path to the checkpoint. Default is '.cosmonaut/checkpoint.jsonl'.
+/// - `incremental_review`: Whether only new or changed files are reviewed, carrying forward the last run's reviews of the rest. Default is false.
+/// - `last_review_path`: The user selected path to the last run's reviews, for an incremental review. Default is '.cosmonaut/last_review.json'.
+/// - `baseline_path`: A JSON report of accepted findings, which are marked as known so do not affect the RAG status. Off if not set.
+/// - `rereview_report_path`: A JSON report whose Red files, or files with Critical or High security issues, are the only files reviewed, to verify their remediation. Off if not set.
+/// - `feedback_path`: The user selected path to the findings marked as false positives, which are dropped from later reviews. Default is '.cosmonaut/feedback.json'.
+/// - `feedback_counter_examples`: Whether the false positives are also given to the LLM as examples of findings not to report. Default is false.
+/// - `record_trends`: Whether the summary metrics of each review of the whole repository are recorded, and charted in the report over time. Default is true.
+/// - `trends_path`: The user selected path to the SQLite database of the recorded metrics. Default is '.cosmonaut/trends.db'.
+/// - `executive_summary`: Whether the report has an executive summary, i.e., a verdict, the top risks, the trend since the last review and the effort to remediate, written by the LLM for a non-technical audience. Default is false.
+/// - `min_severity`: The [`Severity`] below which findings are dropped from the review; an error is Medium, an improvement Low. Off if not set.
+/// - `quality_gate`: The [`QualityGate`] that fails a `--gate` run, with a distinct exit code. Fails on a Red status only, if not set.
+/// - `escalation`: The [`Escalation`], i.e., the stronger model a Red file review is sent to for a second opinion. Off if not set.
+/// - `rag_thresholds`: The [`RagThresholds`] of a file's RAG status, per language name, e.g., 'Rust', overriding the defaults.
+/// - `skip_files_with_secrets`: Whether a file with secrets is not sent to the provider at all, rather than sent with them redacted. Default is false.
+/// - `max_file_size_kb`: The size of a file, in KB, above which it is not sent for review, but is in the statistics, with a summary of its functions. Off if not set.
+/// - `skip_generated_files`: Whether generated files, e.g., protobuf output, lockfiles or those marked '@generated', are not sent for review, but are in the statistics. Default is true.
+/// - `languages`: The names of the only languages reviewed, as resolved by linguist, e.g., 'Rust' and 'SQL'. All, if not set.
+/// - `excluded_languages`: The names of languages not reviewed, e.g., 'Markdown' and 'YAML'.
+/// - `authors`: Regular expressions of author names or emails; only the files more than half written by them, by git blame, are reviewed.
+/// - `changed_within_days`: If set, only the files changed by a commit in the last number of days are reviewed.
+/// - `contributor_findings`: Whether the findings are attributed, by git blame, to the contributors who last changed their code, in the report. Default is false.
+/// - `anonymise_contributor_findings`: Whether the contributors to whom findings are attributed are named by rank, e.g., 'Contributor 1'. Default is false.
+/// - `file_ownership`: Whether the owner of each file, and so the bus factor, is found by git blame, which is slow for a large repository. Default is false.
+/// - `anonymise_contributors`: Whether every contributor is named by their rank by commits, e.g., 'Contributor 1', throughout the review data and reports, e.g., before they are shared outside the team. Default is false.
+/// - `coverage_path`: A test coverage report, in LCOV or Cobertura XML format, whose coverage of each file is given with its review. Off if not set.
+/// - `linter_reports`: The [`LinterReport`]s, i.e., clippy, eslint or flake8 output, whose diagnostics of each file are given with its review.
+/// - `run_linters`: The [`Linter`]s, each opted in explicitly, run in the repository, if present, for their diagnostics. WARNING: this executes code of the repository, e.g., its build scripts, so only opt in for a trusted repository. Default is none.
+/// - `dependency_review`: Whether, after the file reviews, the hygiene of the repository's dependencies is reviewed, e.g., unpinned versions. Default is false.
+/// - `vulnerability_lookup`: Whether the known vulnerabilities of the dependencies are looked up in OSV.dev, sending it their names and versions. Default is false.
+/// - `markers_in_summary`: Whether the TODO, FIXME and HACK markers, and the files with the most, are given to the summary of the repository. Default is false.
+/// - `transcript_path`: For debugging, a directory to which every prompt and response is written, with secrets redacted. Off if not set.
+/// - `prompts_path`: A directory of prompts, e.g., `code_review.json`, each overriding the built-in prompt of that name.
+/// - `slack_webhook_url`: A Slack incoming webhook, e.g., `"${SLACK_WEBHOOK_URL}"`, to which the RAG status, findings and report path of each review are posted. Off if not set.
+/// - `models`: The [`ModelInfo`] of models that are not in, or override those in, the built-in registry.
 ///
 /// `review_type` and `output_type` have default values, but other fields must be explicitly set.
 impl Settings {
@@ -94,12 +283,220 @@ impl Settings {
             ConfigError::Message
                 ("Set the environment variable 'SENSITIVE_SETTINGS_PATH' to point to a valid settings file.".to_string()))?;
 
+        let sensitive_config = std::fs::read_to_string(&path_to_sensitive).map_err(|e| {
+            ConfigError::Message(format!(
+                "Failed to read settings file: {}: {}",
+                path_to_sensitive, e
+            ))
+        })?;
+        let mut sensitive_config: serde_json::Value = serde_json::from_str(&sensitive_config)
+            .map_err(|e| ConfigError::Message(format!("Invalid settings file: {}", e)))?;
+        resolve_env_references(&mut sensitive_config)?;
+
         let config = Config::builder()
             .add_source(File::from_str(DEFAULT_CONFIG, FileFormat::Json))
-            .add_source(File::with_name(&path_to_sensitive).format(FileFormat::Json))
+            .add_source(File::from_str(
+                &sensitive_config.to_string(),
+                FileFormat::Json,
+            ))
             .build()?;
 
-        config.try_deserialize::<Settings>()
+        let settings = config.try_deserialize::<Settings>()?;
+        settings
+            .validate()
+            .map_err(|e| ConfigError::Message(e.to_string()))?;
+
+        Ok(settings)
+    }
+    /// Validates the settings of the active provider at startup, so a misconfiguration fails before any review is run
+    pub(crate) fn validate(&self) -> Result<(), ProviderError> {
+        let provider = self.get_active_provider()?;
+        if provider.name == VERTEX_AI_PROVIDER {
+            let (region, project_id) = provider.get_region_and_project_id(&self.sensitive)?;
+            if !is_valid_region(region) {
+                return Err(ProviderError::InvalidSetting(format!(
+                    "'{}' is not a valid region for {}, e.g., 'us-central1'",
+                    region, provider.name
+                )));
+            }
+            if !is_valid_project_id(project_id) {
+                return Err(ProviderError::InvalidSetting(format!(
+                    "'{}' is not a valid project_id for {}",
+                    project_id, provider.name
+                )));
+            }
+        }
+        if let ReviewType::Custom(name) = &self.review_type {
+            let custom_review_type = self.get_custom_review_type().ok_or_else(|| {
+                ProviderError::InvalidSetting(format!(
+                    "No custom_review_types entry named: '{}'",
+                    name
+                ))
+            })?;
+            for path in std::iter::once(&custom_review_type.prompt_path)
+                .chain(custom_review_type.schema_path.as_ref())
+            {
+                if !std::path::Path::new(path).is_file() {
+                    return Err(ProviderError::InvalidSetting(format!(
+                        "The file for custom review type: '{}' does not exist: {}",
+                        name, path
+                    )));
+                }
+            }
+        }
+        if self.review_scope == ReviewScope::Diff && self.base_ref.is_none() {
+            return Err(ProviderError::InvalidSetting(
+                "A base_ref, e.g., 'main', must be set for the 'diff' review_scope".to_string(),
+            ));
+        }
+        if let Some(review_path) = self.review_paths.iter().find(|review_path| {
+            let review_path = std::path::Path::new(review_path);
+            review_path.is_absolute()
+                || review_path
+                    .components()
+                    .any(|component| component == std::path::Component::ParentDir)
+        }) {
+            return Err(ProviderError::InvalidSetting(format!(
+                "The review_paths must be within the repository_path: {}",
+                review_path
+            )));
+        }
+        if let Some(baseline_path) = &self.baseline_path {
+            if !std::path::Path::new(baseline_path).is_file() {
+                return Err(ProviderError::InvalidSetting(format!(
+                    "The baseline_path must be a JSON report: {}",
+                    baseline_path
+                )));
+            }
+        }
+        if let Some(rereview_report_path) = &self.rereview_report_path {
+            if !std::path::Path::new(rereview_report_path).is_file() {
+                return Err(ProviderError::InvalidSetting(format!(
+                    "The rereview_report_path must be a JSON report: {}",
+                    rereview_report_path
+                )));
+            }
+        }
+        if let Some(escalation) = &self.escalation {
+            escalation.get_provider_and_service(self)?;
+        }
+        if let Some(report_template_path) = &self.report_template_path {
+            if !std::path::Path::new(report_template_path).is_file() {
+                return Err(ProviderError::InvalidSetting(format!(
+                    "The report_template_path must be a Handlebars template: {}",
+                    report_template_path
+                )));
+            }
+        }
+        if let Some(report_partials_path) = &self.report_partials_path {
+            if !std::path::Path::new(report_partials_path).is_dir() {
+                return Err(ProviderError::InvalidSetting(format!(
+                    "The report_partials_path must be a directory: {}",
+                    report_partials_path
+                )));
+            }
+        }
+        if let Err(e) = get_locale(
+            self.report_locale.as_deref(),
+            self.report_locale_path.as_deref(),
+        ) {
+            return Err(ProviderError::InvalidSetting(e.to_string()));
+        }
+        if let Some(prompts_path) = &self.prompts_path {
+            if !std::path::Path::new(prompts_path).is_dir() {
+                return Err(ProviderError::InvalidSetting(format!(
+                    "The prompts_path must be a directory: {}",
+                    prompts_path
+                )));
+            }
+        }
+        if let Some(slack_webhook_url) = &self.slack_webhook_url {
+            if !slack_webhook_url.use_key(|url| url.starts_with("https://")) {
+                return Err(ProviderError::InvalidSetting(
+                    "The slack_webhook_url must be an https URL".to_string(),
+                ));
+            }
+        }
+        if let Err(e) = self.get_author_patterns() {
+            return Err(ProviderError::InvalidSetting(format!(
+                "The authors must be valid regular expressions: {}",
+                e
+            )));
+        }
+        if provider.name == REPLAY_PROVIDER && !std::path::Path::new(&provider.api_url).is_dir() {
+            return Err(ProviderError::InvalidSetting(format!(
+                "The api_url for {} must be a transcript directory: {}",
+                provider.name, provider.api_url
+            )));
+        }
+        Ok(())
+    }
+    /// Gets the language in which the LLM writes the summaries, i.e., that of the `report_locale` if
+    /// `localise_summaries` is set, otherwise none, for the default. The locale is only read for the first prompt.
+    pub(crate) fn get_summary_language(&self) -> Option<&str> {
+        self.summary_language
+            .get_or_init(|| {
+                if !self.localise_summaries {
+                    return None;
+                }
+                get_locale(
+                    self.report_locale.as_deref(),
+                    self.report_locale_path.as_deref(),
+                )
+                .ok()
+                .and_then(|locale| locale.language)
+            })
+            .as_deref()
+    }
+    /// Gets the [`CustomReviewType`] selected as the `review_type`, if any
+    pub(crate) fn get_custom_review_type(&self) -> Option<&CustomReviewType> {
+        match &self.review_type {
+            ReviewType::Custom(name) => self
+                .custom_review_types
+                .iter()
+                .find(|custom_review_type| custom_review_type.name == *name),
+            _ => None,
+        }
+    }
+    /// Gets the [`RagThresholds`] of the language, matched by name regardless of case, or the defaults if not overridden
+    pub(crate) fn get_rag_thresholds(&self, language: Option<&str>) -> RagThresholds {
+        language
+            .and_then(|language| {
+                self.rag_thresholds
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(language))
+            })
+            .map_or_else(RagThresholds::default, |(_, thresholds)| *thresholds)
+    }
+    /// Whether the file at the relative path is reviewed, i.e., it is within one of the `review_paths`, if any are set
+    pub(crate) fn is_path_reviewed(&self, relative_path: &std::path::Path) -> bool {
+        self.review_paths.is_empty()
+            || self.review_paths.iter().any(|review_path| {
+                let review_path = review_path.strip_prefix("./").unwrap_or(review_path);
+                relative_path.starts_with(review_path)
+            })
+    }
+    /// Whether files of the language are reviewed, i.e., it is in the `languages`, if any are set, and not in the
+    /// `excluded_languages`, matched by name regardless of case
+    pub(crate) fn is_language_reviewed(&self, language: &str) -> bool {
+        let is_named =
+            |names: &[String]| names.iter().any(|name| name.eq_ignore_ascii_case(language));
+        (self.languages.is_empty() || is_named(&self.languages))
+            && !is_named(&self.excluded_languages)
+    }
+    /// Gets the `authors` as a [`RegexSet`], matched regardless of case, or [`None`] if no authors are set
+    pub(crate) fn get_author_patterns(&self) -> Result<Option<RegexSet>, regex::Error> {
+        if self.authors.is_empty() {
+            return Ok(None);
+        }
+        RegexSetBuilder::new(&self.authors)
+            .case_insensitive(true)
+            .build()
+            .map(Some)
+    }
+    /// Gets the `head_ref` for a 'diff' review scope, or 'HEAD' if not set
+    pub(crate) fn get_head_ref(&self) -> &str {
+        self.head_ref.as_deref().unwrap_or(DEFAULT_HEAD_REF)
     }
     /// Function gets either the chosen provider or default provider, or gives a ProviderError
     pub(crate) fn get_active_provider(&self) -> Result<&ProviderSettings, ProviderError> {
@@ -129,9 +526,30 @@ pub(crate) struct ProviderSettings {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) api_timeout: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) request_timeouts: Option<RequestTimeouts>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) max_tokens: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) max_retries: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) requests_per_minute: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) tokens_per_minute: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) initial_backoff_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) max_retry_elapsed_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) region: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) project_id: Option<String>,
+    /// Extra HTTP headers attached to every request, e.g., for an enterprise API gateway. Only the names are ever printed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) headers: Option<HashMap<String, String>>,
+    /// The provider's own secrets, used instead of the top-level `sensitive` settings, e.g., when both OpenAI and
+    /// Google are configured. Never printed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) sensitive: Option<SensitiveSettings>,
 }
 impl ProviderSettings {
     pub(crate) fn get_active_service(&self) -> Result<&ServiceSettings, ServiceError> {
@@ -148,6 +566,45 @@ impl ProviderSettings {
             )
         })
     }
+    /// Gets the provider's own [`SensitiveSettings`], if set, otherwise the top-level `sensitive` settings passed in
+    pub(crate) fn get_sensitive<'a>(
+        &'a self,
+        sensitive: &'a SensitiveSettings,
+    ) -> &'a SensitiveSettings {
+        self.sensitive.as_ref().unwrap_or(sensitive)
+    }
+    /// Gets the `region` and `project_id` for the provider, e.g., for 'vertex-ai', falling back to those in the [`SensitiveSettings`]
+    pub(crate) fn get_region_and_project_id<'a>(
+        &'a self,
+        sensitive: &'a SensitiveSettings,
+    ) -> Result<(&'a str, &'a str), ProviderError> {
+        let sensitive = self.get_sensitive(sensitive);
+        let region = self
+            .region
+            .as_deref()
+            .or(sensitive.region.as_deref())
+            .ok_or_else(|| {
+                ProviderError::InvalidSetting(format!("No region specified for {}", self.name))
+            })?;
+        let project_id = self
+            .project_id
+            .as_deref()
+            .or(sensitive.project_id.as_deref())
+            .ok_or_else(|| {
+                ProviderError::InvalidSetting(format!("No project_id specified for {}", self.name))
+            })?;
+        Ok((region, project_id))
+    }
+    /// Gets the timeout, in seconds, for the [`RequestType`], falling back to the `api_timeout`
+    pub(crate) fn get_api_timeout(&self, request_type: &RequestType) -> Option<u64> {
+        self.request_timeouts
+            .as_ref()
+            .and_then(|timeouts| match request_type {
+                RequestType::Review | RequestType::Plan => timeouts.review,
+                RequestType::Summarise => timeouts.summarise,
+            })
+            .or(self.api_timeout)
+    }
     /// Gets a service by name
     pub(crate) fn get_service_by_name(&self, name: &str) -> Option<&ServiceSettings> {
         self.services.iter().find(|s| s.name == name)
@@ -161,7 +618,29 @@ impl fmt::Debug for ProviderSettings {
             .field("services", &self.services)
             .field("api_url", &self.api_url)
             .field("api_timeout", &self.api_timeout)
+            .field("request_timeouts", &self.request_timeouts)
             .field("max_tokens", &self.max_tokens)
+            .field("requests_per_minute", &self.requests_per_minute)
+            .field("tokens_per_minute", &self.tokens_per_minute)
+            .field("max_retries", &self.max_retries)
+            .field("region", &self.region)
+            .field("project_id", &self.project_id)
+            .field("initial_backoff_ms", &self.initial_backoff_ms)
+            .field("max_retry_elapsed_secs", &self.max_retry_elapsed_secs)
+            .field(
+                "headers",
+                &self
+                    .headers
+                    .as_ref()
+                    .map(|headers| headers.keys().collect::<Vec<_>>()),
+            )
+            .field(
+                "sensitive",
+                &self
+                    .sensitive
+                    .as_ref()
+                    .map(|_| "*** sensitive data hidden ***"),
+            )
             .finish()
     }
 }
@@ -173,13 +652,44 @@ impl fmt::Display for ProviderSettings {
             .field("services", &self.services)
             .field("api_url", &self.api_url)
             .field("api_timeout", &self.api_timeout)
+            .field("request_timeouts", &self.request_timeouts)
             .field("max_tokens", &self.max_tokens)
+            .field("requests_per_minute", &self.requests_per_minute)
+            .field("tokens_per_minute", &self.tokens_per_minute)
+            .field("max_retries", &self.max_retries)
+            .field("region", &self.region)
+            .field("project_id", &self.project_id)
+            .field("initial_backoff_ms", &self.initial_backoff_ms)
+            .field("max_retry_elapsed_secs", &self.max_retry_elapsed_secs)
+            .field(
+                "headers",
+                &self
+                    .headers
+                    .as_ref()
+                    .map(|headers| headers.keys().collect::<Vec<_>>()),
+            )
+            .field(
+                "sensitive",
+                &self
+                    .sensitive
+                    .as_ref()
+                    .map(|_| "*** sensitive data hidden ***"),
+            )
             .finish()
     }
 }
+/// The timeouts, in seconds, per [`RequestType`], e.g., as a summary of a large repository takes longer than a single file review
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub(crate) struct RequestTimeouts {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) review: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) summarise: Option<u64>,
+}
 #[derive(Debug)]
 pub(crate) enum ProviderError {
     NotFound(String),
+    InvalidSetting(String),
 }
 impl std::error::Error for ProviderError {}
 /// Custom error for misconfiguration of provider
@@ -187,13 +697,45 @@ impl fmt::Display for ProviderError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ProviderError::NotFound(name) => write!(f, "ProviderSettings not found: {}", name),
+            ProviderError::InvalidSetting(message) => {
+                write!(f, "Invalid ProviderSettings: {}", message)
+            }
         }
     }
 }
+/// The settings for a service offered by a provider
+///
+/// #Fields:
+/// * `name` - The name of the service, e.g., 'gpt-4'
+/// * `model` - The model the service uses, e.g., 'gpt-4-1106-preview'
+/// * `pricing` - The [`ModelPricing`] used to estimate the cost of a review, optional
+/// * `parameters` - The [`ModelParameters`] sent with each request, set alongside `name` and `model`
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub(crate) struct ServiceSettings {
     pub(crate) name: String,
     pub(crate) model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) pricing: Option<ModelPricing>,
+    #[serde(flatten)]
+    pub(crate) parameters: ModelParameters,
+}
+/// The [`Option`]al generation parameters for a model. If not set, the provider's default is used.
+///
+/// #Fields:
+/// * `temperature` - The sampling temperature, e.g., '0.7'
+/// * `top_p` - The nucleus sampling probability mass, e.g., '0.9'
+/// * `max_output_tokens` - The maximum number of tokens the model may generate in its response
+/// * `seed` - A fixed seed, for (mostly) deterministic output across runs. Not set, or 'null', disables it.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub(crate) struct ModelParameters {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) max_output_tokens: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) seed: Option<i64>,
 }
 pub(crate) enum ServiceError {
     NotFound(String),
@@ -218,19 +760,230 @@ impl fmt::Debug for SensitiveSettings {
         write!(f, "*** sensitive data hidden ***")
     }
 }
-#[derive(Debug, Serialize, Deserialize, Default, PartialEq)]
+/// The scope of the review, i.e., which of the repository's files are reviewed
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq, Clone)]
 #[serde(rename_all = "lowercase")]
+pub(crate) enum ReviewScope {
+    /// All files in the repository
+    #[default]
+    All,
+    /// Only the files added or modified between the `base_ref` and `head_ref`
+    Diff,
+    /// Only the files added or modified in the index, i.e., staged for the next commit
+    Staged,
+}
+/// The granularity of the review, i.e., the unit of a source file that is sent for review
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq, Clone)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ReviewGranularity {
+    /// Each file is reviewed whole
+    #[default]
+    File,
+    /// Each function or method of a file, and the code outside any, is reviewed on its own
+    Function,
+}
+/// The type of review, i.e., 'general', 'security', 'codestats', 'compliance', or a [`CustomReviewType`] as
+/// 'custom:{name}'
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq, Clone)]
+#[serde(try_from = "String", into = "String")]
 pub(crate) enum ReviewType {
     #[default]
     General,
     Security,
     CodeStats,
+    Compliance,
+    Custom(String),
+}
+impl ReviewType {
+    /// Whether the review uses a provider, i.e., is not made only of what is calculated without the LLM
+    pub(crate) fn uses_provider(&self) -> bool {
+        !matches!(self, Self::CodeStats | Self::Compliance)
+    }
+}
+const CUSTOM_REVIEW_TYPE_PREFIX: &str = "custom:";
+impl TryFrom<String> for ReviewType {
+    type Error = String;
+
+    fn try_from(review_type: String) -> Result<Self, Self::Error> {
+        match review_type.as_str() {
+            "general" => Ok(Self::General),
+            "security" => Ok(Self::Security),
+            "codestats" => Ok(Self::CodeStats),
+            "compliance" => Ok(Self::Compliance),
+            _ => match review_type.strip_prefix(CUSTOM_REVIEW_TYPE_PREFIX) {
+                Some(name) if !name.is_empty() => Ok(Self::Custom(name.to_string())),
+                _ => Err(format!(
+                    "Unknown review_type: '{}', expected 'general', 'security', 'codestats', 'compliance' or 'custom:{{name}}'",
+                    review_type
+                )),
+            },
+        }
+    }
+}
+impl From<ReviewType> for String {
+    fn from(review_type: ReviewType) -> Self {
+        review_type.to_string()
+    }
+}
+impl fmt::Display for ReviewType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::General => write!(f, "general"),
+            Self::Security => write!(f, "security"),
+            Self::CodeStats => write!(f, "codestats"),
+            Self::Compliance => write!(f, "compliance"),
+            Self::Custom(name) => write!(f, "{}{}", CUSTOM_REVIEW_TYPE_PREFIX, name),
+        }
+    }
+}
+/// A static linter whose diagnostics may be given with each file's review
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Linter {
+    /// The JSON lines of `cargo clippy --message-format=json`
+    Clippy,
+    /// The JSON of `eslint -f json`
+    Eslint,
+    /// The JSON of the `flake8-json` formatter, or the default text output
+    Flake8,
+}
+impl fmt::Display for Linter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Clippy => write!(f, "clippy"),
+            Self::Eslint => write!(f, "eslint"),
+            Self::Flake8 => write!(f, "flake8"),
+        }
+    }
+}
+/// The output of a linter, saved to a file, e.g., by CI
+/// #Fields
+///
+/// - 'tool': The [`Linter`], i.e., 'clippy', 'eslint' or 'flake8'.
+/// - 'path': The path to the output.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub(crate) struct LinterReport {
+    pub(crate) tool: Linter,
+    pub(crate) path: String,
+}
+/// A review type defined in the settings, selected via `"review_type": "custom:{name}"`
+/// #Fields
+///
+/// - 'name': The name of the review type, e.g., 'my-style-guide'.
+/// - 'prompt_path': The path to the prompt file, in the same format as the built-in prompts, e.g., `code_review.json`.
+/// - 'schema_path': The path to the JSON schema the review must comply with, used in place of `file_review.schema.json`.
+///   It must remain compatible with a [`crate::review::data::SourceFileReview`]. The built-in schema is used if not set.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub(crate) struct CustomReviewType {
+    pub(crate) name: String,
+    pub(crate) prompt_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) schema_path: Option<String>,
+}
+
+/// The thresholds of a `--gate` run, e.g., in CI, each of which, if exceeded, fails the run with a distinct exit code
+/// #Fields
+///
+/// - 'fail_on_red': Whether a Red status of the repository fails the run. Default is true.
+/// - 'max_critical_high_security_issues': The number of critical and high security issues allowed. Not checked if not set.
+/// - 'max_errors': The number of errors allowed. Not checked if not set.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub(crate) struct QualityGate {
+    #[serde(default = "default_true")]
+    pub(crate) fail_on_red: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) max_critical_high_security_issues: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) max_errors: Option<i32>,
+}
+impl Default for QualityGate {
+    fn default() -> Self {
+        Self {
+            fail_on_red: true,
+            max_critical_high_security_issues: None,
+            max_errors: None,
+        }
+    }
+}
+
+/// The stronger model that a file review that is Red is escalated to, for a second opinion, whose findings are kept
+/// #Fields
+///
+/// - 'provider': The name of the provider of the service. Default is the active provider.
+/// - 'service': The name of the service, i.e., the model, of the provider, e.g., 'gpt-4'.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub(crate) struct Escalation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) provider: Option<String>,
+    pub(crate) service: String,
+}
+impl Escalation {
+    /// Gets the [`ProviderSettings`] and [`ServiceSettings`] escalated to
+    pub(crate) fn get_provider_and_service<'a>(
+        &'a self,
+        settings: &'a Settings,
+    ) -> Result<(&'a ProviderSettings, &'a ServiceSettings), ProviderError> {
+        let provider: &ProviderSettings = match &self.provider {
+            Some(name) => settings
+                .providers
+                .iter()
+                .find(|provider| provider.name == *name)
+                .ok_or_else(|| ProviderError::NotFound(name.clone()))?,
+            None => settings.get_active_provider()?,
+        };
+        let service: &ServiceSettings =
+            provider.get_service_by_name(&self.service).ok_or_else(|| {
+                ProviderError::InvalidSetting(format!(
+                    "No service named: '{}' to escalate to, of: {}",
+                    self.service, provider.name
+                ))
+            })?;
+        Ok((provider, service))
+    }
+}
+
+/// The thresholds of a file's RAG status, as the ratio of findings to lines of code. A file is Green if within every
+/// 'green' threshold, Amber if within every 'amber' threshold, and Red otherwise. A high or critical security issue is
+/// always Red. Any threshold not set is the default.
+/// #Fields
+///
+/// - 'green_error_ratio': Default is 0.07, i.e., 7% of lines of code.
+/// - 'amber_error_ratio': Default is 0.18.
+/// - 'green_improvement_ratio': Default is 0.15.
+/// - 'amber_improvement_ratio': Default is 0.40.
+/// - 'green_security_issue_ratio': Default is 0.05.
+/// - 'amber_security_issue_ratio': Default is 0.12.
+/// - 'min_coverage': The percentage of lines covered by tests below which a Green file is Amber, if a coverage report is
+///   given. Default is 50.0.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+#[serde(default)]
+pub(crate) struct RagThresholds {
+    pub(crate) green_error_ratio: f64,
+    pub(crate) amber_error_ratio: f64,
+    pub(crate) green_improvement_ratio: f64,
+    pub(crate) amber_improvement_ratio: f64,
+    pub(crate) green_security_issue_ratio: f64,
+    pub(crate) amber_security_issue_ratio: f64,
+    pub(crate) min_coverage: f64,
+}
+impl Default for RagThresholds {
+    fn default() -> Self {
+        Self {
+            green_error_ratio: 0.07,
+            amber_error_ratio: 0.18,
+            green_improvement_ratio: 0.15,
+            amber_improvement_ratio: 0.40,
+            green_security_issue_ratio: 0.05,
+            amber_security_issue_ratio: 0.12,
+            min_coverage: 50.0,
+        }
+    }
 }
 
 /// An [`Option`]al set of settings to control the output of the programme for development purposes
 /// #Fields
 ///
-/// - 'max_file_count': To improve development feedback loop time on big repos, allows sampling.
+/// - 'max_file_count': To improve development feedback loop time on big repos, allows sampling of the riskiest files.
 /// - 'verbose_data_output': a flag to produce a full 'json' file, even if the [`OutputType`] is 'html' or other
 /// - 'developer_path': Provides a developer path through the code.
 /// - 'test_json_path': the path to a previous [`crate::review::data::RepositoryReview`] serialized to a file.
@@ -243,7 +996,7 @@ pub(crate) struct DeveloperMode {
     pub(crate) test_path: bool,
     pub(crate) test_file: Option<String>,
 }
-#[derive(Serialize, Deserialize, PartialEq)]
+#[derive(Serialize, Deserialize, PartialEq, Default)]
 pub(crate) struct SensitiveSettings {
     pub(crate) api_key: Option<APIKey>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -254,6 +1007,8 @@ pub(crate) struct SensitiveSettings {
     pub(crate) region: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) project_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) token_auth: Option<TokenAuth>,
 }
 /// Custom Display implementation for SensitiveSettings to prevent accidental printing of secret
 impl fmt::Display for SensitiveSettings {
@@ -261,6 +1016,24 @@ impl fmt::Display for SensitiveSettings {
         write!(f, "*** sensitive data hidden ***")
     }
 }
+/// Token-based authentication, as an alternative to a static `api_key`, e.g., for Azure AD (OAuth)
+///
+/// - `client_credentials`: Fetches a token via the OAuth client credentials flow from the `token_url`.
+/// - `command`: Runs a user supplied command that prints a token, e.g., `az account get-access-token --query accessToken -o tsv`.
+#[derive(Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum TokenAuth {
+    ClientCredentials {
+        token_url: String,
+        client_id: String,
+        client_secret: APIKey,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        scope: Option<String>,
+    },
+    Command {
+        command: String,
+    },
+}
 #[derive(Serialize, Deserialize, PartialEq)]
 pub(crate) struct APIKey(String); // Sensitive data!
 /// Locking up the APIKey to prevent accidental display
@@ -272,16 +1045,85 @@ impl APIKey {
         f(&self.0)
     }
 }
+/// Whether the region is of the form of a Google Cloud region, e.g., 'us-central1'
+fn is_valid_region(region: &str) -> bool {
+    region.contains('-')
+        && region
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+/// Whether the project_id meets the Google Cloud rules: 6 to 30 lowercase letters, digits or hyphens, starting with a letter
+fn is_valid_project_id(project_id: &str) -> bool {
+    (6..=30).contains(&project_id.len())
+        && project_id.starts_with(|c: char| c.is_ascii_lowercase())
+        && !project_id.ends_with('-')
+        && project_id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+/// Replaces each `${NAME}` reference, in every string value, with the value of the environment variable `NAME`,
+/// e.g., `"api_key": "${OPENAI_API_KEY}"`, so that secrets need not be written to the settings file
+fn resolve_env_references(value: &mut serde_json::Value) -> Result<(), ConfigError> {
+    match value {
+        serde_json::Value::String(text) => *text = resolve_env_string(text)?,
+        serde_json::Value::Array(values) => {
+            for value in values {
+                resolve_env_references(value)?;
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for value in map.values_mut() {
+                resolve_env_references(value)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+/// Resolves the `${NAME}` references in a single string, erroring if an environment variable is not set
+fn resolve_env_string(text: &str) -> Result<String, ConfigError> {
+    let mut resolved = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        let end = rest[start..].find('}').ok_or_else(|| {
+            ConfigError::Message(format!(
+                "Unclosed environment variable reference in: {}",
+                rest
+            ))
+        })? + start;
+        let name = &rest[start + 2..end];
+        let env_value = env::var(name).map_err(|_| {
+            ConfigError::Message(format!(
+                "The environment variable '{}', referenced in the settings file, is not set",
+                name
+            ))
+        })?;
+        resolved.push_str(&rest[..start]);
+        resolved.push_str(&env_value);
+        rest = &rest[end + 1..];
+    }
+    resolved.push_str(rest);
+    Ok(resolved)
+}
 /// Helper to enable a default 'false' value for a boolean field
 fn default_false() -> bool {
     false
 }
+/// Helper to enable a default 'true' value for a boolean field
+fn default_true() -> bool {
+    true
+}
+/// Helper to enable a default of reviewing one file at a time
+fn default_max_concurrent_reviews() -> usize {
+    1
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::fs::File;
     use std::io::Write;
+    use std::path::Path;
     use tempfile::tempdir;
 
     #[test]
@@ -289,6 +1131,8 @@ mod tests {
         let services = vec![ServiceSettings {
             name: "GPT-3".to_string(),
             model: "text-davinci-003".to_string(),
+            pricing: None,
+            parameters: ModelParameters::default(),
         }];
         let provider = ProviderSettings {
             name: "openai".to_string(),
@@ -297,8 +1141,17 @@ mod tests {
             default_service: "gpt-3.5".to_string(),
             api_url: "https://api.openai.com".to_string(),
             api_timeout: Some(60),
+            request_timeouts: None,
             max_tokens: Some(2048),
             max_retries: Some(5),
+            requests_per_minute: None,
+            tokens_per_minute: None,
+            initial_backoff_ms: None,
+            max_retry_elapsed_secs: None,
+            region: None,
+            project_id: None,
+            headers: None,
+            sensitive: None,
         };
 
         let serialized = serde_json::to_string(&provider).unwrap();
@@ -328,6 +1181,193 @@ mod tests {
         assert_eq!(provider.name, "openai");
     }
 
+    #[test]
+    fn test_provider_settings_headers_hidden() {
+        let json = r#"{
+            "name": "openai",
+            "services": [],
+            "default_service": "gpt-4",
+            "api_url": "https://gateway.example.com/v1/chat/completions",
+            "headers": {
+                "X-Org-Token": "secret-token"
+            }
+        }"#;
+
+        let provider: ProviderSettings = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            provider.headers.as_ref().unwrap().get("X-Org-Token"),
+            Some(&"secret-token".to_string())
+        );
+        let displayed = format!("{}", provider);
+        assert!(displayed.contains("X-Org-Token"));
+        assert!(!displayed.contains("secret-token"));
+    }
+
+    #[test]
+    fn test_token_auth_deserialization() {
+        let json = r#"{
+            "token_auth": {
+                "type": "client_credentials",
+                "token_url": "https://login.microsoftonline.com/tenant/oauth2/v2.0/token",
+                "client_id": "client",
+                "client_secret": "secret",
+                "scope": "https://cognitiveservices.azure.com/.default"
+            }
+        }"#;
+        let sensitive: SensitiveSettings = serde_json::from_str(json).unwrap();
+        assert!(sensitive.api_key.is_none());
+        match sensitive.token_auth {
+            Some(TokenAuth::ClientCredentials {
+                client_id, scope, ..
+            }) => {
+                assert_eq!(client_id, "client");
+                assert!(scope.is_some());
+            }
+            _ => panic!("Expected TokenAuth::ClientCredentials"),
+        }
+
+        let json =
+            r#"{ "token_auth": { "type": "command", "command": "az account get-access-token" } }"#;
+        let sensitive: SensitiveSettings = serde_json::from_str(json).unwrap();
+        assert!(matches!(
+            sensitive.token_auth,
+            Some(TokenAuth::Command { .. })
+        ));
+    }
+
+    #[test]
+    fn test_validate_vertex_ai_settings() {
+        let settings_json = |region: &str, project_id: &str| {
+            format!(
+                r#"{{
+                    "providers": [{{
+                        "name": "vertex-ai",
+                        "services": [{{ "name": "gemini-pro", "model": "gemini-pro" }}],
+                        "default_service": "gemini-pro",
+                        "api_url": "https://aiplatform.googleapis.com",
+                        "region": "{}"
+                    }}],
+                    "default_provider": "vertex-ai",
+                    "repository_path": "path/to/repo",
+                    "report_output_path": "path/to/report",
+                    "sensitive": {{ "project_id": "{}" }}
+                }}"#,
+                region, project_id
+            )
+        };
+        let settings: Settings =
+            serde_json::from_str(&settings_json("us-central1", "my-project-123")).unwrap();
+        assert!(settings.validate().is_ok());
+        assert_eq!(
+            settings
+                .get_active_provider()
+                .unwrap()
+                .get_region_and_project_id(&settings.sensitive)
+                .unwrap(),
+            ("us-central1", "my-project-123")
+        );
+
+        let settings: Settings =
+            serde_json::from_str(&settings_json("US Central", "my-project-123")).unwrap();
+        assert!(settings.validate().is_err());
+
+        let settings: Settings =
+            serde_json::from_str(&settings_json("us-central1", "1-bad")).unwrap();
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn test_provider_sensitive_settings() {
+        let json = r#"{
+            "name": "google",
+            "services": [],
+            "default_service": "gemini-pro",
+            "api_url": "https://generativelanguage.googleapis.com",
+            "sensitive": { "api_key": "google-key" }
+        }"#;
+        let global: SensitiveSettings =
+            serde_json::from_str(r#"{ "api_key": "openai-key", "org_id": "org-123" }"#).unwrap();
+
+        let provider: ProviderSettings = serde_json::from_str(json).unwrap();
+        let sensitive = provider.get_sensitive(&global);
+        assert_eq!(sensitive.api_key.as_ref().unwrap().0, "google-key");
+        assert!(sensitive.org_id.is_none());
+        assert!(!format!("{:?}", provider).contains("google-key"));
+        assert!(!format!("{}", provider).contains("google-key"));
+    }
+
+    #[test]
+    fn test_get_api_timeout() {
+        let json = r#"{
+            "name": "google",
+            "services": [],
+            "default_service": "gemini-pro",
+            "api_url": "https://generativelanguage.googleapis.com",
+            "api_timeout": 30,
+            "request_timeouts": { "summarise": 300 }
+        }"#;
+
+        let provider: ProviderSettings = serde_json::from_str(json).unwrap();
+        assert_eq!(provider.get_api_timeout(&RequestType::Review), Some(30));
+        assert_eq!(provider.get_api_timeout(&RequestType::Summarise), Some(300));
+    }
+
+    #[test]
+    fn test_service_settings_parameters() {
+        let json = r#"{
+            "name": "lm-studio",
+            "model": "deepseek-coder-6.7B-instruct",
+            "temperature": 0.7,
+            "max_output_tokens": 2048,
+            "seed": 1234
+        }"#;
+
+        let service: ServiceSettings = serde_json::from_str(json).unwrap();
+        assert_eq!(service.parameters.temperature, Some(0.7));
+        assert_eq!(service.parameters.top_p, None);
+        assert_eq!(service.parameters.max_output_tokens, Some(2048));
+        assert_eq!(service.parameters.seed, Some(1234));
+    }
+
+    #[test]
+    fn test_resolve_env_references() {
+        env::set_var("COSMONAUT_TEST_API_KEY", "secret-key");
+        let mut value = serde_json::json!({
+            "api_key": "${COSMONAUT_TEST_API_KEY}",
+            "token_auth": { "type": "command", "command": "echo ${COSMONAUT_TEST_API_KEY}" },
+            "max_tokens": 2048
+        });
+
+        resolve_env_references(&mut value).unwrap();
+        assert_eq!(value["api_key"], "secret-key");
+        assert_eq!(value["token_auth"]["command"], "echo secret-key");
+        assert_eq!(value["max_tokens"], 2048);
+
+        let mut missing = serde_json::json!({ "api_key": "${COSMONAUT_TEST_UNSET_KEY}" });
+        assert!(resolve_env_references(&mut missing).is_err());
+        assert!(resolve_env_string("${UNCLOSED").is_err());
+    }
+
+    #[test]
+    fn test_review_type_serde() {
+        for (json, review_type) in [
+            ("\"general\"", ReviewType::General),
+            ("\"codestats\"", ReviewType::CodeStats),
+            ("\"compliance\"", ReviewType::Compliance),
+            (
+                "\"custom:my-style-guide\"",
+                ReviewType::Custom("my-style-guide".to_string()),
+            ),
+        ] {
+            assert_eq!(
+                serde_json::from_str::<ReviewType>(json).unwrap(),
+                review_type
+            );
+            assert_eq!(serde_json::to_string(&review_type).unwrap(), json);
+        }
+        assert!(serde_json::from_str::<ReviewType>("\"custom:\"").is_err());
+        assert!(serde_json::from_str::<ReviewType>("\"style\"").is_err());
+    }
     #[test]
     fn test_api_key_use() {
         let api_key = APIKey("secret".to_string());
@@ -412,6 +1452,8 @@ mod tests {
         let services = vec![ServiceSettings {
             name: "GPT-3".to_string(),
             model: "gpt-3.5".to_string(),
+            pricing: None,
+            parameters: ModelParameters::default(),
         }];
         let settings = Settings {
             providers: vec![ProviderSettings {
@@ -421,27 +1463,136 @@ mod tests {
                 default_service: "gpt-3.5".to_string(),
                 api_url: "https://api.openai.com".to_string(),
                 api_timeout: Some(60),
+                request_timeouts: None,
                 max_tokens: Some(2048),
                 max_retries: Some(5),
+                requests_per_minute: None,
+                tokens_per_minute: None,
+                initial_backoff_ms: None,
+                max_retry_elapsed_secs: None,
+                region: None,
+                project_id: None,
+                headers: None,
+                sensitive: None,
             }],
             chosen_provider: None,
             chosen_service: None,
             default_provider: "openai".to_string(),
             output_type: OutputType::Json,
             review_type: ReviewType::General,
+            custom_review_types: Vec::new(),
+            review_scope: ReviewScope::All,
+            review_granularity: ReviewGranularity::File,
+            base_ref: None,
+            head_ref: None,
             review_cycles: None,
+            max_concurrent_reviews: 1,
+            scheduled_providers: Vec::new(),
+            progress_bar: true,
+            plan_then_review: false,
+            use_repository_context: true,
+            architecture_review: false,
             repository_path: "path/to/repo".to_string(),
+            review_paths: Vec::new(),
+            repository_ref: None,
+            require_git: true,
             report_output_path: "path/to/report".to_string(),
+            report_theme: ReportTheme::Detail,
+            report_template_path: None,
+            report_partials_path: None,
+            report_locale: None,
+            report_locale_path: None,
+            localise_summaries: false,
+            use_review_cache: true,
+            review_cache_path: None,
+            use_checkpoint: true,
+            checkpoint_path: None,
+            incremental_review: false,
+            last_review_path: None,
+            baseline_path: None,
+            rereview_report_path: None,
+            feedback_path: None,
+            feedback_counter_examples: false,
+            record_trends: true,
+            trends_path: None,
+            executive_summary: false,
+            min_severity: None,
+            quality_gate: None,
+            escalation: None,
+            rag_thresholds: HashMap::new(),
+            skip_files_with_secrets: false,
+            max_file_size_kb: None,
+            skip_generated_files: true,
+            languages: Vec::new(),
+            excluded_languages: Vec::new(),
+            authors: Vec::new(),
+            changed_within_days: None,
+            contributor_findings: false,
+            anonymise_contributor_findings: false,
+            file_ownership: false,
+            anonymise_contributors: false,
+            coverage_path: None,
+            linter_reports: Vec::new(),
+            run_linters: Vec::new(),
+            dependency_review: false,
+            vulnerability_lookup: false,
+            markers_in_summary: false,
+            transcript_path: None,
+            prompts_path: None,
+            slack_webhook_url: None,
+            models: Vec::new(),
             sensitive: SensitiveSettings {
                 api_key: Some(APIKey("secret".to_string())),
                 org_id: None,
                 org_name: None,
                 region: None,
                 project_id: None,
+                token_auth: None,
             },
             developer_mode: None,
+            summary_language: OnceLock::new(),
         };
         let provider = settings.get_active_provider().unwrap();
         assert_eq!(provider.name, "openai");
     }
+
+    #[test]
+    fn test_is_language_reviewed() {
+        let mut settings: Settings = serde_json::from_str(
+            r#"{
+                "providers": [],
+                "default_provider": "openai",
+                "repository_path": "path/to/repo",
+                "report_output_path": "path/to/report"
+            }"#,
+        )
+        .unwrap();
+        assert!(settings.is_language_reviewed("Markdown"));
+        settings.excluded_languages = vec!["markdown".to_string(), "YAML".to_string()];
+        assert!(!settings.is_language_reviewed("Markdown"));
+        assert!(settings.is_language_reviewed("Rust"));
+        settings.languages = vec!["Rust".to_string(), "SQL".to_string()];
+        assert!(settings.is_language_reviewed("rust"));
+        assert!(!settings.is_language_reviewed("Python"));
+    }
+
+    #[test]
+    fn test_is_path_reviewed() {
+        let mut settings: Settings = serde_json::from_str(
+            r#"{
+                "providers": [],
+                "default_provider": "openai",
+                "repository_path": "path/to/repo",
+                "report_output_path": "path/to/report",
+                "review_paths": ["backend/", "./crates/core"]
+            }"#,
+        )
+        .unwrap();
+        assert!(settings.is_path_reviewed(Path::new("backend/src/main.rs")));
+        assert!(settings.is_path_reviewed(Path::new("crates/core/lib.rs")));
+        assert!(!settings.is_path_reviewed(Path::new("backend2/main.rs")));
+        assert!(!settings.is_path_reviewed(Path::new("crates/cli/main.rs")));
+        settings.review_paths.clear();
+        assert!(settings.is_path_reviewed(Path::new("frontend/index.ts")));
+    }
 }